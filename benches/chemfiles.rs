@@ -0,0 +1,122 @@
+// Chemfiles, a modern library for chemistry file reading and writing
+// Copyright (C) 2015-2018 Guillaume Fraux -- BSD licensed
+use std::path::{Path, PathBuf};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use chemfiles::{Frame, Selection, Trajectory};
+
+fn water_path() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("..").join("data").join("water.xyz")
+}
+
+fn read_water() -> Vec<Frame> {
+    let mut trajectory = Trajectory::open(water_path(), 'r').unwrap();
+    let mut frames = Vec::new();
+    for _ in 0..trajectory.nsteps() {
+        let mut frame = Frame::new();
+        trajectory.read(&mut frame).unwrap();
+        frames.push(frame);
+    }
+    frames
+}
+
+/// Convert the water trajectory to all the formats used in the benchmarks,
+/// and return the corresponding paths.
+fn prepare_files() -> Vec<(&'static str, PathBuf)> {
+    let frames = read_water();
+    let directory = std::env::temp_dir().join("chemfiles-rs-benches");
+    std::fs::create_dir_all(&directory).unwrap();
+
+    let mut files = vec![("XYZ", water_path())];
+    for (format, extension) in [("PDB", "pdb"), ("XTC", "xtc")] {
+        let path = directory.join(format!("water.{extension}"));
+        let mut trajectory = Trajectory::open(&path, 'w').unwrap();
+        for frame in &frames {
+            trajectory.write(frame).unwrap();
+        }
+        files.push((format, path));
+    }
+    files
+}
+
+fn read(c: &mut Criterion) {
+    let mut group = c.benchmark_group("read");
+    for (format, path) in prepare_files() {
+        let mut trajectory = Trajectory::open(&path, 'r').unwrap();
+        group.throughput(Throughput::Elements(trajectory.nsteps() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(format), &path, |b, path| {
+            let mut frame = Frame::new();
+            b.iter(|| {
+                let mut trajectory = Trajectory::open(path, 'r').unwrap();
+                for _ in 0..trajectory.nsteps() {
+                    trajectory.read(&mut frame).unwrap();
+                }
+            });
+        });
+    }
+    group.finish();
+}
+
+fn positions(c: &mut Criterion) {
+    let mut frame = read_water().swap_remove(0);
+    let mut group = c.benchmark_group("positions");
+    group.throughput(Throughput::Elements(frame.size() as u64));
+    group.bench_function("positions", |b| {
+        b.iter(|| frame.positions().iter().map(|p| p[0] + p[1] + p[2]).sum::<f64>());
+    });
+    group.bench_function("positions_mut", |b| {
+        b.iter(|| {
+            for position in frame.positions_mut() {
+                position[0] += 1e-6;
+            }
+        });
+    });
+    group.finish();
+}
+
+fn selections(c: &mut Criterion) {
+    let frame = read_water().swap_remove(0);
+    let mut group = c.benchmark_group("selection");
+    for selection in ["name O", "pairs: name(#1) O and distance(#1, #2) < 3.0"] {
+        let mut selection = Selection::new(selection).unwrap();
+        group.bench_function(selection.string(), |b| {
+            b.iter(|| selection.evaluate(&frame));
+        });
+    }
+    group.finish();
+}
+
+fn properties(c: &mut Criterion) {
+    let mut frame = read_water().swap_remove(0);
+    let values = vec![1.0; frame.size()];
+
+    let mut group = c.benchmark_group("properties");
+    group.throughput(Throughput::Elements(frame.size() as u64));
+    group.bench_function("atom_mut().set", |b| {
+        b.iter(|| {
+            for (i, &value) in values.iter().enumerate() {
+                frame.atom_mut(i).set("value", value);
+            }
+        });
+    });
+    group.bench_function("set_atom_array_property", |b| {
+        b.iter(|| frame.set_atom_array_property("value", &values).unwrap());
+    });
+    group.bench_function("atom().get", |b| {
+        b.iter(|| (0..frame.size()).map(|i| frame.atom(i).get("value")).collect::<Vec<_>>());
+    });
+    group.bench_function("atom_array_property", |b| {
+        b.iter(|| frame.atom_array_property("value"));
+    });
+    group.bench_function("atom().mass", |b| {
+        b.iter(|| (0..frame.size()).map(|i| frame.atom(i).mass()).collect::<Vec<_>>());
+    });
+    group.bench_function("masses", |b| {
+        b.iter(|| frame.masses());
+    });
+    group.finish();
+}
+
+criterion_group!(benches, read, positions, selections, properties);
+criterion_main!(benches);
@@ -0,0 +1,42 @@
+// Chemfiles, a modern library for chemistry file reading and writing
+// Copyright (C) 2015-2018 Guillaume Fraux -- BSD licensed
+
+//! Procedural macros for the [chemfiles](https://docs.rs/chemfiles) crate.
+//!
+//! This crate should not be used directly, but through the `macros` feature
+//! of chemfiles, which re-exports the macros.
+
+#![deny(missing_docs)]
+#![warn(clippy::all, clippy::pedantic)]
+#![allow(clippy::needless_return)]
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, LitStr};
+
+mod selection;
+
+/// Create a `chemfiles::Selection` from a string literal, checking the
+/// selection syntax at compile time.
+///
+/// The selection is checked when the macro is expanded, and a compile error
+/// pointing to the selection string is emitted if it is not valid. The check
+/// uses a Rust implementation of the selection grammar, and does not call the
+/// C++ library. The expanded code creates the selection at runtime with
+/// `Selection::new`, and panics if the C++ library still rejects it.
+///
+/// See the chemfiles documentation for examples.
+#[proc_macro]
+pub fn selection(input: TokenStream) -> TokenStream {
+    let literal = parse_macro_input!(input as LitStr);
+
+    if let Err(message) = selection::check(&literal.value()) {
+        let message = format!("invalid selection: {message}");
+        return syn::Error::new(literal.span(), message).to_compile_error().into();
+    }
+
+    let expanded = quote! {
+        ::chemfiles::Selection::new(#literal).expect("the selection was checked at compile time")
+    };
+    return expanded.into();
+}
@@ -0,0 +1,598 @@
+// Chemfiles, a modern library for chemistry file reading and writing
+// Copyright (C) 2015-2018 Guillaume Fraux -- BSD licensed
+
+//! Checking of the chemfiles selection language, following the grammar of the
+//! parser in the C++ library.
+
+/// Selection contexts, and the number of atoms they match together
+const CONTEXTS: &[(&str, usize)] = &[
+    ("atoms", 1),
+    ("one", 1),
+    ("pairs", 2),
+    ("two", 2),
+    ("three", 3),
+    ("four", 4),
+    ("bonds", 2),
+    ("angles", 3),
+    ("dihedrals", 4),
+];
+
+/// Selectors comparing a string property of the atoms
+const STRING_SELECTORS: &[&str] = &["name", "type", "resname"];
+
+/// Selectors giving a numeric property of the atoms
+const NUMERIC_SELECTORS: &[&str] = &["index", "resid", "mass", "x", "y", "z", "vx", "vy", "vz"];
+
+/// Boolean selectors checking the topology, and their number of arguments
+const BOOLEAN_SELECTORS: &[(&str, usize)] = &[
+    ("is_bonded", 2),
+    ("is_angle", 3),
+    ("is_dihedral", 4),
+    ("is_improper", 4),
+];
+
+/// Numeric functions of multiple atoms, and their number of arguments
+const NUMERIC_FUNCTIONS: &[(&str, usize)] = &[("distance", 2), ("angle", 3), ("dihedral", 4), ("out_of_plane", 4)];
+
+/// Mathematical functions of a single value
+const MATH_FUNCTIONS: &[&str] = &[
+    "sin", "cos", "tan", "asin", "acos", "sqrt", "exp", "log", "log2", "log10", "rad2deg", "deg2rad",
+];
+
+/// Reserved identifiers, which can not be used as string values
+const KEYWORDS: &[&str] = &["and", "or", "not", "all", "none"];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LeftParenthesis,
+    RightParenthesis,
+    Comma,
+    Colon,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Hat,
+    Percent,
+    Number(f64),
+    Ident(String),
+    String(String),
+    Variable(usize),
+    Property(String),
+}
+
+impl Token {
+    fn is_comparison(&self) -> bool {
+        return matches!(
+            self,
+            Token::Equal | Token::NotEqual | Token::Less | Token::LessEqual | Token::Greater | Token::GreaterEqual
+        );
+    }
+
+    fn describe(&self) -> String {
+        return match self {
+            Token::LeftParenthesis => "'('".into(),
+            Token::RightParenthesis => "')'".into(),
+            Token::Comma => "','".into(),
+            Token::Colon => "':'".into(),
+            Token::Equal => "'=='".into(),
+            Token::NotEqual => "'!='".into(),
+            Token::Less => "'<'".into(),
+            Token::LessEqual => "'<='".into(),
+            Token::Greater => "'>'".into(),
+            Token::GreaterEqual => "'>='".into(),
+            Token::Plus => "'+'".into(),
+            Token::Minus => "'-'".into(),
+            Token::Star => "'*'".into(),
+            Token::Slash => "'/'".into(),
+            Token::Hat => "'^'".into(),
+            Token::Percent => "'%'".into(),
+            Token::Number(value) => format!("number {value}"),
+            Token::Ident(ident) => format!("'{ident}'"),
+            Token::String(string) => format!("string \"{string}\""),
+            Token::Variable(variable) => format!("variable #{variable}"),
+            Token::Property(property) => format!("property [{property}]"),
+        };
+    }
+}
+
+/// Check that `selection` is a valid selection string, returning a
+/// description of the error otherwise
+pub fn check(selection: &str) -> Result<(), String> {
+    let mut tokens = tokenize(selection)?;
+
+    let mut size = 1;
+    if let [Token::Ident(context), Token::Colon, ..] = tokens.as_slice() {
+        size = match CONTEXTS.iter().find(|(name, _)| name == context) {
+            Some(&(_, size)) => size,
+            None => return Err(format!("unknown selection context '{context}'")),
+        };
+        tokens.drain(..2);
+    }
+
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+        size,
+    };
+    parser.or_expression()?;
+    if let Some(token) = parser.peek() {
+        return Err(format!(
+            "unexpected {} after the end of the selection",
+            token.describe()
+        ));
+    }
+    return Ok(());
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let chars = input.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        i += 1;
+
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::LeftParenthesis,
+            ')' => Token::RightParenthesis,
+            ',' => Token::Comma,
+            ':' => Token::Colon,
+            '+' => Token::Plus,
+            '-' => Token::Minus,
+            '*' => Token::Star,
+            '/' => Token::Slash,
+            '^' => Token::Hat,
+            '%' => Token::Percent,
+            '=' if next == Some('=') => {
+                i += 1;
+                Token::Equal
+            }
+            '!' if next == Some('=') => {
+                i += 1;
+                Token::NotEqual
+            }
+            '<' | '>' => {
+                let equal = next == Some('=');
+                if equal {
+                    i += 1;
+                }
+                match (c, equal) {
+                    ('<', false) => Token::Less,
+                    ('<', true) => Token::LessEqual,
+                    ('>', false) => Token::Greater,
+                    _ => Token::GreaterEqual,
+                }
+            }
+            '#' => {
+                let start = i;
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+                let digits = chars[start..i].iter().collect::<String>();
+                match digits.parse() {
+                    Ok(variable) => Token::Variable(variable),
+                    Err(_) => return Err("expected a number after '#'".into()),
+                }
+            }
+            '"' => match delimited(&chars, &mut i, '"') {
+                Some(string) => Token::String(string),
+                None => return Err("missing closing '\"' in string".into()),
+            },
+            '[' => match delimited(&chars, &mut i, ']') {
+                Some(name) if name.trim().is_empty() => return Err("empty property name".into()),
+                Some(name) => Token::Property(name),
+                None => return Err("missing closing ']' in property name".into()),
+            },
+            c if c.is_ascii_digit() || (c == '.' && next.map_or(false, |n| n.is_ascii_digit())) => {
+                let start = i - 1;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                    i += 1;
+                    if i < chars.len() && (chars[i] == '+' || chars[i] == '-') {
+                        i += 1;
+                    }
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+                let number = chars[start..i].iter().collect::<String>();
+                match number.parse() {
+                    Ok(value) => Token::Number(value),
+                    Err(_) => return Err(format!("invalid number '{number}'")),
+                }
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let start = i - 1;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                Token::Ident(chars[start..i].iter().collect())
+            }
+            c => return Err(format!("unexpected character '{c}'")),
+        };
+        tokens.push(token);
+    }
+    return Ok(tokens);
+}
+
+/// Read characters starting at `chars[*i]` until the `end` delimiter, and
+/// move `i` after the delimiter. Returns `None` if the delimiter is missing.
+fn delimited(chars: &[char], i: &mut usize, end: char) -> Option<String> {
+    let start = *i;
+    let length = chars[start..].iter().position(|&c| c == end)?;
+    *i = start + length + 1;
+    return Some(chars[start..start + length].iter().collect());
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    /// Number of atoms matched together, from the selection context
+    size: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        return self.tokens.get(self.position);
+    }
+
+    fn peek_ident(&self) -> Option<&str> {
+        return match self.peek() {
+            Some(Token::Ident(ident)) => Some(ident),
+            _ => None,
+        };
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        return token;
+    }
+
+    fn expect(&mut self, expected: &Token) -> Result<(), String> {
+        return match self.advance() {
+            Some(ref token) if token == expected => Ok(()),
+            Some(token) => Err(format!("expected {}, got {}", expected.describe(), token.describe())),
+            None => Err(format!(
+                "expected {}, got the end of the selection",
+                expected.describe()
+            )),
+        };
+    }
+
+    /// Run `function`, going back to the current position if it fails
+    fn attempt(&mut self, function: impl FnOnce(&mut Parser) -> Result<(), String>) -> Result<(), String> {
+        let position = self.position;
+        let result = function(self);
+        if result.is_err() {
+            self.position = position;
+        }
+        return result;
+    }
+
+    fn or_expression(&mut self) -> Result<(), String> {
+        self.and_expression()?;
+        while self.peek_ident() == Some("or") {
+            self.advance();
+            self.and_expression()?;
+        }
+        return Ok(());
+    }
+
+    fn and_expression(&mut self) -> Result<(), String> {
+        self.not_expression()?;
+        while self.peek_ident() == Some("and") {
+            self.advance();
+            self.not_expression()?;
+        }
+        return Ok(());
+    }
+
+    fn not_expression(&mut self) -> Result<(), String> {
+        if self.peek_ident() == Some("not") {
+            self.advance();
+            return self.not_expression();
+        }
+        return self.boolean();
+    }
+
+    fn boolean(&mut self) -> Result<(), String> {
+        match self.peek().cloned() {
+            None => return Err("unexpected end of the selection".into()),
+            Some(Token::LeftParenthesis) => {
+                // this can be a parenthesized boolean expression, or the start
+                // of a mathematical expression
+                let group = self.attempt(|parser| {
+                    parser.advance();
+                    parser.or_expression()?;
+                    return parser.expect(&Token::RightParenthesis);
+                });
+                return match group {
+                    Ok(()) => Ok(()),
+                    Err(error) => self.attempt(Parser::comparison).map_err(|_| error),
+                };
+            }
+            Some(Token::Property(_)) => {
+                let comparison = self.attempt(Parser::comparison);
+                if comparison.is_ok() {
+                    return comparison;
+                }
+                if self.attempt(Parser::string_property).is_ok() {
+                    return Ok(());
+                }
+                // boolean property
+                self.advance();
+                return self.optional_variable();
+            }
+            Some(Token::Ident(ident)) => {
+                if ident == "all" || ident == "none" {
+                    self.advance();
+                    return Ok(());
+                }
+
+                if STRING_SELECTORS.contains(&ident.as_str()) {
+                    return self.string_selector();
+                }
+
+                if let Some(&(_, count)) = BOOLEAN_SELECTORS.iter().find(|(name, _)| *name == ident) {
+                    self.advance();
+                    return self.variables(&ident, count);
+                }
+
+                if NUMERIC_SELECTORS.contains(&ident.as_str()) {
+                    // short form for `<selector> == <value>`
+                    let short = self.attempt(|parser| {
+                        parser.advance();
+                        parser.optional_variable()?;
+                        if !matches!(parser.peek(), Some(Token::Number(_))) {
+                            return Err(String::new());
+                        }
+                        while let Some(Token::Number(_)) = parser.peek() {
+                            parser.advance();
+                        }
+                        return Ok(());
+                    });
+                    if short.is_ok() {
+                        return Ok(());
+                    }
+                }
+
+                return self.comparison();
+            }
+            Some(_) => return self.comparison(),
+        }
+    }
+
+    /// `<selector>[(#n)] [== | !=] <value>...`
+    fn string_selector(&mut self) -> Result<(), String> {
+        let selector = self.advance().expect("missing selector").describe();
+        self.optional_variable()?;
+
+        let operator = matches!(self.peek(), Some(Token::Equal | Token::NotEqual));
+        if operator {
+            self.advance();
+        }
+
+        let mut count = 0;
+        while let Some(token) = self.peek() {
+            let is_value = match token {
+                Token::String(_) => true,
+                Token::Ident(ident) => !KEYWORDS.contains(&ident.as_str()),
+                _ => false,
+            };
+            if !is_value || (operator && count == 1) {
+                break;
+            }
+            self.advance();
+            count += 1;
+        }
+
+        if count == 0 {
+            return Err(format!("expected a value after {selector}"));
+        }
+        return Ok(());
+    }
+
+    /// `[property][(#n)] == | != <value>`
+    fn string_property(&mut self) -> Result<(), String> {
+        self.advance();
+        self.optional_variable()?;
+        match self.advance() {
+            Some(Token::Equal | Token::NotEqual) => {}
+            _ => return Err("expected '==' or '!='".into()),
+        }
+        return match self.advance() {
+            Some(Token::String(_)) => Ok(()),
+            Some(Token::Ident(ident)) if !KEYWORDS.contains(&ident.as_str()) => Ok(()),
+            _ => Err("expected a string value".into()),
+        };
+    }
+
+    /// `<math> <comparison operator> <math>`
+    fn comparison(&mut self) -> Result<(), String> {
+        self.math_sum()?;
+        match self.advance() {
+            Some(ref token) if token.is_comparison() => {}
+            Some(token) => return Err(format!("expected a comparison operator, got {}", token.describe())),
+            None => return Err("expected a comparison operator, got the end of the selection".into()),
+        }
+        return self.math_sum();
+    }
+
+    fn math_sum(&mut self) -> Result<(), String> {
+        self.math_product()?;
+        while matches!(self.peek(), Some(Token::Plus | Token::Minus)) {
+            self.advance();
+            self.math_product()?;
+        }
+        return Ok(());
+    }
+
+    fn math_product(&mut self) -> Result<(), String> {
+        self.math_power()?;
+        while matches!(self.peek(), Some(Token::Star | Token::Slash | Token::Percent)) {
+            self.advance();
+            self.math_power()?;
+        }
+        return Ok(());
+    }
+
+    fn math_power(&mut self) -> Result<(), String> {
+        self.math_unary()?;
+        if let Some(Token::Hat) = self.peek() {
+            self.advance();
+            return self.math_power();
+        }
+        return Ok(());
+    }
+
+    fn math_unary(&mut self) -> Result<(), String> {
+        if matches!(self.peek(), Some(Token::Plus | Token::Minus)) {
+            self.advance();
+            return self.math_unary();
+        }
+        return self.math_value();
+    }
+
+    fn math_value(&mut self) -> Result<(), String> {
+        match self.advance() {
+            Some(Token::Number(_)) => return Ok(()),
+            Some(Token::LeftParenthesis) => {
+                self.math_sum()?;
+                return self.expect(&Token::RightParenthesis);
+            }
+            Some(Token::Property(_)) => return self.optional_variable(),
+            Some(Token::Ident(ident)) => {
+                if NUMERIC_SELECTORS.contains(&ident.as_str()) {
+                    return self.optional_variable();
+                }
+                if let Some(&(_, count)) = NUMERIC_FUNCTIONS.iter().find(|(name, _)| *name == ident) {
+                    return self.variables(&ident, count);
+                }
+                if MATH_FUNCTIONS.contains(&ident.as_str()) {
+                    self.expect(&Token::LeftParenthesis)?;
+                    self.math_sum()?;
+                    return self.expect(&Token::RightParenthesis);
+                }
+                return Err(format!("unknown selector or function '{ident}'"));
+            }
+            Some(token) => return Err(format!("unexpected {}", token.describe())),
+            None => return Err("unexpected end of the selection".into()),
+        }
+    }
+
+    /// Optional `(#n)` after a selector
+    fn optional_variable(&mut self) -> Result<(), String> {
+        if let Some(Token::LeftParenthesis) = self.peek() {
+            if let Some(Token::Variable(_)) = self.tokens.get(self.position + 1) {
+                self.advance();
+                self.variable()?;
+                return self.expect(&Token::RightParenthesis);
+            }
+        }
+        return Ok(());
+    }
+
+    /// `(#a, #b, ...)` with `count` variables after the function `name`
+    fn variables(&mut self, name: &str, count: usize) -> Result<(), String> {
+        let arguments = |parser: &mut Parser| {
+            parser.expect(&Token::LeftParenthesis)?;
+            for i in 0..count {
+                if i != 0 {
+                    parser.expect(&Token::Comma)?;
+                }
+                parser.variable()?;
+            }
+            return parser.expect(&Token::RightParenthesis);
+        };
+        return arguments(self).map_err(|error| format!("{error} in {name}, which takes {count} arguments"));
+    }
+
+    fn variable(&mut self) -> Result<(), String> {
+        return match self.advance() {
+            Some(Token::Variable(variable)) if variable >= 1 && variable <= self.size => Ok(()),
+            Some(Token::Variable(variable)) => Err(format!(
+                "variable #{variable} is out of bounds for a selection of {} atoms",
+                self.size
+            )),
+            Some(token) => Err(format!("expected a variable, got {}", token.describe())),
+            None => Err("expected a variable, got the end of the selection".into()),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check;
+
+    #[test]
+    fn valid() {
+        let selections = [
+            "all",
+            "none",
+            "name H",
+            "name == H",
+            "name != \"C 1\"",
+            "name H O N",
+            "not name H and type O",
+            "resname ALA or (name CA and not index 3)",
+            "index 3",
+            "resid 4 5 6",
+            "x < 3.5e1",
+            "mass(#1) + 2 * 3 ^ 2 >= -1",
+            "sqrt(x^2 + y^2) < 4",
+            "(x + 1) < 3",
+            "((x + 1) < 3 and all)",
+            "[is_hetatm]",
+            "not [is_hetatm] and name CA",
+            "[charge] < 0",
+            "[chain] == A",
+            "pairs: name(#1) H and name(#2) O",
+            "two: distance(#1, #2) < 2",
+            "angles: name(#2) O and angle(#1, #2, #3) > deg2rad(100)",
+            "dihedrals: is_improper(#1, #2, #3, #4)",
+            "bonds: is_bonded(#1, #2) and type(#2) C",
+            "four: out_of_plane(#1, #2, #3, #4) < 0.1",
+        ];
+        for selection in selections {
+            assert_eq!(check(selection), Ok(()), "{selection}");
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        let selections = [
+            ("", "unexpected end of the selection"),
+            ("nmae CA", "unknown selector or function 'nmae'"),
+            ("name", "expected a value after 'name'"),
+            ("name H and", "unexpected end of the selection"),
+            ("name H or or", "unknown selector or function 'or'"),
+            ("foo: all", "unknown selection context 'foo'"),
+            ("name(#2) H", "variable #2 is out of bounds for a selection of 1 atoms"),
+            (
+                "pairs: is_bonded(#1)",
+                "expected ',', got ')' in is_bonded, which takes 2 arguments",
+            ),
+            ("x <", "unexpected end of the selection"),
+            ("x 3 <", "unexpected '<' after the end of the selection"),
+            ("(name H", "expected ')', got the end of the selection"),
+            ("name \"H", "missing closing '\"' in string"),
+            ("resid 1:100", "unexpected ':' after the end of the selection"),
+            ("index & 2", "unexpected character '&'"),
+        ];
+        for (selection, expected) in selections {
+            assert_eq!(check(selection), Err(expected.into()), "{selection}");
+        }
+    }
+}
@@ -11,6 +11,9 @@ fn main() {
 
 mod prebuilt;
 
+/// Version of the chemfiles C++ library in the `chemfiles` submodule
+const CHEMFILES_VERSION: &str = "0.10.4";
+
 fn build_chemfiles() -> PathBuf {
     let path = Path::new("chemfiles").join("CMakeLists.txt");
     if !path.exists() {
@@ -18,13 +21,65 @@ fn build_chemfiles() -> PathBuf {
     }
 
     let mut cmake = cmake::Config::new(".");
-    cmake.define("CHEMFILES_VERSION", "0.10.4");
+    cmake.define("CHEMFILES_VERSION", CHEMFILES_VERSION);
+
+    // use the system version of optional dependencies instead of building the
+    // copy bundled with chemfiles. The static chemfiles library does not
+    // contain these, so we need to link to them explicitly.
+    let system_libs = [
+        ("CHFL_SYSTEM_NETCDF", "netcdf", cfg!(feature = "system-netcdf")),
+        ("CHFL_SYSTEM_ZLIB", "z", cfg!(feature = "system-zlib")),
+        ("CHFL_SYSTEM_LZMA", "lzma", cfg!(feature = "system-lzma")),
+        ("CHFL_SYSTEM_BZIP2", "bz2", cfg!(feature = "system-bzip2")),
+    ];
+    let mut bundled_dependencies = true;
+    for (option, lib, enabled) in system_libs {
+        if enabled {
+            cmake.define(option, "ON");
+            println!("cargo:rustc-link-lib={}", lib);
+            bundled_dependencies = false;
+        }
+    }
+
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=prebuilt.rs");
+    println!("cargo:rerun-if-changed=CMakeLists.txt");
+    println!("cargo:rerun-if-changed=chemfiles");
+    println!("cargo:rerun-if-env-changed=CHEMFILES_BUILD_FROM_SOURCES");
+    println!("cargo:rerun-if-env-changed=CHEMFILES_PREBUILT_URL");
+    println!("cargo:rerun-if-env-changed=CHEMFILES_PREBUILT_SHA256");
+    println!("cargo:rerun-if-env-changed=CHEMFILES_PREBUILT_FETCHER");
+
+    // allow forcing a build from sources without changing the features, for
+    // example to audit the code used in the final binary
+    let from_sources = cfg!(feature = "build-from-sources") || env_flag("CHEMFILES_BUILD_FROM_SOURCES");
+    // pre-built libraries contain the bundled dependencies
+    let from_sources = from_sources || !bundled_dependencies;
 
     let target = std::env::var("TARGET").expect("cargo should set TARGET");
-    if !cfg!(feature = "build-from-sources") {
-        if let Some((target, sha1)) = prebuilt::get_prebuilt_info(&target) {
-            cmake.define("CHFL_RUST_PREBUILT_TARGET", target);
-            cmake.define("CHFL_RUST_PREBUILT_SHA256", sha1);
+    if !from_sources {
+        if let Some(mut prebuilt) = prebuilt::find(&target) {
+            // use a custom command to download the archive, for example to
+            // go through an authenticated proxy
+            let mut fetched = true;
+            if let Some(fetcher) = std::env::var("CHEMFILES_PREBUILT_FETCHER").ok().filter(|f| !f.is_empty()) {
+                let out_dir = PathBuf::from(std::env::var("OUT_DIR").expect("cargo should set OUT_DIR"));
+                match prebuilt::fetch(&prebuilt, &fetcher, &out_dir) {
+                    Ok(url) => prebuilt.url = Some(url),
+                    Err(message) => {
+                        println!("cargo:warning={}, building chemfiles from sources", message);
+                        fetched = false;
+                    }
+                }
+            }
+
+            if fetched {
+                cmake.define("CHFL_RUST_PREBUILT_TARGET", prebuilt.triple);
+                cmake.define("CHFL_RUST_PREBUILT_SHA256", prebuilt.sha256);
+                if let Some(url) = prebuilt.url {
+                    cmake.define("CHFL_RUST_PREBUILT_URL", url);
+                }
+            }
         }
     }
 
@@ -35,6 +90,15 @@ fn build_chemfiles() -> PathBuf {
     return out_dir;
 }
 
+/// Check if the environment variable `name` is set to a value other than
+/// `0`, `false` or the empty string
+fn env_flag(name: &str) -> bool {
+    match std::env::var(name) {
+        Ok(value) => !matches!(value.as_str(), "" | "0" | "false"),
+        Err(_) => false,
+    }
+}
+
 fn list_cxx_libs(build: &Path) {
     let mut dirs_file = File::open(build.join("cxx_link_dirs.cmake")).unwrap();
     let mut content = String::new();
@@ -1,6 +1,110 @@
+use std::env;
+use std::path::Path;
+use std::process::Command;
+
+use super::CHEMFILES_VERSION;
+
+/// Location of a pre-built chemfiles library to download
+pub struct Prebuilt {
+    /// julia triple used in the name of the archive
+    pub triple: &'static str,
+    /// expected SHA256 of the archive
+    pub sha256: String,
+    /// base URL to download the archive from, if different from the default
+    /// GitHub release. This can also be a `file://` URL.
+    pub url: Option<String>,
+}
+
+/// Download the pre-built archive with a custom `command`, instead of the
+/// download code in CMake. The command is split on whitespace, and called with
+/// the URL of the archive and the destination file as additional arguments.
+///
+/// On success, this returns a `file://` URL for the directory containing the
+/// archive, to be used instead of `prebuilt.url`. The SHA256 of the archive is
+/// still checked by CMake.
+pub fn fetch(prebuilt: &Prebuilt, command: &str, out_dir: &Path) -> Result<String, String> {
+    let name = format!("chemfiles-static.v{}.{}.tar.gz", CHEMFILES_VERSION, prebuilt.triple);
+    let url = match &prebuilt.url {
+        Some(url) => format!("{}/{}", url, name),
+        None => format!(
+            "https://github.com/chemfiles/chemfiles-prebuilt/releases/download/v{}/{}",
+            CHEMFILES_VERSION, name
+        ),
+    };
+
+    let directory = out_dir.join("prebuilt");
+    std::fs::create_dir_all(&directory).map_err(|e| format!("failed to create {}: {}", directory.display(), e))?;
+    let destination = directory.join(&name);
+
+    let mut words = command.split_whitespace();
+    let program = words.next().ok_or("CHEMFILES_PREBUILT_FETCHER is empty")?;
+    let status = Command::new(program)
+        .args(words)
+        .arg(&url)
+        .arg(&destination)
+        .status()
+        .map_err(|e| format!("failed to run '{}': {}", command, e))?;
+
+    if !status.success() || !destination.exists() {
+        return Err(format!("'{}' failed to download {}", command, url));
+    }
+
+    return Ok(file_url(&directory));
+}
+
+/// Get a `file://` URL for the given absolute `path`, using forward slashes
+/// and an additional slash before Windows drive letters (`file:///C:/...`).
+fn file_url(path: &Path) -> String {
+    let path = path.display().to_string().replace('\\', "/").replace('%', "%25").replace(' ', "%20");
+    let separator = if path.starts_with('/') { "" } else { "/" };
+    return format!("file://{}{}", separator, path);
+}
+
+/// Find the pre-built library to use for the given rust triple, taking into
+/// account the `CHEMFILES_PREBUILT_URL` and `CHEMFILES_PREBUILT_SHA256`
+/// environment variables.
+///
+/// `CHEMFILES_PREBUILT_URL` allows to fetch the archives from a mirror or a
+/// local directory, and `CHEMFILES_PREBUILT_SHA256` gives the expected hash of
+/// the archive, which is required for targets without a known hash below.
+pub fn find(target: &str) -> Option<Prebuilt> {
+    let url = env::var("CHEMFILES_PREBUILT_URL").ok().filter(|url| !url.is_empty());
+    let sha256 = env::var("CHEMFILES_PREBUILT_SHA256").ok().filter(|sha| !sha.is_empty());
+
+    if let Some((triple, known_sha256)) = get_prebuilt_info(target) {
+        return Some(Prebuilt {
+            triple,
+            sha256: sha256.unwrap_or_else(|| known_sha256.into()),
+            url,
+        });
+    }
+
+    // the hash is required for other targets, we don't download unverified
+    // archives
+    let triple = get_unverified_triple(target)?;
+    return Some(Prebuilt {
+        triple,
+        sha256: sha256?,
+        url,
+    });
+}
+
+/// Get the julia triple for targets where pre-built archives can be used, but
+/// for which we don't know the SHA256 of the archive yet.
+fn get_unverified_triple(target: &str) -> Option<&'static str> {
+    match target {
+        "riscv64gc-unknown-linux-gnu" => Some("riscv64-linux-gnu"),
+        "x86_64-unknown-freebsd" => Some("x86_64-unknown-freebsd"),
+        "aarch64-unknown-freebsd" => Some("aarch64-unknown-freebsd"),
+        "armv6-unknown-linux-gnueabihf" => Some("armv6l-linux-gnueabihf"),
+        "armv6-unknown-linux-musleabihf" => Some("armv6l-linux-musleabihf"),
+        _ => None,
+    }
+}
+
 /// Get the julia triple & sha256 corresponding to the prebuilt chemfiles v0.10.4
 /// for a given rust triple, if it exists
-pub fn get_prebuilt_info(target: &str) -> Option<(&'static str, &'static str)> {
+fn get_prebuilt_info(target: &str) -> Option<(&'static str, &'static str)> {
     match target {
         "aarch64-apple-darwin" => Some((
             "aarch64-apple-darwin",
@@ -13,12 +13,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     for _ in 0..input.nsteps() {
         input.read(&mut frame)?;
 
-        let mut to_remove = selection.list(&frame);
-        to_remove.sort_unstable();
-        to_remove.reverse();
-        for i in to_remove {
-            frame.remove(i);
-        }
+        frame.strip(&mut selection);
 
         output.write(&frame)?;
     }
@@ -0,0 +1,1923 @@
+// Chemfiles, a modern library for chemistry file reading and writing
+// Copyright (C) 2015-2018 Guillaume Fraux -- BSD licensed
+
+//! Analysis algorithms working directly on the data in frames.
+//!
+//! These functions are implemented in Rust on top of the positions of the
+//! frames, and are much faster than calling the corresponding `Frame` method
+//! for each pair or triplet of atoms.
+
+use crate::errors::{Error, Status};
+use crate::math::{self, Periodicity, Vector3D};
+use crate::transform::neighbors_list;
+use crate::{BondOrder, CellShape, Frame, Trajectory};
+
+#[cfg(feature = "voronoi")]
+pub use crate::voronoi::{voronoi, VoronoiCell};
+
+/// Compute the distances between all atoms in `selection_a` and all atoms in
+/// `selection_b`, using the minimal image convention with the frame unit cell.
+///
+/// The result is a flattened matrix in row-major order: the distance between
+/// `selection_a[i]` and `selection_b[j]` is stored at index
+/// `i * selection_b.len() + j`.
+///
+/// # Errors
+///
+/// This function fails if the unit cell of the frame is degenerate, *i.e.*
+/// if its matrix can not be inverted.
+///
+/// # Panics
+///
+/// If any index in `selection_a` or `selection_b` is out of bounds.
+///
+/// # Example
+/// ```
+/// # use chemfiles::{Frame, Atom, UnitCell};
+/// # use chemfiles::analysis::distance_matrix;
+/// let mut frame = Frame::new();
+/// frame.set_cell(&UnitCell::new([10.0, 10.0, 10.0]));
+/// frame.add_atom(&Atom::new("O"), [0.0, 0.0, 0.0], None);
+/// frame.add_atom(&Atom::new("O"), [1.0, 0.0, 0.0], None);
+/// frame.add_atom(&Atom::new("O"), [9.0, 0.0, 0.0], None);
+///
+/// let distances = distance_matrix(&frame, &[0], &[1, 2]).unwrap();
+/// assert_eq!(distances, vec![1.0, 1.0]);
+/// ```
+pub fn distance_matrix(frame: &Frame, selection_a: &[usize], selection_b: &[usize]) -> Result<Vec<f64>, Error> {
+    let periodicity = Periodicity::new(&frame.cell())?;
+    let positions = frame.positions();
+
+    let mut distances = Vec::with_capacity(selection_a.len() * selection_b.len());
+    for &i in selection_a {
+        for &j in selection_b {
+            distances.push(periodicity.distance(positions[i], positions[j]));
+        }
+    }
+    return Ok(distances);
+}
+
+/// A contact between two atoms, as found by [`contacts`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Contact {
+    /// Index of the first atom, taken from the first selection
+    pub first: usize,
+    /// Index of the second atom, taken from the second selection
+    pub second: usize,
+    /// Distance between the two atoms
+    pub distance: f64,
+}
+
+/// Find all the contacts between atoms in `selection_a` and atoms in
+/// `selection_b`, *i.e.* all the pairs of atoms closer than `cutoff`, using the
+/// minimal image convention with the frame unit cell.
+///
+/// Pairs containing the same atom twice are ignored. If the two selections
+/// overlap, pairs of atoms present in both selections are reported twice, once
+/// in each order.
+///
+/// # Errors
+///
+/// This function fails if the unit cell of the frame is degenerate, *i.e.*
+/// if its matrix can not be inverted.
+///
+/// # Panics
+///
+/// If any index in `selection_a` or `selection_b` is out of bounds.
+///
+/// # Example
+/// ```
+/// # use chemfiles::{Frame, Atom};
+/// # use chemfiles::analysis::contacts;
+/// let mut frame = Frame::new();
+/// frame.add_atom(&Atom::new("C"), [0.0, 0.0, 0.0], None);
+/// frame.add_atom(&Atom::new("O"), [3.0, 0.0, 0.0], None);
+/// frame.add_atom(&Atom::new("O"), [6.0, 0.0, 0.0], None);
+///
+/// let contacts = contacts(&frame, &[0], &[1, 2], 4.0).unwrap();
+/// assert_eq!(contacts.len(), 1);
+/// assert_eq!(contacts[0].second, 1);
+/// assert_eq!(contacts[0].distance, 3.0);
+/// ```
+pub fn contacts(
+    frame: &Frame,
+    selection_a: &[usize],
+    selection_b: &[usize],
+    cutoff: f64,
+) -> Result<Vec<Contact>, Error> {
+    let periodicity = Periodicity::new(&frame.cell())?;
+    let positions = frame.positions();
+
+    let mut contacts = Vec::new();
+    for &first in selection_a {
+        for &second in selection_b {
+            if first == second {
+                continue;
+            }
+
+            let distance = periodicity.distance(positions[first], positions[second]);
+            if distance < cutoff {
+                contacts.push(Contact { first, second, distance });
+            }
+        }
+    }
+    return Ok(contacts);
+}
+
+/// Geometric criteria used to detect hydrogen bonds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HydrogenBondCriteria {
+    /// Maximal distance between the donor and the acceptor, in Angstroms.
+    /// The default is 3.5 Å.
+    pub distance: f64,
+    /// Minimal value of the donor-hydrogen-acceptor angle, in degrees. The
+    /// default is 150°.
+    pub angle: f64,
+}
+
+impl Default for HydrogenBondCriteria {
+    fn default() -> HydrogenBondCriteria {
+        HydrogenBondCriteria {
+            distance: 3.5,
+            angle: 150.0,
+        }
+    }
+}
+
+/// A hydrogen bond, as found by [`hydrogen_bonds`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HydrogenBond {
+    /// Index of the donor atom
+    pub donor: usize,
+    /// Index of the hydrogen atom, bonded to the donor
+    pub hydrogen: usize,
+    /// Index of the acceptor atom
+    pub acceptor: usize,
+    /// Distance between the donor and the acceptor, in Angstroms
+    pub distance: f64,
+    /// Donor-hydrogen-acceptor angle, in degrees
+    pub angle: f64,
+}
+
+/// Find hydrogen bonds between the `donors` and `acceptors` atoms, using
+/// purely geometric `criteria`.
+///
+/// The hydrogen atoms are taken from the atoms bonded to each donor in the
+/// frame topology, so the frame must contain bonds (see
+/// [`Frame::guess_bonds`]). An atom is considered to be a hydrogen if its
+/// atomic number is 1.
+///
+/// # Errors
+///
+/// This function fails if the unit cell of the frame is degenerate, *i.e.*
+/// if its matrix can not be inverted.
+///
+/// # Panics
+///
+/// If any index in `donors` or `acceptors` is out of bounds.
+///
+/// # Example
+/// ```
+/// # use chemfiles::{Frame, Atom};
+/// # use chemfiles::analysis::{hydrogen_bonds, HydrogenBondCriteria};
+/// let mut frame = Frame::new();
+/// frame.add_atom(&Atom::new("O"), [0.0, 0.0, 0.0], None);
+/// frame.add_atom(&Atom::new("H"), [1.0, 0.0, 0.0], None);
+/// frame.add_atom(&Atom::new("O"), [2.8, 0.1, 0.0], None);
+/// frame.add_bond(0, 1);
+///
+/// let criteria = HydrogenBondCriteria::default();
+/// let bonds = hydrogen_bonds(&frame, &[0], &[2], &criteria).unwrap();
+/// assert_eq!(bonds.len(), 1);
+/// assert_eq!(bonds[0].hydrogen, 1);
+/// ```
+pub fn hydrogen_bonds(
+    frame: &Frame,
+    donors: &[usize],
+    acceptors: &[usize],
+    criteria: &HydrogenBondCriteria,
+) -> Result<Vec<HydrogenBond>, Error> {
+    let periodicity = Periodicity::new(&frame.cell())?;
+    let positions = frame.positions();
+    let neighbors = neighbors_list(frame);
+
+    let mut bonds = Vec::new();
+    for &donor in donors {
+        let hydrogens = neighbors[donor]
+            .iter()
+            .copied()
+            .filter(|&i| frame.atom(i).atomic_number() == 1)
+            .collect::<Vec<_>>();
+        if hydrogens.is_empty() {
+            continue;
+        }
+
+        for &acceptor in acceptors {
+            if acceptor == donor {
+                continue;
+            }
+
+            let distance = periodicity.distance(positions[donor], positions[acceptor]);
+            if distance > criteria.distance {
+                continue;
+            }
+
+            for &hydrogen in &hydrogens {
+                let h_donor = periodicity.distance_vector(positions[hydrogen], positions[donor]);
+                let h_acceptor = periodicity.distance_vector(positions[hydrogen], positions[acceptor]);
+                let cos = math::dot(h_donor, h_acceptor) / (math::norm(h_donor) * math::norm(h_acceptor));
+                let angle = cos.clamp(-1.0, 1.0).acos().to_degrees();
+                if angle >= criteria.angle {
+                    bonds.push(HydrogenBond {
+                        donor,
+                        hydrogen,
+                        acceptor,
+                        distance,
+                        angle,
+                    });
+                }
+            }
+        }
+    }
+    return Ok(bonds);
+}
+
+/// A rule describing special bonds between residues, such as disulfide
+/// bridges, used by [`find_special_bonds`] and [`add_special_bonds`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpecialBondRule {
+    /// Name of the first atom in the bond
+    pub first: String,
+    /// Name of the second atom in the bond
+    pub second: String,
+    /// Maximal distance between the two atoms, in Angstroms
+    pub cutoff: f64,
+    /// Order of the bonds added to the topology
+    pub order: BondOrder,
+}
+
+impl SpecialBondRule {
+    /// Rule for disulfide bridges between the `SG` atoms of two cysteines,
+    /// with a cutoff of 2.5 Å.
+    pub fn disulfide() -> SpecialBondRule {
+        SpecialBondRule {
+            first: "SG".into(),
+            second: "SG".into(),
+            cutoff: 2.5,
+            order: BondOrder::Single,
+        }
+    }
+}
+
+/// Find the special bonds described by `rule` in the `frame`, *i.e.* the
+/// pairs of atoms with the names given in the rule, in different residues,
+/// and closer than the rule cutoff. Distances use the minimal image
+/// convention with the frame unit cell.
+///
+/// Each atom is part of at most one special bond: when an atom is close to
+/// multiple candidates, the closest pair is used. The bonds are returned
+/// sorted by distance, with `first` matching `rule.first` and `second`
+/// matching `rule.second`.
+///
+/// # Errors
+///
+/// This function fails if the unit cell of the frame is degenerate, *i.e.*
+/// if its matrix can not be inverted.
+///
+/// # Example
+/// ```
+/// # use chemfiles::{Frame, Atom};
+/// # use chemfiles::analysis::{find_special_bonds, SpecialBondRule};
+/// let mut frame = Frame::new();
+/// frame.add_atom(&Atom::new("C"), [0.0, 0.0, 0.0], None);
+/// frame.add_atom(&Atom::new("NZ"), [5.0, 0.0, 0.0], None);
+/// frame.add_atom(&Atom::new("C1"), [6.4, 0.0, 0.0], None);
+///
+/// // an isopeptide bond between a lysine and a ligand
+/// let rule = SpecialBondRule {
+///     first: "NZ".into(),
+///     second: "C1".into(),
+///     cutoff: 1.8,
+///     order: chemfiles::BondOrder::Single,
+/// };
+/// let bonds = find_special_bonds(&frame, &rule).unwrap();
+/// assert_eq!(bonds.len(), 1);
+/// assert_eq!((bonds[0].first, bonds[0].second), (1, 2));
+/// ```
+pub fn find_special_bonds(frame: &Frame, rule: &SpecialBondRule) -> Result<Vec<Contact>, Error> {
+    let topology = frame.topology();
+    let names = (0..frame.size()).map(|i| frame.atom(i).name()).collect::<Vec<_>>();
+    let firsts = (0..frame.size()).filter(|&i| names[i] == rule.first).collect::<Vec<_>>();
+    let seconds = (0..frame.size()).filter(|&i| names[i] == rule.second).collect::<Vec<_>>();
+
+    let mut candidates = contacts(frame, &firsts, &seconds, rule.cutoff)?
+        .into_iter()
+        .filter(|contact| {
+            let first = topology.residue_index_for_atom(contact.first);
+            let second = topology.residue_index_for_atom(contact.second);
+            first.is_none() || first != second
+        })
+        .collect::<Vec<_>>();
+    candidates.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+
+    let mut used = vec![false; frame.size()];
+    let mut bonds = Vec::new();
+    for contact in candidates {
+        if used[contact.first] || used[contact.second] {
+            continue;
+        }
+        used[contact.first] = true;
+        used[contact.second] = true;
+        bonds.push(contact);
+    }
+    return Ok(bonds);
+}
+
+/// Find disulfide bridges in the `frame`, using [`SpecialBondRule::disulfide`].
+///
+/// PDB files often do not contain the corresponding `SSBOND` records, and
+/// this allows to find them from the positions of the atoms. Use
+/// [`add_special_bonds`] to also add the bonds to the frame topology.
+///
+/// # Errors
+///
+/// This function fails if the unit cell of the frame is degenerate, *i.e.*
+/// if its matrix can not be inverted.
+///
+/// # Example
+/// ```
+/// # use chemfiles::{Frame, Atom, Residue};
+/// # use chemfiles::analysis::find_disulfides;
+/// let mut frame = Frame::new();
+/// frame.add_atom(&Atom::new("SG"), [0.0, 0.0, 0.0], None);
+/// frame.add_atom(&Atom::new("SG"), [2.05, 0.0, 0.0], None);
+/// frame.add_atom(&Atom::new("SG"), [12.0, 0.0, 0.0], None);
+/// for (i, id) in [(0, 22), (1, 95), (2, 130)] {
+///     let mut residue = Residue::with_id("CYS", id);
+///     residue.add_atom(i);
+///     frame.add_residue(&residue).unwrap();
+/// }
+///
+/// let disulfides = find_disulfides(&frame).unwrap();
+/// assert_eq!(disulfides.len(), 1);
+/// assert_eq!((disulfides[0].first, disulfides[0].second), (0, 1));
+/// ```
+pub fn find_disulfides(frame: &Frame) -> Result<Vec<Contact>, Error> {
+    return find_special_bonds(frame, &SpecialBondRule::disulfide());
+}
+
+/// Find the special bonds described by `rule` in the `frame` with
+/// [`find_special_bonds`], and add them to the frame topology with the rule
+/// bond order. The bonds found are returned.
+///
+/// # Errors
+///
+/// This function fails if the unit cell of the frame is degenerate, *i.e.*
+/// if its matrix can not be inverted.
+///
+/// # Example
+/// ```
+/// # use chemfiles::{Frame, Atom, BondOrder};
+/// # use chemfiles::analysis::{add_special_bonds, SpecialBondRule};
+/// let mut frame = Frame::new();
+/// frame.add_atom(&Atom::new("SG"), [0.0, 0.0, 0.0], None);
+/// frame.add_atom(&Atom::new("SG"), [2.05, 0.0, 0.0], None);
+///
+/// let bonds = add_special_bonds(&mut frame, &SpecialBondRule::disulfide()).unwrap();
+/// assert_eq!(bonds.len(), 1);
+/// assert_eq!(frame.topology().bonds(), vec![[0, 1]]);
+/// assert_eq!(frame.topology().bond_order(0, 1), BondOrder::Single);
+/// ```
+pub fn add_special_bonds(frame: &mut Frame, rule: &SpecialBondRule) -> Result<Vec<Contact>, Error> {
+    let bonds = find_special_bonds(frame, rule)?;
+    for bond in &bonds {
+        frame.add_bond_with_order(bond.first, bond.second, rule.order);
+    }
+    return Ok(bonds);
+}
+
+/// A one-dimensional histogram, with regularly spaced bins and weighted
+/// values.
+///
+/// # Example
+/// ```
+/// # use chemfiles::analysis::Histogram;
+/// let mut histogram = Histogram::new(0.0, 10.0, 5);
+/// histogram.add(1.0, 1.0);
+/// histogram.add(1.5, 2.0);
+/// histogram.add(9.0, 1.0);
+/// // values outside of the histogram range are ignored
+/// histogram.add(11.0, 1.0);
+///
+/// assert_eq!(histogram.counts(), &[3.0, 0.0, 0.0, 0.0, 1.0]);
+/// assert_eq!(histogram.bin_centers(), vec![1.0, 3.0, 5.0, 7.0, 9.0]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    min: f64,
+    max: f64,
+    counts: Vec<f64>,
+}
+
+impl Histogram {
+    /// Create a new empty histogram with `nbins` bins regularly spaced between
+    /// `min` and `max`.
+    ///
+    /// # Panics
+    ///
+    /// If `nbins` is zero, or if `max` is not larger than `min`.
+    pub fn new(min: f64, max: f64, nbins: usize) -> Histogram {
+        assert!(nbins > 0, "a histogram needs at least one bin");
+        assert!(max > min, "the histogram maximal value must be larger than the minimal value");
+        Histogram {
+            min,
+            max,
+            counts: vec![0.0; nbins],
+        }
+    }
+
+    /// Add a `value` with the given `weight` to this histogram. Values outside
+    /// of the histogram range are ignored.
+    pub fn add(&mut self, value: f64, weight: f64) {
+        if let Some(bin) = bin_index(value, self.min, self.max, self.counts.len()) {
+            self.counts[bin] += weight;
+        }
+    }
+
+    /// Get the width of the bins in this histogram
+    #[allow(clippy::cast_precision_loss)]
+    pub fn bin_width(&self) -> f64 {
+        return (self.max - self.min) / self.counts.len() as f64;
+    }
+
+    /// Get the position of the center of each bin in this histogram
+    #[allow(clippy::cast_precision_loss)]
+    pub fn bin_centers(&self) -> Vec<f64> {
+        let width = self.bin_width();
+        return (0..self.counts.len()).map(|i| self.min + (i as f64 + 0.5) * width).collect();
+    }
+
+    /// Get the accumulated weights in each bin of this histogram
+    pub fn counts(&self) -> &[f64] {
+        &self.counts
+    }
+
+    /// Get mutable access to the accumulated weights in each bin of this
+    /// histogram, for example to normalize it.
+    pub fn counts_mut(&mut self) -> &mut [f64] {
+        &mut self.counts
+    }
+}
+
+/// A two-dimensional histogram, with regularly spaced bins and weighted
+/// values.
+///
+/// # Example
+/// ```
+/// # use chemfiles::analysis::Histogram2D;
+/// let mut histogram = Histogram2D::new([0.0, 0.0], [10.0, 1.0], [5, 2]);
+/// histogram.add([1.0, 0.7], 1.0);
+/// histogram.add([9.0, 0.2], 0.5);
+///
+/// assert_eq!(histogram.get(0, 1), 1.0);
+/// assert_eq!(histogram.get(4, 0), 0.5);
+/// assert_eq!(histogram.get(1, 1), 0.0);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram2D {
+    min: [f64; 2],
+    max: [f64; 2],
+    nbins: [usize; 2],
+    counts: Vec<f64>,
+}
+
+impl Histogram2D {
+    /// Create a new empty histogram with `nbins[0]` bins regularly spaced
+    /// between `min[0]` and `max[0]` in the first dimension, and `nbins[1]`
+    /// bins regularly spaced between `min[1]` and `max[1]` in the second
+    /// dimension.
+    ///
+    /// # Panics
+    ///
+    /// If any of `nbins` is zero, or if `max` is not larger than `min`.
+    pub fn new(min: [f64; 2], max: [f64; 2], nbins: [usize; 2]) -> Histogram2D {
+        assert!(nbins[0] > 0 && nbins[1] > 0, "a histogram needs at least one bin");
+        assert!(
+            max[0] > min[0] && max[1] > min[1],
+            "the histogram maximal value must be larger than the minimal value"
+        );
+        Histogram2D {
+            min,
+            max,
+            nbins,
+            counts: vec![0.0; nbins[0] * nbins[1]],
+        }
+    }
+
+    /// Add a `value` with the given `weight` to this histogram. Values outside
+    /// of the histogram range are ignored.
+    pub fn add(&mut self, value: [f64; 2], weight: f64) {
+        let i = bin_index(value[0], self.min[0], self.max[0], self.nbins[0]);
+        let j = bin_index(value[1], self.min[1], self.max[1], self.nbins[1]);
+        if let (Some(i), Some(j)) = (i, j) {
+            self.counts[i * self.nbins[1] + j] += weight;
+        }
+    }
+
+    /// Get the number of bins in each dimension
+    pub fn nbins(&self) -> [usize; 2] {
+        self.nbins
+    }
+
+    /// Get the accumulated weight in the bin `i` of the first dimension and
+    /// bin `j` of the second dimension.
+    ///
+    /// # Panics
+    ///
+    /// If `i` or `j` are out of bounds.
+    pub fn get(&self, i: usize, j: usize) -> f64 {
+        assert!(i < self.nbins[0] && j < self.nbins[1], "out of bounds index in histogram");
+        return self.counts[i * self.nbins[1] + j];
+    }
+
+    /// Get the accumulated weights in this histogram, as a flattened matrix in
+    /// row-major order.
+    pub fn counts(&self) -> &[f64] {
+        &self.counts
+    }
+}
+
+/// Get the index of the bin containing `value` in a histogram with `nbins`
+/// between `min` and `max`.
+#[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn bin_index(value: f64, min: f64, max: f64, nbins: usize) -> Option<usize> {
+    if !(value >= min && value < max) {
+        return None;
+    }
+    let bin = ((value - min) / (max - min) * nbins as f64) as usize;
+    return Some(bin.min(nbins - 1));
+}
+
+/// Axis of the unit cell, used to compute density profiles
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    /// The first cell vector, `a`
+    A,
+    /// The second cell vector, `b`
+    B,
+    /// The third cell vector, `c`
+    C,
+}
+
+impl Axis {
+    fn index(self) -> usize {
+        match self {
+            Axis::A => 0,
+            Axis::B => 1,
+            Axis::C => 2,
+        }
+    }
+}
+
+/// Number density profile, as computed by [`density_profile`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DensityProfile {
+    /// Position of the center of each bin along the axis, in Angstroms, using
+    /// the average cell size
+    pub positions: Vec<f64>,
+    /// Average number density in each bin, in atoms per cubic Angstrom
+    pub densities: Vec<f64>,
+}
+
+/// Compute the number density profile of the atoms in `selection` along one
+/// `axis` of the unit cell, averaged over all the `frames`.
+///
+/// The cell is split into `nbins` slabs parallel to the faces of the cell
+/// which are not crossed by `axis`, and the number of atoms in each slab is
+/// divided by the slab volume. For orthorhombic cells and `Axis::C`, this gives
+/// the usual density profile along z.
+///
+/// # Errors
+///
+/// This function fails if there are no frames, or if any frame has an
+/// infinite or degenerate unit cell.
+///
+/// # Panics
+///
+/// If `nbins` is zero, or if any index in `selection` is out of bounds.
+///
+/// # Example
+/// ```
+/// # use chemfiles::{Frame, Atom, UnitCell};
+/// # use chemfiles::analysis::{density_profile, Axis};
+/// let mut frame = Frame::new();
+/// frame.set_cell(&UnitCell::new([10.0, 10.0, 10.0]));
+/// frame.add_atom(&Atom::new("Ar"), [5.0, 5.0, 1.0], None);
+/// frame.add_atom(&Atom::new("Ar"), [5.0, 5.0, 2.0], None);
+/// frame.add_atom(&Atom::new("Ar"), [5.0, 5.0, 7.0], None);
+///
+/// let profile = density_profile(&[frame], &[0, 1, 2], Axis::C, 2).unwrap();
+/// assert_eq!(profile.positions, vec![2.5, 7.5]);
+/// assert_eq!(profile.densities, vec![2.0 / 500.0, 1.0 / 500.0]);
+/// ```
+pub fn density_profile<'a>(
+    frames: impl IntoIterator<Item = &'a Frame>,
+    selection: &[usize],
+    axis: Axis,
+    nbins: usize,
+) -> Result<DensityProfile, Error> {
+    let axis = axis.index();
+    let mut histogram = Histogram::new(0.0, 1.0, nbins);
+    let mut height = 0.0;
+    let mut nframes = 0;
+
+    for frame in frames {
+        let periodicity = Periodicity::new(&frame.cell())?;
+        if periodicity.is_infinite() {
+            return Err(Error {
+                status: Status::InvalidArgument,
+                message: "can not compute a density profile with an infinite unit cell".into(),
+            });
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        let weight = nbins as f64 / periodicity.volume();
+        let positions = frame.positions();
+        for &i in selection {
+            let fractional = periodicity.fractional(positions[i])[axis];
+            histogram.add(fractional - fractional.floor(), weight);
+        }
+
+        height += periodicity.height(axis);
+        nframes += 1;
+    }
+
+    if nframes == 0 {
+        return Err(Error {
+            status: Status::InvalidArgument,
+            message: "can not compute a density profile without any frame".into(),
+        });
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    let nframes = f64::from(nframes);
+    let height = height / nframes;
+    return Ok(DensityProfile {
+        positions: histogram.bin_centers().iter().map(|x| x * height).collect(),
+        densities: histogram.counts().iter().map(|count| count / nframes).collect(),
+    });
+}
+
+/// Covariance of the atomic positions over multiple frames, as computed by
+/// [`covariance_matrix`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Covariance {
+    /// Average position of each selected atom
+    pub mean: Vec<[f64; 3]>,
+    /// Covariance matrix of the positions, flattened in row-major order. For
+    /// `n` atoms, this is a `3n x 3n` matrix where the covariance between
+    /// coordinate `a` of atom `i` and coordinate `b` of atom `j` is stored at
+    /// index `(3 * i + a) * 3n + 3 * j + b`.
+    pub matrix: Vec<f64>,
+}
+
+impl Covariance {
+    /// Get the number of rows and columns in the covariance matrix, i.e.
+    /// three times the number of atoms.
+    pub fn size(&self) -> usize {
+        return 3 * self.mean.len();
+    }
+
+    /// Get the covariance between the coordinates `i` and `j`, using the
+    /// same indexes as in [`Covariance::matrix`].
+    ///
+    /// # Panics
+    ///
+    /// If `i` or `j` are out of bounds.
+    pub fn get(&self, i: usize, j: usize) -> f64 {
+        let size = self.size();
+        assert!(i < size && j < size, "out of bounds index in covariance matrix");
+        return self.matrix[i * size + j];
+    }
+}
+
+/// Compute the covariance matrix of the positions of the atoms in `selection`
+/// over all the `frames`, for example to run a principal component analysis
+/// (see [`Covariance::principal_components`]).
+///
+/// The positions are used as-is: the frames should already be aligned on a
+/// reference structure (for example with
+/// [`AlignToReference`](crate::transform::AlignToReference)), and molecules
+/// should not be split by the periodic boundary conditions. The matrix is
+/// accumulated in a single pass over the frames, using the population
+/// covariance (i.e. divided by the number of frames).
+///
+/// # Errors
+///
+/// This function fails if there are no frames, or if `selection` is empty.
+///
+/// # Panics
+///
+/// If any index in `selection` is out of bounds.
+///
+/// # Example
+/// ```
+/// # use chemfiles::{Frame, Atom};
+/// # use chemfiles::analysis::covariance_matrix;
+/// let mut first = Frame::new();
+/// first.add_atom(&Atom::new("Ar"), [0.0, 0.0, 0.0], None);
+/// let mut second = Frame::new();
+/// second.add_atom(&Atom::new("Ar"), [2.0, 0.0, 0.0], None);
+///
+/// let covariance = covariance_matrix(&[first, second], &[0]).unwrap();
+/// assert_eq!(covariance.mean, vec![[1.0, 0.0, 0.0]]);
+/// assert_eq!(covariance.get(0, 0), 1.0);
+/// assert_eq!(covariance.get(1, 1), 0.0);
+/// ```
+pub fn covariance_matrix<'a>(
+    frames: impl IntoIterator<Item = &'a Frame>,
+    selection: &[usize],
+) -> Result<Covariance, Error> {
+    if selection.is_empty() {
+        return Err(Error {
+            status: Status::InvalidArgument,
+            message: "can not compute a covariance matrix with an empty selection".into(),
+        });
+    }
+
+    let size = 3 * selection.len();
+    let mut mean = vec![0.0; size];
+    let mut matrix = vec![0.0; size * size];
+    let mut delta = vec![0.0; size];
+    let mut nframes = 0;
+
+    // Welford's online algorithm, extended to covariance matrices
+    for frame in frames {
+        nframes += 1;
+        let weight = 1.0 / f64::from(nframes);
+
+        let positions = frame.positions();
+        for (k, &i) in selection.iter().enumerate() {
+            for a in 0..3 {
+                delta[3 * k + a] = positions[i][a] - mean[3 * k + a];
+                mean[3 * k + a] += weight * delta[3 * k + a];
+            }
+        }
+
+        for (k, &i) in selection.iter().enumerate() {
+            for a in 0..3 {
+                let updated = positions[i][a] - mean[3 * k + a];
+                let row = &mut matrix[(3 * k + a) * size..(3 * k + a + 1) * size];
+                for (value, delta) in row.iter_mut().zip(&delta) {
+                    *value += delta * updated;
+                }
+            }
+        }
+    }
+
+    if nframes == 0 {
+        return Err(Error {
+            status: Status::InvalidArgument,
+            message: "can not compute a covariance matrix without any frame".into(),
+        });
+    }
+
+    let nframes = f64::from(nframes);
+    for value in &mut matrix {
+        *value /= nframes;
+    }
+
+    return Ok(Covariance {
+        mean: mean.chunks_exact(3).map(|x| [x[0], x[1], x[2]]).collect(),
+        matrix,
+    });
+}
+
+/// Principal components of the atomic motions, as computed by
+/// [`Covariance::principal_components`]
+#[cfg(feature = "nalgebra")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrincipalComponents {
+    /// Average position of each selected atom
+    pub mean: Vec<[f64; 3]>,
+    /// Variance of the positions along each component, in decreasing order
+    pub variances: Vec<f64>,
+    /// Normalized principal components, as one displacement vector for each
+    /// selected atom. `components[i]` corresponds to `variances[i]`.
+    pub components: Vec<Vec<[f64; 3]>>,
+}
+
+#[cfg(feature = "nalgebra")]
+impl Covariance {
+    /// Compute the first `count` principal components of this covariance
+    /// matrix, sorted by decreasing variance. This uses the symmetric
+    /// eigenvalues decomposition from `nalgebra`, and is only available with
+    /// the `nalgebra` feature.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use chemfiles::Frame;
+    /// # use chemfiles::analysis::covariance_matrix;
+    /// # let frames: Vec<Frame> = Vec::new();
+    /// # let selection = [0, 1, 2];
+    /// let covariance = covariance_matrix(&frames, &selection).unwrap();
+    /// let pca = covariance.principal_components(2);
+    ///
+    /// println!("first component variance: {}", pca.variances[0]);
+    /// for frame in &frames {
+    ///     let projection = pca.project(frame, &selection);
+    ///     println!("{} {}", projection[0], projection[1]);
+    /// }
+    /// ```
+    pub fn principal_components(&self, count: usize) -> PrincipalComponents {
+        let size = self.size();
+        let matrix = nalgebra::DMatrix::from_row_slice(size, size, &self.matrix);
+        let eigen = matrix.symmetric_eigen();
+
+        let mut order = (0..size).collect::<Vec<_>>();
+        order.sort_by(|&i, &j| eigen.eigenvalues[j].total_cmp(&eigen.eigenvalues[i]));
+        order.truncate(count);
+
+        let variances = order.iter().map(|&i| eigen.eigenvalues[i]).collect();
+        let components = order
+            .iter()
+            .map(|&i| {
+                let vector = eigen.eigenvectors.column(i);
+                (0..self.mean.len())
+                    .map(|atom| [vector[3 * atom], vector[3 * atom + 1], vector[3 * atom + 2]])
+                    .collect()
+            })
+            .collect();
+
+        return PrincipalComponents {
+            mean: self.mean.clone(),
+            variances,
+            components,
+        };
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl PrincipalComponents {
+    /// Project the positions of the atoms in `selection` from `frame` on all
+    /// the principal components, after removing the mean positions. The
+    /// `selection` should contain the same atoms, in the same order, as the
+    /// one used to compute the covariance matrix.
+    ///
+    /// # Panics
+    ///
+    /// If `selection` does not contain the same number of atoms as the
+    /// principal components, or if any index in `selection` is out of bounds.
+    pub fn project(&self, frame: &Frame, selection: &[usize]) -> Vec<f64> {
+        assert_eq!(
+            selection.len(),
+            self.mean.len(),
+            "the selection size does not match the principal components"
+        );
+
+        let positions = frame.positions();
+        let displacements = selection
+            .iter()
+            .zip(&self.mean)
+            .map(|(&i, &mean)| math::sub(positions[i], mean))
+            .collect::<Vec<_>>();
+
+        return self
+            .components
+            .iter()
+            .map(|component| {
+                component
+                    .iter()
+                    .zip(&displacements)
+                    .map(|(&vector, &displacement)| math::dot(vector, displacement))
+                    .sum()
+            })
+            .collect();
+    }
+}
+
+/// Compute the velocity autocorrelation function (VACF) of the atoms in
+/// `selection`, using the velocities in all the `frames`.
+///
+/// The returned vector contains the value of the VACF for each lag between
+/// frames, averaged over all the atoms in the selection and all the time
+/// origins: `vacf[lag]` is the average of `v_i(t) · v_i(t + lag)`. The first
+/// value is the mean squared velocity of the atoms. The frames should be
+/// evenly spaced in time.
+///
+/// When the `rustfft` feature is enabled, the correlation is computed with
+/// fast Fourier transforms, scaling as `O(n log(n))` with the number of frames
+/// instead of `O(n²)`.
+///
+/// # Errors
+///
+/// This function fails if there are no frames, if `selection` is empty, or if
+/// any frame does not contain velocities.
+///
+/// # Panics
+///
+/// If any index in `selection` is out of bounds.
+///
+/// # Example
+/// ```
+/// # use chemfiles::{Frame, Atom};
+/// # use chemfiles::analysis::vacf;
+/// let mut frames = Vec::new();
+/// for velocity in [1.0, -1.0, 1.0] {
+///     let mut frame = Frame::new();
+///     frame.add_velocities();
+///     frame.add_atom(&Atom::new("Ar"), [0.0, 0.0, 0.0], Some([velocity, 0.0, 0.0]));
+///     frames.push(frame);
+/// }
+///
+/// let vacf = vacf(&frames, &[0]).unwrap();
+/// assert_eq!(vacf.len(), 3);
+/// assert!((vacf[0] - 1.0).abs() < 1e-12);
+/// assert!((vacf[1] + 1.0).abs() < 1e-12);
+/// assert!((vacf[2] - 1.0).abs() < 1e-12);
+/// ```
+pub fn vacf<'a>(frames: impl IntoIterator<Item = &'a Frame>, selection: &[usize]) -> Result<Vec<f64>, Error> {
+    if selection.is_empty() {
+        return Err(Error {
+            status: Status::InvalidArgument,
+            message: "can not compute a velocity autocorrelation with an empty selection".into(),
+        });
+    }
+
+    // velocities[i][t] is the velocity of atom selection[i] in frame t
+    let mut velocities = vec![Vec::new(); selection.len()];
+    for (step, frame) in frames.into_iter().enumerate() {
+        let frame_velocities = match frame.velocities() {
+            Some(velocities) => velocities,
+            None => {
+                return Err(Error {
+                    status: Status::InvalidArgument,
+                    message: format!("the frame at index {step} does not contain velocities"),
+                })
+            }
+        };
+
+        for (atom, &i) in velocities.iter_mut().zip(selection) {
+            atom.push(frame_velocities[i]);
+        }
+    }
+
+    let nframes = velocities[0].len();
+    if nframes == 0 {
+        return Err(Error {
+            status: Status::InvalidArgument,
+            message: "can not compute a velocity autocorrelation without any frame".into(),
+        });
+    }
+
+    let mut correlation = vec![0.0; nframes];
+    for atom in &velocities {
+        for (total, value) in correlation.iter_mut().zip(correlation_sums(atom)) {
+            *total += value;
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    for (lag, value) in correlation.iter_mut().enumerate() {
+        *value /= ((nframes - lag) * selection.len()) as f64;
+    }
+
+    return Ok(correlation);
+}
+
+/// Get the sums over all time origins `t` of `series[t] · series[t + lag]`,
+/// for all possible lags.
+#[cfg(not(feature = "rustfft"))]
+fn correlation_sums(series: &[Vector3D]) -> Vec<f64> {
+    return (0..series.len())
+        .map(|lag| {
+            series
+                .iter()
+                .zip(&series[lag..])
+                .map(|(&first, &second)| math::dot(first, second))
+                .sum()
+        })
+        .collect();
+}
+
+/// Get the sums over all time origins `t` of `series[t] · series[t + lag]`,
+/// for all possible lags. This uses the Wiener–Khinchin theorem, padding the
+/// series with zeros to avoid circular correlations.
+#[cfg(feature = "rustfft")]
+fn correlation_sums(series: &[Vector3D]) -> Vec<f64> {
+    use rustfft::num_complex::Complex;
+
+    let size = 2 * series.len();
+    let mut planner = rustfft::FftPlanner::new();
+    let forward = planner.plan_fft_forward(size);
+    let inverse = planner.plan_fft_inverse(size);
+
+    let mut sums = vec![0.0; series.len()];
+    for k in 0..3 {
+        let mut buffer = vec![Complex::new(0.0, 0.0); size];
+        for (value, position) in buffer.iter_mut().zip(series) {
+            value.re = position[k];
+        }
+
+        forward.process(&mut buffer);
+        for value in &mut buffer {
+            *value = Complex::new(value.norm_sqr(), 0.0);
+        }
+        inverse.process(&mut buffer);
+
+        #[allow(clippy::cast_precision_loss)]
+        for (sum, value) in sums.iter_mut().zip(&buffer) {
+            *sum += value.re / size as f64;
+        }
+    }
+
+    return sums;
+}
+
+/// Vibrational spectrum, as computed by [`vibrational_density_of_states`]
+#[cfg(feature = "rustfft")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spectrum {
+    /// Frequency of each point in the spectrum, in the inverse of the unit
+    /// of the time step
+    pub frequencies: Vec<f64>,
+    /// Intensity of the spectrum at each frequency
+    pub intensities: Vec<f64>,
+}
+
+/// Compute the vibrational density of states (VDOS) from a velocity
+/// autocorrelation function, as computed by [`vacf`]. `timestep` is the time
+/// between two consecutive frames used to compute the VACF.
+///
+/// The VDOS is the Fourier transform of the VACF, computed here from the even
+/// extension of the VACF. The spectrum is not normalized, and contains
+/// `vacf.len()` frequencies between 0 and the Nyquist frequency
+/// `1 / (2 * timestep)`.
+///
+/// This function is only available with the `rustfft` feature.
+///
+/// # Errors
+///
+/// This function fails if `vacf` contains less than two values, or if
+/// `timestep` is not strictly positive.
+///
+/// # Example
+/// ```no_run
+/// # use chemfiles::Frame;
+/// # use chemfiles::analysis::{vacf, vibrational_density_of_states};
+/// # let frames: Vec<Frame> = Vec::new();
+/// # let selection = [0, 1, 2];
+/// // frames written every 2 fs
+/// let vacf = vacf(&frames, &selection).unwrap();
+/// let spectrum = vibrational_density_of_states(&vacf, 2e-3).unwrap();
+/// for (frequency, intensity) in spectrum.frequencies.iter().zip(&spectrum.intensities) {
+///     // frequencies are in THz
+///     println!("{frequency} {intensity}");
+/// }
+/// ```
+#[cfg(feature = "rustfft")]
+pub fn vibrational_density_of_states(vacf: &[f64], timestep: f64) -> Result<Spectrum, Error> {
+    use rustfft::num_complex::Complex;
+
+    if vacf.len() < 2 {
+        return Err(Error {
+            status: Status::InvalidArgument,
+            message: "at least two values are needed to compute a vibrational density of states".into(),
+        });
+    }
+
+    if !timestep.is_finite() || timestep <= 0.0 {
+        return Err(Error {
+            status: Status::InvalidArgument,
+            message: format!("the time step must be positive, got {timestep}"),
+        });
+    }
+
+    // even extension of the VACF: c(0), ..., c(n - 1), c(n - 2), ..., c(1)
+    let size = 2 * (vacf.len() - 1);
+    let mut buffer = vacf
+        .iter()
+        .chain(vacf[1..vacf.len() - 1].iter().rev())
+        .map(|&value| Complex::new(value, 0.0))
+        .collect::<Vec<_>>();
+    debug_assert_eq!(buffer.len(), size);
+
+    rustfft::FftPlanner::new().plan_fft_forward(size).process(&mut buffer);
+
+    #[allow(clippy::cast_precision_loss)]
+    let frequencies = (0..vacf.len()).map(|k| k as f64 / (size as f64 * timestep)).collect();
+    let intensities = buffer[..vacf.len()].iter().map(|value| value.re * timestep).collect();
+    return Ok(Spectrum {
+        frequencies,
+        intensities,
+    });
+}
+
+/// Method used to create the intermediate images of a path in
+/// [`interpolate_path_with`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathInterpolation {
+    /// Linear interpolation of the positions, using the minimal image
+    /// convention
+    Linear,
+    /// Image dependent pair potential (IDPP, Smidstrup *et al.*, J. Chem.
+    /// Phys. 140, 214106, 2014). Starting from the linear interpolation, the
+    /// positions in each image are optimized for the distances between all
+    /// pairs of atoms to be close to the linear interpolation of the
+    /// distances in the first and last frames. This avoids unphysical
+    /// intermediate images, for example when a group of atoms rotates. The
+    /// optimization takes a time proportional to the square of the number of
+    /// atoms.
+    Idpp,
+}
+
+/// Create a path of `nimages` intermediate frames between `start` and `end`,
+/// for example to setup nudged elastic band (NEB) calculations.
+///
+/// The path is created by linear interpolation of the positions, using the
+/// minimal image convention with the unit cell of `start` (see
+/// [`Frame::interpolate_periodic`]). The returned vector contains `start`,
+/// the `nimages` intermediate frames and `end`, with steps set to their index
+/// in the path. The positions in the last frame are the periodic images of
+/// the positions in `end` closest to `start`, making the path continuous.
+///
+/// Use [`interpolate_path_with`] for other interpolation methods, and
+/// [`Trajectory::write_all`] to write the path to a multi-frame trajectory.
+///
+/// # Errors
+///
+/// This function fails if `start` and `end` do not contain the same atoms, or
+/// if the unit cell of `start` is degenerate.
+///
+/// # Example
+/// ```no_run
+/// # use chemfiles::{Frame, Trajectory};
+/// # use chemfiles::analysis::interpolate_path;
+/// # let start = Frame::new();
+/// # let end = Frame::new();
+/// let path = interpolate_path(&start, &end, 5).unwrap();
+/// assert_eq!(path.len(), 7);
+///
+/// let mut trajectory = Trajectory::open("path.xyz", 'w').unwrap();
+/// trajectory.write_all(&path).unwrap();
+/// ```
+pub fn interpolate_path(start: &Frame, end: &Frame, nimages: usize) -> Result<Vec<Frame>, Error> {
+    return interpolate_path_with(start, end, nimages, PathInterpolation::Linear);
+}
+
+/// Create a path of `nimages` intermediate frames between `start` and `end`
+/// with the given interpolation `method`. The path contains `start`, the
+/// intermediate frames and `end`, as in [`interpolate_path`].
+///
+/// # Errors
+///
+/// This function fails if `start` and `end` do not contain the same atoms, or
+/// if the unit cell of `start` is degenerate.
+///
+/// # Example
+/// ```
+/// # use chemfiles::{Frame, Atom};
+/// # use chemfiles::analysis::{interpolate_path_with, PathInterpolation};
+/// let mut start = Frame::new();
+/// start.add_atom(&Atom::new("C"), [0.0, 0.0, 0.0], None);
+/// start.add_atom(&Atom::new("O"), [1.2, 0.0, 0.0], None);
+///
+/// // rotate the C-O bond by 90°
+/// let mut end = start.clone();
+/// end.positions_mut()[1] = [0.0, 1.2, 0.0];
+///
+/// let path = interpolate_path_with(&start, &end, 1, PathInterpolation::Idpp).unwrap();
+/// // the bond length is kept in the intermediate image
+/// assert!((path[1].distance(0, 1) - 1.2).abs() < 1e-3);
+/// ```
+#[allow(clippy::cast_precision_loss)]
+pub fn interpolate_path_with(
+    start: &Frame,
+    end: &Frame,
+    nimages: usize,
+    method: PathInterpolation,
+) -> Result<Vec<Frame>, Error> {
+    let mut path = Vec::with_capacity(nimages + 2);
+    path.push(start.clone());
+    for i in 1..=(nimages + 1) {
+        let t = i as f64 / (nimages + 1) as f64;
+        path.push(start.interpolate_periodic(end, t)?);
+    }
+
+    if method == PathInterpolation::Idpp {
+        let periodicity = Periodicity::new(&start.cell())?;
+        let first = pair_distances(&periodicity, path[0].positions());
+        let last = pair_distances(&periodicity, path[nimages + 1].positions());
+        for (i, image) in path.iter_mut().enumerate().take(nimages + 1).skip(1) {
+            let t = i as f64 / (nimages + 1) as f64;
+            let target = first.iter().zip(&last).map(|(a, b)| a + t * (b - a)).collect::<Vec<_>>();
+            idpp_relax(&periodicity, image.positions_mut(), &target);
+        }
+    }
+
+    for (step, frame) in path.iter_mut().enumerate() {
+        frame.set_step(step);
+    }
+    return Ok(path);
+}
+
+/// Get the distances between all pairs `i < j` of atoms, in the order used by
+/// [`idpp_objective`]
+fn pair_distances(periodicity: &Periodicity, positions: &[Vector3D]) -> Vec<f64> {
+    let mut distances = Vec::with_capacity(positions.len() * positions.len() / 2);
+    for i in 0..positions.len() {
+        for j in (i + 1)..positions.len() {
+            distances.push(periodicity.distance(positions[i], positions[j]));
+        }
+    }
+    return distances;
+}
+
+/// Get the value and gradient of the IDPP objective function for the given
+/// `positions` and `target` pair distances, *i.e.* the sum over all pairs of
+/// `(target - distance)² / distance⁴`
+fn idpp_objective(periodicity: &Periodicity, positions: &[Vector3D], target: &[f64]) -> (f64, Vec<Vector3D>) {
+    let mut value = 0.0;
+    let mut gradient = vec![[0.0; 3]; positions.len()];
+    let mut pair = 0;
+    for i in 0..positions.len() {
+        for j in (i + 1)..positions.len() {
+            let vector = periodicity.distance_vector(positions[i], positions[j]);
+            let distance = math::norm(vector);
+            let delta = target[pair] - distance;
+            pair += 1;
+            if distance < f64::EPSILON {
+                // the gradient is not defined for overlapping atoms
+                continue;
+            }
+
+            let weight = 1.0 / distance.powi(4);
+            value += weight * delta * delta;
+
+            let derivative = -2.0 * weight * delta * (1.0 + 2.0 * delta / distance);
+            let force = math::scale(vector, derivative / distance);
+            gradient[i] = math::sub(gradient[i], force);
+            gradient[j] = math::add(gradient[j], force);
+        }
+    }
+    return (value, gradient);
+}
+
+/// Minimize the IDPP objective function by steepest descent, changing the
+/// step size depending on the success of the previous step
+fn idpp_relax(periodicity: &Periodicity, positions: &mut [Vector3D], target: &[f64]) {
+    const MAX_ITERATIONS: usize = 2000;
+    const MAX_GRADIENT: f64 = 1e-5;
+
+    // maximal displacement of an atom in a step, in Angstroms
+    let mut step = 0.05;
+    let (mut value, mut gradient) = idpp_objective(periodicity, positions, target);
+    for _ in 0..MAX_ITERATIONS {
+        let largest = gradient.iter().map(|&g| math::norm(g)).fold(0.0, f64::max);
+        if largest < MAX_GRADIENT || step < 1e-10 {
+            break;
+        }
+
+        let trial = positions
+            .iter()
+            .zip(&gradient)
+            .map(|(&position, &g)| math::sub(position, math::scale(g, step / largest)))
+            .collect::<Vec<_>>();
+        let (trial_value, trial_gradient) = idpp_objective(periodicity, &trial, target);
+        if trial_value < value {
+            positions.copy_from_slice(&trial);
+            value = trial_value;
+            gradient = trial_gradient;
+            step *= 1.2;
+        } else {
+            step *= 0.5;
+        }
+    }
+}
+
+/// An `Accumulator` collects data from multiple frames, and computes a result
+/// once all the frames have been seen. This is the building block of analyses
+/// running over whole trajectories.
+///
+/// Multiple accumulators can be combined in a tuple or a `Vec`, and driven by
+/// [`Trajectory::fold_frames`] to run multiple analyses in a single pass over
+/// a trajectory file.
+///
+/// [`Trajectory::fold_frames`]: crate::Trajectory::fold_frames
+///
+/// # Example
+/// ```no_run
+/// # use chemfiles::{Frame, Trajectory};
+/// # use chemfiles::analysis::{Accumulator, TrajectoryStats};
+/// /// Compute the average number of atoms in the frames
+/// #[derive(Default)]
+/// struct AverageSize {
+///     atoms: usize,
+///     frames: usize,
+/// }
+///
+/// impl Accumulator for AverageSize {
+///     type Output = f64;
+///
+///     fn accumulate(&mut self, frame: &Frame) {
+///         self.atoms += frame.size();
+///         self.frames += 1;
+///     }
+///
+///     fn finish(self) -> f64 {
+///         self.atoms as f64 / self.frames as f64
+///     }
+/// }
+///
+/// let mut trajectory = Trajectory::open("simulation.xtc", 'r').unwrap();
+/// let (size, stats) = trajectory
+///     .fold_frames((AverageSize::default(), TrajectoryStats::default()))
+///     .unwrap();
+/// ```
+pub trait Accumulator {
+    /// The result of this accumulator
+    type Output;
+
+    /// Add the data from `frame` to this accumulator
+    fn accumulate(&mut self, frame: &Frame);
+
+    /// Compute the final result of this accumulator
+    fn finish(self) -> Self::Output;
+}
+
+impl<T: Accumulator> Accumulator for Vec<T> {
+    type Output = Vec<T::Output>;
+
+    fn accumulate(&mut self, frame: &Frame) {
+        for accumulator in self {
+            accumulator.accumulate(frame);
+        }
+    }
+
+    fn finish(self) -> Self::Output {
+        self.into_iter().map(Accumulator::finish).collect()
+    }
+}
+
+/// Implement `Accumulator` for tuples of accumulators
+macro_rules! tuple_accumulator {
+    ($($name: ident: $index: tt),+) => {
+        impl<$($name: Accumulator),+> Accumulator for ($($name,)+) {
+            type Output = ($($name::Output,)+);
+
+            fn accumulate(&mut self, frame: &Frame) {
+                $(self.$index.accumulate(frame);)+
+            }
+
+            fn finish(self) -> Self::Output {
+                ($(self.$index.finish(),)+)
+            }
+        }
+    };
+}
+
+tuple_accumulator!(A: 0);
+tuple_accumulator!(A: 0, B: 1);
+tuple_accumulator!(A: 0, B: 1, C: 2);
+tuple_accumulator!(A: 0, B: 1, C: 2, D: 3);
+tuple_accumulator!(A: 0, B: 1, C: 2, D: 3, E: 4);
+tuple_accumulator!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5);
+
+/// Atoms further than this distance (in Angstroms) from the origin are
+/// considered to have exploded in [`trajectory_stats`]
+pub const EXPLODED_DISTANCE: f64 = 1e4;
+
+/// Sanity statistics for a single frame, as computed by [`trajectory_stats`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameStats {
+    /// Step of the frame
+    pub step: usize,
+    /// Minimal value of the finite coordinates along each axis
+    pub min: [f64; 3],
+    /// Maximal value of the finite coordinates along each axis
+    pub max: [f64; 3],
+    /// Average value of the finite coordinates along each axis
+    pub mean: [f64; 3],
+    /// Volume of the unit cell, or `None` for infinite cells
+    pub volume: Option<f64>,
+    /// Number of atoms with a NaN or infinite coordinate
+    pub non_finite: usize,
+    /// Number of atoms further than [`EXPLODED_DISTANCE`] from the origin
+    pub exploded: usize,
+}
+
+impl FrameStats {
+    /// Check if this frame contains non-finite or exploded coordinates
+    pub fn is_broken(&self) -> bool {
+        return self.non_finite != 0 || self.exploded != 0;
+    }
+}
+
+/// Sanity statistics for a whole trajectory, as computed by
+/// [`trajectory_stats`]. This can also be used as an [`Accumulator`] to
+/// compute these statistics together with other analyses.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TrajectoryStats {
+    /// Statistics for each frame in the trajectory
+    pub frames: Vec<FrameStats>,
+}
+
+impl TrajectoryStats {
+    /// Get the index of the first frame containing non-finite or exploded
+    /// coordinates, if any.
+    pub fn first_broken_frame(&self) -> Option<usize> {
+        return self.frames.iter().position(FrameStats::is_broken);
+    }
+
+    /// Get the relative drift of the unit cell volume between the first and
+    /// the last frame, *i.e.* `(last - first) / first`. This returns `None`
+    /// if the trajectory is empty, or if the first or last frame has an
+    /// infinite unit cell.
+    pub fn volume_drift(&self) -> Option<f64> {
+        let first = self.frames.first()?.volume?;
+        let last = self.frames.last()?.volume?;
+        return Some((last - first) / first);
+    }
+}
+
+/// Compute sanity statistics for all the frames in `trajectory`, to quickly
+/// check if a simulation went wrong: range and average of the coordinates,
+/// unit cell volume, and number of atoms with NaN or exploding coordinates.
+///
+/// The frames are read from the beginning of the trajectory, regardless of
+/// previous reads.
+///
+/// # Errors
+///
+/// This function fails if any frame can not be read.
+///
+/// # Example
+/// ```no_run
+/// # use chemfiles::Trajectory;
+/// # use chemfiles::analysis::trajectory_stats;
+/// let mut trajectory = Trajectory::open("simulation.xtc", 'r').unwrap();
+/// let stats = trajectory_stats(&mut trajectory).unwrap();
+///
+/// if let Some(i) = stats.first_broken_frame() {
+///     println!("the simulation blew up at step {}", stats.frames[i].step);
+/// }
+/// if let Some(drift) = stats.volume_drift() {
+///     println!("the volume changed by {:.2}%", 100.0 * drift);
+/// }
+/// ```
+pub fn trajectory_stats(trajectory: &mut Trajectory) -> Result<TrajectoryStats, Error> {
+    return trajectory.fold_frames(TrajectoryStats::default());
+}
+
+impl Accumulator for TrajectoryStats {
+    type Output = TrajectoryStats;
+
+    fn accumulate(&mut self, frame: &Frame) {
+        self.frames.push(frame_stats(frame));
+    }
+
+    fn finish(self) -> TrajectoryStats {
+        self
+    }
+}
+
+/// Compute the sanity statistics of a single `frame`
+fn frame_stats(frame: &Frame) -> FrameStats {
+    let mut min = [f64::INFINITY; 3];
+    let mut max = [f64::NEG_INFINITY; 3];
+    let mut sum = [0.0; 3];
+    let mut finite = 0_usize;
+    let mut non_finite = 0;
+    let mut exploded = 0;
+
+    for &position in frame.positions() {
+        if !position.iter().all(|x| x.is_finite()) {
+            non_finite += 1;
+            continue;
+        }
+
+        if math::norm(position) > EXPLODED_DISTANCE {
+            exploded += 1;
+        }
+
+        for k in 0..3 {
+            min[k] = min[k].min(position[k]);
+            max[k] = max[k].max(position[k]);
+            sum[k] += position[k];
+        }
+        finite += 1;
+    }
+
+    let mean = if finite == 0 {
+        min = [f64::NAN; 3];
+        max = [f64::NAN; 3];
+        [f64::NAN; 3]
+    } else {
+        #[allow(clippy::cast_precision_loss)]
+        math::scale(sum, 1.0 / finite as f64)
+    };
+
+    let cell = frame.cell();
+    let volume = if cell.shape() == CellShape::Infinite {
+        None
+    } else {
+        Some(cell.volume())
+    };
+
+    return FrameStats {
+        step: frame.step(),
+        min,
+        max,
+        mean,
+        volume,
+        non_finite,
+        exploded,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Atom, Residue, UnitCell};
+    use approx::assert_ulps_eq;
+
+    #[test]
+    fn distances() {
+        let mut frame = Frame::new();
+        frame.add_atom(&Atom::new("O"), [0.0, 0.0, 0.0], None);
+        frame.add_atom(&Atom::new("H"), [1.0, 2.0, 2.0], None);
+        frame.add_atom(&Atom::new("H"), [0.0, 0.0, 14.0], None);
+
+        let distances = distance_matrix(&frame, &[0, 1], &[0, 1, 2]).unwrap();
+        assert_eq!(distances.len(), 6);
+        assert_ulps_eq!(distances[0], 0.0);
+        assert_ulps_eq!(distances[1], 3.0);
+        assert_ulps_eq!(distances[2], 14.0);
+        assert_ulps_eq!(distances[3], 3.0);
+
+        frame.set_cell(&UnitCell::new([15.0, 15.0, 15.0]));
+        let distances = distance_matrix(&frame, &[0, 1], &[0, 1, 2]).unwrap();
+        assert_ulps_eq!(distances[2], 1.0);
+        for (i, &a) in [0, 1].iter().enumerate() {
+            for (j, b) in (0..3).enumerate() {
+                assert_ulps_eq!(distances[i * 3 + j], frame.distance(a, b), epsilon = 1e-12);
+            }
+        }
+
+        frame.set_cell(&UnitCell::triclinic([15.0, 15.0, 15.0], [80.0, 90.0, 110.0]));
+        let distances = distance_matrix(&frame, &[0, 1], &[0, 1, 2]).unwrap();
+        for (i, &a) in [0, 1].iter().enumerate() {
+            for (j, b) in (0..3).enumerate() {
+                assert_ulps_eq!(distances[i * 3 + j], frame.distance(a, b), epsilon = 1e-12);
+            }
+        }
+
+        // degenerate cell with a zero volume
+        frame.set_cell(&UnitCell::new([15.0, 15.0, 0.0]));
+        let error = distance_matrix(&frame, &[0, 1], &[0, 1, 2]).unwrap_err();
+        assert_eq!(error.status, Status::InvalidArgument);
+    }
+
+    #[test]
+    fn contacts() {
+        let mut frame = Frame::new();
+        frame.set_cell(&UnitCell::new([10.0, 10.0, 10.0]));
+        frame.add_atom(&Atom::new("C"), [0.0, 0.0, 0.0], None);
+        frame.add_atom(&Atom::new("C"), [1.5, 0.0, 0.0], None);
+        frame.add_atom(&Atom::new("O"), [8.0, 0.0, 0.0], None);
+
+        let contacts = super::contacts(&frame, &[0, 1], &[0, 1, 2], 3.0).unwrap();
+        assert_eq!(contacts.len(), 3);
+        assert_eq!((contacts[0].first, contacts[0].second), (0, 1));
+        assert_eq!((contacts[1].first, contacts[1].second), (0, 2));
+        assert_ulps_eq!(contacts[1].distance, 2.0);
+        assert_eq!((contacts[2].first, contacts[2].second), (1, 0));
+    }
+
+    #[test]
+    fn hydrogen_bonds() {
+        let mut frame = Frame::new();
+        frame.add_atom(&Atom::new("O"), [0.0, 0.0, 0.0], None);
+        frame.add_atom(&Atom::new("H"), [0.96, 0.0, 0.0], None);
+        frame.add_atom(&Atom::new("H"), [-0.24, 0.93, 0.0], None);
+        frame.add_atom(&Atom::new("N"), [2.9, 0.0, 0.0], None);
+        frame.add_atom(&Atom::new("N"), [0.0, 0.0, 3.0], None);
+        frame.add_bond(0, 1);
+        frame.add_bond(0, 2);
+
+        let criteria = HydrogenBondCriteria::default();
+        let bonds = super::hydrogen_bonds(&frame, &[0, 3], &[0, 3, 4], &criteria).unwrap();
+        assert_eq!(bonds.len(), 1);
+        assert_eq!(bonds[0].donor, 0);
+        assert_eq!(bonds[0].hydrogen, 1);
+        assert_eq!(bonds[0].acceptor, 3);
+        assert_ulps_eq!(bonds[0].distance, 2.9);
+        assert_ulps_eq!(bonds[0].angle, 180.0);
+
+        let criteria = HydrogenBondCriteria {
+            distance: 3.5,
+            angle: 60.0,
+        };
+        let bonds = super::hydrogen_bonds(&frame, &[0], &[4], &criteria).unwrap();
+        assert_eq!(bonds.len(), 2);
+    }
+
+    #[test]
+    fn special_bonds() {
+        let mut frame = Frame::new();
+        frame.set_cell(&UnitCell::new([20.0, 20.0, 20.0]));
+        frame.add_atom(&Atom::new("SG"), [0.0, 0.0, 0.0], None);
+        // closer to the first atom through periodic boundary conditions
+        frame.add_atom(&Atom::new("SG"), [18.0, 0.0, 0.0], None);
+        frame.add_atom(&Atom::new("SG"), [2.2, 0.0, 0.0], None);
+        frame.add_atom(&Atom::new("SG"), [10.0, 0.0, 0.0], None);
+        frame.add_atom(&Atom::new("SG"), [10.0, 1.0, 0.0], None);
+
+        // atoms 3 and 4 are in the same residue
+        let mut residue = Residue::with_id("CYS", 1);
+        residue.add_atom(3);
+        residue.add_atom(4);
+        frame.add_residue(&residue).unwrap();
+
+        let bonds = find_disulfides(&frame).unwrap();
+        assert_eq!(bonds.len(), 1);
+        assert_eq!((bonds[0].first, bonds[0].second), (0, 1));
+        assert_ulps_eq!(bonds[0].distance, 2.0);
+
+        let rule = SpecialBondRule {
+            order: BondOrder::Double,
+            ..SpecialBondRule::disulfide()
+        };
+        assert_eq!(add_special_bonds(&mut frame, &rule).unwrap().len(), 1);
+        assert_eq!(frame.topology().bonds(), vec![[0, 1]]);
+        assert_eq!(frame.topology().bond_order(0, 1), BondOrder::Double);
+    }
+
+    #[test]
+    fn histograms() {
+        let mut histogram = Histogram::new(-1.0, 1.0, 4);
+        histogram.add(-1.0, 1.0);
+        histogram.add(-0.2, 2.0);
+        histogram.add(0.99, 3.0);
+        histogram.add(1.0, 3.0);
+        histogram.add(f64::NAN, 3.0);
+        assert_eq!(histogram.counts(), &[1.0, 2.0, 0.0, 3.0]);
+        assert_ulps_eq!(histogram.bin_width(), 0.5);
+        assert_eq!(histogram.bin_centers(), vec![-0.75, -0.25, 0.25, 0.75]);
+
+        histogram.counts_mut()[2] = 5.0;
+        assert_eq!(histogram.counts()[2], 5.0);
+
+        let mut histogram = Histogram2D::new([0.0, -1.0], [1.0, 1.0], [2, 4]);
+        histogram.add([0.2, 0.9], 1.0);
+        histogram.add([0.7, -0.9], 2.0);
+        histogram.add([0.7, 2.0], 2.0);
+        assert_eq!(histogram.nbins(), [2, 4]);
+        assert_eq!(histogram.get(0, 3), 1.0);
+        assert_eq!(histogram.get(1, 0), 2.0);
+        assert_eq!(histogram.counts().iter().sum::<f64>(), 3.0);
+    }
+
+    #[test]
+    fn path() {
+        let mut start = Frame::new();
+        start.set_cell(&UnitCell::new([10.0, 10.0, 10.0]));
+        start.add_atom(&Atom::new("Ar"), [1.0, 0.0, 0.0], None);
+        start.add_atom(&Atom::new("Ar"), [5.0, 5.0, 5.0], None);
+
+        let mut end = start.clone();
+        end.positions_mut()[0] = [9.0, 0.0, 0.0];
+        end.positions_mut()[1] = [5.0, 8.0, 5.0];
+
+        let path = interpolate_path(&start, &end, 2).unwrap();
+        assert_eq!(path.len(), 4);
+        for (i, frame) in path.iter().enumerate() {
+            assert_eq!(frame.step(), i);
+        }
+        assert_eq!(path[0].positions(), start.positions());
+        assert_ulps_eq!(path[1].positions()[0][0], 1.0 - 2.0 / 3.0);
+        assert_ulps_eq!(path[2].positions()[1][1], 7.0);
+        assert_ulps_eq!(path[3].positions()[0][0], -1.0);
+
+        assert_eq!(interpolate_path(&start, &end, 0).unwrap().len(), 2);
+
+        end.resize(3);
+        assert!(interpolate_path(&start, &end, 3).is_err());
+    }
+
+    #[test]
+    fn idpp_path() {
+        let mut start = Frame::new();
+        start.add_atom(&Atom::new("C"), [0.0, 0.0, 0.0], None);
+        start.add_atom(&Atom::new("O"), [1.0, 0.0, 0.0], None);
+        start.add_atom(&Atom::new("Ar"), [0.0, 0.0, 5.0], None);
+
+        let mut end = start.clone();
+        end.positions_mut()[1] = [0.0, 1.0, 0.0];
+
+        let linear = interpolate_path(&start, &end, 3).unwrap();
+        assert_ulps_eq!(linear[2].distance(0, 1), f64::sqrt(0.5));
+
+        let path = interpolate_path_with(&start, &end, 3, PathInterpolation::Idpp).unwrap();
+        assert_eq!(path.len(), 5);
+        assert_eq!(path[0].positions(), start.positions());
+        assert_eq!(path[4].positions(), end.positions());
+        for (i, image) in path.iter().enumerate() {
+            assert_eq!(image.step(), i);
+            assert_ulps_eq!(image.distance(0, 1), 1.0, epsilon = 1e-4);
+        }
+    }
+
+    #[test]
+    fn density() {
+        let mut first = Frame::new();
+        first.set_cell(&UnitCell::new([10.0, 10.0, 20.0]));
+        first.add_atom(&Atom::new("O"), [1.0, 1.0, 1.0], None);
+        first.add_atom(&Atom::new("O"), [1.0, 1.0, 11.0], None);
+        first.add_atom(&Atom::new("H"), [1.0, 1.0, 12.0], None);
+
+        let mut second = first.clone();
+        second.positions_mut()[1] = [1.0, 1.0, -1.0];
+
+        let profile = density_profile(vec![&first, &second], &[0, 1], Axis::C, 4).unwrap();
+        assert_eq!(profile.positions, vec![2.5, 7.5, 12.5, 17.5]);
+        // each bin has a volume of 500 A^3
+        assert_ulps_eq!(profile.densities[0], 1.0 / 500.0);
+        assert_ulps_eq!(profile.densities[1], 0.0);
+        assert_ulps_eq!(profile.densities[2], 0.5 / 500.0);
+        assert_ulps_eq!(profile.densities[3], 0.5 / 500.0);
+
+        let error = density_profile(Vec::new(), &[0, 1], Axis::C, 4).unwrap_err();
+        assert_eq!(error.status, Status::InvalidArgument);
+
+        let error = density_profile(&[Frame::new()], &[], Axis::C, 4).unwrap_err();
+        assert_eq!(error.status, Status::InvalidArgument);
+    }
+
+    #[test]
+    fn stats() {
+        let mut frame = Frame::new();
+        frame.add_atom(&Atom::new("Ar"), [0.0, 1.0, 2.0], None);
+        frame.add_atom(&Atom::new("Ar"), [2.0, 3.0, -2.0], None);
+        let stats = frame_stats(&frame);
+        assert_eq!(stats.min, [0.0, 1.0, -2.0]);
+        assert_eq!(stats.max, [2.0, 3.0, 2.0]);
+        assert_eq!(stats.mean, [1.0, 2.0, 0.0]);
+        assert_eq!(stats.volume, None);
+        assert!(!stats.is_broken());
+
+        frame.add_atom(&Atom::new("Ar"), [f64::NAN, 0.0, 0.0], None);
+        frame.add_atom(&Atom::new("Ar"), [0.0, 2e4, 0.0], None);
+        frame.set_cell(&UnitCell::new([10.0, 10.0, 10.0]));
+        let broken = frame_stats(&frame);
+        assert_eq!(broken.non_finite, 1);
+        assert_eq!(broken.exploded, 1);
+        assert_eq!(broken.max[1], 2e4);
+        assert_eq!(broken.volume, Some(1000.0));
+
+        let mut trajectory_stats = TrajectoryStats {
+            frames: vec![stats.clone(), broken],
+        };
+        assert_eq!(trajectory_stats.first_broken_frame(), Some(1));
+        assert_eq!(trajectory_stats.volume_drift(), None);
+
+        trajectory_stats.frames[0].volume = Some(800.0);
+        assert_ulps_eq!(trajectory_stats.volume_drift().unwrap(), 0.25);
+    }
+
+    #[test]
+    fn accumulators() {
+        struct Count(usize);
+        impl Accumulator for Count {
+            type Output = usize;
+            fn accumulate(&mut self, _: &Frame) {
+                self.0 += 1;
+            }
+            fn finish(self) -> usize {
+                self.0
+            }
+        }
+
+        let mut frame = Frame::new();
+        frame.add_atom(&Atom::new("Ar"), [0.0, 1.0, 2.0], None);
+
+        let mut accumulators = (Count(0), TrajectoryStats::default(), vec![Count(0), Count(3)]);
+        accumulators.accumulate(&frame);
+        accumulators.accumulate(&frame);
+
+        let (count, stats, counts) = accumulators.finish();
+        assert_eq!(count, 2);
+        assert_eq!(stats.frames.len(), 2);
+        assert_eq!(stats.frames[0].mean, [0.0, 1.0, 2.0]);
+        assert_eq!(counts, [2, 5]);
+
+        let root = std::path::Path::new(file!()).parent().unwrap().join("..");
+        let mut trajectory = Trajectory::open(root.join("data").join("water.xyz"), 'r').unwrap();
+        let (count, stats) = trajectory.fold_frames((Count(0), TrajectoryStats::default())).unwrap();
+        assert_eq!(count, 100);
+        assert_eq!(stats.frames.len(), 100);
+    }
+
+    #[test]
+    fn covariance() {
+        let mut frames = Vec::new();
+        for x in [0.0, 1.0, 2.0, 3.0] {
+            let mut frame = Frame::new();
+            frame.add_atom(&Atom::new("C"), [x, 0.0, 5.0], None);
+            frame.add_atom(&Atom::new("C"), [-x, 1.0, 5.0], None);
+            frame.add_atom(&Atom::new("C"), [0.0, 0.0, 0.0], None);
+            frames.push(frame);
+        }
+
+        let covariance = covariance_matrix(&frames, &[0, 1]).unwrap();
+        assert_eq!(covariance.size(), 6);
+        assert_eq!(covariance.mean, vec![[1.5, 0.0, 5.0], [-1.5, 1.0, 5.0]]);
+        assert_ulps_eq!(covariance.get(0, 0), 1.25);
+        assert_ulps_eq!(covariance.get(3, 3), 1.25);
+        assert_ulps_eq!(covariance.get(0, 3), -1.25);
+        assert_ulps_eq!(covariance.get(3, 0), -1.25);
+        assert_eq!(covariance.get(1, 1), 0.0);
+        assert_eq!(covariance.get(2, 5), 0.0);
+
+        #[cfg(feature = "nalgebra")]
+        {
+            let pca = covariance.principal_components(2);
+            assert_eq!(pca.variances.len(), 2);
+            assert_ulps_eq!(pca.variances[0], 2.5, max_ulps = 10);
+            assert!(pca.variances[1].abs() < 1e-12);
+
+            let component = &pca.components[0];
+            let sign = component[0][0].signum();
+            assert_ulps_eq!(sign * component[0][0], f64::sqrt(0.5), max_ulps = 10);
+            assert_ulps_eq!(sign * component[1][0], -f64::sqrt(0.5), max_ulps = 10);
+
+            let projection = pca.project(&frames[3], &[0, 1]);
+            assert_ulps_eq!(sign * projection[0], 1.5 * f64::sqrt(2.0), max_ulps = 10);
+        }
+
+        let error = covariance_matrix(&frames, &[]).unwrap_err();
+        assert_eq!(error.status, Status::InvalidArgument);
+        let error = covariance_matrix(Vec::new(), &[0]).unwrap_err();
+        assert_eq!(error.status, Status::InvalidArgument);
+    }
+
+    #[test]
+    fn velocity_autocorrelation() {
+        let mut frames = Vec::new();
+        for t in 0..8 {
+            let mut frame = Frame::new();
+            frame.add_velocities();
+            let velocity = f64::from(t % 2) * 2.0 - 1.0;
+            frame.add_atom(&Atom::new("Ar"), [0.0, 0.0, 0.0], Some([velocity, 0.0, 0.0]));
+            frame.add_atom(&Atom::new("Ar"), [0.0, 0.0, 0.0], Some([0.0, 2.0, 0.0]));
+            frames.push(frame);
+        }
+
+        let correlation = vacf(&frames, &[0, 1]).unwrap();
+        assert_eq!(correlation.len(), 8);
+        for (lag, &value) in correlation.iter().enumerate() {
+            let expected = if lag % 2 == 0 { 2.5 } else { 1.5 };
+            assert!((value - expected).abs() < 1e-12);
+        }
+
+        let error = vacf(&frames, &[]).unwrap_err();
+        assert_eq!(error.status, Status::InvalidArgument);
+        let error = vacf(Vec::new(), &[0]).unwrap_err();
+        assert_eq!(error.status, Status::InvalidArgument);
+
+        frames.push(Frame::new());
+        let error = vacf(&frames, &[0]).unwrap_err();
+        assert_eq!(error.message, "the frame at index 8 does not contain velocities");
+    }
+
+    #[test]
+    fn correlation() {
+        let series = [[1.0, 2.0, 0.0], [0.5, -1.0, 3.0], [2.0, 0.0, -1.0], [0.0, 1.0, 1.0]];
+        let sums = correlation_sums(&series);
+        let expected = [22.25, -4.5, 4.0, 2.0];
+        for (sum, expected) in sums.iter().zip(expected) {
+            assert!((sum - expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "rustfft")]
+    fn vdos() {
+        // a single cosine at a quarter of the sampling frequency
+        let vacf = (0..9)
+            .map(|t| f64::cos(std::f64::consts::FRAC_PI_2 * f64::from(t)))
+            .collect::<Vec<_>>();
+        let spectrum = vibrational_density_of_states(&vacf, 0.5).unwrap();
+        assert_eq!(spectrum.frequencies.len(), 9);
+        assert_ulps_eq!(spectrum.frequencies[4], 0.5);
+        assert_ulps_eq!(spectrum.frequencies[8], 1.0);
+
+        let peak = (0..9)
+            .max_by(|&i, &j| spectrum.intensities[i].total_cmp(&spectrum.intensities[j]))
+            .unwrap();
+        assert_eq!(peak, 4);
+
+        assert!(vibrational_density_of_states(&vacf[..1], 0.5).is_err());
+        assert!(vibrational_density_of_states(&vacf, 0.0).is_err());
+    }
+}
@@ -4,10 +4,90 @@ use std::marker::PhantomData;
 
 use chemfiles_sys as ffi;
 
-use crate::errors::{check_not_null, check_success};
+use crate::errors::{check_not_null, check_success, Error, Status};
+use crate::periodic_table;
 use crate::property::{PropertiesIter, Property, RawProperty};
 use crate::strings;
 
+/// Masses (in atomic mass units) of common isotopes, as (atomic number, mass
+/// number, mass). This contains the stable isotopes of the lightest elements,
+/// and isotopes commonly used for labeling.
+const ISOTOPE_MASSES: &[(u64, u32, f64)] = &[
+    (1, 1, 1.007_825_032_23),
+    (1, 2, 2.014_101_778_12),
+    (1, 3, 3.016_049_277_9),
+    (2, 3, 3.016_029_320_1),
+    (2, 4, 4.002_603_254_13),
+    (3, 6, 6.015_122_887_4),
+    (3, 7, 7.016_003_436_6),
+    (4, 9, 9.012_183_065),
+    (5, 10, 10.012_936_95),
+    (5, 11, 11.009_305_36),
+    (6, 12, 12.0),
+    (6, 13, 13.003_354_835_07),
+    (6, 14, 14.003_241_988_4),
+    (7, 14, 14.003_074_004_43),
+    (7, 15, 15.000_108_898_88),
+    (8, 16, 15.994_914_619_57),
+    (8, 17, 16.999_131_756_5),
+    (8, 18, 17.999_159_612_86),
+    (9, 19, 18.998_403_162_73),
+    (10, 20, 19.992_440_176_2),
+    (10, 21, 20.993_846_685),
+    (10, 22, 21.991_385_114),
+    (11, 23, 22.989_769_282),
+    (12, 24, 23.985_041_697),
+    (12, 25, 24.985_836_976),
+    (12, 26, 25.982_592_968),
+    (13, 27, 26.981_538_53),
+    (14, 28, 27.976_926_534_65),
+    (14, 29, 28.976_494_664_9),
+    (14, 30, 29.973_770_136),
+    (15, 31, 30.973_761_998_42),
+    (16, 32, 31.972_071_174_4),
+    (16, 33, 32.971_458_909_8),
+    (16, 34, 33.967_867_004),
+    (16, 36, 35.967_080_71),
+    (17, 35, 34.968_852_682),
+    (17, 37, 36.965_902_602),
+    (18, 36, 35.967_545_105),
+    (18, 38, 37.962_732_11),
+    (18, 40, 39.962_383_123_7),
+    (19, 39, 38.963_706_486_4),
+    (19, 40, 39.963_998_166),
+    (19, 41, 40.961_825_257_9),
+    (20, 40, 39.962_590_863),
+    (20, 42, 41.958_617_83),
+    (20, 43, 42.958_766_44),
+    (20, 44, 43.955_481_56),
+    (20, 46, 45.953_689),
+    (20, 48, 47.952_522_76),
+    (26, 54, 53.939_608_99),
+    (26, 56, 55.934_936_33),
+    (26, 57, 56.935_392_84),
+    (26, 58, 57.933_274_43),
+    (29, 63, 62.929_597_72),
+    (29, 65, 64.927_789_7),
+    (30, 64, 63.929_142_01),
+    (30, 66, 65.926_033_81),
+    (30, 67, 66.927_127_75),
+    (30, 68, 67.924_844_55),
+    (30, 70, 69.925_319_2),
+    (35, 79, 78.918_337_6),
+    (35, 81, 80.916_289_7),
+    (53, 127, 126.904_471_9),
+    (92, 235, 235.043_930_1),
+    (92, 238, 238.050_788_4),
+];
+
+/// Name of the atomic property used to store the mass number
+const MASS_NUMBER: &str = "mass_number";
+/// Name of the atomic property used to flag virtual sites
+pub(crate) const VIRTUAL_SITE: &str = "virtual_site";
+/// Name of the atomic property used to store the radius of coarse-grained
+/// beads
+pub(crate) const BEAD_RADIUS: &str = "radius";
+
 /// An `Atom` is a particle in the current `Frame`. It stores the following
 /// atomic properties:
 ///
@@ -39,6 +119,14 @@ impl<'a> std::ops::Deref for AtomRef<'a> {
 }
 
 /// An analog to a mutable reference to an atom (`&mut Atom`)
+///
+/// The `Atom` accessible through this type points inside another object (for
+/// example a `Frame`), and must not be moved out of this `AtomMut`. Since
+/// `AtomMut` implements `DerefMut`, this could be done with `std::mem::swap`
+/// or `std::mem::replace`, giving an owned `Atom` pointing into memory which
+/// can later be invalidated. Such a moved-out value must be treated as a
+/// borrow of the original object, and must not be used after this object is
+/// modified or dropped.
 #[derive(Debug)]
 pub struct AtomMut<'a> {
     inner: Atom,
@@ -131,6 +219,219 @@ impl Atom {
         }
     }
 
+    /// Create an atom for the element with the given atomic number `z`,
+    /// using the element symbol as name and type. The mass and radii of the
+    /// atom are the ones of the element.
+    ///
+    /// This is useful to build topologies from numeric element data, as
+    /// found in many machine learning datasets.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if `z` is not the atomic number of a known element
+    /// (between 1 and 118).
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Atom;
+    /// let atom = Atom::from_atomic_number(8).unwrap();
+    /// assert_eq!(atom.name(), "O");
+    /// assert_eq!(atom.atomic_number(), 8);
+    ///
+    /// assert!(Atom::from_atomic_number(0).is_err());
+    /// ```
+    pub fn from_atomic_number(z: u64) -> Result<Atom, Error> {
+        return match periodic_table::by_number(z) {
+            Some(element) => Ok(Atom::new(element.symbol)),
+            None => Err(Error {
+                status: Status::InvalidArgument,
+                message: format!("there is no element with atomic number {z}"),
+            }),
+        };
+    }
+
+    /// Get the mass of the isotope of the element with atomic number `z` and
+    /// the given `mass_number`, in atomic mass units. This returns `None` if
+    /// the isotope is not known.
+    ///
+    /// Only the stable isotopes of the lightest elements (up to calcium) and
+    /// some isotopes commonly used for labeling (<sup>14</sup>C,
+    /// <sup>3</sup>H, <sup>57</sup>Fe, ...) are known.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Atom;
+    /// assert_eq!(Atom::isotope_mass(6, 12), Some(12.0));
+    /// assert_eq!(Atom::isotope_mass(1, 2), Some(2.01410177812));
+    /// assert_eq!(Atom::isotope_mass(1, 7), None);
+    /// ```
+    pub fn isotope_mass(z: u64, mass_number: u32) -> Option<f64> {
+        return ISOTOPE_MASSES
+            .iter()
+            .find(|&&(number, isotope, _)| number == z && isotope == mass_number)
+            .map(|&(_, _, mass)| mass);
+    }
+
+    /// Get the mass number of this atom, if it was set with
+    /// [`Atom::set_mass_number`]. Atoms without a mass number use the
+    /// average mass over the natural isotopes of the element.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Atom;
+    /// let mut atom = Atom::new("H");
+    /// assert_eq!(atom.mass_number(), None);
+    ///
+    /// atom.set_mass_number(2).unwrap();
+    /// assert_eq!(atom.mass_number(), Some(2));
+    /// ```
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn mass_number(&self) -> Option<u32> {
+        return match self.get(MASS_NUMBER) {
+            Some(Property::Double(number)) if number >= 1.0 && number.fract() == 0.0 => Some(number as u32),
+            _ => None,
+        };
+    }
+
+    /// Label this atom as a specific isotope of its element, with the given
+    /// `mass_number`. This sets the mass of the atom to the isotopic mass,
+    /// and stores the mass number in the `"mass_number"` property.
+    ///
+    /// # Errors
+    ///
+    /// This function fails with [`Status::InvalidArgument`] if the atomic type
+    /// of this atom is not an element, or if the isotope is not known (see
+    /// [`Atom::isotope_mass`]).
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Atom;
+    /// let mut atom = Atom::new("C");
+    /// atom.set_mass_number(13).unwrap();
+    /// assert_eq!(atom.mass(), 13.00335483507);
+    /// assert_eq!(atom.mass_number(), Some(13));
+    ///
+    /// assert!(atom.set_mass_number(42).is_err());
+    /// ```
+    pub fn set_mass_number(&mut self, mass_number: u32) -> Result<(), Error> {
+        let z = self.atomic_number();
+        if z == 0 {
+            return Err(Error {
+                status: Status::InvalidArgument,
+                message: format!(
+                    "the atomic type '{}' is not an element, it has no isotopes",
+                    self.atomic_type()
+                ),
+            });
+        }
+
+        let mass = match Atom::isotope_mass(z, mass_number) {
+            Some(mass) => mass,
+            None => {
+                return Err(Error {
+                    status: Status::InvalidArgument,
+                    message: format!("unknown isotope with mass number {mass_number} for element {z}"),
+                })
+            }
+        };
+
+        self.set_mass(mass);
+        self.set(MASS_NUMBER, f64::from(mass_number));
+        return Ok(());
+    }
+
+    /// Create a coarse-grained bead with the given `name`, `mass` (in atomic
+    /// mass units) and `radius` (in Angstroms).
+    ///
+    /// The name is also used as the atomic type, and the radius is stored in
+    /// the `"radius"` property. Formats supporting atomic properties (such as
+    /// extended XYZ) will write the radius, and [`Frame::guess_bonds_with_radii`]
+    /// uses it instead of the covalent radius of the element. This avoids
+    /// confusing beads with elements of the same name, such as Martini `P4`
+    /// or `C1` beads.
+    ///
+    /// [`Frame::guess_bonds_with_radii`]: crate::Frame::guess_bonds_with_radii
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Atom;
+    /// let bead = Atom::bead("P4", 72.0, 2.35);
+    /// assert_eq!(bead.name(), "P4");
+    /// assert_eq!(bead.mass(), 72.0);
+    /// assert_eq!(bead.bead_radius(), Some(2.35));
+    /// ```
+    pub fn bead<'a>(name: impl Into<&'a str>, mass: f64, radius: f64) -> Atom {
+        let mut atom = Atom::new(name);
+        atom.set_mass(mass);
+        atom.set(BEAD_RADIUS, radius);
+        return atom;
+    }
+
+    /// Get the radius of this atom if it is a coarse-grained bead, i.e. the
+    /// value of the `"radius"` property. This returns `None` if the property
+    /// is missing or is not a number.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Atom;
+    /// assert_eq!(Atom::new("C").bead_radius(), None);
+    /// assert_eq!(Atom::bead("C1", 72.0, 2.35).bead_radius(), Some(2.35));
+    /// ```
+    pub fn bead_radius(&self) -> Option<f64> {
+        match self.get(BEAD_RADIUS) {
+            Some(Property::Double(radius)) => Some(radius),
+            _ => None,
+        }
+    }
+
+    /// Check if this atom is a virtual site, *i.e.* a massless particle such
+    /// as the charge site of TIP4P water models or lone pairs. Atoms are
+    /// virtual sites if they are flagged as such with
+    /// [`Atom::set_virtual_site`], or if their mass is zero.
+    ///
+    /// Virtual sites are excluded from mass-weighted analyses such as
+    /// [`Frame::center_of_mass`] and [`Frame::temperature`].
+    ///
+    /// [`Frame::center_of_mass`]: crate::Frame::center_of_mass
+    /// [`Frame::temperature`]: crate::Frame::temperature
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Atom;
+    /// let atom = Atom::new("MW");
+    /// assert_eq!(atom.mass(), 0.0);
+    /// assert!(atom.is_virtual_site());
+    ///
+    /// let mut atom = Atom::new("O");
+    /// assert!(!atom.is_virtual_site());
+    /// atom.set_virtual_site(true);
+    /// assert!(atom.is_virtual_site());
+    /// ```
+    pub fn is_virtual_site(&self) -> bool {
+        return self.has_virtual_site_flag() || self.mass() == 0.0;
+    }
+
+    /// Flag this atom as a virtual site (or remove the flag), using the
+    /// `"virtual_site"` property. This is useful for virtual sites with a
+    /// non-zero mass in the file, which should still be excluded from
+    /// mass-weighted analyses.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Atom, Property};
+    /// let mut atom = Atom::new("M");
+    /// atom.set_virtual_site(true);
+    /// assert_eq!(atom.get("virtual_site"), Some(Property::Bool(true)));
+    /// ```
+    pub fn set_virtual_site(&mut self, virtual_site: bool) {
+        self.set(VIRTUAL_SITE, virtual_site);
+    }
+
+    /// Check if this atom has the `"virtual_site"` flag set
+    pub(crate) fn has_virtual_site_flag(&self) -> bool {
+        return self.get(VIRTUAL_SITE) == Some(Property::Bool(true));
+    }
+
     /// Get the atom mass, in atomic mass units.
     ///
     /// # Example
@@ -376,24 +677,59 @@ impl Atom {
         }
     }
 
-    /// Get an iterator over all (name, property) pairs for this atom
+    /// Get a copy of this atom without the property with the given `name`.
     ///
-    /// # Examples
+    /// The C API of chemfiles does not support removing properties, and an
+    /// `Atom` can be a view inside a `Frame` or `Topology` which can not be
+    /// replaced, so this function returns a new atom instead of modifying
+    /// this one. Use [`crate::Frame::remove_atom_property`] to remove a
+    /// property from all the atoms in a frame.
+    ///
+    /// # Example
     /// ```
     /// # use chemfiles::{Atom, Property};
     /// let mut atom = Atom::new("He");
-    /// atom.set("foo", Property::Double(22.2));
-    /// atom.set("bar", Property::Bool(false));
+    /// atom.set("foo", 2.0);
+    /// atom.set("bar", false);
     ///
-    /// for (name, property) in atom.properties() {
-    ///     if name == "foo" {
-    ///         assert_eq!(property, Property::Double(22.2));
-    ///     } else if name == "bar" {
-    ///         assert_eq!(property, Property::Bool(false));
-    ///     }
-    /// }
+    /// let atom = atom.without_property("foo");
+    /// assert_eq!(atom.get("foo"), None);
+    /// assert_eq!(atom.get("bar"), Some(Property::Bool(false)));
+    /// assert_eq!(atom.name(), "He");
     /// ```
-    pub fn properties(&self) -> PropertiesIter {
+    #[must_use]
+    pub fn without_property(&self, name: &str) -> Atom {
+        return self.filtered_copy(&|other| other != name);
+    }
+
+    /// Get a copy of this atom, only keeping the properties for which `keep`
+    /// returns `true`.
+    pub(crate) fn filtered_copy(&self, keep: &dyn Fn(&str) -> bool) -> Atom {
+        let mut atom = Atom::new(&*self.name());
+        atom.set_atomic_type(&*self.atomic_type());
+        atom.set_mass(self.mass());
+        atom.set_charge(self.charge());
+        for (name, property) in self.properties() {
+            if keep(&name) {
+                atom.set(&name, property);
+            }
+        }
+        return atom;
+    }
+
+    /// Get the names of all properties of this atom, sorted in
+    /// alphabetical order.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Atom;
+    /// let mut atom = Atom::new("He");
+    /// atom.set("foo", 1.0);
+    /// atom.set("bar", false);
+    ///
+    /// assert_eq!(atom.properties_names(), ["bar", "foo"]);
+    /// ```
+    pub fn properties_names(&self) -> Vec<String> {
         let mut count = 0;
         unsafe {
             check_success(ffi::chfl_atom_properties_count(self.as_ptr(), &mut count));
@@ -410,18 +746,52 @@ impl Atom {
             ));
         }
 
-        let mut names = Vec::new();
-        for ptr in c_names {
-            names.push(strings::from_c(ptr));
-        }
+        let mut names = c_names.into_iter().map(|ptr| strings::from_c(ptr)).collect::<Vec<_>>();
+        names.sort_unstable();
+        return names;
+    }
 
+    /// Get an iterator over all (name, property) pairs for this atom, in
+    /// alphabetical order of the names.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chemfiles::{Atom, Property};
+    /// let mut atom = Atom::new("He");
+    /// atom.set("foo", Property::Double(22.2));
+    /// atom.set("bar", Property::Bool(false));
+    ///
+    /// let properties = atom.properties().collect::<Vec<_>>();
+    /// assert_eq!(properties, [
+    ///     ("bar".to_string(), Property::Bool(false)),
+    ///     ("foo".to_string(), Property::Double(22.2)),
+    /// ]);
+    /// ```
+    pub fn properties(&self) -> PropertiesIter {
         PropertiesIter {
-            names: names.into_iter(),
+            names: self.properties_names().into_iter(),
             getter: Box::new(move |name| self.get(name).expect("failed to get property")),
         }
     }
 }
 
+impl PartialEq for Atom {
+    /// Two atoms are equal if they have the same name, type, mass, charge and
+    /// properties.
+    fn eq(&self, other: &Atom) -> bool {
+        if self.name() != other.name()
+            || self.atomic_type() != other.atomic_type()
+            || self.mass() != other.mass()
+            || self.charge() != other.charge()
+        {
+            return false;
+        }
+
+        // properties are sorted by name
+        return self.properties().eq(other.properties());
+    }
+}
+
 impl Drop for Atom {
     fn drop(&mut self) {
         unsafe {
@@ -511,6 +881,59 @@ mod test {
         assert_eq!(atom.atomic_number(), 0);
     }
 
+    #[test]
+    fn virtual_site() {
+        let mut atom = Atom::new("O");
+        assert!(!atom.is_virtual_site());
+        atom.set_virtual_site(true);
+        assert!(atom.is_virtual_site());
+        atom.set_virtual_site(false);
+        assert!(!atom.is_virtual_site());
+
+        atom.set_mass(0.0);
+        assert!(atom.is_virtual_site());
+    }
+
+    #[test]
+    fn bead() {
+        let bead = Atom::bead("SC1", 45.0, 2.1);
+        assert_eq!(bead.name(), "SC1");
+        assert_eq!(bead.atomic_type(), "SC1");
+        assert_eq!(bead.mass(), 45.0);
+        assert_eq!(bead.bead_radius(), Some(2.1));
+
+        let mut atom = Atom::new("C");
+        assert_eq!(atom.bead_radius(), None);
+        atom.set("radius", "large");
+        assert_eq!(atom.bead_radius(), None);
+    }
+
+    #[test]
+    fn isotopes() {
+        let atom = Atom::from_atomic_number(1).unwrap();
+        assert_eq!(atom.name(), "H");
+        let atom = Atom::from_atomic_number(118).unwrap();
+        assert_eq!(atom.atomic_type(), "Og");
+
+        let error = Atom::from_atomic_number(119).unwrap_err();
+        assert_eq!(error.status, Status::InvalidArgument);
+        assert_eq!(error.message, "there is no element with atomic number 119");
+
+        let mut atom = Atom::from_atomic_number(1).unwrap();
+        atom.set_mass_number(2).unwrap();
+        assert_eq!(atom.mass(), 2.014_101_778_12);
+        assert_eq!(atom.mass_number(), Some(2));
+
+        let error = atom.set_mass_number(5).unwrap_err();
+        assert_eq!(error.status, Status::InvalidArgument);
+        assert_eq!(atom.mass_number(), Some(2));
+
+        let mut atom = Atom::new("CA");
+        atom.set_atomic_type("Xxx");
+        let error = atom.set_mass_number(12).unwrap_err();
+        assert_eq!(error.status, Status::InvalidArgument);
+    }
+
     #[test]
     fn property() {
         let mut atom = Atom::new("F");
@@ -528,4 +951,26 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn equality() {
+        let mut atom = Atom::new("He");
+        assert_eq!(atom, Atom::new("He"));
+        assert_eq!(atom, atom.clone());
+        assert_ne!(atom, Atom::new("Ne"));
+
+        let mut other = atom.clone();
+        other.set_charge(1.0);
+        assert_ne!(atom, other);
+
+        atom.set("foo", 3.0);
+        atom.set("bar", "baz");
+        let mut other = Atom::new("He");
+        other.set("bar", "baz");
+        assert_ne!(atom, other);
+        other.set("foo", 3.0);
+        assert_eq!(atom, other);
+        other.set("foo", 4.0);
+        assert_ne!(atom, other);
+    }
 }
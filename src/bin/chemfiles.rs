@@ -0,0 +1,268 @@
+// Chemfiles, a modern library for chemistry file reading and writing
+// Copyright (C) 2015-2018 Guillaume Fraux -- BSD licensed
+
+//! Command line interface to chemfiles, built with the `cli` feature.
+//!
+//! This binary is a thin layer on top of the library API, and is mainly
+//! intended to quickly check how chemfiles reads and writes a given file.
+
+#![warn(clippy::all, clippy::pedantic)]
+#![allow(clippy::needless_return)]
+
+use std::process::ExitCode;
+
+use chemfiles::transform::{SortAtoms, Wrap};
+use chemfiles::{BondOutput, ConvertJob, Error, Frame, Selection, Trajectory};
+
+const USAGE: &str = "usage: chemfiles <command> [options]
+
+commands:
+    formats                         list all the formats supported by chemfiles
+    info <file>                     show information about the frames in a file
+    select <selection> <file>       list the atoms matching a selection
+    convert <input> <output>        convert a file to another format
+
+options:
+    --format <format>               format of the input file (info, select, convert)
+    --output-format <format>        format of the output file (convert)
+    --topology <file>               read the topology from this file (convert)
+    --select <selection>            only keep the atoms matching this selection (convert)
+    --bonds <all|hetero|none>       which bonds to write in the output (convert)
+    --wrap                          wrap the atoms inside the unit cell (convert)
+    --sort                          sort the atoms by residue and name (convert)
+    --stride <stride>               only convert one frame every <stride> (convert)
+    --step <step>                   step to use (select, default 0)";
+
+/// Command line options shared by all the commands
+struct Options {
+    arguments: Vec<String>,
+    format: Option<String>,
+    output_format: Option<String>,
+    topology: Option<String>,
+    selection: Option<String>,
+    bonds: BondOutput,
+    wrap: bool,
+    sort: bool,
+    step: usize,
+    stride: usize,
+}
+
+impl Options {
+    fn parse(args: impl Iterator<Item = String>) -> Result<Options, String> {
+        let mut options = Options {
+            arguments: Vec::new(),
+            format: None,
+            output_format: None,
+            topology: None,
+            selection: None,
+            bonds: BondOutput::All,
+            wrap: false,
+            sort: false,
+            step: 0,
+            stride: 1,
+        };
+        let mut args = args.peekable();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("missing value for {arg}"));
+            match arg.as_str() {
+                "--format" => options.format = Some(value()?),
+                "--output-format" => options.output_format = Some(value()?),
+                "--topology" => options.topology = Some(value()?),
+                "--select" => options.selection = Some(value()?),
+                "--bonds" => {
+                    options.bonds = match value()?.as_str() {
+                        "all" => BondOutput::All,
+                        "hetero" => BondOutput::Hetero,
+                        "none" => BondOutput::None,
+                        other => return Err(format!("invalid value '{other}' for --bonds")),
+                    };
+                }
+                "--step" => {
+                    let step = value()?;
+                    options.step = step.parse().map_err(|_| format!("invalid step '{step}'"))?;
+                }
+                "--stride" => {
+                    let stride = value()?;
+                    options.stride = match stride.parse() {
+                        Ok(stride) if stride > 0 => stride,
+                        _ => return Err(format!("invalid stride '{stride}'")),
+                    };
+                }
+                "--wrap" => options.wrap = true,
+                "--sort" => options.sort = true,
+                _ if arg.starts_with("--") => return Err(format!("unknown option {arg}")),
+                _ => options.arguments.push(arg),
+            }
+        }
+        return Ok(options);
+    }
+
+    /// Check that exactly `count` positional arguments were given
+    fn expect_arguments(&self, count: usize) -> Result<(), String> {
+        if self.arguments.len() == count {
+            Ok(())
+        } else {
+            Err(format!("expected {count} arguments, got {}", self.arguments.len()))
+        }
+    }
+}
+
+fn open(path: &str, mode: char, format: Option<&str>) -> Result<Trajectory, Error> {
+    match format {
+        Some(format) => Trajectory::open_with_format(path, mode, format),
+        None => Trajectory::open(path, mode),
+    }
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
+fn formats() {
+    println!("{:<20} {:<10} {:<5} {:<5} description", "name", "extension", "read", "write");
+    for format in chemfiles::formats_list() {
+        println!(
+            "{:<20} {:<10} {:<5} {:<5} {}",
+            format.name,
+            format.extension.unwrap_or(""),
+            yes_no(format.read),
+            yes_no(format.write),
+            format.description
+        );
+    }
+}
+
+fn info(options: &Options) -> Result<(), Box<dyn std::error::Error>> {
+    options.expect_arguments(1)?;
+    let path = &options.arguments[0];
+    let mut trajectory = open(path, 'r', options.format.as_deref())?;
+
+    let format = match options.format {
+        Some(ref format) => format.clone(),
+        None => chemfiles::guess_format(path)?,
+    };
+    println!("file: {path}");
+    println!("format: {format}");
+    println!("steps: {}", trajectory.nsteps());
+
+    let frame = match trajectory.read_next()? {
+        Some(frame) => frame,
+        None => return Ok(()),
+    };
+    let cell = frame.cell();
+    println!("first frame:");
+    println!("    atoms: {}", frame.size());
+    println!("    residues: {}", frame.residues_count());
+    println!("    bonds: {}", frame.bonds().len());
+    println!("    cell: {:?}", cell.shape());
+    println!("    cell lengths: {:?}", cell.lengths());
+    println!("    cell angles: {:?}", cell.angles());
+    println!("    velocities: {}", yes_no(frame.has_velocities()));
+    for (name, property) in frame.properties() {
+        println!("    property {name}: {property:?}");
+    }
+    return Ok(());
+}
+
+fn select(options: &Options) -> Result<(), Box<dyn std::error::Error>> {
+    options.expect_arguments(2)?;
+    let mut selection = Selection::new(&*options.arguments[0])?;
+    let mut trajectory = open(&options.arguments[1], 'r', options.format.as_deref())?;
+
+    let mut frame = Frame::new();
+    trajectory.read_step(options.step, &mut frame)?;
+    for matched in selection.evaluate(&frame) {
+        let atoms = matched
+            .iter()
+            .map(|&i| format!("{i} ({})", frame.atom(i).name()))
+            .collect::<Vec<_>>();
+        println!("{}", atoms.join(", "));
+    }
+    return Ok(());
+}
+
+fn convert(options: &Options) -> Result<(), Box<dyn std::error::Error>> {
+    options.expect_arguments(2)?;
+    let mut job = ConvertJob::new(&options.arguments[0], &options.arguments[1])
+        .bond_output(options.bonds)
+        .stride(options.stride);
+
+    if let Some(ref format) = options.format {
+        job = job.input_format(format);
+    }
+    if let Some(ref format) = options.output_format {
+        job = job.output_format(format);
+    }
+    if let Some(ref topology) = options.topology {
+        job = job.topology(topology);
+    }
+    if options.wrap {
+        job = job.with(Wrap);
+    }
+    if options.sort {
+        job = job.with(SortAtoms);
+    }
+    if let Some(ref selection) = options.selection {
+        job = job.select(selection);
+    }
+
+    let report = job.run()?;
+    for warning in &report.warnings {
+        eprintln!("warning: {warning}");
+    }
+    println!(
+        "converted {} of {} steps in {:.2}s",
+        report.frames_written,
+        report.steps,
+        report.duration.as_secs_f64()
+    );
+    return Ok(());
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let command = if let Some(command) = args.next() {
+        command
+    } else {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    };
+
+    let options = match Options::parse(args) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("error: {message}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let result = match command.as_str() {
+        "formats" => {
+            formats();
+            Ok(())
+        }
+        "info" => info(&options),
+        "select" => select(&options),
+        "convert" => convert(&options),
+        "help" | "--help" | "-h" => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        _ => {
+            eprintln!("error: unknown command '{command}'\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,230 @@
+// Chemfiles, a modern library for chemistry file reading and writing
+// Copyright (C) 2015-2018 Guillaume Fraux -- BSD licensed
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{Error, Status};
+use crate::{Frame, Trajectory};
+
+/// Name of the frame property used to store bookmarks
+pub(crate) const BOOKMARK: &str = "bookmark";
+
+/// `Bookmarks` associate labels (`"equilibrated"`, `"production"`, ...) with
+/// steps of a trajectory file, instead of hard-coding step numbers in analysis
+/// scripts.
+///
+/// Bookmarks are stored in a small JSON sidecar file next to the trajectory
+/// (see [`Bookmarks::sidecar`]), mapping each label to a step:
+///
+/// ```json
+/// {
+///   "equilibrated": 500,
+///   "production": 1000
+/// }
+/// ```
+///
+/// Alternatively, bookmarks can be stored in the `"bookmark"` property of the
+/// frames when writing them (see [`Frame::set_bookmark`]), for formats which
+/// support frame properties. [`Bookmarks::scan`] can then be used to collect
+/// them.
+///
+/// [`Frame::set_bookmark`]: crate::Frame::set_bookmark
+///
+/// # Example
+/// ```no_run
+/// # use chemfiles::{Bookmarks, Trajectory};
+/// let mut bookmarks = Bookmarks::new();
+/// bookmarks.insert("equilibrated", 500);
+/// bookmarks.save("trajectory.xtc").unwrap();
+///
+/// // later, in another script
+/// let mut trajectory = Trajectory::open("trajectory.xtc", 'r').unwrap();
+/// let frame = trajectory.read_bookmark("equilibrated").unwrap();
+/// assert_eq!(frame.step(), 500);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Bookmarks {
+    steps: BTreeMap<String, usize>,
+}
+
+impl Bookmarks {
+    /// Create a new empty set of bookmarks
+    pub fn new() -> Bookmarks {
+        return Bookmarks::default();
+    }
+
+    /// Get the path of the sidecar file containing the bookmarks for the
+    /// trajectory at `path`. This is the path of the trajectory with an
+    /// additional `.bookmarks.json` extension.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Bookmarks;
+    /// # use std::path::Path;
+    /// let sidecar = Bookmarks::sidecar("data/water.xyz");
+    /// assert_eq!(sidecar, Path::new("data/water.xyz.bookmarks.json"));
+    /// ```
+    pub fn sidecar(path: impl AsRef<Path>) -> PathBuf {
+        let mut sidecar = path.as_ref().as_os_str().to_owned();
+        sidecar.push(".bookmarks.json");
+        return sidecar.into();
+    }
+
+    /// Load the bookmarks for the trajectory at `path` from the corresponding
+    /// sidecar file. If the sidecar file does not exist, this returns an empty
+    /// set of bookmarks.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the sidecar file exists but can not be read, or
+    /// if it does not contain valid bookmarks.
+    pub fn load(path: impl AsRef<Path>) -> Result<Bookmarks, Error> {
+        let sidecar = Bookmarks::sidecar(path);
+        let content = match std::fs::read_to_string(&sidecar) {
+            Ok(content) => content,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Bookmarks::new()),
+            Err(error) => {
+                return Err(Error {
+                    status: Status::FileError,
+                    message: format!("failed to read bookmarks at '{}': {error}", sidecar.display()),
+                })
+            }
+        };
+
+        return Bookmarks::from_json(&content).map_err(|message| Error {
+            status: Status::FormatError,
+            message: format!("invalid bookmarks file at '{}': {message}", sidecar.display()),
+        });
+    }
+
+    /// Save these bookmarks to the sidecar file of the trajectory at `path`,
+    /// replacing any existing sidecar file.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the sidecar file can not be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let sidecar = Bookmarks::sidecar(path);
+        return std::fs::write(&sidecar, self.to_json()).map_err(|error| Error {
+            status: Status::FileError,
+            message: format!("failed to write bookmarks at '{}': {error}", sidecar.display()),
+        });
+    }
+
+    /// Collect the bookmarks stored in the `"bookmark"` property of the frames
+    /// in `trajectory`, by reading all the frames. If multiple frames use the
+    /// same label, the first one is used.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if any frame can not be read.
+    pub fn scan(trajectory: &mut Trajectory) -> Result<Bookmarks, Error> {
+        let mut bookmarks = Bookmarks::new();
+        let mut frame = Frame::new();
+        for step in 0..trajectory.nsteps() {
+            trajectory.read_step(step, &mut frame)?;
+            if let Some(label) = frame.bookmark() {
+                let _ = bookmarks.steps.entry(label).or_insert(step);
+            }
+        }
+        return Ok(bookmarks);
+    }
+
+    /// Associate `label` with the given `step`, replacing any previous step
+    /// for this label.
+    pub fn insert(&mut self, label: &str, step: usize) {
+        let _ = self.steps.insert(label.into(), step);
+    }
+
+    /// Remove the bookmark with the given `label`, returning the
+    /// corresponding step if it was present.
+    pub fn remove(&mut self, label: &str) -> Option<usize> {
+        return self.steps.remove(label);
+    }
+
+    /// Get the step associated with `label`, if any.
+    pub fn get(&self, label: &str) -> Option<usize> {
+        return self.steps.get(label).copied();
+    }
+
+    /// Get an iterator over all the labels and steps, in alphabetical order
+    /// of the labels.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, usize)> {
+        return self.steps.iter().map(|(label, &step)| (label.as_str(), step));
+    }
+
+    /// Get the number of bookmarks
+    pub fn len(&self) -> usize {
+        return self.steps.len();
+    }
+
+    /// Check if there are no bookmarks
+    pub fn is_empty(&self) -> bool {
+        return self.steps.is_empty();
+    }
+
+    /// Write these bookmarks as a JSON object
+    fn to_json(&self) -> String {
+        let mut json = serde_json::to_string_pretty(self).expect("failed to serialize bookmarks");
+        json.push('\n');
+        return json;
+    }
+
+    /// Read bookmarks from a JSON object mapping labels to steps
+    fn from_json(json: &str) -> Result<Bookmarks, String> {
+        return serde_json::from_str(json).map_err(|error| error.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json() {
+        let mut bookmarks = Bookmarks::new();
+        assert_eq!(bookmarks.to_json(), "{}\n");
+        assert_eq!(Bookmarks::from_json(" { } ").unwrap(), bookmarks);
+
+        bookmarks.insert("production", 1000);
+        bookmarks.insert("equilibrated", 500);
+        bookmarks.insert("with \"quotes\"", 3);
+        let json = bookmarks.to_json();
+        assert_eq!(
+            json,
+            "{\n  \"equilibrated\": 500,\n  \"production\": 1000,\n  \"with \\\"quotes\\\"\": 3\n}\n"
+        );
+        assert_eq!(Bookmarks::from_json(&json).unwrap(), bookmarks);
+
+        let bookmarks = Bookmarks::from_json(r#"{"café": 2,"a\nb":0}"#).unwrap();
+        assert_eq!(bookmarks.get("café"), Some(2));
+        assert_eq!(bookmarks.get("a\nb"), Some(0));
+
+        let bookmarks = Bookmarks::from_json(r#"{"\uD83D\uDE00": 1}"#).unwrap();
+        assert_eq!(bookmarks.get("\u{1F600}"), Some(1));
+
+        assert!(Bookmarks::from_json("").is_err());
+        assert!(Bookmarks::from_json(r#"{"a": -1}"#).is_err());
+        assert!(Bookmarks::from_json(r#"{"a": 1,}"#).is_err());
+        assert!(Bookmarks::from_json(r#"{"a": 1} 2"#).is_err());
+        assert!(Bookmarks::from_json(r#"{"a: 1}"#).is_err());
+    }
+
+    #[test]
+    fn sidecar() {
+        let path = "test-bookmarks-tmp.xyz";
+        assert!(Bookmarks::load(path).unwrap().is_empty());
+
+        let mut bookmarks = Bookmarks::new();
+        bookmarks.insert("start", 0);
+        bookmarks.insert("end", 12);
+        bookmarks.save(path).unwrap();
+
+        assert_eq!(Bookmarks::load(path).unwrap(), bookmarks);
+        assert_eq!(bookmarks.iter().collect::<Vec<_>>(), [("end", 12), ("start", 0)]);
+        std::fs::remove_file(Bookmarks::sidecar(path)).unwrap();
+    }
+}
@@ -5,7 +5,8 @@ use std::marker::PhantomData;
 #[allow(clippy::wildcard_imports)]
 use chemfiles_sys as ffi;
 
-use crate::errors::{check, check_not_null, check_success, Error};
+use crate::errors::{check, check_not_null, check_success, Error, Status};
+use crate::math;
 
 /// Available unit cell shapes.
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -73,6 +74,14 @@ impl<'a> std::ops::Deref for UnitCellRef<'a> {
 }
 
 /// An analog to a mutable reference to an unit cell (`&mut UnitCell`)
+///
+/// The `UnitCell` accessible through this type points inside another object (for
+/// example a `Frame`), and must not be moved out of this `UnitCellMut`. Since
+/// `UnitCellMut` implements `DerefMut`, this could be done with `std::mem::swap`
+/// or `std::mem::replace`, giving an owned `UnitCell` pointing into memory which
+/// can later be invalidated. Such a moved-out value must be treated as a
+/// borrow of the original object, and must not be used after this object is
+/// modified or dropped.
 #[derive(Debug)]
 pub struct UnitCellMut<'a> {
     inner: UnitCell,
@@ -208,6 +217,124 @@ impl UnitCell {
         }
     }
 
+    /// Create a cubic `UnitCell` with the given length `a` (in Angstroms) for
+    /// all three cell vectors.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if `a` is not a positive finite number.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{UnitCell, CellShape};
+    /// let cell = UnitCell::cubic(20.0).unwrap();
+    /// assert_eq!(cell.lengths(), [20.0, 20.0, 20.0]);
+    /// assert_eq!(cell.shape(), CellShape::Orthorhombic);
+    ///
+    /// assert!(UnitCell::cubic(-3.0).is_err());
+    /// ```
+    pub fn cubic(a: f64) -> Result<UnitCell, Error> {
+        return UnitCell::from_lengths_angles([a, a, a], [90.0, 90.0, 90.0]);
+    }
+
+    /// Create an hexagonal `UnitCell`, with two cell vectors of length `a`
+    /// separated by 120°, and a perpendicular vector of length `c` (lengths in
+    /// Angstroms).
+    ///
+    /// # Errors
+    ///
+    /// This function fails if `a` or `c` are not positive finite numbers.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{UnitCell, CellShape};
+    /// let cell = UnitCell::hexagonal(3.0, 5.0).unwrap();
+    /// assert_eq!(cell.lengths(), [3.0, 3.0, 5.0]);
+    /// assert_eq!(cell.shape(), CellShape::Triclinic);
+    /// ```
+    pub fn hexagonal(a: f64, c: f64) -> Result<UnitCell, Error> {
+        return UnitCell::from_lengths_angles([a, a, c], [90.0, 90.0, 120.0]);
+    }
+
+    /// Create a rhombohedral `UnitCell`, with three cell vectors of length `a`
+    /// (in Angstroms) and the same angle `alpha` (in degrees) between each
+    /// pair of vectors.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if `a` is not a positive finite number, or if
+    /// `alpha` is not strictly between 0° and 120°.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::UnitCell;
+    /// let cell = UnitCell::rhombohedral(5.0, 60.0).unwrap();
+    /// assert_eq!(cell.lengths(), [5.0, 5.0, 5.0]);
+    ///
+    /// assert!(UnitCell::rhombohedral(5.0, 130.0).is_err());
+    /// ```
+    pub fn rhombohedral(a: f64, alpha: f64) -> Result<UnitCell, Error> {
+        return UnitCell::from_lengths_angles([a, a, a], [alpha, alpha, alpha]);
+    }
+
+    /// Create an `UnitCell` from the three lengths (in Angstroms) and three
+    /// angles (in degrees), checking that they describe a valid cell. See
+    /// [`UnitCell::triclinic`] for the definition of the angles.
+    ///
+    /// The cell is `Orthorhombic` if all the angles are 90°, and `Triclinic`
+    /// otherwise.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if any length is not a positive finite number, if
+    /// any angle is not strictly between 0° and 180°, or if the angles can
+    /// not be realized by three vectors in space (for example if one angle is
+    /// larger than the sum of the two others).
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{UnitCell, CellShape};
+    /// let cell = UnitCell::from_lengths_angles([10.0, 11.0, 12.0], [90.0, 90.0, 90.0]).unwrap();
+    /// assert_eq!(cell.shape(), CellShape::Orthorhombic);
+    ///
+    /// let cell = UnitCell::from_lengths_angles([10.0, 11.0, 12.0], [80.0, 90.0, 100.0]).unwrap();
+    /// assert_eq!(cell.shape(), CellShape::Triclinic);
+    ///
+    /// assert!(UnitCell::from_lengths_angles([10.0, 11.0, 12.0], [30.0, 30.0, 90.0]).is_err());
+    /// ```
+    pub fn from_lengths_angles(lengths: [f64; 3], angles: [f64; 3]) -> Result<UnitCell, Error> {
+        let invalid = |message: String| Error {
+            status: Status::InvalidArgument,
+            message,
+        };
+
+        if lengths.iter().any(|&length| !(length.is_finite() && length > 0.0)) {
+            return Err(invalid(format!(
+                "invalid unit cell lengths {lengths:?}, they must be positive"
+            )));
+        }
+
+        if angles.iter().any(|&angle| !(angle > 0.0 && angle < 180.0)) {
+            return Err(invalid(format!(
+                "invalid unit cell angles {angles:?}, they must be between 0 and 180 degrees"
+            )));
+        }
+
+        let [alpha, beta, gamma] = angles;
+        let sum = alpha + beta + gamma;
+        if sum >= 360.0 || alpha >= beta + gamma || beta >= alpha + gamma || gamma >= alpha + beta {
+            return Err(invalid(format!(
+                "invalid unit cell angles {angles:?}, they do not describe a valid cell"
+            )));
+        }
+
+        #[allow(clippy::float_cmp)]
+        if angles.iter().all(|&angle| angle == 90.0) {
+            return Ok(UnitCell::new(lengths));
+        }
+        return Ok(UnitCell::triclinic(lengths, angles));
+    }
+
     /// Create an `UnitCell` from a cell matrix. If `matrix` contains only
     /// zeros, then an `Infinite` cell is created. If only the diagonal of the
     /// matrix is non-zero, then the cell is `Orthorhombic`. Else a
@@ -418,6 +545,48 @@ impl UnitCell {
             check_success(ffi::chfl_cell_wrap(self.as_ptr(), vector.as_mut_ptr()));
         }
     }
+
+    /// Get the Niggli-reduced cell corresponding to this cell, together with
+    /// the transformation matrix `T` from this cell to the reduced cell.
+    ///
+    /// The Niggli-reduced cell is a unique representation of the lattice
+    /// described by this cell, with the shortest possible cell vectors,
+    /// which allows to compare structures coming from different codes. The
+    /// cell vectors of the reduced cell are given by `M T`, where `M` is the
+    /// matrix of this cell (see [`UnitCell::matrix`]). `T` only contains
+    /// integers and has a determinant of 1. Use
+    /// [`crate::Frame::apply_cell_transform`] to change the cell of a frame
+    /// while keeping the same crystal.
+    ///
+    /// Infinite cells are returned unchanged, with the identity matrix.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::UnitCell;
+    /// let cell = UnitCell::from_matrix([
+    ///     [10.0, 10.0, 0.0],
+    ///     [0.0, 10.0, 0.0],
+    ///     [0.0, 0.0, 10.0],
+    /// ]);
+    ///
+    /// let (reduced, transform) = cell.niggli_reduced();
+    /// let lengths = reduced.lengths();
+    /// assert!((lengths[0] - 10.0).abs() < 1e-9);
+    /// assert!((lengths[1] - 10.0).abs() < 1e-9);
+    /// assert!((lengths[2] - 10.0).abs() < 1e-9);
+    /// ```
+    #[must_use]
+    pub fn niggli_reduced(&self) -> (UnitCell, [[f64; 3]; 3]) {
+        let identity = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+        if self.shape() == CellShape::Infinite {
+            return (self.clone(), identity);
+        }
+
+        let matrix = self.matrix();
+        let transform = math::niggli_reduction(&matrix);
+        let reduced = UnitCell::from_matrix(math::matrix_matrix(&matrix, &transform));
+        return (reduced, transform);
+    }
 }
 
 impl Drop for UnitCell {
@@ -467,6 +636,63 @@ mod test {
         crate::assert_vector3d_eq(&cell.angles(), &[80.0, 90.0, 100.0], 1e-6);
     }
 
+    #[test]
+    fn constructors() {
+        let cell = UnitCell::cubic(3.0).unwrap();
+        assert_eq!(cell.shape(), CellShape::Orthorhombic);
+        assert_eq!(cell.volume(), 27.0);
+
+        let cell = UnitCell::hexagonal(2.0, 4.0).unwrap();
+        crate::assert_vector3d_eq(&cell.angles(), &[90.0, 90.0, 120.0], 1e-6);
+        approx::assert_ulps_eq!(cell.volume(), 2.0 * 2.0 * 4.0 * f64::sqrt(3.0) / 2.0, epsilon = 1e-12);
+
+        let cell = UnitCell::rhombohedral(2.0, 70.0).unwrap();
+        crate::assert_vector3d_eq(&cell.angles(), &[70.0, 70.0, 70.0], 1e-6);
+
+        for (lengths, angles) in [
+            ([0.0, 1.0, 1.0], [90.0, 90.0, 90.0]),
+            ([f64::NAN, 1.0, 1.0], [90.0, 90.0, 90.0]),
+            ([1.0, 1.0, 1.0], [0.0, 90.0, 90.0]),
+            ([1.0, 1.0, 1.0], [90.0, 180.0, 90.0]),
+            ([1.0, 1.0, 1.0], [120.0, 120.0, 120.0]),
+            ([1.0, 1.0, 1.0], [20.0, 30.0, 60.0]),
+        ] {
+            let error = UnitCell::from_lengths_angles(lengths, angles).unwrap_err();
+            assert_eq!(error.status, Status::InvalidArgument);
+        }
+        assert!(UnitCell::cubic(f64::INFINITY).is_err());
+        assert!(UnitCell::hexagonal(1.0, -1.0).is_err());
+    }
+
+    #[test]
+    fn niggli() {
+        // example from Krivy & Gruber (1976)
+        let cell = UnitCell::triclinic(
+            [3.0, 5.196_152_422_706_632, 2.0],
+            [103.919_748_556_095_6, 109.471_220_634_490_7, 134.882_107_116_805_6],
+        );
+        let (reduced, transform) = cell.niggli_reduced();
+
+        let reduced_squared = reduced.lengths().map(|length| length * length);
+        crate::assert_vector3d_eq(&reduced_squared, &[4.0, 9.0, 9.0], 1e-6);
+        approx::assert_ulps_eq!(reduced.volume(), cell.volume(), epsilon = 1e-6);
+        approx::assert_ulps_eq!(math::determinant(&transform), 1.0, epsilon = 1e-12);
+        for row in transform {
+            for value in row {
+                assert_eq!(value, value.round());
+            }
+        }
+
+        // reducing again gives the same cell
+        let (again, transform) = reduced.niggli_reduced();
+        crate::assert_vector3d_eq(&again.lengths(), &reduced.lengths(), 1e-9);
+        crate::assert_vector3d_eq(&again.angles(), &reduced.angles(), 1e-6);
+        assert_eq!(transform, [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]);
+
+        let (infinite, _) = UnitCell::infinite().niggli_reduced();
+        assert_eq!(infinite.shape(), CellShape::Infinite);
+    }
+
     #[test]
     fn volume() {
         let cell = UnitCell::new([2.0, 3.0, 4.0]);
@@ -0,0 +1,169 @@
+// Chemfiles, a modern library for chemistry file reading and writing
+// Copyright (C) 2015-2018 Guillaume Fraux -- BSD licensed
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::errors::{Error, Status};
+
+/// A `ChargeTable` associates partial charges to atoms, using the name of the
+/// atom and the name of the residue containing it. This is typically built
+/// from force-field templates, and used with [`Topology::apply_charges`] to
+/// add charges to systems read from formats without them (XYZ, GRO, ...).
+///
+/// Charge tables can be read from a simple text format, where each line
+/// contains a residue name, an atom name and a charge separated by
+/// whitespace. Empty lines and everything after a `#` are ignored.
+///
+/// ```text
+/// # TIP3P water
+/// HOH  O   -0.834
+/// HOH  H1   0.417
+/// HOH  H2   0.417
+/// ```
+///
+/// [`Topology::apply_charges`]: crate::Topology::apply_charges
+///
+/// # Example
+/// ```
+/// # use chemfiles::{ChargeTable, Frame, Residue, Atom};
+/// let table: ChargeTable = "HOH O -0.834\nHOH H 0.417".parse().unwrap();
+/// assert_eq!(table.get("HOH", "O"), Some(-0.834));
+///
+/// let mut frame = Frame::new();
+/// frame.add_atom(&Atom::new("O"), [0.0, 0.0, 0.0], None);
+/// frame.add_atom(&Atom::new("H"), [1.0, 0.0, 0.0], None);
+/// let mut residue = Residue::new("HOH");
+/// residue.add_atom(0);
+/// residue.add_atom(1);
+/// frame.add_residue(&residue).unwrap();
+///
+/// let mut topology = frame.topology().clone();
+/// assert_eq!(topology.apply_charges(&table.as_map()), 2);
+/// frame.set_topology(&topology).unwrap();
+///
+/// assert_eq!(frame.atom(1).charge(), 0.417);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChargeTable {
+    charges: HashMap<(String, String), f64>,
+}
+
+impl ChargeTable {
+    /// Create a new empty charge table
+    pub fn new() -> ChargeTable {
+        return ChargeTable::default();
+    }
+
+    /// Read a charge table from the file at the given `path`.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the file can not be read, or if it is not a
+    /// valid charge table.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<ChargeTable, Error> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|error| Error {
+            status: Status::FileError,
+            message: format!("failed to read charge table at '{}': {error}", path.display()),
+        })?;
+        return content.parse();
+    }
+
+    /// Set the charge of atoms named `atom` in residues named `residue` to
+    /// `charge`, replacing any previous value.
+    pub fn insert(&mut self, residue: &str, atom: &str, charge: f64) {
+        let _ = self.charges.insert((residue.into(), atom.into()), charge);
+    }
+
+    /// Get the charge of atoms named `atom` in residues named `residue`, if
+    /// it is in this table.
+    pub fn get(&self, residue: &str, atom: &str) -> Option<f64> {
+        // allocating here is fine, charge tables are small
+        return self.charges.get(&(residue.into(), atom.into())).copied();
+    }
+
+    /// Get the number of entries in this table
+    pub fn len(&self) -> usize {
+        return self.charges.len();
+    }
+
+    /// Check if this table is empty
+    pub fn is_empty(&self) -> bool {
+        return self.charges.is_empty();
+    }
+
+    /// Get the content of this table as a map from (residue name, atom name)
+    /// to charges, as expected by [`Topology::apply_charges`].
+    ///
+    /// [`Topology::apply_charges`]: crate::Topology::apply_charges
+    pub fn as_map(&self) -> HashMap<(&str, &str), f64> {
+        self.charges
+            .iter()
+            .map(|((residue, atom), &charge)| ((residue.as_str(), atom.as_str()), charge))
+            .collect()
+    }
+}
+
+impl FromStr for ChargeTable {
+    type Err = Error;
+
+    fn from_str(content: &str) -> Result<ChargeTable, Error> {
+        let mut table = ChargeTable::new();
+        for (i, line) in content.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("");
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            match fields[..] {
+                [] => {}
+                [residue, atom, charge] => {
+                    let charge = charge.parse::<f64>().map_err(|_| Error {
+                        status: Status::FormatError,
+                        message: format!("invalid charge '{charge}' on line {} of charge table", i + 1),
+                    })?;
+                    table.insert(residue, atom, charge);
+                }
+                _ => {
+                    return Err(Error {
+                        status: Status::FormatError,
+                        message: format!(
+                            "expected 'residue atom charge' on line {} of charge table, got '{}'",
+                            i + 1,
+                            line.trim()
+                        ),
+                    })
+                }
+            }
+        }
+        return Ok(table);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let table: ChargeTable = "
+            # TIP3P water
+            HOH  O   -0.834
+            HOH  H1   0.417  # first hydrogen
+            HOH  H2   0.417
+        "
+        .parse()
+        .unwrap();
+
+        assert_eq!(table.len(), 3);
+        assert_eq!(table.get("HOH", "O"), Some(-0.834));
+        assert_eq!(table.get("HOH", "H2"), Some(0.417));
+        assert_eq!(table.get("WAT", "O"), None);
+        assert_eq!(table.as_map()[&("HOH", "H1")], 0.417);
+
+        let error = "HOH O".parse::<ChargeTable>().unwrap_err();
+        assert_eq!(error.status, Status::FormatError);
+        assert_eq!(error.message, "expected 'residue atom charge' on line 1 of charge table, got 'HOH O'");
+
+        let error = "\nHOH O abc".parse::<ChargeTable>().unwrap_err();
+        assert_eq!(error.message, "invalid charge 'abc' on line 2 of charge table");
+    }
+}
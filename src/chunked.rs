@@ -0,0 +1,211 @@
+// Chemfiles, a modern library for chemistry file reading and writing
+// Copyright (C) 2015-2018 Guillaume Fraux -- BSD licensed
+use std::path::PathBuf;
+
+use crate::errors::{Error, Status};
+use crate::{BondOutput, Frame, Topology, Trajectory};
+
+/// A `ChunkedWriter` writes frames to a series of files, starting a new file
+/// every `frames_per_file` frames.
+///
+/// The file names are created from a pattern containing a `printf`-style
+/// integer placeholder (`%d`, or `%04d` to pad the number with zeros), which
+/// is replaced by the index of the file, starting at 0. The format is guessed
+/// from the extension of the files.
+///
+/// The topology and bond output set on the writer are used for all the
+/// files, so that each file is consistent with the others.
+///
+/// # Example
+/// ```no_run
+/// # use chemfiles::{ChunkedWriter, Trajectory, Frame};
+/// let mut input = Trajectory::open("input.xtc", 'r').unwrap();
+/// let mut output = ChunkedWriter::new("output_%04d.pdb", 1000).unwrap();
+///
+/// let mut frame = Frame::new();
+/// for _ in 0..input.nsteps() {
+///     input.read(&mut frame).unwrap();
+///     // writes to output_0000.pdb, output_0001.pdb, ...
+///     output.write(&frame).unwrap();
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ChunkedWriter {
+    pattern: String,
+    frames_per_file: usize,
+    current: Option<Trajectory>,
+    frames_in_current: usize,
+    paths: Vec<PathBuf>,
+    topology: Option<Topology>,
+    bond_output: BondOutput,
+}
+
+impl ChunkedWriter {
+    /// Create a new `ChunkedWriter` writing `frames_per_file` frames in each
+    /// file, with file names created from `pattern`. No file is created until
+    /// the first frame is written.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if `pattern` does not contain a valid integer
+    /// placeholder, or if `frames_per_file` is zero.
+    pub fn new(pattern: &str, frames_per_file: usize) -> Result<ChunkedWriter, Error> {
+        if frames_per_file == 0 {
+            return Err(Error {
+                status: Status::InvalidArgument,
+                message: "the number of frames per file must be at least 1 in ChunkedWriter".into(),
+            });
+        }
+        // check the pattern once here, formatting can not fail after this
+        let _ = chunk_path(pattern, 0)?;
+
+        return Ok(ChunkedWriter {
+            pattern: pattern.into(),
+            frames_per_file,
+            current: None,
+            frames_in_current: 0,
+            paths: Vec::new(),
+            topology: None,
+            bond_output: BondOutput::All,
+        });
+    }
+
+    /// Write a `frame`, creating a new file if the current one already
+    /// contains `frames_per_file` frames.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if a new file can not be created, or if writing
+    /// the frame fails.
+    pub fn write(&mut self, frame: &Frame) -> Result<(), Error> {
+        if self.current.is_none() || self.frames_in_current == self.frames_per_file {
+            self.next_file()?;
+        }
+
+        let trajectory = self.current.as_mut().expect("missing trajectory");
+        trajectory.write(frame)?;
+        self.frames_in_current += 1;
+        return Ok(());
+    }
+
+    /// Use `topology` for all the frames written with this writer, in the
+    /// current and all the following files.
+    pub fn set_topology(&mut self, topology: &Topology) {
+        if let Some(ref mut trajectory) = self.current {
+            trajectory.set_topology(topology);
+        }
+        self.topology = Some(topology.clone());
+    }
+
+    /// Set which bonds are written, in the current and all the following
+    /// files. See [`Trajectory::set_bond_output`].
+    pub fn set_bond_output(&mut self, output: BondOutput) {
+        if let Some(ref mut trajectory) = self.current {
+            trajectory.set_bond_output(output);
+        }
+        self.bond_output = output;
+    }
+
+    /// Get the paths of all the files created by this writer, in order
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// Close the current file, making sure all the data is written to disk.
+    /// The next written frame will be written to a new file.
+    pub fn close(&mut self) {
+        self.current = None;
+    }
+
+    /// Close the current file, and open the next one
+    fn next_file(&mut self) -> Result<(), Error> {
+        // close the previous file before opening a new one
+        self.current = None;
+
+        let path = PathBuf::from(chunk_path(&self.pattern, self.paths.len())?);
+        let mut trajectory = Trajectory::open(&path, 'w')?;
+        if let Some(ref topology) = self.topology {
+            trajectory.set_topology(topology);
+        }
+        trajectory.set_bond_output(self.bond_output);
+
+        self.paths.push(path);
+        self.current = Some(trajectory);
+        self.frames_in_current = 0;
+        return Ok(());
+    }
+}
+
+/// Replace the integer placeholder in `pattern` with `index`
+fn chunk_path(pattern: &str, index: usize) -> Result<String, Error> {
+    let invalid = || Error {
+        status: Status::InvalidArgument,
+        message: format!("invalid pattern '{pattern}' in ChunkedWriter: expected a placeholder like '%d' or '%04d'"),
+    };
+
+    let start = pattern.find('%').ok_or_else(invalid)?;
+    let specifier = &pattern[start + 1..];
+    let width_length = specifier.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+    if !specifier[width_length..].starts_with('d') {
+        return Err(invalid());
+    }
+
+    let width = &specifier[..width_length];
+    let number = if width.is_empty() {
+        index.to_string()
+    } else {
+        let zero_padded = width.starts_with('0');
+        let width = width.parse::<usize>().map_err(|_| invalid())?;
+        if zero_padded {
+            format!("{index:0width$}")
+        } else {
+            format!("{index:width$}")
+        }
+    };
+
+    let rest = &specifier[width_length + 1..];
+    if rest.contains('%') {
+        return Err(invalid());
+    }
+    return Ok(format!("{}{number}{rest}", &pattern[..start]));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Atom;
+
+    #[test]
+    fn paths() {
+        assert_eq!(chunk_path("output_%04d.pdb", 3).unwrap(), "output_0003.pdb");
+        assert_eq!(chunk_path("output_%d.pdb", 12).unwrap(), "output_12.pdb");
+        assert_eq!(chunk_path("%3d.xyz", 7).unwrap(), "  7.xyz");
+        assert_eq!(chunk_path("output_%02d.pdb", 123).unwrap(), "output_123.pdb");
+
+        for pattern in ["output.pdb", "output_%s.pdb", "output_%04", "%d_%d.pdb"] {
+            let error = chunk_path(pattern, 0).unwrap_err();
+            assert_eq!(error.status, Status::InvalidArgument);
+        }
+
+        assert!(ChunkedWriter::new("output_%d.pdb", 0).is_err());
+    }
+
+    #[test]
+    fn write() {
+        let mut writer = ChunkedWriter::new("test-chunked-tmp-%02d.xyz", 2).unwrap();
+        let mut frame = Frame::new();
+        frame.add_atom(&Atom::new("Ar"), [0.0, 0.0, 0.0], None);
+        for _ in 0..5 {
+            writer.write(&frame).unwrap();
+        }
+        writer.close();
+
+        let expected = ["test-chunked-tmp-00.xyz", "test-chunked-tmp-01.xyz", "test-chunked-tmp-02.xyz"];
+        assert_eq!(writer.paths(), expected.map(PathBuf::from));
+
+        for (path, nsteps) in expected.iter().zip([2, 2, 1]) {
+            assert_eq!(Trajectory::open(path, 'r').unwrap().nsteps(), nsteps);
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+}
@@ -0,0 +1,312 @@
+// Chemfiles, a modern library for chemistry file reading and writing
+// Copyright (C) 2015-2018 Guillaume Fraux -- BSD licensed
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::errors::{Error, Status};
+use crate::transform::Transform;
+use crate::{with_warning_capture, BondOutput, Frame, Selection, Trajectory};
+
+/// Summary of a conversion done with [`ConvertJob::run`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConvertReport {
+    /// Number of steps in the input file
+    pub steps: usize,
+    /// Number of frames read from the input file
+    pub frames_read: usize,
+    /// Number of frames written to the output file
+    pub frames_written: usize,
+    /// Warnings emitted by chemfiles during the conversion
+    pub warnings: Vec<String>,
+    /// Wall time used by the conversion
+    pub duration: Duration,
+}
+
+/// A `ConvertJob` converts a trajectory file to another file, optionally
+/// applying transformations, keeping only some atoms or some of the frames.
+///
+/// The number of steps in the input file is queried before the conversion
+/// starts (which allows reporting progress), and then the selected frames are
+/// read, transformed and written one at a time.
+///
+/// # Example
+/// ```no_run
+/// # use chemfiles::ConvertJob;
+/// # use chemfiles::transform::Wrap;
+/// let report = ConvertJob::new("trajectory.dcd", "protein.xtc")
+///     .topology("system.pdb")
+///     .select("protein")
+///     .stride(10)
+///     .with(Wrap)
+///     .on_progress(|done, total| eprint!("\r{done}/{total} frames"))
+///     .run()
+///     .unwrap();
+///
+/// println!(
+///     "wrote {} frames in {:?} ({} warnings)",
+///     report.frames_written,
+///     report.duration,
+///     report.warnings.len()
+/// );
+/// ```
+pub struct ConvertJob {
+    input: PathBuf,
+    output: PathBuf,
+    input_format: Option<String>,
+    output_format: Option<String>,
+    topology: Option<PathBuf>,
+    selection: Option<String>,
+    stride: usize,
+    bond_output: BondOutput,
+    transforms: Vec<Box<dyn Transform>>,
+    progress: Option<Box<dyn FnMut(usize, usize)>>,
+}
+
+impl std::fmt::Debug for ConvertJob {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt.debug_struct("ConvertJob")
+            .field("input", &self.input)
+            .field("output", &self.output)
+            .field("input_format", &self.input_format)
+            .field("output_format", &self.output_format)
+            .field("topology", &self.topology)
+            .field("selection", &self.selection)
+            .field("stride", &self.stride)
+            .field("bond_output", &self.bond_output)
+            .field("transforms", &self.transforms.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl ConvertJob {
+    /// Create a new job converting the file at `input` to the file at
+    /// `output`, guessing both formats from the file extensions.
+    pub fn new(input: impl AsRef<Path>, output: impl AsRef<Path>) -> ConvertJob {
+        ConvertJob {
+            input: input.as_ref().to_owned(),
+            output: output.as_ref().to_owned(),
+            input_format: None,
+            output_format: None,
+            topology: None,
+            selection: None,
+            stride: 1,
+            bond_output: BondOutput::All,
+            transforms: Vec::new(),
+            progress: None,
+        }
+    }
+
+    /// Use the given `format` to read the input file.
+    #[must_use]
+    pub fn input_format(mut self, format: &str) -> ConvertJob {
+        self.input_format = Some(format.into());
+        return self;
+    }
+
+    /// Use the given `format` to write the output file.
+    #[must_use]
+    pub fn output_format(mut self, format: &str) -> ConvertJob {
+        self.output_format = Some(format.into());
+        return self;
+    }
+
+    /// Read the topology of the input frames from the file at `path`, see
+    /// [`Trajectory::set_topology_file`].
+    #[must_use]
+    pub fn topology(mut self, path: impl AsRef<Path>) -> ConvertJob {
+        self.topology = Some(path.as_ref().to_owned());
+        return self;
+    }
+
+    /// Only write the atoms matching the given `selection`. The selection is
+    /// applied after all the transforms, and must match single atoms.
+    #[must_use]
+    pub fn select(mut self, selection: &str) -> ConvertJob {
+        self.selection = Some(selection.into());
+        return self;
+    }
+
+    /// Only convert one frame every `stride` frames, starting with the first
+    /// one.
+    ///
+    /// # Panics
+    ///
+    /// If `stride` is zero.
+    #[must_use]
+    pub fn stride(mut self, stride: usize) -> ConvertJob {
+        assert!(stride > 0, "stride must be at least 1");
+        self.stride = stride;
+        return self;
+    }
+
+    /// Set which bonds to write in the output file, see
+    /// [`Trajectory::set_bond_output`].
+    #[must_use]
+    pub fn bond_output(mut self, output: BondOutput) -> ConvertJob {
+        self.bond_output = output;
+        return self;
+    }
+
+    /// Add a new `transform` applied to all the frames before writing them.
+    /// Transforms are applied in the same order they are added.
+    #[must_use]
+    pub fn with(mut self, transform: impl Transform + 'static) -> ConvertJob {
+        self.transforms.push(Box::new(transform));
+        return self;
+    }
+
+    /// Call `callback` after each frame is written, with the number of frames
+    /// written so far and the total number of frames to write.
+    #[must_use]
+    pub fn on_progress(mut self, callback: impl FnMut(usize, usize) + 'static) -> ConvertJob {
+        self.progress = Some(Box::new(callback));
+        return self;
+    }
+
+    /// Run the conversion, and get a report of what was done.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the input or output files can not be opened, if
+    /// the selection is invalid or does not match single atoms, or if any
+    /// frame can not be read, transformed or written.
+    pub fn run(mut self) -> Result<ConvertReport, Error> {
+        let start = Instant::now();
+        let (result, warnings) = with_warning_capture(|| self.convert());
+        let (steps, frames_read, frames_written) = result?;
+
+        return Ok(ConvertReport {
+            steps,
+            frames_read,
+            frames_written,
+            warnings,
+            duration: start.elapsed(),
+        });
+    }
+
+    /// Do the actual conversion, returning the number of steps, frames read
+    /// and frames written.
+    fn convert(&mut self) -> Result<(usize, usize, usize), Error> {
+        let mut selection = match self.selection {
+            Some(ref selection) => Some(Selection::new(&**selection)?),
+            None => None,
+        };
+        if let Some(ref selection) = selection {
+            if selection.size() != 1 {
+                return Err(Error {
+                    status: Status::SelectionError,
+                    message: format!("the selection '{}' must match single atoms", selection.string()),
+                });
+            }
+        }
+
+        let mut input = open(&self.input, 'r', self.input_format.as_deref())?;
+        if let Some(ref topology) = self.topology {
+            input.set_topology_file(topology)?;
+        }
+        let mut output = open(&self.output, 'w', self.output_format.as_deref())?;
+        output.set_bond_output(self.bond_output);
+
+        let steps = input.nsteps();
+        let total = (steps + self.stride - 1) / self.stride;
+
+        let mut frame = Frame::new();
+        let mut frames_read = 0;
+        let mut frames_written = 0;
+        for step in (0..steps).step_by(self.stride) {
+            input.read_step(step, &mut frame)?;
+            frames_read += 1;
+
+            for transform in &mut self.transforms {
+                transform.apply(&mut frame)?;
+            }
+
+            if let Some(ref mut selection) = selection {
+                frame = frame.subset(&selection.list(&frame));
+            }
+
+            output.write(&frame)?;
+            frames_written += 1;
+
+            if let Some(ref mut progress) = self.progress {
+                progress(frames_written, total);
+            }
+        }
+
+        return Ok((steps, frames_read, frames_written));
+    }
+}
+
+/// Open the file at `path` with the given `mode`, and optional `format`
+fn open(path: &Path, mode: char, format: Option<&str>) -> Result<Trajectory, Error> {
+    let trajectory = match format {
+        Some(format) => Trajectory::open_with_format(path, mode, format),
+        None => Trajectory::open(path, mode),
+    };
+
+    return trajectory.map_err(|error| Error {
+        status: error.status,
+        message: format!("failed to open '{}': {}", path.display(), error.message),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::transform::Translate;
+    use crate::{Atom, Status};
+
+    #[test]
+    fn convert() {
+        let mut trajectory = Trajectory::open("test-convert-tmp.xyz", 'w').unwrap();
+        let mut frame = Frame::new();
+        frame.add_atom(&Atom::new("O"), [0.0, 0.0, 0.0], None);
+        frame.add_atom(&Atom::new("H"), [1.0, 0.0, 0.0], None);
+        for _ in 0..5 {
+            trajectory.write(&frame).unwrap();
+        }
+        drop(trajectory);
+
+        let calls = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&calls);
+        let report = ConvertJob::new("test-convert-tmp.xyz", "test-convert-tmp.pdb")
+            .select("name O")
+            .stride(2)
+            .with(Translate::new([1.0, 2.0, 3.0]))
+            .on_progress(move |done, total| {
+                assert_eq!(total, 3);
+                counter.set(done);
+            })
+            .run()
+            .unwrap();
+
+        assert_eq!(report.steps, 5);
+        assert_eq!(report.frames_read, 3);
+        assert_eq!(report.frames_written, 3);
+        assert_eq!(calls.get(), 3);
+
+        let mut trajectory = Trajectory::open("test-convert-tmp.pdb", 'r').unwrap();
+        assert_eq!(trajectory.nsteps(), 3);
+        trajectory.read(&mut frame).unwrap();
+        assert_eq!(frame.size(), 1);
+        assert_eq!(frame.positions()[0], [1.0, 2.0, 3.0]);
+        drop(trajectory);
+
+        let error = ConvertJob::new("test-convert-tmp.xyz", "test-convert-tmp.pdb")
+            .select("name O and")
+            .run()
+            .unwrap_err();
+        assert_eq!(error.status, Status::SelectionError);
+
+        let error = ConvertJob::new("not-there.xyz", "test-convert-tmp.pdb")
+            .run()
+            .unwrap_err();
+        assert!(error.message.starts_with("failed to open 'not-there.xyz'"));
+
+        std::fs::remove_file("test-convert-tmp.xyz").unwrap();
+        std::fs::remove_file("test-convert-tmp.pdb").unwrap();
+    }
+}
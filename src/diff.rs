@@ -0,0 +1,554 @@
+// Chemfiles, a modern library for chemistry file reading and writing
+// Copyright (C) 2015-2018 Guillaume Fraux -- BSD licensed
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::{AtomRef, BondOrder, Frame, Property};
+
+/// Tolerances used when comparing two frames with [`Frame::diff`].
+///
+/// All tolerances are absolute: two values `a` and `b` are considered equal if
+/// `|a - b| <= tolerance`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiffOptions {
+    /// Tolerance on the positions, in Angstroms
+    pub positions: f64,
+    /// Tolerance on the velocities
+    pub velocities: f64,
+    /// Tolerance on the unit cell matrix, in Angstroms
+    pub cell: f64,
+    /// Tolerance on atomic masses, charges and numeric properties
+    pub properties: f64,
+}
+
+impl Default for DiffOptions {
+    fn default() -> DiffOptions {
+        DiffOptions {
+            positions: 1e-6,
+            velocities: 1e-6,
+            cell: 1e-6,
+            properties: 1e-6,
+        }
+    }
+}
+
+/// A single difference between two frames, as found by [`Frame::diff`]. In
+/// all variants, `left` refers to the frame `diff` was called on, and `right`
+/// to the other frame.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Difference {
+    /// The frames do not contain the same number of atoms. When this happens,
+    /// atoms and bonds are not compared.
+    Size {
+        /// Number of atoms in the left frame
+        left: usize,
+        /// Number of atoms in the right frame
+        right: usize,
+    },
+    /// The frames steps are different
+    Step {
+        /// Step of the left frame
+        left: usize,
+        /// Step of the right frame
+        right: usize,
+    },
+    /// The unit cell matrices are different
+    Cell {
+        /// Cell matrix of the left frame
+        left: [[f64; 3]; 3],
+        /// Cell matrix of the right frame
+        right: [[f64; 3]; 3],
+    },
+    /// The position of an atom is different
+    Position {
+        /// Index of the atom
+        atom: usize,
+        /// Position in the left frame
+        left: [f64; 3],
+        /// Position in the right frame
+        right: [f64; 3],
+    },
+    /// Only one of the frames contains velocities
+    Velocities {
+        /// Does the left frame contain velocities
+        left: bool,
+        /// Does the right frame contain velocities
+        right: bool,
+    },
+    /// The velocity of an atom is different
+    Velocity {
+        /// Index of the atom
+        atom: usize,
+        /// Velocity in the left frame
+        left: [f64; 3],
+        /// Velocity in the right frame
+        right: [f64; 3],
+    },
+    /// The name of an atom is different
+    AtomName {
+        /// Index of the atom
+        atom: usize,
+        /// Name in the left frame
+        left: String,
+        /// Name in the right frame
+        right: String,
+    },
+    /// The type of an atom is different
+    AtomType {
+        /// Index of the atom
+        atom: usize,
+        /// Type in the left frame
+        left: String,
+        /// Type in the right frame
+        right: String,
+    },
+    /// The mass of an atom is different
+    Mass {
+        /// Index of the atom
+        atom: usize,
+        /// Mass in the left frame
+        left: f64,
+        /// Mass in the right frame
+        right: f64,
+    },
+    /// The charge of an atom is different
+    Charge {
+        /// Index of the atom
+        atom: usize,
+        /// Charge in the left frame
+        left: f64,
+        /// Charge in the right frame
+        right: f64,
+    },
+    /// A property of an atom is different or missing in one of the frames
+    AtomProperty {
+        /// Index of the atom
+        atom: usize,
+        /// Name of the property
+        name: String,
+        /// Property in the left frame, if any
+        left: Option<Property>,
+        /// Property in the right frame, if any
+        right: Option<Property>,
+    },
+    /// A bond is missing in one of the frames, or has a different bond order
+    Bond {
+        /// Indexes of the bonded atoms
+        bond: [usize; 2],
+        /// Bond order in the left frame, if the bond exists
+        left: Option<BondOrder>,
+        /// Bond order in the right frame, if the bond exists
+        right: Option<BondOrder>,
+    },
+    /// A frame property is different or missing in one of the frames
+    Property {
+        /// Name of the property
+        name: String,
+        /// Property in the left frame, if any
+        left: Option<Property>,
+        /// Property in the right frame, if any
+        right: Option<Property>,
+    },
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Difference::Size { left, right } => write!(fmt, "different number of atoms: {left} != {right}"),
+            Difference::Step { left, right } => write!(fmt, "different step: {left} != {right}"),
+            Difference::Cell { left, right } => write!(fmt, "different unit cell: {left:?} != {right:?}"),
+            Difference::Position { atom, left, right } => {
+                write!(fmt, "different position for atom {atom}: {left:?} != {right:?}")
+            }
+            Difference::Velocities { left, right } => {
+                write!(fmt, "different velocities presence: {left} != {right}")
+            }
+            Difference::Velocity { atom, left, right } => {
+                write!(fmt, "different velocity for atom {atom}: {left:?} != {right:?}")
+            }
+            Difference::AtomName { atom, left, right } => {
+                write!(fmt, "different name for atom {atom}: '{left}' != '{right}'")
+            }
+            Difference::AtomType { atom, left, right } => {
+                write!(fmt, "different type for atom {atom}: '{left}' != '{right}'")
+            }
+            Difference::Mass { atom, left, right } => {
+                write!(fmt, "different mass for atom {atom}: {left} != {right}")
+            }
+            Difference::Charge { atom, left, right } => {
+                write!(fmt, "different charge for atom {atom}: {left} != {right}")
+            }
+            Difference::AtomProperty { atom, name, left, right } => write!(
+                fmt,
+                "different '{name}' property for atom {atom}: {left:?} != {right:?}"
+            ),
+            Difference::Bond { bond, left, right } => write!(
+                fmt,
+                "different bond between atoms {} and {}: {left:?} != {right:?}",
+                bond[0], bond[1]
+            ),
+            Difference::Property { name, left, right } => {
+                write!(fmt, "different '{name}' property: {left:?} != {right:?}")
+            }
+        }
+    }
+}
+
+/// The list of differences between two frames, created by [`Frame::diff`].
+///
+/// The `Display` implementation of this struct prints one difference per line,
+/// which is useful for assertion messages in tests.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FrameDiff {
+    differences: Vec<Difference>,
+}
+
+impl FrameDiff {
+    /// Check if the two frames are equal within the tolerances, *i.e.* if
+    /// there are no differences.
+    pub fn is_empty(&self) -> bool {
+        return self.differences.is_empty();
+    }
+
+    /// Get the number of differences between the frames
+    pub fn len(&self) -> usize {
+        return self.differences.len();
+    }
+
+    /// Get the list of differences between the frames
+    pub fn differences(&self) -> &[Difference] {
+        &self.differences
+    }
+
+    /// Get an iterator over the differences between the frames
+    pub fn iter(&self) -> std::slice::Iter<'_, Difference> {
+        return self.differences.iter();
+    }
+}
+
+impl<'a> IntoIterator for &'a FrameDiff {
+    type Item = &'a Difference;
+    type IntoIter = std::slice::Iter<'a, Difference>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.differences.iter()
+    }
+}
+
+impl fmt::Display for FrameDiff {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        for difference in &self.differences {
+            writeln!(fmt, "{difference}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Compare `left` and `right`, and collect all the differences
+pub(crate) fn compare(left: &Frame, right: &Frame, options: DiffOptions) -> FrameDiff {
+    let mut differences = Vec::new();
+
+    if left.step() != right.step() {
+        differences.push(Difference::Step {
+            left: left.step(),
+            right: right.step(),
+        });
+    }
+
+    let left_cell = left.cell().matrix();
+    let right_cell = right.cell().matrix();
+    let same_cell = left_cell
+        .iter()
+        .zip(&right_cell)
+        .all(|(l, r)| vector_eq(*l, *r, options.cell));
+    if !same_cell {
+        differences.push(Difference::Cell {
+            left: left_cell,
+            right: right_cell,
+        });
+    }
+
+    compare_properties(
+        &properties_map(left.properties()),
+        &properties_map(right.properties()),
+        options.properties,
+        |name, left, right| differences.push(Difference::Property { name, left, right }),
+    );
+
+    if left.size() != right.size() {
+        differences.push(Difference::Size {
+            left: left.size(),
+            right: right.size(),
+        });
+        return FrameDiff { differences };
+    }
+
+    for (atom, (&l, &r)) in left.positions().iter().zip(right.positions()).enumerate() {
+        if !vector_eq(l, r, options.positions) {
+            differences.push(Difference::Position { atom, left: l, right: r });
+        }
+    }
+
+    match (left.velocities(), right.velocities()) {
+        (Some(left), Some(right)) => {
+            for (atom, (&l, &r)) in left.iter().zip(right).enumerate() {
+                if !vector_eq(l, r, options.velocities) {
+                    differences.push(Difference::Velocity { atom, left: l, right: r });
+                }
+            }
+        }
+        (None, None) => {}
+        (left, right) => differences.push(Difference::Velocities {
+            left: left.is_some(),
+            right: right.is_some(),
+        }),
+    }
+
+    for atom in 0..left.size() {
+        compare_atoms(atom, &left.atom(atom), &right.atom(atom), options, &mut differences);
+    }
+
+    let left_bonds = bonds_map(left);
+    let right_bonds = bonds_map(right);
+    for (&bond, &order) in &left_bonds {
+        let other = right_bonds.get(&bond).copied();
+        if other != Some(order) {
+            differences.push(Difference::Bond {
+                bond,
+                left: Some(order),
+                right: other,
+            });
+        }
+    }
+    for (&bond, &order) in &right_bonds {
+        if !left_bonds.contains_key(&bond) {
+            differences.push(Difference::Bond {
+                bond,
+                left: None,
+                right: Some(order),
+            });
+        }
+    }
+
+    return FrameDiff { differences };
+}
+
+fn compare_atoms(atom: usize, left: &AtomRef, right: &AtomRef, options: DiffOptions, differences: &mut Vec<Difference>) {
+    if left.name() != right.name() {
+        differences.push(Difference::AtomName {
+            atom,
+            left: left.name(),
+            right: right.name(),
+        });
+    }
+
+    if left.atomic_type() != right.atomic_type() {
+        differences.push(Difference::AtomType {
+            atom,
+            left: left.atomic_type(),
+            right: right.atomic_type(),
+        });
+    }
+
+    if (left.mass() - right.mass()).abs() > options.properties {
+        differences.push(Difference::Mass {
+            atom,
+            left: left.mass(),
+            right: right.mass(),
+        });
+    }
+
+    if (left.charge() - right.charge()).abs() > options.properties {
+        differences.push(Difference::Charge {
+            atom,
+            left: left.charge(),
+            right: right.charge(),
+        });
+    }
+
+    compare_properties(
+        &properties_map(left.properties()),
+        &properties_map(right.properties()),
+        options.properties,
+        |name, left, right| {
+            differences.push(Difference::AtomProperty {
+                atom,
+                name,
+                left,
+                right,
+            });
+        },
+    );
+}
+
+/// Collect properties in a map sorted by name, to get a deterministic order
+fn properties_map<'a>(properties: impl Iterator<Item = (String, Property)> + 'a) -> BTreeMap<String, Property> {
+    return properties.collect();
+}
+
+/// Compare two sets of properties, calling `report` for each property which
+/// is different or only present in one of the sets.
+fn compare_properties(
+    left: &BTreeMap<String, Property>,
+    right: &BTreeMap<String, Property>,
+    tolerance: f64,
+    mut report: impl FnMut(String, Option<Property>, Option<Property>),
+) {
+    for (name, l) in left {
+        match right.get(name) {
+            Some(r) if property_eq(l, r, tolerance) => {}
+            r => report(name.clone(), Some(l.clone()), r.cloned()),
+        }
+    }
+    for (name, r) in right {
+        if !left.contains_key(name) {
+            report(name.clone(), None, Some(r.clone()));
+        }
+    }
+}
+
+/// Get all the bonds in a frame, associated with their bond order
+fn bonds_map(frame: &Frame) -> BTreeMap<[usize; 2], BondOrder> {
+    let topology = frame.topology();
+    return topology.bonds().into_iter().zip(topology.bond_orders()).collect();
+}
+
+fn property_eq(left: &Property, right: &Property, tolerance: f64) -> bool {
+    match (left, right) {
+        (Property::Double(l), Property::Double(r)) => (l - r).abs() <= tolerance,
+        (Property::Vector3D(l), Property::Vector3D(r)) => vector_eq(*l, *r, tolerance),
+        _ => left == right,
+    }
+}
+
+fn vector_eq(left: [f64; 3], right: [f64; 3], tolerance: f64) -> bool {
+    return left.iter().zip(&right).all(|(l, r)| (l - r).abs() <= tolerance);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Atom, UnitCell};
+
+    fn frame() -> Frame {
+        let mut frame = Frame::new();
+        frame.set_cell(&UnitCell::new([10.0, 10.0, 10.0]));
+        frame.add_atom(&Atom::new("O"), [0.0, 0.0, 0.0], None);
+        frame.add_atom(&Atom::new("H"), [1.0, 0.0, 0.0], None);
+        frame.add_atom(&Atom::new("H"), [0.0, 1.0, 0.0], None);
+        frame.add_bond(0, 1);
+        frame.add_bond(0, 2);
+        frame.set("name", "water");
+        return frame;
+    }
+
+    #[test]
+    fn equal() {
+        let left = frame();
+        let mut right = frame();
+        right.positions_mut()[0][0] = 1e-8;
+        right.set("name", "water");
+
+        let diff = left.diff(&right, DiffOptions::default());
+        assert!(diff.is_empty(), "{}", diff);
+        assert_eq!(diff.to_string(), "");
+    }
+
+    #[test]
+    fn differences() {
+        let left = frame();
+        let mut right = frame();
+        right.set_step(3);
+        right.set_cell(&UnitCell::new([10.0, 10.0, 12.0]));
+        right.positions_mut()[1] = [1.5, 0.0, 0.0];
+        right.atom_mut(2).set_name("D");
+        right.atom_mut(2).set_charge(0.5);
+        right.atom_mut(0).set("foo", 2.0);
+        right.remove_bond(0, 2);
+        right.add_bond(1, 2);
+        right.set("name", "heavy water");
+        right.add_velocities();
+
+        let diff = left.diff(&right, DiffOptions::default());
+        assert_eq!(diff.len(), 10, "{diff}");
+        assert_eq!(diff.differences()[0], Difference::Step { left: 0, right: 3 });
+        assert!(matches!(diff.differences()[1], Difference::Cell { .. }));
+        assert_eq!(
+            diff.differences()[2],
+            Difference::Property {
+                name: "name".into(),
+                left: Some(Property::String("water".into())),
+                right: Some(Property::String("heavy water".into())),
+            }
+        );
+        assert_eq!(
+            diff.differences()[3],
+            Difference::Position {
+                atom: 1,
+                left: [1.0, 0.0, 0.0],
+                right: [1.5, 0.0, 0.0]
+            }
+        );
+        assert_eq!(
+            diff.differences()[4],
+            Difference::Velocities {
+                left: false,
+                right: true
+            }
+        );
+        assert_eq!(
+            diff.differences()[5],
+            Difference::AtomProperty {
+                atom: 0,
+                name: "foo".into(),
+                left: None,
+                right: Some(Property::Double(2.0)),
+            }
+        );
+        assert_eq!(
+            diff.differences()[6],
+            Difference::AtomName {
+                atom: 2,
+                left: "H".into(),
+                right: "D".into()
+            }
+        );
+        assert!(matches!(diff.differences()[7], Difference::Charge { atom: 2, .. }));
+        assert_eq!(
+            diff.differences()[8],
+            Difference::Bond {
+                bond: [0, 2],
+                left: Some(BondOrder::Unknown),
+                right: None
+            }
+        );
+        assert_eq!(
+            diff.differences()[9],
+            Difference::Bond {
+                bond: [1, 2],
+                left: None,
+                right: Some(BondOrder::Unknown)
+            }
+        );
+
+        assert!(diff.to_string().contains("different position for atom 1: [1.0, 0.0, 0.0] != [1.5, 0.0, 0.0]\n"));
+
+        let options = DiffOptions {
+            positions: 1.0,
+            cell: 5.0,
+            properties: 1.0,
+            ..Default::default()
+        };
+        assert_eq!(left.diff(&right, options).len(), 7);
+    }
+
+    #[test]
+    fn size() {
+        let left = frame();
+        let mut right = frame();
+        right.remove(2);
+
+        let diff = left.diff(&right, DiffOptions::default());
+        assert_eq!(diff.differences(), &[Difference::Size { left: 3, right: 2 }]);
+    }
+}
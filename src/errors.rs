@@ -1,8 +1,11 @@
 // Chemfiles, a modern library for chemistry file reading and writing
 // Copyright (C) 2015-2018 Guillaume Fraux -- BSD licensed
+use std::any::Any;
+use std::cell::RefCell;
 use std::os::raw::c_char;
-use std::panic::{self, RefUnwindSafe};
+use std::panic::{self, RefUnwindSafe, UnwindSafe};
 use std::path::Path;
+use std::sync::{Mutex, Once, PoisonError};
 
 use chemfiles_sys as ffi;
 
@@ -45,6 +48,44 @@ pub enum Status {
     /// The given path is not valid UTF8
     // TODO: rename this to UTF8Error in the next breaking release
     UTF8PathError,
+    /// Invalid argument given to a function implemented in Rust
+    InvalidArgument,
+}
+
+/// More specific cause of an error with [`Status::FileError`], as returned by
+/// [`Error::file_error_kind`].
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileErrorKind {
+    /// A file could not be opened because it does not exist
+    NotFound,
+    /// A file could not be opened because of insufficient permissions
+    PermissionDenied,
+    /// The compression method of a file is unknown, or not supported by the
+    /// file format
+    UnsupportedCompression,
+    /// A file ended in the middle of a step, *i.e.* it is truncated
+    TruncatedFile,
+    /// Any other error while reading or writing a file
+    Other,
+}
+
+impl FileErrorKind {
+    /// Get the kind of a file error from its `message`, or `Other` if the
+    /// message is not recognized.
+    fn from_message(message: &str) -> FileErrorKind {
+        let message = message.to_lowercase();
+        if message.contains("no such file") || message.contains("file not found") {
+            return FileErrorKind::NotFound;
+        } else if message.contains("permission denied") {
+            return FileErrorKind::PermissionDenied;
+        } else if message.contains("compression") {
+            return FileErrorKind::UnsupportedCompression;
+        } else if message.contains("end of file") {
+            return FileErrorKind::TruncatedFile;
+        }
+        return FileErrorKind::Other;
+    }
 }
 
 impl From<ffi::chfl_status> for Error {
@@ -78,6 +119,7 @@ impl From<std::str::Utf8Error> for Error {
 
 impl Error {
     /// Create a new error because the given `path` is invalid UTF-8 data
+    #[cfg(not(unix))]
     pub(crate) fn utf8_path_error(path: &Path) -> Error {
         Error {
             status: Status::UTF8PathError,
@@ -85,6 +127,68 @@ impl Error {
         }
     }
 
+    /// Add the reason for an error obtained when opening the file at `path`
+    /// for reading to the message, if the file does not exist or is not
+    /// readable. The C++ library does not always report this reason.
+    pub(crate) fn refine_open_error(mut self, path: &Path, mode: char) -> Error {
+        if mode == 'r' && self.file_error_kind() == Some(FileErrorKind::Other) {
+            if let Err(error) = std::fs::File::open(path) {
+                match error.kind() {
+                    std::io::ErrorKind::NotFound => self.message.push_str(": file not found"),
+                    std::io::ErrorKind::PermissionDenied => self.message.push_str(": permission denied"),
+                    _ => {}
+                }
+            }
+        }
+        return self;
+    }
+
+    /// Get a more specific cause for errors with [`Status::FileError`], or
+    /// `None` for other errors.
+    ///
+    /// The C API only reports a generic file error, so the cause is guessed
+    /// from the error message. Errors which can not be classified have the
+    /// [`FileErrorKind::Other`] kind.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use chemfiles::{FileErrorKind, Trajectory};
+    /// let error = Trajectory::open("not-there.xyz", 'r').unwrap_err();
+    /// assert_eq!(error.file_error_kind(), Some(FileErrorKind::NotFound));
+    /// ```
+    pub fn file_error_kind(&self) -> Option<FileErrorKind> {
+        if self.status == Status::FileError {
+            return Some(FileErrorKind::from_message(&self.message));
+        }
+        return None;
+    }
+
+    /// Check if this error was created by trying to read past the last step
+    /// of a trajectory.
+    ///
+    /// This allows to distinguish the normal end of a trajectory from other
+    /// errors (for example a corrupted or truncated file) when reading all the
+    /// steps of a trajectory one after the other.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use chemfiles::{Trajectory, Frame};
+    /// let mut trajectory = Trajectory::open("water.xyz", 'r').unwrap();
+    /// let mut frame = Frame::new();
+    /// loop {
+    ///     match trajectory.read(&mut frame) {
+    ///         Ok(()) => println!("read a frame with {} atoms", frame.size()),
+    ///         Err(error) if error.is_eof() => break,
+    ///         Err(error) => panic!("failed to read the trajectory: {error}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn is_eof(&self) -> bool {
+        let is_file_error = matches!(self.status, Status::FileError | Status::OutOfBounds);
+        return is_file_error
+            && (self.message.contains("maximal step is") || self.message.contains("does not contain any step"));
+    }
+
     /// Get the last error message from the C++ library.
     pub fn last_error() -> String {
         unsafe { strings::from_c(ffi::chfl_last_error()) }
@@ -126,18 +230,121 @@ impl<T> WarningCallback for T where T: RefUnwindSafe + Fn(&str) {}
 
 static mut LOGGING_CALLBACK: Option<*mut dyn WarningCallback<Output = ()>> = None;
 
+thread_local! {
+    /// Warnings captured by `with_warning_capture` on the current thread, if
+    /// a capture is active.
+    static CAPTURED_WARNINGS: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
 extern "C" fn warning_callback(message: *const c_char) {
+    send_warning(strings::from_c(message));
+}
+
+/// Send a warning `message` to the current warning callback, or to the
+/// active warning capture. This is used for warnings emitted from Rust code.
+pub(crate) fn send_warning(message: String) {
+    let not_captured = CAPTURED_WARNINGS.with(|captured| {
+        if let Some(warnings) = captured.borrow_mut().as_mut() {
+            warnings.push(message);
+            None
+        } else {
+            Some(message)
+        }
+    });
+    let message = match not_captured {
+        Some(message) => message,
+        None => return,
+    };
+
     unsafe {
-        let callback = &*LOGGING_CALLBACK.expect("No callback provided, this is an internal bug");
-        // ignore result. If a panic happened, everything is going badly anyway
-        let _result = panic::catch_unwind(|| {
-            callback(&strings::from_c(message));
-        });
+        if let Some(callback) = LOGGING_CALLBACK {
+            let callback = &*callback;
+            call_callback("warning", || callback(&message));
+        } else {
+            // same behavior as the default chemfiles callback
+            eprintln!("[chemfiles] {message}");
+        }
     }
 }
 
+/// A panic that happened inside a user-provided callback, as returned by
+/// [`take_callback_panics`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CallbackPanic {
+    /// Name of the callback which panicked (for example `"warning"` for the
+    /// callback set with [`set_warning_callback`])
+    pub callback: &'static str,
+    /// The panic message, if the panic payload was a string
+    pub message: Option<String>,
+}
+
+/// Panics from user callbacks which have not yet been retrieved with
+/// `take_callback_panics`
+static CALLBACK_PANICS: Mutex<Vec<CallbackPanic>> = Mutex::new(Vec::new());
+
+/// Call the user-provided `callback`, catching any panic and recording it in
+/// `CALLBACK_PANICS`. Panics must not unwind through the C++ library, so this
+/// must be used for all callbacks called from C code.
+pub(crate) fn call_callback(name: &'static str, callback: impl FnOnce() + UnwindSafe) {
+    if let Err(payload) = panic::catch_unwind(callback) {
+        let panic = CallbackPanic {
+            callback: name,
+            message: panic_message(&*payload),
+        };
+        CALLBACK_PANICS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(panic);
+    }
+}
+
+/// Get the message of a panic from its `payload`, if it is a string
+fn panic_message(payload: &(dyn Any + Send)) -> Option<String> {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        return Some((*message).to_owned());
+    }
+    return payload.downcast_ref::<String>().cloned();
+}
+
+/// Get all the panics which happened in user-provided callbacks (such as the
+/// warning callback) since the last call to this function, in order.
+///
+/// Callbacks are called from the C++ library, where panics can not propagate.
+/// Instead, panics are caught and recorded, and can be retrieved with this
+/// function.
+///
+/// # Example
+/// ```
+/// # use chemfiles::{Frame, MemoryTrajectoryReader};
+/// chemfiles::set_warning_callback(|_| panic!("unexpected warning"));
+///
+/// let mut trajectory = MemoryTrajectoryReader::new("FOOBAR\nEND\n".as_bytes(), "PDB").unwrap();
+/// let mut frame = Frame::new();
+/// trajectory.read(&mut frame).unwrap();
+///
+/// let panics = chemfiles::take_callback_panics();
+/// assert_eq!(panics.len(), 1);
+/// assert_eq!(panics[0].callback, "warning");
+/// assert_eq!(panics[0].message.as_deref(), Some("unexpected warning"));
+/// ```
+pub fn take_callback_panics() -> Vec<CallbackPanic> {
+    let mut panics = CALLBACK_PANICS.lock().unwrap_or_else(PoisonError::into_inner);
+    return std::mem::take(&mut *panics);
+}
+
+/// Make sure the C library sends warnings to `warning_callback`
+fn install_warning_callback() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| unsafe {
+        check_success(ffi::chfl_set_warning_callback(warning_callback));
+    });
+}
+
 /// Use `callback` for every chemfiles warning. The callback will be passed
 /// the warning message. This will drop any previous warning callback.
+///
+/// Panics in the callback do not propagate to the caller, and can be
+/// retrieved with [`take_callback_panics`].
 pub fn set_warning_callback<F>(callback: F)
 where
     F: WarningCallback + 'static,
@@ -149,15 +356,58 @@ where
             // drop the previous callback
             let previous = Box::from_raw(previous);
             std::mem::drop(previous);
-            // set the LOGGING_CALLBACK to the new one
-            LOGGING_CALLBACK = Some(callback);
-        } else {
-            // set the LOGGING_CALLBACK
-            LOGGING_CALLBACK = Some(callback);
-            // Tell C code to use Rust-provided callback
-            check_success(ffi::chfl_set_warning_callback(warning_callback));
         }
+        // set the LOGGING_CALLBACK to the new one
+        LOGGING_CALLBACK = Some(callback);
     }
+    // Tell C code to use Rust-provided callback
+    install_warning_callback();
+}
+
+/// Call `function`, and capture all the warnings emitted by chemfiles on the
+/// current thread while it runs, instead of sending them to the warning
+/// callback. The captured warnings are returned together with the result of
+/// `function`.
+///
+/// This allows to associate warnings with the operation that produced them
+/// (for example reading a specific file). Warnings emitted on other threads
+/// are not captured. When captures are nested, the warnings are only returned
+/// by the innermost capture.
+///
+/// # Example
+/// ```
+/// # use chemfiles::{Frame, MemoryTrajectoryReader};
+/// let (result, warnings) = chemfiles::with_warning_capture(|| {
+///     let mut trajectory = MemoryTrajectoryReader::new("FOOBAR\nEND\n".as_bytes(), "PDB").unwrap();
+///     let mut frame = Frame::new();
+///     trajectory.read(&mut frame)
+/// });
+///
+/// assert!(result.is_ok());
+/// assert_eq!(warnings.len(), 1);
+/// ```
+pub fn with_warning_capture<T>(function: impl FnOnce() -> T) -> (T, Vec<String>) {
+    /// Restore the previous capture state, even if `function` panics
+    struct RestoreGuard {
+        previous: Option<Vec<String>>,
+    }
+
+    impl Drop for RestoreGuard {
+        fn drop(&mut self) {
+            let previous = self.previous.take();
+            CAPTURED_WARNINGS.with(|captured| *captured.borrow_mut() = previous);
+        }
+    }
+
+    install_warning_callback();
+    let previous = CAPTURED_WARNINGS.with(|captured| captured.borrow_mut().replace(Vec::new()));
+    let guard = RestoreGuard { previous };
+
+    let result = function();
+    let warnings = CAPTURED_WARNINGS.with(|captured| captured.borrow_mut().take().unwrap_or_default());
+    std::mem::drop(guard);
+
+    return (result, warnings);
 }
 
 impl std::fmt::Display for Error {
@@ -180,6 +430,7 @@ impl std::error::Error for Error {
             Status::ConfigurationError => "Error in configuration files",
             Status::OutOfBounds => "Out of bounds indexing",
             Status::PropertyError => "Error in property",
+            Status::InvalidArgument => "Invalid argument",
         }
     }
 }
@@ -202,6 +453,87 @@ mod test {
         assert_eq!(Error::last_error(), "");
     }
 
+    #[test]
+    fn warning_capture() {
+        let send_warning = |message: &str| {
+            let message = strings::to_c(message);
+            warning_callback(message.as_ptr());
+        };
+
+        let ((), warnings) = with_warning_capture(|| {
+            send_warning("first");
+            let ((), inner) = with_warning_capture(|| send_warning("inner"));
+            assert_eq!(inner, ["inner"]);
+            send_warning("second");
+        });
+        assert_eq!(warnings, ["first", "second"]);
+
+        // the capture is stopped even if the function panics
+        let result = panic::catch_unwind(|| with_warning_capture(|| panic!("oops")));
+        assert!(result.is_err());
+        CAPTURED_WARNINGS.with(|captured| assert!(captured.borrow().is_none()));
+
+        // warnings emitted by the C++ library
+        let (result, warnings) = with_warning_capture(|| {
+            let mut trajectory = crate::MemoryTrajectoryReader::new("FOOBAR\nEND\n".as_bytes(), "PDB").unwrap();
+            let mut frame = crate::Frame::new();
+            trajectory.read(&mut frame)
+        });
+        assert!(result.is_ok());
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn callback_panics() {
+        call_callback("test", || {});
+        call_callback("test", || panic!("static message"));
+        call_callback("test", || panic!("formatted {}", 42));
+        call_callback("test", || std::panic::panic_any(42));
+
+        let panics = take_callback_panics()
+            .into_iter()
+            .filter(|panic| panic.callback == "test")
+            .map(|panic| panic.message)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            panics,
+            [Some("static message".into()), Some("formatted 42".into()), None]
+        );
+    }
+
+    #[test]
+    fn file_errors() {
+        let error = Trajectory::open("not-there.xyz", 'r').unwrap_err();
+        assert_eq!(error.status, Status::FileError);
+        assert_eq!(error.file_error_kind(), Some(FileErrorKind::NotFound));
+        assert!(!error.is_eof());
+
+        let root = Path::new(file!()).parent().unwrap().join("..");
+        let mut trajectory = Trajectory::open(root.join("data").join("water.xyz"), 'r').unwrap();
+        let mut frame = crate::Frame::new();
+        for _ in 0..trajectory.nsteps() {
+            trajectory.read(&mut frame).unwrap();
+        }
+        let error = trajectory.read(&mut frame).unwrap_err();
+        assert!(error.is_eof(), "{error}");
+
+        assert_eq!(
+            FileErrorKind::from_message("unknown compression method 'zip'"),
+            FileErrorKind::UnsupportedCompression
+        );
+        assert_eq!(
+            FileErrorKind::from_message("unexpected end of file while reading step 3"),
+            FileErrorKind::TruncatedFile
+        );
+        assert_eq!(FileErrorKind::from_message("something else"), FileErrorKind::Other);
+
+        let error = Error {
+            status: Status::FormatError,
+            message: "unexpected end of file".into(),
+        };
+        assert_eq!(error.file_error_kind(), None);
+    }
+
     #[test]
     fn codes() {
         assert_eq!(Error::from(ffi::chfl_status::CHFL_SUCCESS).status, Status::Success);
@@ -0,0 +1,221 @@
+// Chemfiles, a modern library for chemistry file reading and writing
+// Copyright (C) 2015-2018 Guillaume Fraux -- BSD licensed
+use crate::errors::{Error, Status};
+use crate::{Frame, Selection, Trajectory};
+
+/// The atoms to keep in a `FilteredTrajectory`
+#[derive(Debug)]
+enum Filter {
+    /// Evaluate the selection for each frame
+    Selection(Selection),
+    /// Use a fixed list of (sorted) atomic indexes
+    Indexes(Vec<usize>),
+}
+
+/// A `FilteredTrajectory` reads frames from a `Trajectory`, and only keeps the
+/// atoms matching a `Selection` or a fixed list of atomic indexes.
+///
+/// The frames produced by this adapter contain the positions, velocities and
+/// atoms of the selected atoms only. The topology is remapped to the new
+/// atomic indexes: bonds and residues involving atoms that are not selected
+/// are removed.
+#[derive(Debug)]
+pub struct FilteredTrajectory {
+    trajectory: Trajectory,
+    filter: Filter,
+    buffer: Frame,
+}
+
+impl FilteredTrajectory {
+    /// Create a new `FilteredTrajectory` reading from `trajectory`, and only
+    /// keeping the atoms matching the `selection`. The selection is evaluated
+    /// again for each frame.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the selection size is not 1.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use chemfiles::{FilteredTrajectory, Frame, Selection, Trajectory};
+    /// let trajectory = Trajectory::open("solvated.pdb", 'r').unwrap();
+    /// let selection = Selection::new("not resname HOH").unwrap();
+    /// let mut trajectory = FilteredTrajectory::new(trajectory, selection).unwrap();
+    ///
+    /// let mut frame = Frame::new();
+    /// trajectory.read(&mut frame).unwrap();
+    /// ```
+    pub fn new(trajectory: Trajectory, selection: Selection) -> Result<FilteredTrajectory, Error> {
+        if selection.size() != 1 {
+            return Err(Error {
+                status: Status::SelectionError,
+                message: format!(
+                    "can not use a selection of size {} to filter a trajectory, expected a selection of size 1",
+                    selection.size()
+                ),
+            });
+        }
+
+        return Ok(FilteredTrajectory {
+            trajectory,
+            filter: Filter::Selection(selection),
+            buffer: Frame::new(),
+        });
+    }
+
+    /// Create a new `FilteredTrajectory` reading from `trajectory`, and only
+    /// keeping the atoms at the given `indexes`. The atoms in the filtered
+    /// frames are sorted by increasing index, and duplicated indexes are
+    /// ignored.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use chemfiles::{FilteredTrajectory, Frame, Trajectory};
+    /// let trajectory = Trajectory::open("water.xyz", 'r').unwrap();
+    /// let mut trajectory = FilteredTrajectory::with_indexes(trajectory, vec![0, 1, 2]);
+    ///
+    /// let mut frame = Frame::new();
+    /// trajectory.read(&mut frame).unwrap();
+    /// assert_eq!(frame.size(), 3);
+    /// ```
+    pub fn with_indexes(trajectory: Trajectory, mut indexes: Vec<usize>) -> FilteredTrajectory {
+        indexes.sort_unstable();
+        indexes.dedup();
+        return FilteredTrajectory {
+            trajectory,
+            filter: Filter::Indexes(indexes),
+            buffer: Frame::new(),
+        };
+    }
+
+    /// Read the next step of the underlying trajectory, and store the selected
+    /// atoms into `frame`.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if reading the trajectory fails, or if some of the
+    /// indexes given to [`FilteredTrajectory::with_indexes`] are out of bounds
+    /// for the frame.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use chemfiles::{FilteredTrajectory, Frame, Selection, Trajectory};
+    /// let trajectory = Trajectory::open("water.xyz", 'r').unwrap();
+    /// let selection = Selection::new("name O").unwrap();
+    /// let mut trajectory = FilteredTrajectory::new(trajectory, selection).unwrap();
+    ///
+    /// let mut frame = Frame::new();
+    /// trajectory.read(&mut frame).unwrap();
+    /// ```
+    pub fn read(&mut self, frame: &mut Frame) -> Result<(), Error> {
+        self.trajectory.read(&mut self.buffer)?;
+        *frame = self.filter()?;
+        return Ok(());
+    }
+
+    /// Read a specific `step` of the underlying trajectory, and store the
+    /// selected atoms into `frame`.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if reading the trajectory fails, or if some of the
+    /// indexes given to [`FilteredTrajectory::with_indexes`] are out of bounds
+    /// for the frame.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use chemfiles::{FilteredTrajectory, Frame, Trajectory};
+    /// let trajectory = Trajectory::open("water.xyz", 'r').unwrap();
+    /// let mut trajectory = FilteredTrajectory::with_indexes(trajectory, vec![0, 1, 2]);
+    ///
+    /// let mut frame = Frame::new();
+    /// trajectory.read_step(10, &mut frame).unwrap();
+    /// ```
+    pub fn read_step(&mut self, step: usize, frame: &mut Frame) -> Result<(), Error> {
+        self.trajectory.read_step(step, &mut self.buffer)?;
+        *frame = self.filter()?;
+        return Ok(());
+    }
+
+    /// Get the number of steps (the number of frames) in the underlying
+    /// trajectory.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use chemfiles::{FilteredTrajectory, Trajectory};
+    /// let trajectory = Trajectory::open("water.xyz", 'r').unwrap();
+    /// let mut trajectory = FilteredTrajectory::with_indexes(trajectory, vec![0, 1, 2]);
+    ///
+    /// let steps = trajectory.nsteps();
+    /// ```
+    pub fn nsteps(&mut self) -> usize {
+        return self.trajectory.nsteps();
+    }
+
+    /// Get back the underlying trajectory.
+    pub fn into_inner(self) -> Trajectory {
+        self.trajectory
+    }
+
+    /// Apply the filter to the frame stored in `self.buffer`
+    fn filter(&mut self) -> Result<Frame, Error> {
+        let indexes = match self.filter {
+            Filter::Selection(ref mut selection) => selection.list(&self.buffer),
+            Filter::Indexes(ref indexes) => {
+                let size = self.buffer.size();
+                if let Some(&index) = indexes.iter().find(|&&index| index >= size) {
+                    return Err(Error {
+                        status: Status::OutOfBounds,
+                        message: format!("out of bounds atomic index {index} in a frame with {size} atoms"),
+                    });
+                }
+                indexes.clone()
+            }
+        };
+
+        return Ok(self.buffer.subset(&indexes));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::path::Path;
+
+    fn water() -> Trajectory {
+        let root = Path::new(file!()).parent().unwrap().join("..");
+        let filename = root.join("data").join("water.xyz");
+        return Trajectory::open(filename, 'r').unwrap();
+    }
+
+    #[test]
+    fn selection() {
+        let selection = Selection::new("name O").unwrap();
+        let mut trajectory = FilteredTrajectory::new(water(), selection).unwrap();
+        assert_eq!(trajectory.nsteps(), 100);
+
+        let mut frame = Frame::new();
+        trajectory.read(&mut frame).unwrap();
+        assert_eq!(frame.size(), 99);
+        assert!(frame.iter_atoms().all(|atom| atom.name() == "O"));
+        assert_eq!(frame.positions()[0], [0.417219, 8.303366, 11.737172]);
+
+        let selection = Selection::new("pairs: all").unwrap();
+        let error = FilteredTrajectory::new(water(), selection).unwrap_err();
+        assert_eq!(error.status, Status::SelectionError);
+    }
+
+    #[test]
+    fn indexes() {
+        let mut trajectory = FilteredTrajectory::with_indexes(water(), vec![2, 0, 1, 0]);
+
+        let mut frame = Frame::new();
+        trajectory.read_step(41, &mut frame).unwrap();
+        assert_eq!(frame.size(), 3);
+        assert_eq!(frame.positions()[0], [0.761277, 8.106125, 10.622949]);
+
+        let mut trajectory = FilteredTrajectory::with_indexes(trajectory.into_inner(), vec![0, 300]);
+        let error = trajectory.read(&mut frame).unwrap_err();
+        assert_eq!(error.status, Status::OutOfBounds);
+    }
+}
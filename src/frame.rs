@@ -2,14 +2,163 @@
 // Copyright (C) 2015-2018 Guillaume Fraux -- BSD licensed
 #[allow(clippy::wildcard_imports)]
 use chemfiles_sys as ffi;
+use std::ops::{Deref, DerefMut};
+use std::path::Path;
 
 use crate::{Atom, AtomMut, AtomRef};
-use crate::{BondOrder, Residue, Topology, TopologyRef};
-use crate::{UnitCell, UnitCellMut, UnitCellRef};
+use crate::{BondOrder, Residue, ResidueRef, Topology, TopologyRef};
+use crate::{CellShape, Selection, Trajectory, UnitCell, UnitCellMut, UnitCellRef};
 
-use crate::errors::{check, check_not_null, check_success, Error};
+use crate::bookmarks::BOOKMARK;
+use crate::diff::{self, DiffOptions, FrameDiff};
+use crate::errors::{check, check_not_null, check_success, Error, Status};
+use crate::math;
 use crate::property::{PropertiesIter, Property, RawProperty};
 use crate::strings;
+use crate::symmetry::{SpaceGroup, SymmetryOperation};
+use crate::units::LengthUnit;
+
+/// Name of the atomic property used to store forces
+const FORCES: &str = "forces";
+/// Name of the frame property used to store the energy
+const ENERGY: &str = "energy";
+/// Name of the frame property used to store the energy of the last SCF cycle
+const SCF_ENERGY: &str = "scf_energy";
+/// Name of the frame property used to store the total charge of the system
+const TOTAL_CHARGE: &str = "charge";
+/// Name of the frame property used to store the spin multiplicity
+const MULTIPLICITY: &str = "multiplicity";
+/// Name of the frame property used to store the stress tensor
+const STRESS: &str = "stress";
+/// Name of the frame property used to store the virial tensor
+const VIRIAL: &str = "virial";
+/// Approximate size in bytes of a C++ `Frame`, without the topology and
+/// positions
+const FRAME_SIZE: usize = 320;
+/// Approximate size in bytes of a single frame property and its name
+const PROPERTY_SIZE: usize = 96;
+/// Conversion factor from atomic mass units per cubic Ångström to grams per
+/// cubic centimeter
+const AMU_PER_A3_TO_G_PER_CM3: f64 = 1.660_539_066_6;
+/// Name of the Amber `NetCDF` restart format in chemfiles
+const AMBER_RESTART: &str = "Amber Restart";
+/// Name of the frame property used to store the simulation time
+const TIME: &str = "time";
+/// Name of the frame property used to store the free energy lambda in TRR files
+const TRR_LAMBDA: &str = "trr_lambda";
+/// Name of the frame property used to store the precision of XTC files
+const XTC_PRECISION: &str = "xtc_precision";
+/// Name of the frame property containing symmetry operations
+const SYMMETRY_OPERATIONS: &str = "symmetry_operations";
+/// Name of the frame property containing the space group name
+const SPACE_GROUP: &str = "space_group";
+/// Name of the frame property containing the space group number
+const SPACE_GROUP_NUMBER: &str = "space_group_number";
+/// Prefix of the frame properties set by `Frame::stamp_provenance`
+const PROVENANCE: &str = "provenance.";
+
+/// Parse a 3x3 matrix stored as a string of 9 whitespace-separated values
+fn property_to_matrix(property: &Property) -> Option<[[f64; 3]; 3]> {
+    let value = match property {
+        Property::String(value) => value,
+        _ => return None,
+    };
+
+    let values = value
+        .split_whitespace()
+        .map(str::parse)
+        .collect::<Result<Vec<f64>, _>>()
+        .ok()?;
+    if values.len() != 9 {
+        return None;
+    }
+
+    let mut matrix = [[0.0; 3]; 3];
+    for (i, row) in matrix.iter_mut().enumerate() {
+        row.copy_from_slice(&values[3 * i..3 * i + 3]);
+    }
+    return Some(matrix);
+}
+
+/// Store a 3x3 matrix as a string of 9 whitespace-separated values
+fn matrix_to_property(matrix: [[f64; 3]; 3]) -> Property {
+    let values = matrix.iter().flatten().map(ToString::to_string).collect::<Vec<_>>();
+    return Property::String(values.join(" "));
+}
+
+/// Get the dipole moment of the atoms at `indexes`, using positions relative
+/// to the first atom with the minimal image convention, and the geometric
+/// center of the atoms as origin.
+fn dipole_moment(
+    periodicity: &math::Periodicity,
+    positions: &[[f64; 3]],
+    charges: &[f64],
+    indexes: &[usize],
+) -> [f64; 3] {
+    let first = match indexes.first() {
+        Some(&first) => first,
+        None => return [0.0; 3],
+    };
+
+    let relative = indexes
+        .iter()
+        .map(|&i| periodicity.distance_vector(positions[first], positions[i]))
+        .collect::<Vec<_>>();
+
+    #[allow(clippy::cast_precision_loss)]
+    let center = math::scale(
+        relative.iter().fold([0.0; 3], |a, &b| math::add(a, b)),
+        1.0 / relative.len() as f64,
+    );
+
+    let mut dipole = [0.0; 3];
+    for (&i, &position) in indexes.iter().zip(&relative) {
+        dipole = math::add(dipole, math::scale(math::sub(position, center), charges[i]));
+    }
+    return dipole;
+}
+
+/// Check that the size of per-atom `data` with the given `name` matches the
+/// number of atoms in a frame
+fn check_data_size(name: &str, size: usize, natoms: usize) -> Result<(), Error> {
+    if size != natoms {
+        return Err(Error {
+            status: Status::InvalidArgument,
+            message: format!("expected {natoms} {name} for this frame, got {size}"),
+        });
+    }
+    return Ok(());
+}
+
+/// Format a number of `seconds` since the Unix epoch as an ISO 8601 UTC
+/// timestamp (`YYYY-MM-DDTHH:MM:SSZ`)
+fn iso8601_timestamp(seconds: u64) -> String {
+    let days = seconds / 86400;
+    let time = seconds % 86400;
+
+    // convert days since the epoch to a civil date, using the algorithm from
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    return format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        time / 3600,
+        (time % 3600) / 60,
+        time % 60
+    );
+}
 
 /// A `Frame` contains data from one simulation step: the current unit
 /// cell, the topology, the positions, and the velocities of the particles in
@@ -20,6 +169,12 @@ pub struct Frame {
     handle: *mut ffi::CHFL_FRAME,
 }
 
+// SAFETY: a `Frame` owns its C++ object, which is only accessed through this
+// handle. The reference counting used by the C library to manage memory is
+// protected by a mutex, so frames can be created and freed in any thread.
+// Views into a frame (`AtomRef`, `TopologyRef`, ...) are not `Send`.
+unsafe impl Send for Frame {}
+
 impl Clone for Frame {
     fn clone(&self) -> Frame {
         unsafe {
@@ -85,6 +240,19 @@ impl Frame {
 
     /// Get a reference to the atom at the given `index` in this frame.
     ///
+    /// The returned `AtomRef` borrows the frame, so the frame can not be
+    /// modified while the atom is alive:
+    ///
+    /// ```compile_fail
+    /// # use chemfiles::{Frame, Atom};
+    /// let mut frame = Frame::new();
+    /// frame.add_atom(&Atom::new("Zn"), [0.0; 3], None);
+    ///
+    /// let atom = frame.atom(0);
+    /// frame.remove(0);
+    /// assert_eq!(atom.name(), "Zn");
+    /// ```
+    ///
     /// # Panics
     ///
     /// If `index` is out of bounds.
@@ -98,8 +266,10 @@ impl Frame {
     /// let atom = frame.atom(0);
     /// assert_eq!(atom.name(), "Zn");
     /// ```
-    pub fn atom(&self, index: usize) -> AtomRef {
+    pub fn atom(&self, index: usize) -> AtomRef<'_> {
         unsafe {
+            // the C API only gives mutable pointers to atoms, but the
+            // AtomRef can only be used for reading and borrows `self`
             let handle = ffi::chfl_atom_from_frame(self.as_mut_ptr_MANUALLY_CHECKING_BORROW(), index as u64);
             Atom::ref_from_ptr(handle)
         }
@@ -107,6 +277,20 @@ impl Frame {
 
     /// Get a mutable reference to the atom at the given `index` in this frame.
     ///
+    /// The returned `AtomMut` mutably borrows the whole frame, so the
+    /// positions can not be modified at the same time:
+    ///
+    /// ```compile_fail
+    /// # use chemfiles::{Frame, Atom};
+    /// let mut frame = Frame::new();
+    /// frame.add_atom(&Atom::new("Zn"), [0.0; 3], None);
+    ///
+    /// let mut atom = frame.atom_mut(0);
+    /// let positions = frame.positions_mut();
+    /// atom.set_name("Fe");
+    /// positions[0] = [1.0, 2.0, 3.0];
+    /// ```
+    ///
     /// # Panics
     ///
     /// If `index` is out of bounds.
@@ -121,6 +305,11 @@ impl Frame {
     ///
     /// frame.atom_mut(0).set_name("Fe");
     /// assert_eq!(frame.atom(0).name(), "Fe");
+    ///
+    /// // the borrow ends after the last use of the atom
+    /// let mut atom = frame.atom_mut(0);
+    /// atom.set_name("Cu");
+    /// assert_eq!(frame.size(), 1);
     /// ```
     pub fn atom_mut(&mut self, index: usize) -> AtomMut {
         unsafe {
@@ -153,6 +342,11 @@ impl Frame {
     /// `natoms` atoms. Previous data is conserved, as well as the presence of
     /// absence of velocities.
     ///
+    /// New atoms are unnamed atoms with default properties, use
+    /// [`Frame::resize_with`] to use another atom instead. To remove atoms at
+    /// the end of the frame, use [`Frame::truncate`] which also removes the
+    /// corresponding bonds and residue memberships.
+    ///
     /// # Example
     /// ```
     /// # use chemfiles::Frame;
@@ -166,6 +360,95 @@ impl Frame {
         }
     }
 
+    /// Reserve memory for at least `natoms` atoms in this frame, without
+    /// changing its size. This avoids repeated reallocations when adding
+    /// atoms one by one with [`Frame::add_atom`] and the final size is known
+    /// in advance.
+    ///
+    /// The C API does not expose capacity management, so this function
+    /// resizes the frame to `natoms` and then back to its initial size, which
+    /// keeps the allocated memory around. It does nothing if the frame already
+    /// contains `natoms` atoms or more.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Atom};
+    /// let mut frame = Frame::new();
+    /// frame.reserve(1000);
+    /// assert_eq!(frame.size(), 0);
+    ///
+    /// for i in 0..1000 {
+    ///     frame.add_atom(&Atom::new("Ar"), [0.0, 0.0, i as f64], None);
+    /// }
+    /// assert_eq!(frame.size(), 1000);
+    /// ```
+    pub fn reserve(&mut self, natoms: usize) {
+        let size = self.size();
+        if natoms > size {
+            self.resize(natoms);
+            self.resize(size);
+        }
+    }
+
+    /// Resize this frame to contain `natoms` atoms, using copies of
+    /// `template` for the new atoms.
+    ///
+    /// New atoms are placed at the origin, with zero velocity if this frame
+    /// contains velocities. If `natoms` is smaller than the current size, this
+    /// is the same as [`Frame::truncate`].
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Atom};
+    /// let mut frame = Frame::new();
+    /// frame.add_atom(&Atom::new("Zn"), [1.0, 1.0, 2.0], None);
+    ///
+    /// frame.resize_with(3, &Atom::new("Ar"));
+    /// assert_eq!(frame.size(), 3);
+    /// assert_eq!(frame.atom(0).name(), "Zn");
+    /// assert_eq!(frame.atom(2).name(), "Ar");
+    /// assert_eq!(frame.positions()[2], [0.0, 0.0, 0.0]);
+    /// ```
+    pub fn resize_with(&mut self, natoms: usize, template: &Atom) {
+        let size = self.size();
+        if natoms <= size {
+            self.truncate(natoms);
+            return;
+        }
+
+        self.reserve(natoms);
+        let velocity = if self.has_velocities() { Some([0.0; 3]) } else { None };
+        for _ in size..natoms {
+            self.add_atom(template, [0.0; 3], velocity);
+        }
+    }
+
+    /// Shorten this frame to contain only the first `natoms` atoms.
+    ///
+    /// Bonds involving the removed atoms are removed, and the removed atoms
+    /// are removed from their residues, keeping the topology consistent. This
+    /// function does nothing if the frame contains `natoms` atoms or less.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Atom};
+    /// let mut frame = Frame::new();
+    /// frame.add_atom(&Atom::new("O"), [0.0, 0.0, 0.0], None);
+    /// frame.add_atom(&Atom::new("H"), [1.0, 0.0, 0.0], None);
+    /// frame.add_atom(&Atom::new("H"), [0.0, 1.0, 0.0], None);
+    /// frame.add_bond(0, 1);
+    /// frame.add_bond(0, 2);
+    ///
+    /// frame.truncate(2);
+    /// assert_eq!(frame.size(), 2);
+    /// assert_eq!(frame.bonds(), vec![[0, 1]]);
+    /// ```
+    pub fn truncate(&mut self, natoms: usize) {
+        for i in (natoms..self.size()).rev() {
+            self.remove(i);
+        }
+    }
+
     /// Add an `Atom` and the corresponding position and optionally velocity
     /// data to this frame.
     ///
@@ -216,6 +499,58 @@ impl Frame {
         }
     }
 
+    /// Remove all the atoms matching the `selection` from this frame, and
+    /// return them in a new frame.
+    ///
+    /// The returned frame contains the stripped atoms in the same order as
+    /// in this frame, with their positions, velocities, the bonds between
+    /// them and the residues containing them. It also has the same unit cell,
+    /// step and properties as this frame. The bonds and residues of the
+    /// atoms remaining in this frame are updated accordingly, and residues
+    /// without any remaining atom are removed from this frame.
+    ///
+    /// # Panics
+    ///
+    /// If the selection size is not 1
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Atom, Selection};
+    /// let mut frame = Frame::new();
+    /// frame.add_atom(&Atom::new("Zn"), [0.0, 0.0, 0.0], None);
+    /// frame.add_atom(&Atom::new("O"), [1.0, 0.0, 0.0], None);
+    /// frame.add_atom(&Atom::new("H"), [2.0, 0.0, 0.0], None);
+    /// frame.add_atom(&Atom::new("H"), [0.0, 2.0, 0.0], None);
+    /// frame.add_bond(1, 2);
+    /// frame.add_bond(1, 3);
+    ///
+    /// let mut selection = Selection::new("name O or name H").unwrap();
+    /// let water = frame.strip(&mut selection);
+    ///
+    /// assert_eq!(frame.size(), 1);
+    /// assert_eq!(frame.atom(0).name(), "Zn");
+    ///
+    /// assert_eq!(water.size(), 3);
+    /// assert_eq!(water.positions()[0], [1.0, 0.0, 0.0]);
+    /// assert_eq!(water.bonds(), vec![[0, 1], [0, 2]]);
+    /// ```
+    #[allow(clippy::return_self_not_must_use)]
+    pub fn strip(&mut self, selection: &mut Selection) -> Frame {
+        let mut atoms = selection.list(self);
+        atoms.sort_unstable();
+        atoms.dedup();
+
+        let mut stripped = vec![false; self.size()];
+        for &i in &atoms {
+            stripped[i] = true;
+        }
+        let remaining = (0..self.size()).filter(|&i| !stripped[i]).collect::<Vec<_>>();
+
+        let stripped = self.subset(&atoms);
+        *self = self.subset(&remaining);
+        return stripped;
+    }
+
     /// Add a bond between the atoms at indexes `i` and `j` in the frame.
     ///
     /// The bond order is set to `BondOrder::Unknown`.
@@ -293,6 +628,82 @@ impl Frame {
         }
     }
 
+    /// Get the list of bonds in this frame's topology.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Frame;
+    /// let mut frame = Frame::new();
+    /// frame.resize(4);
+    ///
+    /// frame.add_bond(0, 1);
+    /// frame.add_bond(2, 1);
+    /// frame.add_bond(2, 3);
+    /// assert_eq!(frame.bonds(), vec![[0, 1], [1, 2], [2, 3]]);
+    ///
+    /// for [i, j] in frame.bonds() {
+    ///     assert_eq!(frame.distance(i, j), 0.0);
+    /// }
+    /// ```
+    pub fn bonds(&self) -> Vec<[usize; 2]> {
+        return self.topology().bonds();
+    }
+
+    /// Get the list of angles in this frame's topology.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Atom};
+    /// let mut frame = Frame::new();
+    /// frame.add_atom(&Atom::new("H"), [1.0, 0.0, 0.0], None);
+    /// frame.add_atom(&Atom::new("O"), [0.0, 0.0, 0.0], None);
+    /// frame.add_atom(&Atom::new("H"), [0.0, 1.0, 0.0], None);
+    ///
+    /// frame.add_bond(0, 1);
+    /// frame.add_bond(1, 2);
+    /// assert_eq!(frame.angles(), vec![[0, 1, 2]]);
+    ///
+    /// let angles = frame.angles().into_iter().map(|[i, j, k]| frame.angle(i, j, k)).collect::<Vec<_>>();
+    /// assert_eq!(angles, vec![std::f64::consts::FRAC_PI_2]);
+    /// ```
+    pub fn angles(&self) -> Vec<[usize; 3]> {
+        return self.topology().angles();
+    }
+
+    /// Get the list of dihedral angles in this frame's topology.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Frame;
+    /// let mut frame = Frame::new();
+    /// frame.resize(4);
+    ///
+    /// frame.add_bond(0, 1);
+    /// frame.add_bond(2, 1);
+    /// frame.add_bond(2, 3);
+    /// assert_eq!(frame.dihedrals(), vec![[0, 1, 2, 3]]);
+    /// ```
+    pub fn dihedrals(&self) -> Vec<[usize; 4]> {
+        return self.topology().dihedrals();
+    }
+
+    /// Get the list of improper dihedral angles in this frame's topology.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Frame;
+    /// let mut frame = Frame::new();
+    /// frame.resize(4);
+    ///
+    /// frame.add_bond(0, 1);
+    /// frame.add_bond(0, 2);
+    /// frame.add_bond(0, 3);
+    /// assert_eq!(frame.impropers(), vec![[1, 0, 2, 3]]);
+    /// ```
+    pub fn impropers(&self) -> Vec<[usize; 4]> {
+        return self.topology().impropers();
+    }
+
     /// Add a copy of `residue` to this frame.
     ///
     /// # Errors
@@ -317,6 +728,86 @@ impl Frame {
         unsafe { check(ffi::chfl_frame_add_residue(self.as_mut_ptr(), residue.as_ptr())) }
     }
 
+    /// Get a reference to the residue at index `index` in this frame's
+    /// topology.
+    ///
+    /// The residue index in the topology is not always the same as the residue
+    /// `id`.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Residue};
+    /// let mut frame = Frame::new();
+    /// frame.add_residue(&Residue::new("water")).unwrap();
+    ///
+    /// let residue = frame.residue(0).unwrap();
+    /// assert_eq!(residue.name(), "water");
+    ///
+    /// assert!(frame.residue(1).is_none());
+    /// ```
+    pub fn residue(&self, index: usize) -> Option<ResidueRef<'_>> {
+        let topology = self.topology();
+        unsafe {
+            // the residue pointer is kept alive by the frame, not by the
+            // topology pointer, so it can outlive `topology`
+            let handle = ffi::chfl_residue_from_topology(topology.as_ptr(), index as u64);
+            if handle.is_null() {
+                None
+            } else {
+                Some(Residue::ref_from_ptr(handle))
+            }
+        }
+    }
+
+    /// Get a reference to the residue containing the atom at index `index` in
+    /// this frame, if any.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Residue};
+    /// let mut frame = Frame::new();
+    /// frame.resize(8);
+    ///
+    /// let mut residue = Residue::new("water");
+    /// residue.add_atom(0);
+    /// residue.add_atom(1);
+    /// residue.add_atom(2);
+    /// frame.add_residue(&residue).unwrap();
+    ///
+    /// let residue = frame.residue_for_atom(0).unwrap();
+    /// assert_eq!(residue.name(), "water");
+    ///
+    /// assert!(frame.residue_for_atom(6).is_none());
+    /// ```
+    pub fn residue_for_atom(&self, index: usize) -> Option<ResidueRef<'_>> {
+        let topology = self.topology();
+        unsafe {
+            // see `Frame::residue` for the lifetime of the residue pointer
+            let handle = ffi::chfl_residue_for_atom(topology.as_ptr(), index as u64);
+            if handle.is_null() {
+                None
+            } else {
+                Some(Residue::ref_from_ptr(handle))
+            }
+        }
+    }
+
+    /// Get the number of residues in this frame's topology.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Residue};
+    /// let mut frame = Frame::new();
+    /// assert_eq!(frame.residues_count(), 0);
+    ///
+    /// frame.add_residue(&Residue::with_id("water", 0)).unwrap();
+    /// frame.add_residue(&Residue::with_id("protein", 1)).unwrap();
+    /// assert_eq!(frame.residues_count(), 2);
+    /// ```
+    pub fn residues_count(&self) -> u64 {
+        return self.topology().residues_count();
+    }
+
     /// Get the distance between the atoms at indexes `i` and `j` in this frame,
     /// accounting for periodic boundary conditions. The result is expressed in
     /// Angstroms.
@@ -436,68 +927,239 @@ impl Frame {
         return distance;
     }
 
-    /// Get a view into the positions of this frame.
+    /// Get the length of all the bonds in this frame's topology, in the same
+    /// order as [`Frame::bonds`], accounting for periodic boundary conditions.
+    /// The results are expressed in Angstroms.
+    ///
+    /// This is faster than calling [`Frame::distance`] for each bond.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the unit cell of this frame is degenerate,
+    /// *i.e.* if its matrix can not be inverted.
     ///
     /// # Example
     /// ```
-    /// # use chemfiles::Frame;
+    /// # use chemfiles::{Frame, Atom};
     /// let mut frame = Frame::new();
-    /// frame.resize(67);
+    /// frame.add_atom(&Atom::new("H"), [1.0, 0.0, 0.0], None);
+    /// frame.add_atom(&Atom::new("O"), [0.0, 0.0, 0.0], None);
+    /// frame.add_atom(&Atom::new("H"), [0.0, 2.0, 0.0], None);
+    /// frame.add_bond(0, 1);
+    /// frame.add_bond(1, 2);
     ///
-    /// let positions = frame.positions();
-    /// assert_eq!(positions.len(), 67);
-    /// assert_eq!(positions[0], [0.0, 0.0, 0.0]);
+    /// assert_eq!(frame.measure_bonds().unwrap(), vec![1.0, 2.0]);
     /// ```
-    pub fn positions(&self) -> &[[f64; 3]] {
-        let mut ptr = std::ptr::null_mut();
-        let mut natoms = 0;
-        unsafe {
-            check_success(ffi::chfl_frame_positions(
-                self.as_mut_ptr_MANUALLY_CHECKING_BORROW(),
-                &mut ptr,
-                &mut natoms,
-            ));
-        }
-
-        #[allow(clippy::cast_possible_truncation)]
-        let size = natoms as usize;
-        unsafe {
-            return std::slice::from_raw_parts(ptr, size);
-        }
+    pub fn measure_bonds(&self) -> Result<Vec<f64>, Error> {
+        let periodicity = math::Periodicity::new(&self.cell())?;
+        let positions = self.positions();
+        return Ok(self
+            .bonds()
+            .into_iter()
+            .map(|[i, j]| periodicity.distance(positions[i], positions[j]))
+            .collect());
     }
 
-    /// Get a mutable view into the positions of this frame.
+    /// Get the value of all the angles in this frame's topology, in the same
+    /// order as [`Frame::angles`], accounting for periodic boundary
+    /// conditions. The results are expressed in radians.
+    ///
+    /// This is faster than calling [`Frame::angle`] for each angle.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the unit cell of this frame is degenerate,
+    /// *i.e.* if its matrix can not be inverted.
     ///
     /// # Example
     /// ```
-    /// # use chemfiles::Frame;
+    /// # use chemfiles::{Frame, Atom};
+    /// # use std::f64;
     /// let mut frame = Frame::new();
-    /// frame.resize(67);
-    /// {
-    ///     let positions = frame.positions_mut();
-    ///     assert_eq!(positions[0], [0.0, 0.0, 0.0]);
-    ///     positions[0] = [1.0, 2.0, 3.0];
-    /// }
+    /// frame.add_atom(&Atom::new("H"), [1.0, 0.0, 0.0], None);
+    /// frame.add_atom(&Atom::new("O"), [0.0, 0.0, 0.0], None);
+    /// frame.add_atom(&Atom::new("H"), [0.0, 2.0, 0.0], None);
+    /// frame.add_bond(0, 1);
+    /// frame.add_bond(1, 2);
     ///
-    /// let positions = frame.positions();
-    /// assert_eq!(positions[0], [1.0, 2.0, 3.0]);
+    /// assert_eq!(frame.measure_angles().unwrap(), vec![f64::consts::PI / 2.0]);
     /// ```
-    pub fn positions_mut(&mut self) -> &mut [[f64; 3]] {
-        let mut ptr = std::ptr::null_mut();
-        let mut natoms = 0;
-        unsafe {
-            check_success(ffi::chfl_frame_positions(self.as_mut_ptr(), &mut ptr, &mut natoms));
-        }
-        #[allow(clippy::cast_possible_truncation)]
-        let size = natoms as usize;
-        unsafe {
-            return std::slice::from_raw_parts_mut(ptr, size);
-        }
+    pub fn measure_angles(&self) -> Result<Vec<f64>, Error> {
+        let periodicity = math::Periodicity::new(&self.cell())?;
+        let positions = self.positions();
+        return Ok(self
+            .angles()
+            .into_iter()
+            .map(|[i, j, k]| periodicity.angle(positions[i], positions[j], positions[k]))
+            .collect());
     }
 
-    /// Get a view into the velocities of this frame.
+    /// Get the value of all the dihedral angles in this frame's topology, in
+    /// the same order as [`Frame::dihedrals`], accounting for periodic boundary
+    /// conditions. The results are expressed in radians.
     ///
-    /// # Example
+    /// This is faster than calling [`Frame::dihedral`] for each dihedral angle.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the unit cell of this frame is degenerate,
+    /// *i.e.* if its matrix can not be inverted.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Atom};
+    /// # use std::f64;
+    /// let mut frame = Frame::new();
+    /// frame.add_atom(&Atom::new("A"), [1.0, 0.0, 0.0], None);
+    /// frame.add_atom(&Atom::new("B"), [0.0, 0.0, 0.0], None);
+    /// frame.add_atom(&Atom::new("C"), [0.0, 1.0, 0.0], None);
+    /// frame.add_atom(&Atom::new("D"), [-1.0, 1.0, 0.0], None);
+    /// frame.add_bond(0, 1);
+    /// frame.add_bond(1, 2);
+    /// frame.add_bond(2, 3);
+    ///
+    /// assert_eq!(frame.measure_dihedrals().unwrap(), vec![f64::consts::PI]);
+    /// ```
+    pub fn measure_dihedrals(&self) -> Result<Vec<f64>, Error> {
+        let periodicity = math::Periodicity::new(&self.cell())?;
+        let positions = self.positions();
+        return Ok(self
+            .dihedrals()
+            .into_iter()
+            .map(|[i, j, k, m]| periodicity.dihedral(positions[i], positions[j], positions[k], positions[m]))
+            .collect());
+    }
+
+    /// Get a view into the positions of this frame.
+    ///
+    /// The returned slice borrows the frame, so functions which could
+    /// reallocate the positions (`resize`, `add_atom`, `remove`, ...) can not
+    /// be called while the slice is alive:
+    ///
+    /// ```compile_fail
+    /// # use chemfiles::{Frame, Atom};
+    /// let mut frame = Frame::new();
+    /// let positions = frame.positions();
+    /// frame.add_atom(&Atom::new("Zn"), [0.0; 3], None);
+    /// // this would read freed memory
+    /// let first = positions[0];
+    /// ```
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Frame;
+    /// let mut frame = Frame::new();
+    /// frame.resize(67);
+    ///
+    /// let positions = frame.positions();
+    /// assert_eq!(positions.len(), 67);
+    /// assert_eq!(positions[0], [0.0, 0.0, 0.0]);
+    /// ```
+    pub fn positions(&self) -> &[[f64; 3]] {
+        let mut ptr = std::ptr::null_mut();
+        let mut natoms = 0;
+        unsafe {
+            check_success(ffi::chfl_frame_positions(
+                self.as_mut_ptr_MANUALLY_CHECKING_BORROW(),
+                &mut ptr,
+                &mut natoms,
+            ));
+            return slice_from_raw(ptr, natoms);
+        }
+    }
+
+    /// Get a copy of the positions of this frame, converted to the given
+    /// length `unit`.
+    ///
+    /// This is useful to pass positions to tools using nanometers (such as
+    /// GROMACS) without having to remember that chemfiles uses Ångströms.
+    /// Trajectories in GROMACS formats already do this conversion when reading
+    /// and writing files.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Atom};
+    /// # use chemfiles::units::LengthUnit;
+    /// let mut frame = Frame::new();
+    /// frame.add_atom(&Atom::new("Zn"), [10.0, 20.0, 5.0], None);
+    ///
+    /// assert_eq!(frame.positions_in(LengthUnit::Nanometer), [[1.0, 2.0, 0.5]]);
+    /// ```
+    pub fn positions_in(&self, unit: LengthUnit) -> Vec<[f64; 3]> {
+        return self
+            .positions()
+            .iter()
+            .map(|position| position.map(|x| unit.convert_from_angstrom(x)))
+            .collect();
+    }
+
+    /// Get a mutable view into the positions of this frame.
+    ///
+    /// The returned slice mutably borrows the frame, preventing any other
+    /// access to the frame while it is alive, including reading the atoms or
+    /// the velocities:
+    ///
+    /// ```compile_fail
+    /// # use chemfiles::Frame;
+    /// let mut frame = Frame::new();
+    /// frame.resize(3);
+    /// let positions = frame.positions_mut();
+    /// let atom = frame.atom(0);
+    /// positions[0] = [1.0, 2.0, 3.0];
+    /// ```
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Frame;
+    /// let mut frame = Frame::new();
+    /// frame.resize(67);
+    /// {
+    ///     let positions = frame.positions_mut();
+    ///     assert_eq!(positions[0], [0.0, 0.0, 0.0]);
+    ///     positions[0] = [1.0, 2.0, 3.0];
+    /// }
+    ///
+    /// let positions = frame.positions();
+    /// assert_eq!(positions[0], [1.0, 2.0, 3.0]);
+    /// ```
+    pub fn positions_mut(&mut self) -> &mut [[f64; 3]] {
+        let mut ptr = std::ptr::null_mut();
+        let mut natoms = 0;
+        unsafe {
+            check_success(ffi::chfl_frame_positions(self.as_mut_ptr(), &mut ptr, &mut natoms));
+            return slice_from_raw_mut(ptr, natoms);
+        }
+    }
+
+    /// Set the positions of all the atoms in this frame from `positions`,
+    /// using `positions[i]` for the atom at index `i`.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the number of `positions` does not match the
+    /// number of atoms in this frame.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Frame;
+    /// let mut frame = Frame::new();
+    /// frame.resize(2);
+    ///
+    /// let positions = vec![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]];
+    /// frame.set_positions(&positions).unwrap();
+    /// assert_eq!(frame.positions(), positions);
+    ///
+    /// assert!(frame.set_positions(&[[0.0; 3]]).is_err());
+    /// ```
+    pub fn set_positions(&mut self, positions: &[[f64; 3]]) -> Result<(), Error> {
+        check_data_size("positions", positions.len(), self.size())?;
+        self.positions_mut().copy_from_slice(positions);
+        return Ok(());
+    }
+
+    /// Get a view into the velocities of this frame.
+    ///
+    /// # Example
     /// ```
     /// # use chemfiles::Frame;
     /// let mut frame = Frame::new();
@@ -521,11 +1183,7 @@ impl Frame {
                 &mut ptr,
                 &mut natoms,
             ));
-        }
-        #[allow(clippy::cast_possible_truncation)]
-        let size = natoms as usize;
-        unsafe {
-            return Some(std::slice::from_raw_parts(ptr, size));
+            return Some(slice_from_raw(ptr, natoms));
         }
     }
 
@@ -555,11 +1213,73 @@ impl Frame {
         let mut natoms = 0;
         unsafe {
             check_success(ffi::chfl_frame_velocities(self.as_mut_ptr(), &mut ptr, &mut natoms));
+            return Some(slice_from_raw_mut(ptr, natoms));
         }
-        #[allow(clippy::cast_possible_truncation)]
-        let size = natoms as usize;
+    }
+
+    /// Set the velocities of all the atoms in this frame from `velocities`,
+    /// using `velocities[i]` for the atom at index `i`. Velocities are added
+    /// to this frame if needed.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the number of `velocities` does not match the
+    /// number of atoms in this frame.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Frame;
+    /// let mut frame = Frame::new();
+    /// frame.resize(2);
+    /// assert!(!frame.has_velocities());
+    ///
+    /// let velocities = vec![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]];
+    /// frame.set_velocities(&velocities).unwrap();
+    /// assert_eq!(frame.velocities().unwrap(), velocities);
+    ///
+    /// assert!(frame.set_velocities(&[[0.0; 3]; 3]).is_err());
+    /// ```
+    pub fn set_velocities(&mut self, velocities: &[[f64; 3]]) -> Result<(), Error> {
+        check_data_size("velocities", velocities.len(), self.size())?;
+        self.add_velocities();
+        self.velocities_mut()
+            .expect("velocities were just added")
+            .copy_from_slice(velocities);
+        return Ok(());
+    }
+
+    /// Get mutable views into both the positions and the velocities (if any)
+    /// of this frame at the same time.
+    ///
+    /// The borrow checker prevents holding the results of
+    /// [`Frame::positions_mut`] and [`Frame::velocities_mut`] at the same
+    /// time, since both borrow the whole frame mutably. This function can be
+    /// used instead, for example when integrating equations of motion.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Frame;
+    /// let mut frame = Frame::new();
+    /// frame.resize(2);
+    /// frame.add_velocities();
+    ///
+    /// let (positions, velocities) = frame.positions_and_velocities_mut();
+    /// let velocities = velocities.expect("missing velocities");
+    /// velocities[1] = [1.0, 0.0, 0.0];
+    /// for (position, velocity) in positions.iter_mut().zip(velocities.iter()) {
+    ///     position[0] += 0.5 * velocity[0];
+    /// }
+    ///
+    /// assert_eq!(frame.positions()[1], [0.5, 0.0, 0.0]);
+    /// ```
+    #[allow(trivial_casts)]
+    pub fn positions_and_velocities_mut(&mut self) -> (&mut [[f64; 3]], Option<&mut [[f64; 3]]>) {
+        let velocities = self.velocities_mut().map(|velocities| velocities as *mut _);
+        let positions = self.positions_mut() as *mut _;
+        // positions and velocities are stored in separate arrays in the C++
+        // frame, so these two slices do not alias, and they both borrow `self`
         unsafe {
-            return Some(std::slice::from_raw_parts_mut(ptr, size));
+            return (&mut *positions, velocities.map(|velocities| &mut *velocities));
         }
     }
 
@@ -600,6 +1320,116 @@ impl Frame {
         }
     }
 
+    /// Get the forces acting on the atoms in this frame, if any.
+    ///
+    /// Chemfiles does not have a dedicated storage for forces, and formats
+    /// supporting them (such as extended XYZ) store them as a `Vector3D`
+    /// property named `"forces"` on each atom. This function returns `None` if
+    /// any atom is missing this property.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Frame;
+    /// let mut frame = Frame::new();
+    /// frame.resize(2);
+    /// assert_eq!(frame.forces(), None);
+    ///
+    /// frame.add_forces();
+    /// assert_eq!(frame.forces(), Some(vec![[0.0; 3], [0.0; 3]]));
+    /// ```
+    pub fn forces(&self) -> Option<Vec<[f64; 3]>> {
+        let properties = self.atom_array_property(FORCES)?;
+        return properties
+            .into_iter()
+            .map(|property| match property {
+                Property::Vector3D(force) => Some(force),
+                _ => None,
+            })
+            .collect();
+    }
+
+    /// Get a mutable view of the forces acting on the atoms in this frame, if
+    /// any. The forces are written back to the atoms when the returned
+    /// [`ForcesMut`] is dropped.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Frame;
+    /// let mut frame = Frame::new();
+    /// frame.resize(2);
+    /// assert!(frame.forces_mut().is_none());
+    ///
+    /// frame.add_forces();
+    /// if let Some(mut forces) = frame.forces_mut() {
+    ///     forces[1] = [1.0, 2.0, 3.0];
+    /// }
+    /// assert_eq!(frame.forces(), Some(vec![[0.0; 3], [1.0, 2.0, 3.0]]));
+    /// ```
+    pub fn forces_mut(&mut self) -> Option<ForcesMut<'_>> {
+        let forces = self.forces()?;
+        return Some(ForcesMut { frame: self, forces });
+    }
+
+    /// Set the forces acting on the atoms in this frame, using `forces[i]` for
+    /// the atom at index `i`.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the number of `forces` does not match the
+    /// number of atoms in this frame.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Frame;
+    /// let mut frame = Frame::new();
+    /// frame.resize(1);
+    ///
+    /// frame.set_forces(&[[0.5, 0.0, -0.5]]).unwrap();
+    /// assert_eq!(frame.forces(), Some(vec![[0.5, 0.0, -0.5]]));
+    ///
+    /// assert!(frame.set_forces(&[[0.0; 3]; 2]).is_err());
+    /// ```
+    pub fn set_forces(&mut self, forces: &[[f64; 3]]) -> Result<(), Error> {
+        check_data_size("forces", forces.len(), self.size())?;
+        return self.set_atom_array_property(FORCES, forces);
+    }
+
+    /// Check if this frame contains forces, *i.e.* if all atoms have a
+    /// `Vector3D` property named `"forces"`. Frames without atoms never contain
+    /// forces.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Frame;
+    /// let mut frame = Frame::new();
+    /// frame.resize(3);
+    /// assert_eq!(frame.has_forces(), false);
+    ///
+    /// frame.add_forces();
+    /// assert_eq!(frame.has_forces(), true);
+    /// ```
+    pub fn has_forces(&self) -> bool {
+        return self.size() != 0 && self.forces().is_some();
+    }
+
+    /// Add forces data to this frame, setting the forces to zero. If the frame
+    /// already have forces, this does nothing.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Frame;
+    /// let mut frame = Frame::new();
+    /// frame.resize(3);
+    /// frame.add_forces();
+    /// assert_eq!(frame.forces(), Some(vec![[0.0; 3]; 3]));
+    /// ```
+    pub fn add_forces(&mut self) {
+        if !self.has_forces() {
+            let forces = vec![[0.0; 3]; self.size()];
+            self.set_forces(&forces).expect("forces have the size of the frame");
+        }
+    }
+
     /// Get a reference to the `UnitCell` from this frame.
     ///
     /// # Example
@@ -673,6 +1503,33 @@ impl Frame {
         }
     }
 
+    /// Call `function` with a reference to the `Topology` of this frame, and
+    /// return the result.
+    ///
+    /// Each call to [`Frame::topology`] creates a new reference to the
+    /// topology through the C API. This function fetches the topology only
+    /// once, and should be preferred when accessing the topology multiple
+    /// times.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Atom};
+    /// let mut frame = Frame::new();
+    /// frame.add_atom(&Atom::new("O"), [0.0; 3], None);
+    /// frame.add_atom(&Atom::new("H"), [1.0, 0.0, 0.0], None);
+    /// frame.add_bond(0, 1);
+    ///
+    /// let (count, bonds) = frame.with_topology(|topology| {
+    ///     (topology.bonds_count(), topology.bonds())
+    /// });
+    /// assert_eq!(count, 1);
+    /// assert_eq!(bonds, vec![[0, 1]]);
+    /// ```
+    pub fn with_topology<T>(&self, function: impl FnOnce(&Topology) -> T) -> T {
+        let topology = self.topology();
+        return function(&topology);
+    }
+
     /// Set the `Topology` of this frame to `topology`.
     ///
     /// # Errors
@@ -758,6 +1615,74 @@ impl Frame {
         unsafe { check(ffi::chfl_frame_guess_bonds(self.as_mut_ptr())) }
     }
 
+    /// Guess the bonds in this `frame` from the radii of the atoms, adding a
+    /// bond between all pairs of atoms closer than `tolerance` times the sum
+    /// of their radii. This returns the number of new bonds.
+    ///
+    /// The radius of coarse-grained beads (see [`Atom::bead`]) is used if
+    /// available, and the covalent radius of the element otherwise. Unlike
+    /// [`Frame::guess_bonds`], this also works for coarse-grained systems,
+    /// where bead names do not correspond to elements. Distances use the
+    /// minimal image convention, and existing bonds are kept.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if `tolerance` is not strictly positive, or if an
+    /// atom has no bead radius and no known covalent radius.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Atom};
+    /// let mut frame = Frame::new();
+    /// frame.add_atom(&Atom::bead("P4", 72.0, 2.35), [0.0, 0.0, 0.0], None);
+    /// frame.add_atom(&Atom::bead("C1", 72.0, 2.35), [4.7, 0.0, 0.0], None);
+    /// frame.add_atom(&Atom::bead("C1", 72.0, 2.35), [12.0, 0.0, 0.0], None);
+    ///
+    /// assert_eq!(frame.guess_bonds_with_radii(1.1).unwrap(), 1);
+    /// assert_eq!(frame.topology().bonds(), vec![[0, 1]]);
+    /// ```
+    pub fn guess_bonds_with_radii(&mut self, tolerance: f64) -> Result<usize, Error> {
+        if !tolerance.is_finite() || tolerance <= 0.0 {
+            return Err(Error {
+                status: Status::InvalidArgument,
+                message: format!("the tolerance for bond guessing must be positive, got {tolerance}"),
+            });
+        }
+
+        let mut radii = Vec::with_capacity(self.size());
+        for atom in self.iter_atoms() {
+            let radius = atom.bead_radius().unwrap_or_else(|| atom.covalent_radius());
+            if radius <= 0.0 {
+                return Err(Error {
+                    status: Status::InvalidArgument,
+                    message: format!(
+                        "missing radius for atom '{}', use Atom::bead to set the radius of coarse-grained beads",
+                        atom.name()
+                    ),
+                });
+            }
+            radii.push(radius);
+        }
+
+        let periodicity = math::Periodicity::new(&self.cell())?;
+        let positions = self.positions();
+        let mut bonds = Vec::new();
+        for i in 0..positions.len() {
+            for j in (i + 1)..positions.len() {
+                let distance = periodicity.distance(positions[i], positions[j]);
+                if distance < tolerance * (radii[i] + radii[j]) {
+                    bonds.push((i, j));
+                }
+            }
+        }
+
+        let existing = self.topology().bonds_count();
+        for (i, j) in bonds {
+            self.add_bond(i, j);
+        }
+        return Ok(self.topology().bonds_count() - existing);
+    }
+
     /// Remove all existing bonds, angles, dihedral angles and improper
     /// dihedral angles in the topology of the frame.
     ///
@@ -836,24 +1761,19 @@ impl Frame {
         }
     }
 
-    /// Get an iterator over all (name, property) pairs for this frame
+    /// Get the names of all properties of this frame, sorted in
+    /// alphabetical order.
     ///
-    /// # Examples
+    /// # Example
     /// ```
-    /// # use chemfiles::{Frame, Property};
+    /// # use chemfiles::Frame;
     /// let mut frame = Frame::new();
-    /// frame.set("foo", Property::Double(22.2));
-    /// frame.set("bar", Property::Bool(false));
+    /// frame.set("foo", 1.0);
+    /// frame.set("bar", false);
     ///
-    /// for (name, property) in frame.properties() {
-    ///     if name == "foo" {
-    ///         assert_eq!(property, Property::Double(22.2));
-    ///     } else if name == "bar" {
-    ///         assert_eq!(property, Property::Bool(false));
-    ///     }
-    /// }
+    /// assert_eq!(frame.properties_names(), ["bar", "foo"]);
     /// ```
-    pub fn properties(&self) -> PropertiesIter {
+    pub fn properties_names(&self) -> Vec<String> {
         let mut count = 0;
         unsafe {
             check_success(ffi::chfl_frame_properties_count(self.as_ptr(), &mut count));
@@ -870,30 +1790,1692 @@ impl Frame {
             ));
         }
 
-        let mut names = Vec::new();
-        for ptr in c_names {
-            names.push(strings::from_c(ptr));
-        }
+        let mut names = c_names.into_iter().map(|ptr| strings::from_c(ptr)).collect::<Vec<_>>();
+        names.sort_unstable();
+        return names;
+    }
 
+    /// Get an iterator over all (name, property) pairs for this frame, in
+    /// alphabetical order of the names.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chemfiles::{Frame, Property};
+    /// let mut frame = Frame::new();
+    /// frame.set("foo", Property::Double(22.2));
+    /// frame.set("bar", Property::Bool(false));
+    ///
+    /// let properties = frame.properties().collect::<Vec<_>>();
+    /// assert_eq!(properties, [
+    ///     ("bar".to_string(), Property::Bool(false)),
+    ///     ("foo".to_string(), Property::Double(22.2)),
+    /// ]);
+    /// ```
+    pub fn properties(&self) -> PropertiesIter {
         PropertiesIter {
-            names: names.into_iter(),
+            names: self.properties_names().into_iter(),
             getter: Box::new(move |name| self.get(name).expect("failed to get property")),
         }
     }
 
-    /// Gets an iterator over atoms
+    /// Get an iterator over the properties of this frame with names starting
+    /// with `prefix`, in alphabetical order. The prefix is removed from the
+    /// names returned by the iterator.
+    ///
+    /// Prefixes allow different tools to store their own metadata in a frame
+    /// without name collisions, for example using `"md/"` for all the
+    /// properties set by a simulation engine.
     ///
     /// # Example
     /// ```
-    /// # use chemfiles::{Atom, AtomRef, Frame};
+    /// # use chemfiles::{Frame, Property};
     /// let mut frame = Frame::new();
+    /// frame.set_with_prefix("md/", "thermostat", "nose-hoover");
+    /// frame.set_with_prefix("md/", "temperature", 300.0);
+    /// frame.set("name", "water");
     ///
-    /// frame.add_atom(&Atom::new("O"), [0.0, 0.0, 0.0], None);
-    /// frame.add_atom(&Atom::new("H"), [1.0, 0.0, 0.0], None);
-    ///
-    /// let mut atoms: Vec<AtomRef> = Vec::new();
-    ///
-    /// for atom in frame.iter_atoms() {
+    /// let properties = frame.properties_with_prefix("md/").collect::<Vec<_>>();
+    /// assert_eq!(properties, [
+    ///     ("temperature".to_string(), Property::Double(300.0)),
+    ///     ("thermostat".to_string(), Property::String("nose-hoover".into())),
+    /// ]);
+    /// ```
+    pub fn properties_with_prefix(&self, prefix: &str) -> PropertiesIter<'_> {
+        let names = self
+            .properties_names()
+            .into_iter()
+            .filter_map(|name| name.strip_prefix(prefix).map(String::from))
+            .collect::<Vec<_>>();
+
+        let prefix = prefix.to_owned();
+        PropertiesIter {
+            names: names.into_iter(),
+            getter: Box::new(move |name| self.get_with_prefix(&prefix, name).expect("failed to get property")),
+        }
+    }
+
+    /// Get the property with the given `name` under `prefix`, *i.e.* the
+    /// property named `"{prefix}{name}"`, if it exists.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Property};
+    /// let mut frame = Frame::new();
+    /// frame.set("md/temperature", 300.0);
+    /// assert_eq!(frame.get_with_prefix("md/", "temperature"), Some(Property::Double(300.0)));
+    /// ```
+    pub fn get_with_prefix(&self, prefix: &str, name: &str) -> Option<Property> {
+        return self.get(&format!("{prefix}{name}"));
+    }
+
+    /// Add a new `property` with the given `name` under `prefix`, *i.e.* with
+    /// the name `"{prefix}{name}"`. If a property with the same name already
+    /// exists, it is replaced.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Property};
+    /// let mut frame = Frame::new();
+    /// frame.set_with_prefix("md/", "temperature", 300.0);
+    /// assert_eq!(frame.get("md/temperature"), Some(Property::Double(300.0)));
+    /// ```
+    pub fn set_with_prefix(&mut self, prefix: &str, name: &str, property: impl Into<Property>) {
+        self.set(&format!("{prefix}{name}"), property);
+    }
+
+    /// Remove the property with the given `name` from this frame, returning
+    /// it if it existed.
+    ///
+    /// The C API of chemfiles does not support removing properties, so this
+    /// function rebuilds the frame with all the other data, which takes a
+    /// time proportional to the number of atoms.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Property};
+    /// let mut frame = Frame::new();
+    /// frame.set("foo", 3.0);
+    ///
+    /// assert_eq!(frame.remove_property("foo"), Some(Property::Double(3.0)));
+    /// assert_eq!(frame.get("foo"), None);
+    /// assert_eq!(frame.remove_property("foo"), None);
+    /// ```
+    pub fn remove_property(&mut self, name: &str) -> Option<Property> {
+        let property = self.get(name)?;
+        let _ = self.retain_properties(|other| other != name);
+        return Some(property);
+    }
+
+    /// Remove all the properties with names starting with `prefix` from this
+    /// frame, and return the number of removed properties. See
+    /// [`Frame::remove_property`] for the cost of removing properties.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Frame;
+    /// let mut frame = Frame::new();
+    /// frame.set("md/temperature", 300.0);
+    /// frame.set("md/pressure", 1.0);
+    /// frame.set("name", "water");
+    ///
+    /// assert_eq!(frame.remove_properties_with_prefix("md/"), 2);
+    /// assert_eq!(frame.properties_names(), ["name"]);
+    /// ```
+    pub fn remove_properties_with_prefix(&mut self, prefix: &str) -> usize {
+        return self.retain_properties(|name| !name.starts_with(prefix));
+    }
+
+    /// Remove all the properties of this frame. See
+    /// [`Frame::remove_property`] for the cost of removing properties.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Frame;
+    /// let mut frame = Frame::new();
+    /// frame.set("foo", 3.0);
+    /// frame.set("bar", "baz");
+    ///
+    /// frame.clear_properties();
+    /// assert_eq!(frame.properties().count(), 0);
+    /// ```
+    pub fn clear_properties(&mut self) {
+        let _ = self.retain_properties(|_| false);
+    }
+
+    /// Remove the property with the given `name` from all the atoms in this
+    /// frame, and return the number of atoms which had this property. This
+    /// is useful to drop stale per-atom data before writing a file, for
+    /// example with extended XYZ.
+    ///
+    /// The C API of chemfiles does not support removing properties, so this
+    /// function rebuilds the frame with all the other data, which takes a
+    /// time proportional to the number of atoms.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Atom, Frame};
+    /// let mut frame = Frame::new();
+    /// let mut atom = Atom::new("O");
+    /// atom.set("forces", [1.0, 2.0, 3.0]);
+    /// frame.add_atom(&atom, [0.0, 0.0, 0.0], None);
+    /// frame.add_atom(&Atom::new("H"), [1.0, 0.0, 0.0], None);
+    ///
+    /// assert_eq!(frame.remove_atom_property("forces"), 1);
+    /// assert_eq!(frame.atom(0).get("forces"), None);
+    /// ```
+    pub fn remove_atom_property(&mut self, name: &str) -> usize {
+        let count = self.iter_atoms().filter(|atom| atom.get(name).is_some()).count();
+        if count != 0 {
+            let indexes = (0..self.size()).collect::<Vec<_>>();
+            *self = self.rebuild(&indexes, &|_| true, Some(&|other| other != name));
+        }
+        return count;
+    }
+
+    /// Only keep the properties for which `keep` returns `true`, rebuilding
+    /// the frame if needed. This returns the number of removed properties.
+    fn retain_properties(&mut self, keep: impl Fn(&str) -> bool) -> usize {
+        let removed = self.properties_names().iter().filter(|name| !keep(name)).count();
+        if removed != 0 {
+            let indexes = (0..self.size()).collect::<Vec<_>>();
+            *self = self.rebuild(&indexes, &keep, None);
+        }
+        return removed;
+    }
+
+    /// Get the energy of this frame, stored in the `"energy"` property by
+    /// extended XYZ and other formats produced by electronic structure codes.
+    ///
+    /// This returns `None` if the property is missing or is not a number.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Frame;
+    /// let mut frame = Frame::new();
+    /// assert_eq!(frame.energy(), None);
+    ///
+    /// frame.set_energy(-42.5);
+    /// assert_eq!(frame.energy(), Some(-42.5));
+    /// ```
+    pub fn energy(&self) -> Option<f64> {
+        return self.get_number(ENERGY);
+    }
+
+    /// Get the value of the property with the given `name` as a number,
+    /// parsing string properties if needed
+    fn get_number(&self, name: &str) -> Option<f64> {
+        match self.get(name) {
+            Some(Property::Double(value)) => Some(value),
+            Some(Property::String(value)) => value.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Set the energy of this frame in the `"energy"` property.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Property};
+    /// let mut frame = Frame::new();
+    /// frame.set_energy(-42.5);
+    /// assert_eq!(frame.get("energy"), Some(Property::Double(-42.5)));
+    /// ```
+    pub fn set_energy(&mut self, energy: f64) {
+        self.set(ENERGY, energy);
+    }
+
+    /// Get the converged self-consistent field (SCF) energy of this frame,
+    /// stored in the `"scf_energy"` property. For quantum chemistry
+    /// calculations, this is the electronic energy, while [`Frame::energy`]
+    /// can contain additional corrections.
+    ///
+    /// This returns `None` if the property is missing or is not a number.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Frame;
+    /// let mut frame = Frame::new();
+    /// assert_eq!(frame.scf_energy(), None);
+    ///
+    /// frame.set("scf_energy", "-76.4266");
+    /// assert_eq!(frame.scf_energy(), Some(-76.4266));
+    /// ```
+    pub fn scf_energy(&self) -> Option<f64> {
+        return self.get_number(SCF_ENERGY);
+    }
+
+    /// Set the SCF energy of this frame in the `"scf_energy"` property.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Property};
+    /// let mut frame = Frame::new();
+    /// frame.set_scf_energy(-76.4266);
+    /// assert_eq!(frame.get("scf_energy"), Some(Property::Double(-76.4266)));
+    /// ```
+    pub fn set_scf_energy(&mut self, energy: f64) {
+        self.set(SCF_ENERGY, energy);
+    }
+
+    /// Get the total charge of the system in this frame, in units of the
+    /// elementary charge, stored in the `"charge"` property by extended XYZ
+    /// files and other formats produced by electronic structure codes.
+    ///
+    /// This is the charge used for the electronic structure calculation, and
+    /// can be different from the sum of the atomic (partial) charges given by
+    /// [`Frame::charges`]. This returns `None` if the property is missing or
+    /// is not a number.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Frame;
+    /// let mut frame = Frame::new();
+    /// assert_eq!(frame.total_charge(), None);
+    ///
+    /// frame.set_total_charge(-1.0);
+    /// assert_eq!(frame.total_charge(), Some(-1.0));
+    /// ```
+    pub fn total_charge(&self) -> Option<f64> {
+        return self.get_number(TOTAL_CHARGE);
+    }
+
+    /// Set the total charge of the system in this frame, in the `"charge"`
+    /// property.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Property};
+    /// let mut frame = Frame::new();
+    /// frame.set_total_charge(2.0);
+    /// assert_eq!(frame.get("charge"), Some(Property::Double(2.0)));
+    /// ```
+    pub fn set_total_charge(&mut self, charge: f64) {
+        self.set(TOTAL_CHARGE, charge);
+    }
+
+    /// Get the spin multiplicity (2S + 1) of the system in this frame, stored
+    /// in the `"multiplicity"` property.
+    ///
+    /// This returns `None` if the property is missing, or is not a strictly
+    /// positive integer.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Frame;
+    /// let mut frame = Frame::new();
+    /// assert_eq!(frame.multiplicity(), None);
+    ///
+    /// frame.set("multiplicity", "3");
+    /// assert_eq!(frame.multiplicity(), Some(3));
+    /// ```
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn multiplicity(&self) -> Option<u32> {
+        let multiplicity = self.get_number(MULTIPLICITY)?;
+        if multiplicity >= 1.0 && multiplicity <= f64::from(u32::MAX) && multiplicity.fract() == 0.0 {
+            return Some(multiplicity as u32);
+        }
+        return None;
+    }
+
+    /// Set the spin multiplicity of the system in this frame, in the
+    /// `"multiplicity"` property.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Property};
+    /// let mut frame = Frame::new();
+    /// frame.set_multiplicity(2);
+    /// assert_eq!(frame.get("multiplicity"), Some(Property::Double(2.0)));
+    /// ```
+    pub fn set_multiplicity(&mut self, multiplicity: u32) {
+        self.set(MULTIPLICITY, f64::from(multiplicity));
+    }
+
+    /// Get the stress tensor of this frame, stored in the `"stress"` property.
+    ///
+    /// Chemfiles properties can not contain matrices, so extended XYZ files
+    /// store the stress as a string containing the 9 components of the matrix
+    /// in row-major order. This returns `None` if the property is missing or
+    /// can not be parsed as a 3x3 matrix.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Frame;
+    /// let mut frame = Frame::new();
+    /// frame.set("stress", "1 0 0 0 2 0 0 0 3");
+    /// assert_eq!(frame.stress(), Some([[1.0, 0.0, 0.0], [0.0, 2.0, 0.0], [0.0, 0.0, 3.0]]));
+    /// ```
+    pub fn stress(&self) -> Option<[[f64; 3]; 3]> {
+        return self.get(STRESS).and_then(|property| property_to_matrix(&property));
+    }
+
+    /// Set the stress tensor of this frame in the `"stress"` property, using
+    /// the same string representation as extended XYZ files.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Property};
+    /// let mut frame = Frame::new();
+    /// frame.set_stress([[1.0, 0.0, 0.0], [0.0, 2.0, 0.0], [0.0, 0.0, 3.5]]);
+    /// assert_eq!(frame.get("stress"), Some(Property::String("1 0 0 0 2 0 0 0 3.5".into())));
+    /// ```
+    pub fn set_stress(&mut self, stress: [[f64; 3]; 3]) {
+        self.set(STRESS, matrix_to_property(stress));
+    }
+
+    /// Get the virial tensor of this frame, stored in the `"virial"` property.
+    ///
+    /// See [`Frame::stress`] for the representation of this property.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Frame;
+    /// let mut frame = Frame::new();
+    /// assert_eq!(frame.virial(), None);
+    ///
+    /// frame.set_virial([[1.0, 2.0, 3.0], [2.0, 4.0, 5.0], [3.0, 5.0, 6.0]]);
+    /// assert_eq!(frame.virial(), Some([[1.0, 2.0, 3.0], [2.0, 4.0, 5.0], [3.0, 5.0, 6.0]]));
+    /// ```
+    pub fn virial(&self) -> Option<[[f64; 3]; 3]> {
+        return self.get(VIRIAL).and_then(|property| property_to_matrix(&property));
+    }
+
+    /// Set the virial tensor of this frame in the `"virial"` property, using
+    /// the same string representation as extended XYZ files.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Frame;
+    /// let mut frame = Frame::new();
+    /// frame.set_virial([[0.0; 3]; 3]);
+    /// assert_eq!(frame.virial(), Some([[0.0; 3]; 3]));
+    /// ```
+    pub fn set_virial(&mut self, virial: [[f64; 3]; 3]) {
+        self.set(VIRIAL, matrix_to_property(virial));
+    }
+
+    /// Get the simulation time of this frame in picoseconds, stored in the
+    /// `"time"` property by the XTC and TRR formats.
+    ///
+    /// This returns `None` if the property is missing or is not a number.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Frame;
+    /// let mut frame = Frame::new();
+    /// assert_eq!(frame.time(), None);
+    ///
+    /// frame.set_time(12.5);
+    /// assert_eq!(frame.time(), Some(12.5));
+    /// ```
+    pub fn time(&self) -> Option<f64> {
+        return match self.get(TIME) {
+            Some(Property::Double(time)) => Some(time),
+            _ => None,
+        };
+    }
+
+    /// Set the simulation time of this frame in picoseconds, in the `"time"`
+    /// property. This value is used by the XTC and TRR writers.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Property};
+    /// let mut frame = Frame::new();
+    /// frame.set_time(0.002);
+    /// assert_eq!(frame.get("time"), Some(Property::Double(0.002)));
+    /// ```
+    pub fn set_time(&mut self, time: f64) {
+        self.set(TIME, time);
+    }
+
+    /// Get the free energy perturbation parameter lambda of this frame,
+    /// stored in the `"trr_lambda"` property by the TRR format.
+    ///
+    /// This returns `None` if the property is missing or is not a number.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Frame;
+    /// let mut frame = Frame::new();
+    /// assert_eq!(frame.lambda(), None);
+    ///
+    /// frame.set_lambda(0.25);
+    /// assert_eq!(frame.lambda(), Some(0.25));
+    /// ```
+    pub fn lambda(&self) -> Option<f64> {
+        return match self.get(TRR_LAMBDA) {
+            Some(Property::Double(lambda)) => Some(lambda),
+            _ => None,
+        };
+    }
+
+    /// Set the free energy perturbation parameter lambda of this frame, in the
+    /// `"trr_lambda"` property. This value is used by the TRR writer.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Property};
+    /// let mut frame = Frame::new();
+    /// frame.set_lambda(1.0);
+    /// assert_eq!(frame.get("trr_lambda"), Some(Property::Double(1.0)));
+    /// ```
+    pub fn set_lambda(&mut self, lambda: f64) {
+        self.set(TRR_LAMBDA, lambda);
+    }
+
+    /// Get the precision used to compress the positions of this frame,
+    /// stored in the `"xtc_precision"` property by the XTC format. Positions
+    /// are rounded to `1 / precision` nanometers.
+    ///
+    /// This returns `None` if the property is missing or is not a number.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Frame;
+    /// let mut frame = Frame::new();
+    /// assert_eq!(frame.xtc_precision(), None);
+    ///
+    /// frame.set_xtc_precision(1000.0);
+    /// assert_eq!(frame.xtc_precision(), Some(1000.0));
+    /// ```
+    pub fn xtc_precision(&self) -> Option<f64> {
+        return match self.get(XTC_PRECISION) {
+            Some(Property::Double(precision)) => Some(precision),
+            _ => None,
+        };
+    }
+
+    /// Set the precision used to compress the positions of this frame in the
+    /// `"xtc_precision"` property. This value is used by the XTC writer, which
+    /// uses a default precision of 1000 if the property is missing.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Property};
+    /// let mut frame = Frame::new();
+    /// frame.set_xtc_precision(100.0);
+    /// assert_eq!(frame.get("xtc_precision"), Some(Property::Double(100.0)));
+    /// ```
+    pub fn set_xtc_precision(&mut self, precision: f64) {
+        self.set(XTC_PRECISION, precision);
+    }
+
+    /// Get the bookmark label of this frame, stored in the `"bookmark"`
+    /// property. See [`Bookmarks`](crate::Bookmarks) for more information.
+    ///
+    /// This returns `None` if the property is missing or is not a string.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Frame;
+    /// let mut frame = Frame::new();
+    /// assert_eq!(frame.bookmark(), None);
+    ///
+    /// frame.set_bookmark("equilibrated");
+    /// assert_eq!(frame.bookmark().as_deref(), Some("equilibrated"));
+    /// ```
+    pub fn bookmark(&self) -> Option<String> {
+        match self.get(BOOKMARK) {
+            Some(Property::String(label)) => Some(label),
+            _ => None,
+        }
+    }
+
+    /// Set the bookmark label of this frame in the `"bookmark"` property.
+    /// When writing to a format supporting frame properties, the bookmarks
+    /// can be collected later with [`Bookmarks::scan`](crate::Bookmarks::scan).
+    pub fn set_bookmark(&mut self, label: &str) {
+        self.set(BOOKMARK, label);
+    }
+
+    /// Get the space group of this frame, stored in the `"space_group"`
+    /// (Hermann-Mauguin name) and `"space_group_number"` properties.
+    ///
+    /// The CIF and mmCIF readers of the chemfiles C library do not store the
+    /// space group, so this returns `None` for these files unless the space
+    /// group was set with [`Frame::set_space_group`]. This also returns `None`
+    /// if the `"space_group"` property is missing or is not a string.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, SpaceGroup};
+    /// let mut frame = Frame::new();
+    /// assert_eq!(frame.space_group(), None);
+    ///
+    /// frame.set("space_group", "P 1");
+    /// assert_eq!(frame.space_group(), Some(SpaceGroup::new("P 1", None)));
+    /// ```
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn space_group(&self) -> Option<SpaceGroup> {
+        let name = match self.get(SPACE_GROUP) {
+            Some(Property::String(name)) => name,
+            _ => return None,
+        };
+
+        let number = match self.get(SPACE_GROUP_NUMBER) {
+            Some(Property::Double(number)) if number >= 1.0 && number.fract() == 0.0 => Some(number as u32),
+            Some(Property::String(number)) => number.trim().parse().ok(),
+            _ => None,
+        };
+
+        return Some(SpaceGroup::new(name.trim(), number));
+    }
+
+    /// Set the space group of this frame in the `"space_group"` and
+    /// `"space_group_number"` properties. The number property is removed if
+    /// the space group does not have a number, see [`Frame::remove_property`]
+    /// for the cost of removing properties.
+    ///
+    /// These properties are written by formats storing frame properties
+    /// (such as extended XYZ), but the CIF writer of the chemfiles C library
+    /// always writes the `P 1` space group, with all atoms in the unit cell.
+    /// Use [`Frame::expand_symmetry`] before writing to a CIF file to get all
+    /// the atoms in the output.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Property, SpaceGroup};
+    /// let mut frame = Frame::new();
+    /// frame.set_space_group(&SpaceGroup::new("F m -3 m", 225));
+    /// assert_eq!(frame.get("space_group"), Some(Property::String("F m -3 m".into())));
+    /// assert_eq!(frame.get("space_group_number"), Some(Property::Double(225.0)));
+    /// ```
+    pub fn set_space_group(&mut self, space_group: &SpaceGroup) {
+        self.set(SPACE_GROUP, space_group.name.as_str());
+        match space_group.number {
+            Some(number) => self.set(SPACE_GROUP_NUMBER, f64::from(number)),
+            None => {
+                let _ = self.remove_property(SPACE_GROUP_NUMBER);
+            }
+        }
+    }
+
+    /// Record which tool produced this frame, by setting the
+    /// `"provenance.tool"`, `"provenance.version"`, `"provenance.timestamp"`
+    /// and `"provenance.command_line"` properties. Existing provenance
+    /// properties are replaced.
+    ///
+    /// The timestamp is the current time, as an ISO 8601 UTC date, and the
+    /// command line contains the arguments of the current process, separated
+    /// by spaces. These properties are written by formats storing frame
+    /// properties (such as extended XYZ).
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Property};
+    /// let mut frame = Frame::new();
+    /// frame.stamp_provenance("my-tool", "1.2.0");
+    ///
+    /// assert_eq!(frame.get("provenance.tool"), Some(Property::String("my-tool".into())));
+    /// assert_eq!(frame.get("provenance.version"), Some(Property::String("1.2.0".into())));
+    /// assert!(frame.get("provenance.timestamp").is_some());
+    /// ```
+    pub fn stamp_provenance(&mut self, tool: &str, version: &str) {
+        let seconds = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+
+        let command_line = std::env::args_os()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        self.set_with_prefix(PROVENANCE, "tool", tool);
+        self.set_with_prefix(PROVENANCE, "version", version);
+        self.set_with_prefix(PROVENANCE, "timestamp", iso8601_timestamp(seconds));
+        self.set_with_prefix(PROVENANCE, "command_line", command_line);
+    }
+
+    /// Read the single frame contained in the Amber `NetCDF` restart file at
+    /// `path`, including velocities and unit cell when they are present.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the file can not be read as an Amber restart
+    /// file, or if it does not contain exactly one frame.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use chemfiles::Frame;
+    /// let frame = Frame::from_restart("equilibrated.ncrst").unwrap();
+    /// assert!(frame.has_velocities());
+    /// ```
+    pub fn from_restart<P: AsRef<Path>>(path: P) -> Result<Frame, Error> {
+        let path = path.as_ref();
+        let mut trajectory = Trajectory::open_with_format(path, 'r', AMBER_RESTART)?;
+        let nsteps = trajectory.nsteps();
+        if nsteps != 1 {
+            return Err(Error {
+                status: Status::FormatError,
+                message: format!(
+                    "expected exactly one frame in Amber restart file '{}', got {nsteps}",
+                    path.display()
+                ),
+            });
+        }
+
+        let mut frame = Frame::new();
+        trajectory.read(&mut frame)?;
+        return Ok(frame);
+    }
+
+    /// Write this frame to a new Amber `NetCDF` restart file at `path`,
+    /// including velocities and unit cell when they are present. Any existing
+    /// file at `path` is overwritten.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the file can not be created, or if writing the
+    /// frame fails.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use chemfiles::{Atom, Frame, UnitCell};
+    /// let mut frame = Frame::new();
+    /// frame.set_cell(&UnitCell::new([20.0, 20.0, 20.0]));
+    /// frame.add_velocities();
+    /// frame.add_atom(&Atom::new("Ar"), [0.0, 0.0, 0.0], [1.0, 0.0, 0.0]);
+    ///
+    /// frame.write_restart("argon.ncrst").unwrap();
+    /// ```
+    pub fn write_restart<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let mut trajectory = Trajectory::open_with_format(path, 'w', AMBER_RESTART)?;
+        return trajectory.write(self);
+    }
+
+    /// Get an estimate of the memory used by this frame in the C++ library,
+    /// in bytes.
+    ///
+    /// This includes the positions, velocities, topology (see
+    /// [`Topology::memory_footprint`]), and frame properties. The chemfiles
+    /// C API does not expose allocation statistics, so this is an estimate
+    /// computed from the typical size of the corresponding C++ types on
+    /// 64-bit platforms, which can be used to plan how many frames fit in
+    /// memory.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Atom, Frame};
+    /// let mut frame = Frame::new();
+    /// frame.add_atom(&Atom::new("Ar"), [0.0, 0.0, 0.0], None);
+    ///
+    /// let without_velocities = frame.memory_footprint();
+    /// frame.add_velocities();
+    /// assert_eq!(frame.memory_footprint(), without_velocities + 24);
+    /// ```
+    pub fn memory_footprint(&self) -> usize {
+        let vectors = if self.has_velocities() { 2 } else { 1 };
+        let mut size = FRAME_SIZE + self.size() * vectors * std::mem::size_of::<[f64; 3]>();
+        size += self.properties().count() * PROPERTY_SIZE;
+        size += self.with_topology(Topology::memory_footprint);
+        return size;
+    }
+
+    /// Compare this frame with `other`, and list all the differences between
+    /// them, using the tolerances in `options` for floating point values.
+    ///
+    /// This is mainly useful to check that a frame survives a round-trip
+    /// through a file format.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Atom, DiffOptions, Difference};
+    /// let mut frame = Frame::new();
+    /// frame.add_atom(&Atom::new("Zn"), [0.0, 1.0, 2.0], None);
+    ///
+    /// let mut other = frame.clone();
+    /// assert!(frame.diff(&other, DiffOptions::default()).is_empty());
+    ///
+    /// other.positions_mut()[0][2] = 2.5;
+    /// let diff = frame.diff(&other, DiffOptions::default());
+    /// assert_eq!(diff.differences(), &[Difference::Position {
+    ///     atom: 0,
+    ///     left: [0.0, 1.0, 2.0],
+    ///     right: [0.0, 1.0, 2.5],
+    /// }]);
+    /// ```
+    pub fn diff(&self, other: &Frame, options: DiffOptions) -> FrameDiff {
+        return diff::compare(self, other, options);
+    }
+
+    /// Linearly interpolate between this frame (for `t = 0`) and `other` (for
+    /// `t = 1`), returning a new frame with interpolated positions.
+    ///
+    /// The new frame is a copy of this frame, with positions (and velocities
+    /// if both frames have them) set to `(1 - t) * self + t * other`. This is
+    /// useful to create smooth animations, or initial guesses for transition
+    /// states. See [`Frame::interpolate_periodic`] to interpolate across the
+    /// boundaries of the unit cell.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if `t` is not finite, or if the frames do not
+    /// contain the same atoms (same number of atoms and same atom names).
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Atom};
+    /// let mut first = Frame::new();
+    /// first.add_atom(&Atom::new("Ar"), [0.0, 0.0, 0.0], None);
+    ///
+    /// let mut second = first.clone();
+    /// second.positions_mut()[0] = [4.0, 2.0, 0.0];
+    ///
+    /// let middle = first.interpolate(&second, 0.25).unwrap();
+    /// assert_eq!(middle.positions()[0], [1.0, 0.5, 0.0]);
+    /// ```
+    pub fn interpolate(&self, other: &Frame, t: f64) -> Result<Frame, Error> {
+        return self.interpolate_impl(other, t, None);
+    }
+
+    /// Linearly interpolate between this frame (for `t = 0`) and `other` (for
+    /// `t = 1`) using the minimal image convention with the unit cell of this
+    /// frame, returning a new frame with interpolated positions.
+    ///
+    /// This is the same as [`Frame::interpolate`], except that atoms crossing
+    /// a boundary of the unit cell between the two frames move along the
+    /// shortest path instead of crossing the whole cell.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if `t` is not finite, or if the frames do not
+    /// contain the same atoms (same number of atoms and same atom names).
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Atom, UnitCell};
+    /// let mut first = Frame::new();
+    /// first.set_cell(&UnitCell::new([10.0, 10.0, 10.0]));
+    /// first.add_atom(&Atom::new("Ar"), [9.0, 0.0, 0.0], None);
+    ///
+    /// let mut second = first.clone();
+    /// second.positions_mut()[0] = [1.0, 0.0, 0.0];
+    ///
+    /// let middle = first.interpolate_periodic(&second, 0.5).unwrap();
+    /// assert_eq!(middle.positions()[0], [10.0, 0.0, 0.0]);
+    /// ```
+    pub fn interpolate_periodic(&self, other: &Frame, t: f64) -> Result<Frame, Error> {
+        let periodicity = math::Periodicity::new(&self.cell())?;
+        return self.interpolate_impl(other, t, Some(&periodicity));
+    }
+
+    fn interpolate_impl(&self, other: &Frame, t: f64, periodicity: Option<&math::Periodicity>) -> Result<Frame, Error> {
+        if !t.is_finite() {
+            return Err(Error {
+                status: Status::InvalidArgument,
+                message: format!("interpolation parameter must be finite, got {t}"),
+            });
+        }
+
+        if self.size() != other.size() {
+            return Err(Error {
+                status: Status::InvalidArgument,
+                message: format!(
+                    "can not interpolate between frames with different sizes: {} != {}",
+                    self.size(),
+                    other.size()
+                ),
+            });
+        }
+
+        for i in 0..self.size() {
+            let (name, other_name) = (self.atom(i).name(), other.atom(i).name());
+            if name != other_name {
+                return Err(Error {
+                    status: Status::InvalidArgument,
+                    message: format!(
+                        "can not interpolate between frames with different atoms: atom {i} is '{name}' and '{other_name}'"
+                    ),
+                });
+            }
+        }
+
+        let interpolate = |start: &[[f64; 3]], end: &[[f64; 3]], output: &mut [[f64; 3]], periodic: bool| {
+            for ((start, end), output) in start.iter().zip(end).zip(output) {
+                let delta = match periodicity {
+                    Some(periodicity) if periodic => periodicity.distance_vector(*start, *end),
+                    _ => math::sub(*end, *start),
+                };
+                *output = math::add(*start, math::scale(delta, t));
+            }
+        };
+
+        let mut frame = self.clone();
+        interpolate(self.positions(), other.positions(), frame.positions_mut(), true);
+        if let (Some(start), Some(end)) = (self.velocities(), other.velocities()) {
+            let output = frame.velocities_mut().expect("missing velocities");
+            interpolate(start, end, output, false);
+        }
+
+        return Ok(frame);
+    }
+
+    /// Generate the full content of the unit cell by applying the symmetry
+    /// operations stored in the `"symmetry_operations"` property of this
+    /// frame to all atoms.
+    ///
+    /// The property should contain the operations in the `x,y,z` notation
+    /// used by CIF files, separated by new lines or `;`. See
+    /// [`Frame::expand_symmetry_with`] for more information.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the property is missing or invalid, or if the
+    /// unit cell is infinite.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Atom, UnitCell};
+    /// let mut frame = Frame::new();
+    /// frame.set_cell(&UnitCell::new([10.0, 10.0, 10.0]));
+    /// frame.add_atom(&Atom::new("Na"), [1.0, 1.0, 1.0], None);
+    /// frame.set("symmetry_operations", "x,y,z; -x,-y,-z");
+    ///
+    /// frame.expand_symmetry().unwrap();
+    /// assert_eq!(frame.size(), 2);
+    /// assert_eq!(frame.positions()[1], [9.0, 9.0, 9.0]);
+    /// ```
+    pub fn expand_symmetry(&mut self) -> Result<(), Error> {
+        let operations = match self.get(SYMMETRY_OPERATIONS) {
+            Some(Property::String(operations)) => operations,
+            _ => {
+                return Err(Error {
+                    status: Status::PropertyError,
+                    message: format!("missing '{SYMMETRY_OPERATIONS}' string property in this frame"),
+                })
+            }
+        };
+
+        let operations = operations
+            .split([';', '\n'])
+            .filter(|operation| !operation.trim().is_empty())
+            .map(str::parse)
+            .collect::<Result<Vec<SymmetryOperation>, Error>>()?;
+
+        return self.expand_symmetry_with(&operations);
+    }
+
+    /// Generate the full content of the unit cell by applying all the
+    /// symmetry `operations` to the atoms in this frame, typically the
+    /// asymmetric unit of a crystal.
+    ///
+    /// New atoms are added at the end of the frame, wrapped inside the unit
+    /// cell. Atoms falling within 0.01 Å of an existing atom (using periodic
+    /// boundary conditions) are not added, so special positions are only
+    /// occupied once, and including the identity operation is not required.
+    /// Bonds and residues are not created for the new atoms.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the unit cell is infinite.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Atom, UnitCell};
+    /// let mut frame = Frame::new();
+    /// frame.set_cell(&UnitCell::new([10.0, 10.0, 10.0]));
+    /// frame.add_atom(&Atom::new("Na"), [0.0, 0.0, 0.0], None);
+    /// frame.add_atom(&Atom::new("Cl"), [5.0, 5.0, 5.0], None);
+    ///
+    /// let operations = ["x+1/2,y+1/2,z".parse().unwrap(), "-x,-y,-z".parse().unwrap()];
+    /// frame.expand_symmetry_with(&operations).unwrap();
+    ///
+    /// // (5, 5, 0) and (0, 0, 5) were added, the inversion of atoms at the
+    /// // origin and at the center is not a new atom
+    /// assert_eq!(frame.size(), 4);
+    /// ```
+    pub fn expand_symmetry_with(&mut self, operations: &[SymmetryOperation]) -> Result<(), Error> {
+        const TOLERANCE: f64 = 0.01;
+
+        let periodicity = math::Periodicity::new(&self.cell())?;
+        if periodicity.is_infinite() {
+            return Err(Error {
+                status: Status::InvalidArgument,
+                message: "can not expand symmetry in a frame with an infinite unit cell".into(),
+            });
+        }
+
+        let asymmetric = (0..self.size())
+            .map(|i| ((*self.atom(i)).clone(), self.positions()[i]))
+            .collect::<Vec<_>>();
+
+        let mut positions = self.positions().to_vec();
+        for operation in operations {
+            for (atom, position) in &asymmetric {
+                let fractional = operation.apply(periodicity.fractional(*position));
+                let new = periodicity.inside(periodicity.cartesian(fractional));
+
+                let duplicated = positions
+                    .iter()
+                    .any(|&existing| periodicity.distance(existing, new) < TOLERANCE);
+                if !duplicated {
+                    self.add_atom(atom, new, None);
+                    positions.push(new);
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    /// Change the unit cell of this frame to the cell with vectors `M T`,
+    /// where `M` is the current cell matrix and `T` is the `transform` matrix,
+    /// without changing the crystal described by this frame.
+    ///
+    /// This is typically used with the transformation returned by
+    /// [`UnitCell::niggli_reduced`]. The positions (and velocities) are
+    /// rotated if the C library changes the orientation of the new cell, and
+    /// all atoms are wrapped inside the new cell.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the unit cell of this frame is infinite, or if
+    /// the determinant of `transform` is not 1, since the new cell would not
+    /// contain the same number of atoms as the current one.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Atom, Frame, UnitCell};
+    /// let mut frame = Frame::new();
+    /// frame.set_cell(&UnitCell::from_matrix([[10.0, 10.0, 0.0], [0.0, 10.0, 0.0], [0.0, 0.0, 10.0]]));
+    /// frame.add_atom(&Atom::new("Ar"), [15.0, 5.0, 5.0], None);
+    ///
+    /// let (_, transform) = frame.cell().niggli_reduced();
+    /// frame.apply_cell_transform(transform).unwrap();
+    ///
+    /// let lengths = frame.cell().lengths();
+    /// assert!((lengths[0] - 10.0).abs() < 1e-9);
+    /// let position = frame.positions()[0];
+    /// assert!((position[0] - 5.0).abs() < 1e-9);
+    /// ```
+    pub fn apply_cell_transform(&mut self, transform: [[f64; 3]; 3]) -> Result<(), Error> {
+        let periodicity = math::Periodicity::new(&self.cell())?;
+        if periodicity.is_infinite() {
+            return Err(Error {
+                status: Status::InvalidArgument,
+                message: "can not transform an infinite unit cell".into(),
+            });
+        }
+
+        let determinant = math::determinant(&transform);
+        if (determinant - 1.0).abs() > 1e-6 {
+            return Err(Error {
+                status: Status::InvalidArgument,
+                message: format!("the cell transformation must have a determinant of 1, got {determinant}"),
+            });
+        }
+
+        let matrix = math::matrix_matrix(&self.cell().matrix(), &transform);
+        let cell = UnitCell::from_matrix(matrix);
+
+        // the C library might store the cell with a different orientation
+        let inverse = math::inverse(&matrix).expect("the new cell matrix should be invertible");
+        let rotation = math::matrix_matrix(&cell.matrix(), &inverse);
+        let new_periodicity = math::Periodicity::new(&cell)?;
+
+        let (positions, velocities) = self.positions_and_velocities_mut();
+        for position in positions.iter_mut() {
+            *position = new_periodicity.inside(math::matrix_vector(&rotation, *position));
+        }
+        if let Some(velocities) = velocities {
+            for velocity in velocities {
+                *velocity = math::matrix_vector(&rotation, *velocity);
+            }
+        }
+
+        self.set_cell(&cell);
+        return Ok(());
+    }
+
+    /// Create a new frame containing only the atoms at the given `indexes`
+    /// in this frame, in the same order as `indexes`.
+    ///
+    /// The unit cell, step and properties of this frame are copied in the new
+    /// frame. Bonds and residues are remapped to the new atomic indexes, and
+    /// bonds or residues involving atoms not in `indexes` are dropped.
+    pub(crate) fn subset(&self, indexes: &[usize]) -> Frame {
+        return self.rebuild(indexes, &|_| true, None);
+    }
+
+    /// Same as `Frame::subset`, only copying the frame properties for which
+    /// `keep_property` returns `true`, and the atomic properties for which
+    /// `keep_atom_property` returns `true`. This is used to emulate property
+    /// removal, which is not available in the C API.
+    fn rebuild(
+        &self,
+        indexes: &[usize],
+        keep_property: &dyn Fn(&str) -> bool,
+        keep_atom_property: Option<&dyn Fn(&str) -> bool>,
+    ) -> Frame {
+        let mut subset = Frame::new();
+        subset.set_cell(&self.cell());
+        subset.set_step(self.step());
+        for (name, property) in self.properties() {
+            if keep_property(&name) {
+                subset.set(&name, property);
+            }
+        }
+
+        let positions = self.positions();
+        let velocities = self.velocities();
+        if velocities.is_some() {
+            subset.add_velocities();
+        }
+
+        let mut new_indexes = vec![None; self.size()];
+        for (new, &old) in indexes.iter().enumerate() {
+            new_indexes[old] = Some(new);
+            let velocity = velocities.map(|v| v[old]);
+            match keep_atom_property {
+                Some(keep) => subset.add_atom(&self.atom(old).filtered_copy(keep), positions[old], velocity),
+                None => subset.add_atom(&self.atom(old), positions[old], velocity),
+            }
+        }
+
+        let topology = self.topology();
+        for (bond, order) in topology.bonds().iter().zip(topology.bond_orders()) {
+            if let (Some(i), Some(j)) = (new_indexes[bond[0]], new_indexes[bond[1]]) {
+                subset.add_bond_with_order(i, j, order);
+            }
+        }
+
+        #[allow(clippy::cast_possible_truncation)]
+        for index in 0..topology.residues_count() as usize {
+            let residue = topology.residue(index).expect("missing residue");
+            let atoms = residue
+                .atoms()
+                .iter()
+                .filter_map(|&atom| new_indexes[atom])
+                .collect::<Vec<_>>();
+            if atoms.is_empty() {
+                continue;
+            }
+
+            let mut new_residue = match residue.id() {
+                Some(id) => Residue::with_id(&*residue.name(), id),
+                None => Residue::new(&*residue.name()),
+            };
+            for atom in atoms {
+                new_residue.add_atom(atom);
+            }
+            for (name, property) in residue.properties() {
+                new_residue.set(&name, property);
+            }
+            subset.add_residue(&new_residue).expect("failed to add residue");
+        }
+
+        return subset;
+    }
+
+    /// Translate the atoms in this frame by `vector`. If `selection` is
+    /// `Some`, only the atoms at the given indexes are moved, otherwise all
+    /// atoms are moved.
+    ///
+    /// # Panics
+    ///
+    /// If any index in `selection` is out of bounds.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Atom};
+    /// let mut frame = Frame::new();
+    /// frame.add_atom(&Atom::new("O"), [1.0, 2.0, 3.0], None);
+    /// frame.add_atom(&Atom::new("H"), [1.0, 2.0, 3.0], None);
+    ///
+    /// frame.translate([1.0, 0.0, -1.0], None);
+    /// assert_eq!(frame.positions(), &[[2.0, 2.0, 2.0], [2.0, 2.0, 2.0]]);
+    ///
+    /// frame.translate([1.0, 0.0, 0.0], Some(&[1]));
+    /// assert_eq!(frame.positions(), &[[2.0, 2.0, 2.0], [3.0, 2.0, 2.0]]);
+    /// ```
+    pub fn translate(&mut self, vector: [f64; 3], selection: Option<&[usize]>) {
+        let positions = self.positions_mut();
+        match selection {
+            Some(selection) => {
+                for &i in selection {
+                    positions[i] = math::add(positions[i], vector);
+                }
+            }
+            None => {
+                for position in positions {
+                    *position = math::add(*position, vector);
+                }
+            }
+        }
+    }
+
+    /// Rotate the atoms in this frame around `center`, using the given
+    /// `rotation` matrix. If `selection` is `Some`, only the atoms at the given
+    /// indexes are rotated, otherwise all atoms are rotated.
+    ///
+    /// The velocities of the rotated atoms (if any) are rotated as well. The
+    /// unit cell of the frame is not modified.
+    ///
+    /// # Panics
+    ///
+    /// If any index in `selection` is out of bounds.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Atom};
+    /// let mut frame = Frame::new();
+    /// frame.add_atom(&Atom::new("O"), [1.0, 0.0, 0.0], None);
+    /// frame.add_atom(&Atom::new("H"), [2.0, 0.0, 0.0], None);
+    ///
+    /// // rotation of 90° around the z axis
+    /// let rotation = [[0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]];
+    /// frame.rotate(rotation, [1.0, 0.0, 0.0], Some(&[1]));
+    /// assert_eq!(frame.positions(), &[[1.0, 0.0, 0.0], [1.0, 1.0, 0.0]]);
+    /// ```
+    pub fn rotate(&mut self, rotation: [[f64; 3]; 3], center: [f64; 3], selection: Option<&[usize]>) {
+        let indexes = match selection {
+            Some(selection) => selection.to_vec(),
+            None => (0..self.size()).collect(),
+        };
+
+        let positions = self.positions_mut();
+        for &i in &indexes {
+            let rotated = math::matrix_vector(&rotation, math::sub(positions[i], center));
+            positions[i] = math::add(rotated, center);
+        }
+
+        if let Some(velocities) = self.velocities_mut() {
+            for &i in &indexes {
+                velocities[i] = math::matrix_vector(&rotation, velocities[i]);
+            }
+        }
+    }
+
+    /// Rotate this frame around its center of mass, so that the principal
+    /// axes of inertia are aligned with the x, y and z axes. The axis with the
+    /// smallest moment of inertia is aligned with x, and the axis with the
+    /// largest moment of inertia with z.
+    ///
+    /// If `selection` is `Some`, only the atoms at the given indexes are used
+    /// to compute the center of mass and inertia tensor, but the whole frame
+    /// is rotated. If all the atoms have a mass of zero, all atoms are given
+    /// the same weight.
+    ///
+    /// # Panics
+    ///
+    /// If any index in `selection` is out of bounds.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Atom};
+    /// let mut frame = Frame::new();
+    /// frame.add_atom(&Atom::new("C"), [0.0, 0.0, -1.0], None);
+    /// frame.add_atom(&Atom::new("C"), [0.0, 0.0, 1.0], None);
+    ///
+    /// frame.align_principal_axes(None);
+    /// let positions = frame.positions();
+    /// assert!((positions[0][0].abs() - 1.0).abs() < 1e-12);
+    /// assert!(positions[0][2].abs() < 1e-12);
+    /// ```
+    pub fn align_principal_axes(&mut self, selection: Option<&[usize]>) {
+        let indexes = match selection {
+            Some(selection) => selection.to_vec(),
+            None => (0..self.size()).collect(),
+        };
+        if indexes.is_empty() {
+            return;
+        }
+
+        let mut masses = indexes
+            .iter()
+            .map(|&i| {
+                let atom = self.atom(i);
+                if atom.has_virtual_site_flag() {
+                    0.0
+                } else {
+                    atom.mass()
+                }
+            })
+            .collect::<Vec<_>>();
+        if masses.iter().all(|&mass| mass == 0.0) {
+            masses = vec![1.0; indexes.len()];
+        }
+        let total_mass = masses.iter().sum::<f64>();
+
+        let positions = self.positions();
+        let mut center = [0.0; 3];
+        for (&i, &mass) in indexes.iter().zip(&masses) {
+            center = math::add(center, math::scale(positions[i], mass));
+        }
+        let center = math::scale(center, 1.0 / total_mass);
+
+        let mut inertia = vec![vec![0.0; 3]; 3];
+        for (&i, &mass) in indexes.iter().zip(&masses) {
+            let r = math::sub(positions[i], center);
+            let r2 = math::dot(r, r);
+            for a in 0..3 {
+                for b in 0..3 {
+                    let delta = if a == b { r2 } else { 0.0 };
+                    inertia[a][b] += mass * (delta - r[a] * r[b]);
+                }
+            }
+        }
+
+        // eigenvectors are sorted by decreasing moment of inertia
+        let (_, axes) = math::symmetric_eigen(&inertia);
+        let x = [axes[2][0], axes[2][1], axes[2][2]];
+        let y = [axes[1][0], axes[1][1], axes[1][2]];
+        // use the cross product to always get a proper rotation
+        let z = math::cross(x, y);
+
+        self.rotate([x, y, z], center, None);
+    }
+
+    /// Set a property with the given `name` on all the atoms in this frame at
+    /// once, using `values[i]` for the atom at index `i`.
+    ///
+    /// Formats supporting additional per-atom data (such as extended XYZ) will
+    /// write these properties as extra columns.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the number of `values` does not match the
+    /// number of atoms in this frame.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Atom, Property};
+    /// let mut frame = Frame::new();
+    /// frame.add_atom(&Atom::new("H"), [0.0; 3], None);
+    /// frame.add_atom(&Atom::new("O"), [1.0; 3], None);
+    ///
+    /// frame.set_atom_array_property("forces", &[[0.0, 1.0, 2.0], [3.0, 4.0, 5.0]]).unwrap();
+    /// assert_eq!(frame.atom(1).get("forces"), Some(Property::Vector3D([3.0, 4.0, 5.0])));
+    ///
+    /// assert!(frame.set_atom_array_property("forces", &[[0.0; 3]]).is_err());
+    /// ```
+    pub fn set_atom_array_property<P>(&mut self, name: &str, values: &[P]) -> Result<(), Error>
+    where
+        P: Into<Property> + Clone,
+    {
+        check_data_size("values", values.len(), self.size())?;
+
+        let buffer = strings::to_c(name);
+        for (i, value) in values.iter().enumerate() {
+            let property = value.clone().into().as_raw();
+            unsafe {
+                let atom = ffi::chfl_atom_from_frame(self.as_mut_ptr(), i as u64);
+                check_success(ffi::chfl_atom_set_property(atom, buffer.as_ptr(), property.as_ptr()));
+                let _ = ffi::chfl_free(atom.cast());
+            }
+        }
+        return Ok(());
+    }
+
+    /// Get the values of the property with the given `name` for all the atoms
+    /// in this frame, or `None` if any atom does not have this property.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Atom, Property};
+    /// let mut frame = Frame::new();
+    /// frame.add_atom(&Atom::new("H"), [0.0; 3], None);
+    /// frame.add_atom(&Atom::new("O"), [1.0; 3], None);
+    ///
+    /// frame.set_atom_array_property("cluster", &[1.0, 2.0]).unwrap();
+    /// assert_eq!(
+    ///     frame.atom_array_property("cluster"),
+    ///     Some(vec![Property::Double(1.0), Property::Double(2.0)])
+    /// );
+    ///
+    /// frame.atom_mut(0).set("rmsf", 0.3);
+    /// assert_eq!(frame.atom_array_property("rmsf"), None);
+    /// ```
+    pub fn atom_array_property(&self, name: &str) -> Option<Vec<Property>> {
+        return (0..self.size()).map(|i| self.atom(i).get(name)).collect();
+    }
+
+    /// Get the masses of all the atoms in this frame.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Atom};
+    /// let mut frame = Frame::new();
+    /// frame.add_atom(&Atom::new("H"), [0.0; 3], None);
+    /// frame.add_atom(&Atom::new("O"), [1.0; 3], None);
+    ///
+    /// assert_eq!(frame.masses(), vec![1.008, 15.999]);
+    /// ```
+    pub fn masses(&self) -> Vec<f64> {
+        return (0..self.size()).map(|i| self.atom(i).mass()).collect();
+    }
+
+    /// Get the volume of the unit cell of this frame, in cubic Ångströms.
+    /// This is zero for infinite cells.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, UnitCell};
+    /// let mut frame = Frame::new();
+    /// assert_eq!(frame.volume(), 0.0);
+    ///
+    /// frame.set_cell(&UnitCell::new([10.0, 20.0, 30.0]));
+    /// assert_eq!(frame.volume(), 6000.0);
+    /// ```
+    pub fn volume(&self) -> f64 {
+        return self.cell().volume();
+    }
+
+    /// Get the center of mass of the atoms in this frame. If `selection` is
+    /// `Some`, only the atoms at the given indexes are used, otherwise all
+    /// atoms are used. Virtual sites (see [`Atom::is_virtual_site`]) are
+    /// excluded.
+    ///
+    /// Positions are used as-is, without considering periodic boundary
+    /// conditions: molecules should be made whole first, for example with the
+    /// [`Unwrap`](crate::transform::Unwrap) transform. This returns `None` if
+    /// there are no atoms with a mass in the selection.
+    ///
+    /// # Panics
+    ///
+    /// If any index in `selection` is out of bounds.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Atom};
+    /// let mut frame = Frame::new();
+    /// frame.add_atom(&Atom::new("O"), [0.0, 0.0, 0.0], None);
+    /// frame.add_atom(&Atom::new("O"), [2.0, 0.0, 0.0], None);
+    /// // TIP4P-like virtual site, with a mass in the file
+    /// let mut site = Atom::new("MW");
+    /// site.set_mass(16.0);
+    /// site.set_virtual_site(true);
+    /// frame.add_atom(&site, [10.0, 0.0, 0.0], None);
+    ///
+    /// assert_eq!(frame.center_of_mass(None), Some([1.0, 0.0, 0.0]));
+    /// assert_eq!(frame.center_of_mass(Some(&[2])), None);
+    /// ```
+    pub fn center_of_mass(&self, selection: Option<&[usize]>) -> Option<[f64; 3]> {
+        let indexes = match selection {
+            Some(selection) => selection.to_vec(),
+            None => (0..self.size()).collect(),
+        };
+
+        let positions = self.positions();
+        let mut total_mass = 0.0;
+        let mut center = [0.0; 3];
+        for i in indexes {
+            let atom = self.atom(i);
+            if atom.is_virtual_site() {
+                continue;
+            }
+            let mass = atom.mass();
+            total_mass += mass;
+            center = math::add(center, math::scale(positions[i], mass));
+        }
+
+        if total_mass <= 0.0 {
+            return None;
+        }
+        return Some(math::scale(center, 1.0 / total_mass));
+    }
+
+    /// Get the instantaneous temperature of this frame in Kelvin, computed
+    /// from the kinetic energy of the atoms. Velocities are expected in Å/ps.
+    ///
+    /// Virtual sites (see [`Atom::is_virtual_site`]) do not contribute to the
+    /// kinetic energy or to the number of degrees of freedom, which is
+    /// `3 * N - constraints` for `N` atoms which are not virtual sites.
+    /// `constraints` should contain the number of constrained bonds and
+    /// angles (3 for each rigid water molecule), plus 3 if the center of mass
+    /// motion was removed during the simulation.
+    ///
+    /// This returns `None` if the frame does not contain velocities, or if
+    /// there are no degrees of freedom left.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Atom};
+    /// let mut frame = Frame::new();
+    /// assert_eq!(frame.temperature(0), None);
+    ///
+    /// frame.add_velocities();
+    /// frame.add_atom(&Atom::new("Ar"), [0.0; 3], Some([5.0, 0.0, 0.0]));
+    /// frame.add_atom(&Atom::new("Ar"), [0.0; 3], Some([-5.0, 0.0, 0.0]));
+    /// let temperature = frame.temperature(0).unwrap();
+    /// assert!((temperature - 400.4).abs() < 0.1);
+    /// ```
+    pub fn temperature(&self, constraints: usize) -> Option<f64> {
+        /// Molar gas constant, in kJ/mol/K
+        const GAS_CONSTANT: f64 = 8.314_462_618e-3;
+        /// Conversion factor from amu Å²/ps² to kJ/mol
+        const KINETIC_TO_KJ_PER_MOL: f64 = 0.01;
+
+        let velocities = self.velocities()?;
+        let mut kinetic = 0.0;
+        let mut atoms: usize = 0;
+        for (atom, velocity) in self.iter_atoms().zip(velocities) {
+            if atom.is_virtual_site() {
+                continue;
+            }
+            kinetic += 0.5 * atom.mass() * math::dot(*velocity, *velocity);
+            atoms += 1;
+        }
+
+        let degrees_of_freedom = (3 * atoms).checked_sub(constraints).filter(|&dof| dof > 0)?;
+        #[allow(clippy::cast_precision_loss)]
+        let degrees_of_freedom = degrees_of_freedom as f64;
+        return Some(2.0 * kinetic * KINETIC_TO_KJ_PER_MOL / (degrees_of_freedom * GAS_CONSTANT));
+    }
+
+    /// Get the mass density of this frame in g/cm³, computed from the masses
+    /// of the atoms and the volume of the unit cell. This returns `None` for
+    /// infinite cells.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Atom, Frame, UnitCell};
+    /// let mut frame = Frame::new();
+    /// frame.add_atom(&Atom::new("Ar"), [0.0, 0.0, 0.0], None);
+    /// assert_eq!(frame.mass_density(), None);
+    ///
+    /// frame.set_cell(&UnitCell::new([4.0, 4.0, 4.0]));
+    /// let density = frame.mass_density().unwrap();
+    /// assert!((density - 1.0365).abs() < 1e-3);
+    /// ```
+    pub fn mass_density(&self) -> Option<f64> {
+        let cell = self.cell();
+        if cell.shape() == CellShape::Infinite {
+            return None;
+        }
+        let mass = self.masses().iter().sum::<f64>();
+        return Some(mass / cell.volume() * AMU_PER_A3_TO_G_PER_CM3);
+    }
+
+    /// Get the charges of all the atoms in this frame.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Atom};
+    /// let mut frame = Frame::new();
+    /// frame.add_atom(&Atom::new("H"), [0.0; 3], None);
+    /// frame.add_atom(&Atom::new("O"), [1.0; 3], None);
+    /// frame.atom_mut(1).set_charge(-0.8);
+    ///
+    /// assert_eq!(frame.charges(), vec![0.0, -0.8]);
+    /// ```
+    pub fn charges(&self) -> Vec<f64> {
+        return (0..self.size()).map(|i| self.atom(i).charge()).collect();
+    }
+
+    /// Get the molecules in this frame, defined as the groups of atoms
+    /// connected by bonds in the topology. Atoms without bonds are in their
+    /// own molecule.
+    ///
+    /// Molecules are sorted by their first atom, and the atoms in each
+    /// molecule are sorted by index.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Atom};
+    /// let mut frame = Frame::new();
+    /// frame.add_atom(&Atom::new("H"), [0.0; 3], None);
+    /// frame.add_atom(&Atom::new("Na"), [0.0; 3], None);
+    /// frame.add_atom(&Atom::new("O"), [0.0; 3], None);
+    /// frame.add_atom(&Atom::new("H"), [0.0; 3], None);
+    /// frame.add_bond(0, 2);
+    /// frame.add_bond(2, 3);
+    ///
+    /// assert_eq!(frame.molecules(), vec![vec![0, 2, 3], vec![1]]);
+    /// ```
+    pub fn molecules(&self) -> Vec<Vec<usize>> {
+        let neighbors = crate::transform::neighbors_list(self);
+        let mut visited = vec![false; neighbors.len()];
+        let mut molecules = Vec::new();
+        for start in 0..neighbors.len() {
+            if visited[start] {
+                continue;
+            }
+            visited[start] = true;
+
+            let mut molecule = vec![start];
+            let mut current = 0;
+            while current < molecule.len() {
+                for &j in &neighbors[molecule[current]] {
+                    if !visited[j] {
+                        visited[j] = true;
+                        molecule.push(j);
+                    }
+                }
+                current += 1;
+            }
+            molecule.sort_unstable();
+            molecules.push(molecule);
+        }
+        return molecules;
+    }
+
+    /// Get the electric dipole moment of the atoms in this frame, using the
+    /// charges of the atoms. If `selection` is `Some`, only the atoms at the
+    /// given indexes are used, otherwise all atoms are used. The dipole is in
+    /// units of elementary charge times Angstrom (e·Å).
+    ///
+    /// Positions are taken relative to the first selected atom using the
+    /// minimal image convention, so a selection (typically a single molecule)
+    /// split by the periodic boundary conditions gives the right dipole as
+    /// long as it is smaller than half of the unit cell. For charged
+    /// selections, the dipole is computed relative to the geometric center of
+    /// the selection. Use [`Frame::molecular_dipoles`] or
+    /// [`Frame::polarization`] for whole periodic systems.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the unit cell of this frame is degenerate,
+    /// *i.e.* if its matrix can not be inverted.
+    ///
+    /// # Panics
+    ///
+    /// If any index in `selection` is out of bounds.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Atom, UnitCell};
+    /// let mut frame = Frame::new();
+    /// frame.set_cell(&UnitCell::new([10.0, 10.0, 10.0]));
+    /// frame.add_atom(&Atom::new("Na"), [9.5, 0.0, 0.0], None);
+    /// frame.add_atom(&Atom::new("Cl"), [0.5, 0.0, 0.0], None);
+    /// frame.atom_mut(0).set_charge(1.0);
+    /// frame.atom_mut(1).set_charge(-1.0);
+    ///
+    /// assert_eq!(frame.dipole_moment(None).unwrap(), [-1.0, 0.0, 0.0]);
+    /// ```
+    pub fn dipole_moment(&self, selection: Option<&[usize]>) -> Result<[f64; 3], Error> {
+        let indexes = match selection {
+            Some(selection) => selection.to_vec(),
+            None => (0..self.size()).collect(),
+        };
+
+        let periodicity = math::Periodicity::new(&self.cell())?;
+        return Ok(dipole_moment(&periodicity, self.positions(), &self.charges(), &indexes));
+    }
+
+    /// Get the electric dipole moment of each molecule in this frame, as
+    /// defined by [`Frame::molecules`]. `dipoles[i]` is the dipole of the
+    /// molecule at index `i` in `frame.molecules()`, computed as with
+    /// [`Frame::dipole_moment`].
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the unit cell of this frame is degenerate,
+    /// *i.e.* if its matrix can not be inverted.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Atom};
+    /// let mut frame = Frame::new();
+    /// frame.add_atom(&Atom::new("C"), [0.0, 0.0, 0.0], None);
+    /// frame.add_atom(&Atom::new("O"), [1.0, 0.0, 0.0], None);
+    /// frame.add_atom(&Atom::new("Ar"), [5.0, 0.0, 0.0], None);
+    /// frame.add_bond(0, 1);
+    /// frame.atom_mut(0).set_charge(0.5);
+    /// frame.atom_mut(1).set_charge(-0.5);
+    ///
+    /// assert_eq!(frame.molecular_dipoles().unwrap(), vec![[-0.5, 0.0, 0.0], [0.0, 0.0, 0.0]]);
+    /// ```
+    pub fn molecular_dipoles(&self) -> Result<Vec<[f64; 3]>, Error> {
+        let periodicity = math::Periodicity::new(&self.cell())?;
+        let positions = self.positions();
+        let charges = self.charges();
+        return Ok(self
+            .molecules()
+            .iter()
+            .map(|molecule| dipole_moment(&periodicity, positions, &charges, molecule))
+            .collect());
+    }
+
+    /// Get the polarization of this frame, *i.e.* the sum of all the
+    /// molecular dipoles (see [`Frame::molecular_dipoles`]) divided by the
+    /// volume of the unit cell, in e/Å². This returns `None` if the unit cell
+    /// is infinite.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the unit cell of this frame is degenerate,
+    /// *i.e.* if its matrix can not be inverted.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Frame, Atom, UnitCell};
+    /// let mut frame = Frame::new();
+    /// frame.add_atom(&Atom::new("C"), [0.0, 0.0, 0.0], None);
+    /// frame.add_atom(&Atom::new("O"), [1.0, 0.0, 0.0], None);
+    /// frame.add_bond(0, 1);
+    /// frame.atom_mut(0).set_charge(0.5);
+    /// frame.atom_mut(1).set_charge(-0.5);
+    /// assert_eq!(frame.polarization().unwrap(), None);
+    ///
+    /// frame.set_cell(&UnitCell::new([10.0, 10.0, 10.0]));
+    /// assert_eq!(frame.polarization().unwrap(), Some([-0.5 / 1000.0, 0.0, 0.0]));
+    /// ```
+    pub fn polarization(&self) -> Result<Option<[f64; 3]>, Error> {
+        let periodicity = math::Periodicity::new(&self.cell())?;
+        if periodicity.is_infinite() {
+            return Ok(None);
+        }
+
+        let total = self.molecular_dipoles()?.into_iter().fold([0.0; 3], math::add);
+        return Ok(Some(math::scale(total, 1.0 / periodicity.volume())));
+    }
+
+    /// Gets an iterator over atoms
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Atom, AtomRef, Frame};
+    /// let mut frame = Frame::new();
+    ///
+    /// frame.add_atom(&Atom::new("O"), [0.0, 0.0, 0.0], None);
+    /// frame.add_atom(&Atom::new("H"), [1.0, 0.0, 0.0], None);
+    ///
+    /// let mut atoms: Vec<AtomRef> = Vec::new();
+    ///
+    /// for atom in frame.iter_atoms() {
     ///     atoms.push(atom);
     /// }
     ///
@@ -906,6 +3488,266 @@ impl Frame {
             size: self.size(),
         }
     }
+
+    /// Get an iterator over all the atoms in this frame, together with their
+    /// index, position and velocity.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Atom, Frame};
+    /// let mut frame = Frame::new();
+    /// frame.add_atom(&Atom::new("O"), [0.0, 0.0, 0.0], None);
+    /// frame.add_atom(&Atom::new("H"), [1.0, 0.0, 0.0], None);
+    ///
+    /// for view in frame.iter() {
+    ///     println!("{} {}: {:?}", view.index, view.atom.name(), view.position);
+    ///     assert!(view.velocity.is_none());
+    /// }
+    ///
+    /// let hydrogen = frame.iter().find(|view| view.atom.name() == "H").unwrap();
+    /// assert_eq!(hydrogen.index, 1);
+    /// assert_eq!(hydrogen.position, &[1.0, 0.0, 0.0]);
+    /// ```
+    pub fn iter(&self) -> FrameIter<'_> {
+        FrameIter {
+            data: FrameViewData::new(self),
+            indexes: FrameIterIndexes::All(0..self.size()),
+        }
+    }
+
+    /// Get an iterator over the atoms at the given `indexes` in this frame,
+    /// together with their index, position and velocity. The atoms are
+    /// visited in the same order as `indexes`.
+    ///
+    /// # Panics
+    ///
+    /// When advancing the iterator, if any index is out of bounds.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Atom, Frame, Selection};
+    /// let mut frame = Frame::new();
+    /// frame.add_atom(&Atom::new("O"), [0.0, 0.0, 0.0], None);
+    /// frame.add_atom(&Atom::new("H"), [1.0, 0.0, 0.0], None);
+    /// frame.add_atom(&Atom::new("H"), [0.0, 1.0, 0.0], None);
+    ///
+    /// let hydrogens = Selection::new("name H").unwrap().list(&frame);
+    /// let positions = frame.iter_indexes(&hydrogens).map(|view| *view.position).collect::<Vec<_>>();
+    /// assert_eq!(positions, [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]);
+    /// ```
+    pub fn iter_indexes<'a>(&'a self, indexes: &'a [usize]) -> FrameIter<'a> {
+        FrameIter {
+            data: FrameViewData::new(self),
+            indexes: FrameIterIndexes::Some(indexes.iter()),
+        }
+    }
+
+    /// Get a parallel iterator over all the atoms in this frame, together
+    /// with their index, position and velocity. This is the parallel version
+    /// of [`Frame::iter`], using [rayon](https://docs.rs/rayon).
+    ///
+    /// All the views are created on the current thread before the parallel
+    /// iteration starts, so the C library is never called concurrently with
+    /// the same frame. The items are [`ParAtomView`], which can be used as an
+    /// [`AtomView`] and sent to other threads.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Atom, Frame};
+    /// use rayon::prelude::*;
+    ///
+    /// let mut frame = Frame::new();
+    /// for i in 0..100 {
+    ///     frame.add_atom(&Atom::new("Ar"), [i as f64, 0.0, 0.0], None);
+    /// }
+    ///
+    /// let total = frame.par_iter().map(|view| view.position[0] * view.atom.mass()).sum::<f64>();
+    /// assert!((total - 4950.0 * 39.948).abs() < 1e-6);
+    /// ```
+    #[cfg(feature = "rayon")]
+    pub fn par_iter(&self) -> impl rayon::iter::IndexedParallelIterator<Item = ParAtomView<'_>> {
+        use rayon::prelude::*;
+        let data = FrameViewData::new(self);
+        let views = (0..self.size()).map(|index| ParAtomView(data.view(index))).collect::<Vec<_>>();
+        return views.into_par_iter();
+    }
+}
+
+/// Create a slice from a pointer and a number of atoms coming from the C API.
+///
+/// # Safety
+///
+/// If `natoms` is not zero, `ptr` must point to `natoms` valid vectors, which
+/// stay alive and are not mutated for the lifetime `'a`.
+unsafe fn slice_from_raw<'a>(ptr: *const [f64; 3], natoms: u64) -> &'a [[f64; 3]] {
+    // the pointer can be NULL for empty frames, which is not allowed by
+    // `std::slice::from_raw_parts`
+    if natoms == 0 {
+        return &[];
+    }
+    #[allow(clippy::cast_possible_truncation)]
+    return std::slice::from_raw_parts(ptr, natoms as usize);
+}
+
+/// Create a mutable slice from a pointer and a number of atoms coming from
+/// the C API.
+///
+/// # Safety
+///
+/// If `natoms` is not zero, `ptr` must point to `natoms` valid vectors, which
+/// stay alive and are not accessed through any other pointer for the lifetime
+/// `'a`.
+unsafe fn slice_from_raw_mut<'a>(ptr: *mut [f64; 3], natoms: u64) -> &'a mut [[f64; 3]] {
+    if natoms == 0 {
+        return &mut [];
+    }
+    #[allow(clippy::cast_possible_truncation)]
+    return std::slice::from_raw_parts_mut(ptr, natoms as usize);
+}
+
+/// Mutable view of the forces in a [`Frame`], created by
+/// [`Frame::forces_mut`]. The forces are written back to the frame when this
+/// view is dropped.
+#[derive(Debug)]
+pub struct ForcesMut<'a> {
+    frame: &'a mut Frame,
+    forces: Vec<[f64; 3]>,
+}
+
+impl Deref for ForcesMut<'_> {
+    type Target = [[f64; 3]];
+    fn deref(&self) -> &[[f64; 3]] {
+        &self.forces
+    }
+}
+
+impl DerefMut for ForcesMut<'_> {
+    fn deref_mut(&mut self) -> &mut [[f64; 3]] {
+        &mut self.forces
+    }
+}
+
+impl Drop for ForcesMut<'_> {
+    fn drop(&mut self) {
+        self.frame.set_forces(&self.forces).expect("the size of the forces can not change");
+    }
+}
+
+/// A view of a single atom in a [`Frame`], with its position and velocity,
+/// as returned by [`Frame::iter`].
+#[derive(Debug)]
+pub struct AtomView<'a> {
+    /// Index of the atom in the frame
+    pub index: usize,
+    /// The atom itself
+    pub atom: AtomRef<'a>,
+    /// Position of the atom
+    pub position: &'a [f64; 3],
+    /// Velocity of the atom, if the frame contains velocities
+    pub velocity: Option<&'a [f64; 3]>,
+}
+
+/// An [`AtomView`] which can be sent to another thread, as returned by
+/// [`Frame::par_iter`].
+#[cfg(feature = "rayon")]
+#[derive(Debug)]
+pub struct ParAtomView<'a>(AtomView<'a>);
+
+#[cfg(feature = "rayon")]
+impl<'a> ParAtomView<'a> {
+    /// Get the underlying [`AtomView`]
+    pub fn into_inner(self) -> AtomView<'a> {
+        self.0
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'a> std::ops::Deref for ParAtomView<'a> {
+    type Target = AtomView<'a>;
+    fn deref(&self) -> &AtomView<'a> {
+        &self.0
+    }
+}
+
+// SAFETY: `ParAtomView` can only be created by `Frame::par_iter`, which
+// creates all of them (and the C handles of their `AtomRef`) on the current
+// thread. Each view then owns a separate handle, used by a single thread for
+// read-only access to the atom while the frame is immutably borrowed. Freeing
+// the handle in another thread is fine, since the reference counting used by
+// the C library is protected by a mutex.
+#[cfg(feature = "rayon")]
+unsafe impl Send for ParAtomView<'_> {}
+
+/// The data needed to create [`AtomView`], extracted once from a frame
+#[derive(Debug, Clone, Copy)]
+struct FrameViewData<'a> {
+    frame: &'a Frame,
+    positions: &'a [[f64; 3]],
+    velocities: Option<&'a [[f64; 3]]>,
+}
+
+impl<'a> FrameViewData<'a> {
+    fn new(frame: &'a Frame) -> FrameViewData<'a> {
+        FrameViewData {
+            frame,
+            positions: frame.positions(),
+            velocities: frame.velocities(),
+        }
+    }
+
+    fn view(&self, index: usize) -> AtomView<'a> {
+        AtomView {
+            index,
+            atom: self.frame.atom(index),
+            position: &self.positions[index],
+            velocity: self.velocities.map(|velocities| &velocities[index]),
+        }
+    }
+}
+
+/// Iterator over the atoms in a frame, as returned by [`Frame::iter`] and
+/// [`Frame::iter_indexes`]
+#[derive(Debug)]
+pub struct FrameIter<'a> {
+    data: FrameViewData<'a>,
+    indexes: FrameIterIndexes<'a>,
+}
+
+/// The indexes of the atoms visited by a `FrameIter`
+#[derive(Debug)]
+enum FrameIterIndexes<'a> {
+    All(std::ops::Range<usize>),
+    Some(std::slice::Iter<'a, usize>),
+}
+
+impl<'a> Iterator for FrameIter<'a> {
+    type Item = AtomView<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = match self.indexes {
+            FrameIterIndexes::All(ref mut range) => range.next()?,
+            FrameIterIndexes::Some(ref mut indexes) => *indexes.next()?,
+        };
+        return Some(self.data.view(index));
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.indexes {
+            FrameIterIndexes::All(ref range) => range.size_hint(),
+            FrameIterIndexes::Some(ref indexes) => indexes.size_hint(),
+        }
+    }
+}
+
+impl ExactSizeIterator for FrameIter<'_> {}
+
+impl<'a> IntoIterator for &'a Frame {
+    type Item = AtomView<'a>;
+    type IntoIter = FrameIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
 }
 
 impl Drop for Frame {
@@ -954,6 +3796,127 @@ mod test {
         assert_eq!(frame.size(), 12);
     }
 
+    #[test]
+    fn reserve() {
+        let mut frame = Frame::new();
+        frame.add_velocities();
+        frame.add_atom(&Atom::new("Zn"), [1.0, 2.0, 3.0], [4.0, 5.0, 6.0]);
+        frame.add_atom(&Atom::new("O"), [0.0; 3], None);
+        frame.add_bond(0, 1);
+
+        frame.reserve(100);
+        assert_eq!(frame.size(), 2);
+        assert_eq!(frame.positions()[0], [1.0, 2.0, 3.0]);
+        assert_eq!(frame.velocities().unwrap()[0], [4.0, 5.0, 6.0]);
+        assert_eq!(frame.atom(1).name(), "O");
+        assert_eq!(frame.bonds(), vec![[0, 1]]);
+
+        // reserving less than the current size does nothing
+        frame.reserve(1);
+        assert_eq!(frame.size(), 2);
+    }
+
+    #[test]
+    fn resize_with_truncate() {
+        let mut frame = Frame::new();
+        frame.add_velocities();
+        frame.add_atom(&Atom::new("O"), [1.0, 0.0, 0.0], [1.0, 0.0, 0.0]);
+        frame.add_atom(&Atom::new("H"), [2.0, 0.0, 0.0], [2.0, 0.0, 0.0]);
+        frame.add_atom(&Atom::new("H"), [3.0, 0.0, 0.0], [3.0, 0.0, 0.0]);
+        frame.add_atom(&Atom::new("Na"), [4.0, 0.0, 0.0], [4.0, 0.0, 0.0]);
+        frame.add_bond(0, 1);
+        frame.add_bond(0, 2);
+
+        let mut residue = Residue::new("WAT");
+        residue.add_atom(0);
+        residue.add_atom(1);
+        residue.add_atom(2);
+        frame.add_residue(&residue).unwrap();
+
+        frame.resize_with(6, &Atom::new("Cl"));
+        assert_eq!(frame.size(), 6);
+        assert_eq!(frame.atom(3).name(), "Na");
+        assert_eq!(frame.atom(4).name(), "Cl");
+        assert_eq!(frame.atom(5).name(), "Cl");
+        assert_eq!(frame.positions()[5], [0.0, 0.0, 0.0]);
+        assert_eq!(frame.velocities().unwrap()[5], [0.0, 0.0, 0.0]);
+        assert_eq!(frame.bonds(), vec![[0, 1], [0, 2]]);
+
+        frame.truncate(10);
+        assert_eq!(frame.size(), 6);
+
+        frame.truncate(2);
+        assert_eq!(frame.size(), 2);
+        assert_eq!(frame.positions(), [[1.0, 0.0, 0.0], [2.0, 0.0, 0.0]]);
+        assert_eq!(frame.bonds(), vec![[0, 1]]);
+        let residue = frame.residue(0).unwrap();
+        assert_eq!(residue.atoms(), vec![0, 1]);
+
+        frame.resize_with(1, &Atom::new("Cl"));
+        assert_eq!(frame.size(), 1);
+        assert!(frame.bonds().is_empty());
+    }
+
+    #[test]
+    fn set_positions_velocities() {
+        let mut frame = Frame::new();
+        frame.resize(2);
+
+        frame.set_positions(&[[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]).unwrap();
+        assert_eq!(frame.positions(), [[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+
+        let error = frame.set_positions(&[[0.0; 3]; 3]).unwrap_err();
+        assert_eq!(error.status, Status::InvalidArgument);
+        assert_eq!(error.message, "expected 2 positions for this frame, got 3");
+        assert_eq!(frame.positions(), [[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+
+        let error = frame.set_velocities(&[[0.0; 3]]).unwrap_err();
+        assert_eq!(error.message, "expected 2 velocities for this frame, got 1");
+        assert!(!frame.has_velocities());
+
+        frame.set_velocities(&[[-1.0, 0.0, 0.0], [0.0, 0.0, 1.0]]).unwrap();
+        assert_eq!(frame.velocities().unwrap(), [[-1.0, 0.0, 0.0], [0.0, 0.0, 1.0]]);
+    }
+
+    #[test]
+    fn iter() {
+        let mut frame = Frame::new();
+        frame.add_atom(&Atom::new("O"), [0.0, 0.0, 0.0], None);
+        frame.add_atom(&Atom::new("H"), [1.0, 0.0, 0.0], None);
+        frame.add_atom(&Atom::new("H"), [0.0, 1.0, 0.0], None);
+
+        let iter = frame.iter();
+        assert_eq!(iter.len(), 3);
+        let names = iter.map(|view| view.atom.name()).collect::<Vec<_>>();
+        assert_eq!(names, ["O", "H", "H"]);
+        assert!(frame.iter().all(|view| view.velocity.is_none()));
+
+        frame.add_velocities();
+        frame.velocities_mut().unwrap()[2] = [1.0, 2.0, 3.0];
+        let views = frame.iter_indexes(&[2, 0]).collect::<Vec<_>>();
+        assert_eq!(views.len(), 2);
+        assert_eq!(views[0].index, 2);
+        assert_eq!(views[0].position, &[0.0, 1.0, 0.0]);
+        assert_eq!(views[0].velocity, Some(&[1.0, 2.0, 3.0]));
+        assert_eq!(views[1].atom.name(), "O");
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    #[allow(clippy::cast_precision_loss)]
+    fn par_iter() {
+        use rayon::prelude::*;
+
+        let mut frame = Frame::new();
+        for i in 0..1000 {
+            frame.add_atom(&Atom::new("Ar"), [f64::from(i), 0.0, 0.0], None);
+        }
+
+        let indexes = frame.par_iter().map(|view| view.index).collect::<Vec<_>>();
+        assert_eq!(indexes, (0..1000).collect::<Vec<_>>());
+        assert!(frame.par_iter().all(|view| view.position[0] == view.index as f64));
+    }
+
     #[test]
     fn add_atom() {
         let mut frame = Frame::new();
@@ -1033,6 +3996,44 @@ mod test {
         assert_eq!(frame.velocities().unwrap(), expected);
     }
 
+    #[test]
+    fn positions_and_velocities() {
+        // empty frames give empty slices, even if the C++ pointer is NULL
+        let mut frame = Frame::new();
+        frame.add_velocities();
+        assert!(frame.positions().is_empty());
+        assert!(frame.positions_mut().is_empty());
+        assert!(frame.velocities().unwrap().is_empty());
+        assert!(frame.velocities_mut().unwrap().is_empty());
+
+        frame.resize(3);
+        let (positions, velocities) = frame.positions_and_velocities_mut();
+        let velocities = velocities.unwrap();
+        assert_eq!(positions.len(), 3);
+        assert_eq!(velocities.len(), 3);
+        // the two slices must not overlap
+        let positions_range = positions.as_ptr_range();
+        let velocities_range = velocities.as_ptr_range();
+        assert!(positions_range.end <= velocities_range.start || velocities_range.end <= positions_range.start);
+
+        positions[2] = [1.0, 2.0, 3.0];
+        velocities[2] = [4.0, 5.0, 6.0];
+        // interleaved writes through both slices, as in an integrator
+        for (position, velocity) in positions.iter_mut().zip(velocities.iter_mut()) {
+            velocity[0] += 1.0;
+            position[1] += velocity[0];
+            velocity[1] = position[1] + velocity[2];
+        }
+        assert_eq!(frame.positions(), [[0.0, 1.0, 0.0], [0.0, 1.0, 0.0], [1.0, 7.0, 3.0]]);
+        assert_eq!(frame.velocities().unwrap(), [[1.0, 1.0, 0.0], [1.0, 1.0, 0.0], [5.0, 13.0, 6.0]]);
+
+        let mut frame = Frame::new();
+        frame.resize(3);
+        let (positions, velocities) = frame.positions_and_velocities_mut();
+        assert_eq!(positions.len(), 3);
+        assert!(velocities.is_none());
+    }
+
     #[test]
     fn cell() {
         let mut frame = Frame::new();
@@ -1073,6 +4074,7 @@ mod test {
         assert_eq!(frame.topology().bonds_count(), 3);
 
         assert_eq!(frame.topology().bonds(), vec![[0, 1], [2, 9], [3, 7]]);
+        assert_eq!(frame.bonds(), vec![[0, 1], [2, 9], [3, 7]]);
         let expected = vec![BondOrder::Unknown, BondOrder::Unknown, BondOrder::Aromatic];
         assert_eq!(frame.topology().bond_orders(), expected);
 
@@ -1086,6 +4088,14 @@ mod test {
 
         frame.clear_bonds();
         assert_eq!(frame.topology().bonds_count(), 0);
+
+        frame.add_bond(0, 1);
+        frame.add_bond(1, 2);
+        frame.add_bond(2, 3);
+        frame.add_bond(2, 4);
+        assert_eq!(frame.angles(), vec![[0, 1, 2], [1, 2, 3], [1, 2, 4], [3, 2, 4]]);
+        assert_eq!(frame.dihedrals(), vec![[0, 1, 2, 3], [0, 1, 2, 4]]);
+        assert_eq!(frame.impropers(), vec![[1, 2, 3, 4]]);
     }
 
     #[test]
@@ -1124,6 +4134,18 @@ mod test {
 
         assert_eq!(frame.topology().residues_count(), 3);
         assert_eq!(frame.topology().residue(0).unwrap().name(), "foobar");
+
+        assert_eq!(frame.residues_count(), 3);
+        assert_eq!(frame.residue(2).unwrap().name(), "foobar");
+        assert!(frame.residue(3).is_none());
+
+        frame.resize(4);
+        let mut residue = Residue::with_id("bar", 33);
+        residue.add_atom(2);
+        frame.add_residue(&residue).unwrap();
+        assert_eq!(frame.residue_for_atom(2).unwrap().id(), Some(33));
+        assert!(frame.residue_for_atom(1).is_none());
+        assert!(frame.residue_for_atom(10).is_none());
     }
 
     #[test]
@@ -1151,6 +4173,131 @@ mod test {
         }
     }
 
+    #[test]
+    fn atom_array_property() {
+        let mut frame = Frame::new();
+        frame.resize(3);
+
+        frame
+            .set_atom_array_property("forces", &[[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]])
+            .unwrap();
+        assert_eq!(frame.atom(2).get("forces"), Some(Property::Vector3D([0.0, 0.0, 1.0])));
+        assert_eq!(
+            frame.atom_array_property("forces").unwrap()[1],
+            Property::Vector3D([0.0, 1.0, 0.0])
+        );
+
+        frame.set_atom_array_property("id", &["a", "b", "c"]).unwrap();
+        assert_eq!(
+            frame.atom_array_property("id"),
+            Some(vec![Property::from("a"), Property::from("b"), Property::from("c")])
+        );
+
+        assert_eq!(frame.atom_array_property("missing"), None);
+        frame.resize(0);
+        assert_eq!(frame.atom_array_property("missing"), Some(vec![]));
+    }
+
+    #[test]
+    fn atom_array_property_wrong_size() {
+        let mut frame = Frame::new();
+        frame.resize(3);
+        let error = frame.set_atom_array_property("foo", &[1.0]).unwrap_err();
+        assert_eq!(error.status, Status::InvalidArgument);
+        assert_eq!(error.message, "expected 3 values for this frame, got 1");
+        assert_eq!(frame.atom_array_property("foo"), None);
+    }
+
+    #[test]
+    fn electronic_structure() {
+        let mut frame = Frame::new();
+        assert_eq!(frame.scf_energy(), None);
+        assert_eq!(frame.total_charge(), None);
+        assert_eq!(frame.multiplicity(), None);
+
+        frame.set_scf_energy(-40.5);
+        frame.set_total_charge(1.0);
+        frame.set_multiplicity(2);
+        assert_eq!(frame.scf_energy(), Some(-40.5));
+        assert_eq!(frame.total_charge(), Some(1.0));
+        assert_eq!(frame.multiplicity(), Some(2));
+
+        frame.set("charge", "-2");
+        assert_eq!(frame.total_charge(), Some(-2.0));
+        frame.set("multiplicity", " 1 ");
+        assert_eq!(frame.multiplicity(), Some(1));
+        frame.set("multiplicity", 0.0);
+        assert_eq!(frame.multiplicity(), None);
+        frame.set("multiplicity", 1.5);
+        assert_eq!(frame.multiplicity(), None);
+        frame.set("multiplicity", "singlet");
+        assert_eq!(frame.multiplicity(), None);
+    }
+
+    #[test]
+    fn energy_stress_virial() {
+        let mut frame = Frame::new();
+        assert_eq!(frame.energy(), None);
+        assert_eq!(frame.stress(), None);
+        assert_eq!(frame.virial(), None);
+
+        frame.set_energy(-3.25);
+        assert_eq!(frame.energy(), Some(-3.25));
+        frame.set("energy", " -1.5e2 ");
+        assert_eq!(frame.energy(), Some(-150.0));
+        frame.set("energy", true);
+        assert_eq!(frame.energy(), None);
+
+        let stress = [[1.5, -2.0, 3.0], [4.0, 5.0, 6.0], [7.0, 8.0, 9.25]];
+        frame.set_stress(stress);
+        assert_eq!(frame.stress(), Some(stress));
+        frame.set("stress", "1 2 3 4 5 6");
+        assert_eq!(frame.stress(), None);
+        frame.set("stress", "1 2 3 4 5 6 7 8 nine");
+        assert_eq!(frame.stress(), None);
+        frame.set("stress", [1.0, 2.0, 3.0]);
+        assert_eq!(frame.stress(), None);
+
+        frame.set_virial(stress);
+        assert_eq!(frame.virial(), Some(stress));
+    }
+
+    #[test]
+    fn forces() {
+        let mut frame = Frame::new();
+        assert!(!frame.has_forces());
+        frame.add_forces();
+        assert!(!frame.has_forces());
+
+        frame.resize(2);
+        assert_eq!(frame.forces(), None);
+        assert!(frame.forces_mut().is_none());
+
+        frame.add_forces();
+        assert!(frame.has_forces());
+        assert_eq!(frame.forces(), Some(vec![[0.0; 3]; 2]));
+
+        frame.set_forces(&[[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]).unwrap();
+        frame.add_forces();
+        assert_eq!(frame.forces(), Some(vec![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]));
+
+        {
+            let mut forces = frame.forces_mut().unwrap();
+            assert_eq!(forces.len(), 2);
+            forces[0][2] = -3.0;
+        }
+        assert_eq!(frame.atom(0).get("forces"), Some(Property::Vector3D([1.0, 2.0, -3.0])));
+
+        let error = frame.set_forces(&[[1.0, 2.0, 3.0]]).unwrap_err();
+        assert_eq!(error.status, Status::InvalidArgument);
+        assert_eq!(error.message, "expected 2 forces for this frame, got 1");
+        assert_eq!(frame.forces(), Some(vec![[1.0, 2.0, -3.0], [4.0, 5.0, 6.0]]));
+
+        frame.atom_mut(1).set("forces", "not a vector");
+        assert_eq!(frame.forces(), None);
+        assert!(!frame.has_forces());
+    }
+
     #[test]
     fn pbc_geometry() {
         use std::f64::consts::PI;
@@ -1170,6 +4317,39 @@ mod test {
         assert_eq!(frame.out_of_plane(1, 4, 0, 2), 2.0);
     }
 
+    #[test]
+    fn measure() {
+        let mut frame = Frame::new();
+        frame.set_cell(&UnitCell::triclinic([8.0, 9.0, 10.0], [80.0, 95.0, 100.0]));
+        let atom = &Atom::new("");
+        frame.add_atom(atom, [1.0, 0.5, 0.2], None);
+        frame.add_atom(atom, [7.5, 0.3, 0.1], None);
+        frame.add_atom(atom, [7.0, 8.5, 1.0], None);
+        frame.add_atom(atom, [6.5, 8.0, 9.5], None);
+        frame.add_atom(atom, [0.5, 1.5, 9.0], None);
+        frame.add_bond(0, 1);
+        frame.add_bond(1, 2);
+        frame.add_bond(2, 3);
+        frame.add_bond(3, 4);
+
+        let bonds = frame.measure_bonds().unwrap();
+        for (&[i, j], value) in frame.bonds().iter().zip(bonds) {
+            approx::assert_ulps_eq!(frame.distance(i, j), value, epsilon = 1e-12);
+        }
+
+        let angles = frame.measure_angles().unwrap();
+        assert_eq!(angles.len(), 3);
+        for (&[i, j, k], value) in frame.angles().iter().zip(angles) {
+            approx::assert_ulps_eq!(frame.angle(i, j, k), value, epsilon = 1e-12);
+        }
+
+        let dihedrals = frame.measure_dihedrals().unwrap();
+        assert_eq!(dihedrals.len(), 2);
+        for (&[i, j, k, m], value) in frame.dihedrals().iter().zip(dihedrals) {
+            approx::assert_ulps_eq!(frame.dihedral(i, j, k, m), value, epsilon = 1e-12);
+        }
+    }
+
     #[test]
     fn atom_iterator() {
         let mut frame = Frame::new();
@@ -1191,4 +4371,469 @@ mod test {
         assert_eq!(items[1].1, &[0.0_f64, 1.0_f64, 0.0_f64]);
         assert_eq!(items[3].1, &[1.0_f64, 1.0_f64, 1.0_f64]);
     }
+
+    #[test]
+    fn subset() {
+        let mut frame = Frame::new();
+        frame.add_velocities();
+        frame.add_atom(&Atom::new("O"), [0.0, 0.0, 0.0], [1.0, 0.0, 0.0]);
+        frame.add_atom(&Atom::new("H"), [1.0, 0.0, 0.0], [2.0, 0.0, 0.0]);
+        frame.add_atom(&Atom::new("H"), [0.0, 1.0, 0.0], [3.0, 0.0, 0.0]);
+        frame.add_atom(&Atom::new("Na"), [5.0, 5.0, 5.0], [4.0, 0.0, 0.0]);
+        frame.add_bond_with_order(0, 1, BondOrder::Single);
+        frame.add_bond(0, 2);
+        frame.set_step(42);
+        frame.set("foo", "bar");
+
+        let mut residue = Residue::with_id("HOH", 3);
+        residue.add_atom(0);
+        residue.add_atom(1);
+        residue.add_atom(2);
+        residue.set("chainid", "A");
+        frame.add_residue(&residue).unwrap();
+
+        let subset = frame.subset(&[1, 0, 3]);
+        assert_eq!(subset.size(), 3);
+        assert_eq!(subset.step(), 42);
+        assert_eq!(subset.get("foo"), Some(Property::String("bar".into())));
+        assert_eq!(subset.atom(0).name(), "H");
+        assert_eq!(subset.atom(1).name(), "O");
+        assert_eq!(subset.atom(2).name(), "Na");
+        assert_eq!(subset.positions(), &[[1.0, 0.0, 0.0], [0.0, 0.0, 0.0], [5.0, 5.0, 5.0]]);
+        assert_eq!(
+            subset.velocities().unwrap(),
+            &[[2.0, 0.0, 0.0], [1.0, 0.0, 0.0], [4.0, 0.0, 0.0]]
+        );
+
+        let topology = subset.topology();
+        assert_eq!(topology.bonds(), vec![[0, 1]]);
+        assert_eq!(topology.bond_order(0, 1), BondOrder::Single);
+
+        assert_eq!(topology.residues_count(), 1);
+        let residue = topology.residue(0).unwrap();
+        assert_eq!(residue.name(), "HOH");
+        assert_eq!(residue.id(), Some(3));
+        assert_eq!(residue.atoms(), vec![0, 1]);
+        assert_eq!(residue.get("chainid"), Some(Property::String("A".into())));
+    }
+
+    #[test]
+    fn strip() {
+        let mut frame = Frame::new();
+        frame.add_atom(&Atom::new("Na"), [5.0, 5.0, 5.0], None);
+        frame.add_atom(&Atom::new("O"), [0.0, 0.0, 0.0], None);
+        frame.add_atom(&Atom::new("H"), [1.0, 0.0, 0.0], None);
+        frame.add_atom(&Atom::new("C"), [3.0, 0.0, 0.0], None);
+        frame.add_atom(&Atom::new("H"), [0.0, 1.0, 0.0], None);
+        frame.add_bond(1, 2);
+        frame.add_bond(1, 4);
+        frame.add_bond(0, 3);
+
+        let mut residue = Residue::with_id("HOH", 2);
+        residue.add_atom(1);
+        residue.add_atom(2);
+        residue.add_atom(4);
+        frame.add_residue(&residue).unwrap();
+
+        let mut selection = Selection::new("resname HOH").unwrap();
+        let water = frame.strip(&mut selection);
+        assert_eq!(water.size(), 3);
+        assert_eq!(water.bonds(), vec![[0, 1], [0, 2]]);
+        assert_eq!(water.residue(0).unwrap().atoms(), vec![0, 1, 2]);
+
+        assert_eq!(frame.size(), 2);
+        assert_eq!(frame.atom(1).name(), "C");
+        assert_eq!(frame.positions(), &[[5.0, 5.0, 5.0], [3.0, 0.0, 0.0]]);
+        assert_eq!(frame.bonds(), vec![[0, 1]]);
+        assert_eq!(frame.residues_count(), 0);
+    }
+
+    #[test]
+    fn interpolate() {
+        let mut first = Frame::new();
+        first.set_cell(&UnitCell::new([10.0, 10.0, 10.0]));
+        first.add_velocities();
+        first.add_atom(&Atom::new("Ar"), [1.0, 1.0, 1.0], [0.0, 0.0, 0.0]);
+        first.add_atom(&Atom::new("Kr"), [0.5, 5.0, 5.0], [1.0, 0.0, 0.0]);
+
+        let mut second = first.clone();
+        second.positions_mut()[0] = [3.0, 1.0, 1.0];
+        second.positions_mut()[1] = [9.5, 5.0, 5.0];
+        second.velocities_mut().unwrap()[1] = [3.0, 0.0, 0.0];
+
+        let frame = first.interpolate(&second, 0.5).unwrap();
+        assert_eq!(frame.positions(), &[[2.0, 1.0, 1.0], [5.0, 5.0, 5.0]]);
+        assert_eq!(frame.velocities().unwrap()[1], [2.0, 0.0, 0.0]);
+
+        let frame = first.interpolate_periodic(&second, 0.5).unwrap();
+        assert_eq!(frame.positions(), &[[2.0, 1.0, 1.0], [0.0, 5.0, 5.0]]);
+
+        let error = first.interpolate(&second, f64::NAN).unwrap_err();
+        assert_eq!(error.status, Status::InvalidArgument);
+
+        second.atom_mut(1).set_name("Xe");
+        let error = first.interpolate(&second, 0.5).unwrap_err();
+        assert_eq!(
+            error.message,
+            "can not interpolate between frames with different atoms: atom 1 is 'Kr' and 'Xe'"
+        );
+
+        second.resize(3);
+        assert!(first.interpolate(&second, 0.5).is_err());
+    }
+
+    #[test]
+    fn gromacs_metadata() {
+        let mut frame = Frame::new();
+        frame.add_atom(&Atom::new("Ar"), [1.0, 2.0, 3.0], None);
+        frame.set_cell(&UnitCell::new([20.0, 20.0, 20.0]));
+        frame.set_time(12.5);
+        frame.set_lambda(0.75);
+        frame.set_xtc_precision(100.0);
+
+        let mut trajectory = Trajectory::open("test-gromacs-tmp.trr", 'w').unwrap();
+        trajectory.write(&frame).unwrap();
+        drop(trajectory);
+
+        let mut trajectory = Trajectory::open("test-gromacs-tmp.trr", 'r').unwrap();
+        let mut read = Frame::new();
+        trajectory.read(&mut read).unwrap();
+        drop(trajectory);
+        std::fs::remove_file("test-gromacs-tmp.trr").unwrap();
+
+        assert_eq!(read.time(), Some(12.5));
+        assert_eq!(read.lambda(), Some(0.75));
+
+        let mut trajectory = Trajectory::open("test-gromacs-tmp.xtc", 'w').unwrap();
+        trajectory.write(&frame).unwrap();
+        drop(trajectory);
+
+        let mut trajectory = Trajectory::open("test-gromacs-tmp.xtc", 'r').unwrap();
+        trajectory.read(&mut read).unwrap();
+        drop(trajectory);
+        std::fs::remove_file("test-gromacs-tmp.xtc").unwrap();
+
+        assert_eq!(read.time(), Some(12.5));
+        assert_eq!(read.xtc_precision(), Some(100.0));
+    }
+
+    #[test]
+    fn amber_restart() {
+        let mut frame = Frame::new();
+        frame.set_cell(&UnitCell::new([20.0, 21.0, 22.0]));
+        frame.add_velocities();
+        frame.add_atom(&Atom::new("Ar"), [1.0, 2.0, 3.0], [0.5, 0.0, -0.5]);
+        frame.add_atom(&Atom::new("Ar"), [4.0, 5.0, 6.0], [-1.0, 2.0, 0.25]);
+        frame.write_restart("test-restart-tmp.ncrst").unwrap();
+
+        let read = Frame::from_restart("test-restart-tmp.ncrst").unwrap();
+        std::fs::remove_file("test-restart-tmp.ncrst").unwrap();
+
+        assert_eq!(read.size(), 2);
+        assert_eq!(read.cell().lengths(), [20.0, 21.0, 22.0]);
+        crate::assert_vector3d_eq(&read.positions()[1], &[4.0, 5.0, 6.0], 1e-6);
+
+        let velocities = read.velocities().expect("velocities should be written to the restart file");
+        crate::assert_vector3d_eq(&velocities[0], &[0.5, 0.0, -0.5], 1e-6);
+        crate::assert_vector3d_eq(&velocities[1], &[-1.0, 2.0, 0.25], 1e-6);
+    }
+
+    #[test]
+    fn remove_property() {
+        let mut frame = Frame::new();
+        frame.set_cell(&UnitCell::new([10.0, 11.0, 12.0]));
+        frame.set_step(42);
+        frame.add_velocities();
+        frame.add_atom(&Atom::new("O"), [1.0, 2.0, 3.0], [0.5, 0.5, 0.5]);
+        frame.add_atom(&Atom::new("H"), [4.0, 5.0, 6.0], [0.1, 0.1, 0.1]);
+        frame.add_bond_with_order(0, 1, BondOrder::Single);
+        frame.set("md/temperature", 300.0);
+        frame.set("md/pressure", 1.0);
+        frame.set("name", "water");
+
+        assert_eq!(frame.properties_with_prefix("md/").count(), 2);
+        assert_eq!(frame.properties_with_prefix("nope/").count(), 0);
+
+        assert_eq!(frame.remove_properties_with_prefix("nope/"), 0);
+        assert_eq!(frame.remove_property("md/pressure"), Some(Property::Double(1.0)));
+        assert_eq!(frame.properties_names(), ["md/temperature", "name"]);
+
+        // everything else is preserved
+        assert_eq!(frame.step(), 42);
+        assert_eq!(frame.cell().lengths(), [10.0, 11.0, 12.0]);
+        assert_eq!(frame.positions(), [[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        assert_eq!(frame.velocities().unwrap(), [[0.5, 0.5, 0.5], [0.1, 0.1, 0.1]]);
+        assert_eq!(frame.bonds(), [[0, 1]]);
+        assert_eq!(frame.atom(1).name(), "H");
+    }
+
+    #[test]
+    fn clear_properties() {
+        let mut frame = Frame::new();
+        let mut atom = Atom::new("O");
+        atom.set("foo", 1.0);
+        atom.set("bar", 2.0);
+        frame.add_atom(&atom, [1.0, 2.0, 3.0], None);
+        frame.add_atom(&atom, [4.0, 5.0, 6.0], None);
+        frame.set("foo", "bar");
+
+        assert_eq!(frame.remove_atom_property("foo"), 2);
+        assert_eq!(frame.remove_atom_property("foo"), 0);
+        for atom in frame.iter_atoms() {
+            assert_eq!(atom.properties_names(), ["bar"]);
+            assert_eq!(atom.name(), "O");
+        }
+        assert_eq!(frame.positions(), [[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        assert_eq!(frame.get("foo"), Some(Property::String("bar".into())));
+
+        frame.clear_properties();
+        assert!(frame.properties_names().is_empty());
+        assert_eq!(frame.atom(0).properties_names(), ["bar"]);
+    }
+
+    #[test]
+    fn apply_cell_transform() {
+        let mut frame = Frame::new();
+        frame.set_cell(&UnitCell::new([10.0, 10.0, 10.0]));
+        frame.add_velocities();
+        frame.add_atom(&Atom::new("Ar"), [1.0, 2.0, 3.0], [1.0, 0.0, 0.0]);
+
+        let shear = [[1.0, 1.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+        frame.apply_cell_transform(shear).unwrap();
+        let volume = frame.cell().volume();
+        assert!((volume - 1000.0).abs() < 1e-9);
+
+        let (reduced, transform) = frame.cell().niggli_reduced();
+        frame.apply_cell_transform(transform).unwrap();
+        crate::assert_vector3d_eq(&frame.cell().lengths(), &reduced.lengths(), 1e-9);
+        crate::assert_vector3d_eq(&frame.cell().lengths(), &[10.0, 10.0, 10.0], 1e-9);
+        crate::assert_vector3d_eq(&frame.positions()[0], &[1.0, 2.0, 3.0], 1e-9);
+        crate::assert_vector3d_eq(&frame.velocities().unwrap()[0], &[1.0, 0.0, 0.0], 1e-9);
+
+        let error = frame
+            .apply_cell_transform([[2.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]])
+            .unwrap_err();
+        assert_eq!(error.status, Status::InvalidArgument);
+
+        frame.set_cell(&UnitCell::infinite());
+        assert!(frame.apply_cell_transform(shear).is_err());
+    }
+
+    #[test]
+    fn mass_density() {
+        let mut frame = Frame::new();
+        let mut atom = Atom::new("X");
+        atom.set_mass(10.0);
+        frame.add_atom(&atom, [0.0, 0.0, 0.0], None);
+        frame.add_atom(&atom, [1.0, 0.0, 0.0], None);
+        assert_eq!(frame.mass_density(), None);
+
+        frame.set_cell(&UnitCell::new([2.0, 5.0, 2.0]));
+        assert_eq!(frame.volume(), 20.0);
+        approx::assert_ulps_eq!(frame.mass_density().unwrap(), 1.660_539_066_6, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn space_group() {
+        let mut frame = Frame::new();
+        assert_eq!(frame.space_group(), None);
+
+        frame.set_space_group(&SpaceGroup::new("P 21/c", 14));
+        assert_eq!(frame.space_group(), Some(SpaceGroup::new("P 21/c", 14)));
+
+        frame.set_space_group(&SpaceGroup::new("P -1", None));
+        assert_eq!(frame.space_group(), Some(SpaceGroup::new("P -1", None)));
+        assert_eq!(frame.get("space_group_number"), None);
+
+        frame.set("space_group_number", "  2 ");
+        assert_eq!(frame.space_group(), Some(SpaceGroup::new("P -1", 2)));
+    }
+
+    #[test]
+    fn expand_symmetry() {
+        let mut frame = Frame::new();
+        frame.add_atom(&Atom::new("Si"), [1.0, 2.0, 3.0], None);
+        assert_eq!(frame.expand_symmetry().unwrap_err().status, Status::PropertyError);
+
+        frame.set("symmetry_operations", "x,y,z\n-x,y+1/2,-z");
+        assert_eq!(frame.expand_symmetry().unwrap_err().status, Status::InvalidArgument);
+
+        frame.set_cell(&UnitCell::new([4.0, 4.0, 4.0]));
+        frame.expand_symmetry().unwrap();
+        assert_eq!(frame.size(), 2);
+        assert_eq!(frame.atom(1).name(), "Si");
+        assert_eq!(frame.positions()[1], [3.0, 0.0, 1.0]);
+
+        frame.set("symmetry_operations", "x,y");
+        assert_eq!(frame.expand_symmetry().unwrap_err().status, Status::FormatError);
+    }
+
+    #[test]
+    fn translate_rotate() {
+        let mut frame = Frame::new();
+        frame.add_velocities();
+        frame.add_atom(&Atom::new("O"), [0.0, 0.0, 0.0], [1.0, 0.0, 0.0]);
+        frame.add_atom(&Atom::new("H"), [1.0, 0.0, 0.0], [1.0, 0.0, 0.0]);
+        frame.add_atom(&Atom::new("H"), [0.0, 1.0, 0.0], [1.0, 0.0, 0.0]);
+
+        frame.translate([1.0, 1.0, 1.0], None);
+        assert_eq!(frame.positions(), &[[1.0, 1.0, 1.0], [2.0, 1.0, 1.0], [1.0, 2.0, 1.0]]);
+
+        frame.translate([-1.0, 0.0, 0.0], Some(&[0, 2]));
+        assert_eq!(frame.positions(), &[[0.0, 1.0, 1.0], [2.0, 1.0, 1.0], [0.0, 2.0, 1.0]]);
+
+        let rotation = [[1.0, 0.0, 0.0], [0.0, 0.0, -1.0], [0.0, 1.0, 0.0]];
+        frame.rotate(rotation, [0.0, 1.0, 1.0], Some(&[2]));
+        assert_eq!(frame.positions()[2], [0.0, 1.0, 2.0]);
+        assert_eq!(frame.velocities().unwrap()[2], [1.0, 0.0, 0.0]);
+
+        let rotation = [[0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]];
+        frame.rotate(rotation, [0.0, 0.0, 0.0], None);
+        assert_eq!(frame.positions()[1], [-1.0, 2.0, 1.0]);
+        assert_eq!(frame.velocities().unwrap(), &[[0.0, 1.0, 0.0]; 3]);
+    }
+
+    #[test]
+    fn align_principal_axes() {
+        let mut frame = Frame::new();
+        frame.add_atom(&Atom::new("O"), [1.0, 1.0, 1.0], None);
+        frame.add_atom(&Atom::new("O"), [2.0, 2.0, 2.0], None);
+        frame.add_atom(&Atom::new("O"), [3.0, 3.0, 3.0], None);
+        frame.add_atom(&Atom::new("H"), [2.0, 2.5, 1.5], None);
+        frame.add_atom(&Atom::new("H"), [2.0, 1.5, 2.5], None);
+        frame.add_atom(&Atom::new("Zn"), [10.0, 0.0, 0.0], None);
+
+        // only use the first atoms, the zinc atom is moved with the others
+        frame.align_principal_axes(Some(&[0, 1, 2, 3, 4]));
+        let positions = frame.positions();
+
+        // the oxygen line is now along x
+        approx::assert_ulps_eq!(positions[0][1], positions[2][1], epsilon = 1e-9);
+        approx::assert_ulps_eq!(positions[0][2], positions[2][2], epsilon = 1e-9);
+        approx::assert_ulps_eq!(
+            (positions[2][0] - positions[0][0]).abs(),
+            12.0_f64.sqrt(),
+            epsilon = 1e-9
+        );
+        // the hydrogens are in the xy plane
+        approx::assert_ulps_eq!(positions[3][2], positions[0][2], epsilon = 1e-9);
+        approx::assert_ulps_eq!(positions[4][2], positions[0][2], epsilon = 1e-9);
+
+        // distances are conserved
+        approx::assert_ulps_eq!(frame.distance(1, 5), f64::sqrt(64.0 + 4.0 + 4.0), epsilon = 1e-9);
+    }
+
+    #[test]
+    fn guess_bonds_with_radii() {
+        let mut frame = Frame::new();
+        frame.set_cell(&UnitCell::new([20.0, 20.0, 20.0]));
+        frame.add_atom(&Atom::bead("Qd", 72.0, 2.35), [1.0, 0.0, 0.0], None);
+        frame.add_atom(&Atom::bead("Na", 72.0, 2.35), [17.0, 0.0, 0.0], None);
+        frame.add_atom(&Atom::bead("C1", 72.0, 2.35), [10.0, 0.0, 0.0], None);
+        frame.add_atom(&Atom::new("H"), [10.0, 2.5, 0.0], None);
+
+        // the bond between the first two beads goes through the periodic
+        // boundaries, and the hydrogen uses its covalent radius
+        assert_eq!(frame.guess_bonds_with_radii(1.0).unwrap(), 2);
+        assert_eq!(frame.topology().bonds(), vec![[0, 1], [2, 3]]);
+        assert_eq!(frame.guess_bonds_with_radii(1.0).unwrap(), 0);
+
+        let error = frame.guess_bonds_with_radii(0.0).unwrap_err();
+        assert_eq!(error.status, Status::InvalidArgument);
+
+        frame.add_atom(&Atom::new("SC3"), [5.0, 5.0, 5.0], None);
+        let error = frame.guess_bonds_with_radii(1.0).unwrap_err();
+        assert_eq!(error.status, Status::InvalidArgument);
+    }
+
+    #[test]
+    fn virtual_sites() {
+        let mut frame = Frame::new();
+        frame.add_velocities();
+        frame.add_atom(&Atom::new("O"), [0.0, 0.0, 0.0], Some([0.0, 0.0, 0.0]));
+        frame.add_atom(&Atom::new("H"), [1.0, 0.0, 0.0], Some([10.0, 0.0, 0.0]));
+        frame.add_atom(&Atom::new("H"), [0.0, 1.0, 0.0], Some([0.0, 10.0, 0.0]));
+        frame.add_atom(&Atom::new("MW"), [0.1, 0.1, 0.0], Some([100.0, 0.0, 0.0]));
+
+        let com = frame.center_of_mass(None).unwrap();
+        let total = 15.999 + 2.0 * 1.008;
+        approx::assert_ulps_eq!(com[0], 1.008 / total);
+        approx::assert_ulps_eq!(com[1], 1.008 / total);
+        assert_eq!(frame.center_of_mass(Some(&[3])), None);
+
+        // the virtual site velocity is ignored
+        let expected = 2.0 * 1.008 * 100.0 * 0.01 / (9.0 * 8.314_462_618e-3);
+        approx::assert_ulps_eq!(frame.temperature(0).unwrap(), expected);
+        let expected = 2.0 * 1.008 * 100.0 * 0.01 / (6.0 * 8.314_462_618e-3);
+        approx::assert_ulps_eq!(frame.temperature(3).unwrap(), expected);
+        assert_eq!(frame.temperature(9), None);
+        assert_eq!(frame.temperature(10), None);
+
+        // flagged virtual sites are ignored even with a mass
+        frame.atom_mut(3).set_mass(16.0);
+        frame.atom_mut(3).set_virtual_site(true);
+        approx::assert_ulps_eq!(frame.center_of_mass(None).unwrap()[0], 1.008 / total);
+    }
+
+    #[test]
+    fn dipoles() {
+        let mut frame = Frame::new();
+        frame.set_cell(&UnitCell::new([10.0, 10.0, 10.0]));
+        frame.add_atom(&Atom::new("O"), [0.2, 5.0, 5.0], None);
+        frame.add_atom(&Atom::new("H"), [9.4, 5.5, 5.0], None);
+        frame.add_atom(&Atom::new("H"), [0.6, 4.3, 5.0], None);
+        frame.add_atom(&Atom::new("Na"), [3.0, 3.0, 3.0], None);
+        frame.add_bond(0, 1);
+        frame.add_bond(0, 2);
+        frame.atom_mut(0).set_charge(-0.8);
+        frame.atom_mut(1).set_charge(0.4);
+        frame.atom_mut(2).set_charge(0.4);
+        frame.atom_mut(3).set_charge(1.0);
+
+        assert_eq!(frame.molecules(), vec![vec![0, 1, 2], vec![3]]);
+
+        // dipole of the water molecule, using the unwrapped positions
+        let expected = [0.4 * (-0.8) + 0.4 * 0.4, 0.4 * 0.5 + 0.4 * (-0.7), 0.0];
+        let dipole = frame.dipole_moment(Some(&[0, 1, 2])).unwrap();
+        for k in 0..3 {
+            approx::assert_ulps_eq!(dipole[k], expected[k], epsilon = 1e-12);
+        }
+
+        let dipoles = frame.molecular_dipoles().unwrap();
+        assert_eq!(dipoles.len(), 2);
+        assert_eq!(dipoles[1], [0.0, 0.0, 0.0]);
+        for k in 0..3 {
+            approx::assert_ulps_eq!(dipoles[0][k], expected[k], epsilon = 1e-12);
+        }
+
+        let polarization = frame.polarization().unwrap().unwrap();
+        approx::assert_ulps_eq!(polarization[0], expected[0] / 1000.0, epsilon = 1e-12);
+
+        assert_eq!(frame.dipole_moment(Some(&[])).unwrap(), [0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn provenance() {
+        assert_eq!(iso8601_timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(iso8601_timestamp(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(iso8601_timestamp(1_700_000_000), "2023-11-14T22:13:20Z");
+
+        let mut frame = Frame::new();
+        frame.stamp_provenance("test", "0.1");
+        assert_eq!(frame.get("provenance.tool"), Some(Property::String("test".into())));
+        assert_eq!(frame.get("provenance.version"), Some(Property::String("0.1".into())));
+
+        let timestamp = match frame.get("provenance.timestamp") {
+            Some(Property::String(timestamp)) => timestamp,
+            _ => panic!("missing timestamp"),
+        };
+        assert_eq!(timestamp.len(), 20);
+        assert!(timestamp.ends_with('Z'));
+
+        let command_line = match frame.get("provenance.command_line") {
+            Some(Property::String(command_line)) => command_line,
+            _ => panic!("missing command line"),
+        };
+        assert!(!command_line.is_empty());
+    }
 }
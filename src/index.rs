@@ -0,0 +1,405 @@
+// Chemfiles, a modern library for chemistry file reading and writing
+// Copyright (C) 2015-2018 Guillaume Fraux -- BSD licensed
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::errors::{Error, Status};
+use crate::{Frame, MemoryTrajectoryReader};
+
+/// Text formats supported by `TrajectoryIndex`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IndexedFormat {
+    Xyz,
+    Lammps,
+}
+
+impl IndexedFormat {
+    fn from_name(name: &str) -> Option<IndexedFormat> {
+        match name.to_ascii_uppercase().as_str() {
+            "XYZ" => Some(IndexedFormat::Xyz),
+            "LAMMPS" => Some(IndexedFormat::Lammps),
+            _ => None,
+        }
+    }
+
+    fn from_extension(path: &Path) -> Option<IndexedFormat> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "xyz" => Some(IndexedFormat::Xyz),
+            "lammpstrj" | "dump" => Some(IndexedFormat::Lammps),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            IndexedFormat::Xyz => "XYZ",
+            IndexedFormat::Lammps => "LAMMPS",
+        }
+    }
+}
+
+/// A `TrajectoryIndex` stores the position of each step in a text trajectory
+/// file, allowing to read any step without reading all the previous ones.
+///
+/// The file is scanned once when creating the index, and the index can be
+/// saved to a small sidecar file to be re-used later. Only uncompressed XYZ
+/// and LAMMPS dump files are supported, where each step is self-contained.
+///
+/// # Example
+/// ```no_run
+/// # use chemfiles::{TrajectoryIndex, Frame};
+/// let index = TrajectoryIndex::build("huge.xyz").unwrap();
+/// index.save("huge.xyz.idx").unwrap();
+///
+/// let mut frame = Frame::new();
+/// index.read_step(index.nsteps() - 1, &mut frame).unwrap();
+///
+/// // later
+/// let index = TrajectoryIndex::load("huge.xyz", "huge.xyz.idx").unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrajectoryIndex {
+    path: PathBuf,
+    format: IndexedFormat,
+    /// Size of the file when the index was created
+    file_size: u64,
+    /// Offset of the start of each step in the file, followed by the offset
+    /// of the end of the last step
+    offsets: Vec<u64>,
+}
+
+impl TrajectoryIndex {
+    /// Build the index of the file at `path`, guessing the format from the
+    /// extension (`.xyz` for XYZ, `.lammpstrj` or `.dump` for LAMMPS).
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the format is not supported, or if the file can
+    /// not be read.
+    pub fn build(path: impl AsRef<Path>) -> Result<TrajectoryIndex, Error> {
+        let path = path.as_ref();
+        let format = IndexedFormat::from_extension(path).ok_or_else(|| Error {
+            status: Status::FormatError,
+            message: format!(
+                "can not guess a format supported by TrajectoryIndex for '{}'",
+                path.display()
+            ),
+        })?;
+        return TrajectoryIndex::build_with_format(path, format);
+    }
+
+    /// Build the index of the file at `path`, using the given `format`. Only
+    /// `"XYZ"` and `"LAMMPS"` are supported.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the format is not supported, or if the file can
+    /// not be read.
+    pub fn with_format(path: impl AsRef<Path>, format: &str) -> Result<TrajectoryIndex, Error> {
+        let format = IndexedFormat::from_name(format).ok_or_else(|| Error {
+            status: Status::FormatError,
+            message: format!("the '{format}' format is not supported by TrajectoryIndex"),
+        })?;
+        return TrajectoryIndex::build_with_format(path.as_ref(), format);
+    }
+
+    fn build_with_format(path: &Path, format: IndexedFormat) -> Result<TrajectoryIndex, Error> {
+        let file = File::open(path).map_err(|error| io_error(path, &error))?;
+        let file_size = file.metadata().map_err(|error| io_error(path, &error))?.len();
+        let mut reader = BufReader::new(file);
+
+        let offsets = match format {
+            IndexedFormat::Xyz => scan_xyz(&mut reader, path).map_err(|error| io_error(path, &error))??,
+            IndexedFormat::Lammps => scan_lammps(&mut reader).map_err(|error| io_error(path, &error))?,
+        };
+
+        return Ok(TrajectoryIndex {
+            path: path.to_owned(),
+            format,
+            file_size,
+            offsets,
+        });
+    }
+
+    /// Load the index for the file at `path` from the `sidecar` file created
+    /// with [`TrajectoryIndex::save`].
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the sidecar file can not be read, if it is
+    /// invalid, or if the size of the trajectory file changed since the index
+    /// was created.
+    pub fn load(path: impl AsRef<Path>, sidecar: impl AsRef<Path>) -> Result<TrajectoryIndex, Error> {
+        let path = path.as_ref();
+        let sidecar = sidecar.as_ref();
+        let invalid = || Error {
+            status: Status::FormatError,
+            message: format!("invalid trajectory index file at '{}'", sidecar.display()),
+        };
+
+        let content = std::fs::read_to_string(sidecar).map_err(|error| io_error(sidecar, &error))?;
+        let mut lines = content.lines();
+
+        let header = lines.next().ok_or_else(invalid)?;
+        let (format, file_size) = match header.split_whitespace().collect::<Vec<_>>()[..] {
+            ["chemfiles-index", "1", format, size] => (
+                IndexedFormat::from_name(format).ok_or_else(invalid)?,
+                size.parse::<u64>().map_err(|_| invalid())?,
+            ),
+            _ => return Err(invalid()),
+        };
+
+        let offsets = lines
+            .map(|line| line.trim().parse::<u64>().map_err(|_| invalid()))
+            .collect::<Result<Vec<_>, _>>()?;
+        if offsets.is_empty() {
+            return Err(invalid());
+        }
+
+        let size = std::fs::metadata(path).map_err(|error| io_error(path, &error))?.len();
+        if size != file_size {
+            return Err(Error {
+                status: Status::FileError,
+                message: format!(
+                    "the file at '{}' changed since the index at '{}' was created",
+                    path.display(),
+                    sidecar.display()
+                ),
+            });
+        }
+
+        return Ok(TrajectoryIndex {
+            path: path.to_owned(),
+            format,
+            file_size,
+            offsets,
+        });
+    }
+
+    /// Save this index to the given `sidecar` file.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the sidecar file can not be written.
+    pub fn save(&self, sidecar: impl AsRef<Path>) -> Result<(), Error> {
+        let sidecar = sidecar.as_ref();
+        let mut content = format!("chemfiles-index 1 {} {}\n", self.format.name(), self.file_size);
+        for offset in &self.offsets {
+            content.push_str(&offset.to_string());
+            content.push('\n');
+        }
+
+        let mut file = File::create(sidecar).map_err(|error| io_error(sidecar, &error))?;
+        file.write_all(content.as_bytes()).map_err(|error| io_error(sidecar, &error))?;
+        return Ok(());
+    }
+
+    /// Get the number of steps in the indexed file
+    pub fn nsteps(&self) -> usize {
+        return self.offsets.len() - 1;
+    }
+
+    /// Read the given `step` of the indexed file into `frame`.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if `step` is out of bounds, if the file can not be
+    /// read, or if the data is incorrectly formatted.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn read_step(&self, step: usize, frame: &mut Frame) -> Result<(), Error> {
+        if step >= self.nsteps() {
+            return Err(Error {
+                status: Status::OutOfBounds,
+                message: format!(
+                    "can not read file '{}' at step {step}: maximal step is {}",
+                    self.path.display(),
+                    self.nsteps().saturating_sub(1)
+                ),
+            });
+        }
+
+        let start = self.offsets[step];
+        let size = (self.offsets[step + 1] - start) as usize;
+        let mut data = vec![0; size];
+
+        let mut file = File::open(&self.path).map_err(|error| io_error(&self.path, &error))?;
+        file.seek(SeekFrom::Start(start))
+            .and_then(|_| file.read_exact(&mut data))
+            .map_err(|error| io_error(&self.path, &error))?;
+
+        let mut trajectory = MemoryTrajectoryReader::new(data.as_slice(), self.format.name())?;
+        trajectory.read(frame)?;
+        frame.set_step(step);
+        return Ok(());
+    }
+}
+
+/// Create an error for an I/O `error` with the file at `path`
+fn io_error(path: &Path, error: &std::io::Error) -> Error {
+    Error {
+        status: Status::FileError,
+        message: format!("failed to read or write '{}': {error}", path.display()),
+    }
+}
+
+/// Read the next line in `reader` in `line`, returning the number of bytes
+/// read (0 at the end of the file).
+fn next_line(reader: &mut impl BufRead, line: &mut String) -> std::io::Result<u64> {
+    line.clear();
+    return reader.read_line(line).map(|size| size as u64);
+}
+
+/// Find the offsets of all steps in a XYZ file. The outer error is an I/O
+/// error, the inner one an error in the file format.
+fn scan_xyz(reader: &mut impl BufRead, path: &Path) -> std::io::Result<Result<Vec<u64>, Error>> {
+    let mut offsets = Vec::new();
+    let mut offset = 0;
+    let mut line = String::new();
+    loop {
+        let size = next_line(reader, &mut line)?;
+        if size == 0 {
+            break;
+        }
+
+        if line.trim().is_empty() {
+            // trailing empty lines are fine, but not empty lines between steps
+            while next_line(reader, &mut line)? != 0 {
+                if !line.trim().is_empty() {
+                    return Ok(Err(Error {
+                        status: Status::FormatError,
+                        message: format!(
+                            "unexpected empty line in '{}' before step {}",
+                            path.display(),
+                            offsets.len()
+                        ),
+                    }));
+                }
+            }
+            break;
+        }
+
+        let natoms = match line.trim().parse::<usize>() {
+            Ok(natoms) => natoms,
+            Err(_) => {
+                return Ok(Err(Error {
+                    status: Status::FormatError,
+                    message: format!(
+                        "can not read the number of atoms in '{}' at step {}: got '{}'",
+                        path.display(),
+                        offsets.len(),
+                        line.trim()
+                    ),
+                }))
+            }
+        };
+
+        offsets.push(offset);
+        offset += size;
+        // comment line and atoms
+        for i in 0..=natoms {
+            let size = next_line(reader, &mut line)?;
+            if size == 0 {
+                return Ok(Err(Error {
+                    status: Status::FileError,
+                    message: format!("unexpected end of file in '{}'", path.display()),
+                }));
+            }
+            if i != 0 && line.trim().is_empty() {
+                return Ok(Err(Error {
+                    status: Status::FormatError,
+                    message: format!(
+                        "unexpected empty line in '{}' in the atoms of step {}",
+                        path.display(),
+                        offsets.len() - 1
+                    ),
+                }));
+            }
+            offset += size;
+        }
+    }
+
+    offsets.push(offset);
+    return Ok(Ok(offsets));
+}
+
+/// Find the offsets of all steps in a LAMMPS dump file
+fn scan_lammps(reader: &mut impl BufRead) -> std::io::Result<Vec<u64>> {
+    let mut offsets = Vec::new();
+    let mut offset = 0;
+    let mut line = String::new();
+    loop {
+        let size = next_line(reader, &mut line)?;
+        if size == 0 {
+            break;
+        }
+        if line.starts_with("ITEM: TIMESTEP") {
+            offsets.push(offset);
+        }
+        offset += size;
+    }
+    offsets.push(offset);
+    return Ok(offsets);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xyz() {
+        let root = Path::new(file!()).parent().unwrap().join("..");
+        let path = root.join("data").join("water.xyz");
+
+        let index = TrajectoryIndex::build(&path).unwrap();
+        assert_eq!(index.nsteps(), 100);
+
+        let mut frame = Frame::new();
+        index.read_step(41, &mut frame).unwrap();
+        assert_eq!(frame.size(), 297);
+        assert_eq!(frame.step(), 41);
+
+        let mut expected = Frame::new();
+        crate::Trajectory::open(&path, 'r').unwrap().read_step(41, &mut expected).unwrap();
+        assert_eq!(frame.positions(), expected.positions());
+
+        let error = index.read_step(100, &mut frame).unwrap_err();
+        assert!(error.is_eof());
+
+        let sidecar = "test-index-tmp.idx";
+        index.save(sidecar).unwrap();
+        assert_eq!(TrajectoryIndex::load(&path, sidecar).unwrap(), index);
+
+        let other = root.join("data").join("topology.xyz");
+        assert!(TrajectoryIndex::load(other, sidecar).is_err());
+        std::fs::remove_file(sidecar).unwrap();
+    }
+
+    #[test]
+    fn scan() {
+        let xyz = "2\n\nH 0 0 0\nH 0 0 1\n1\ncomment\nHe 0 0 0\n\n";
+        let offsets = scan_xyz(&mut xyz.as_bytes(), Path::new("test.xyz")).unwrap().unwrap();
+        assert_eq!(offsets, [0, 19, 38]);
+
+        let truncated = "2\n\nH 0 0 0\n";
+        let error = scan_xyz(&mut truncated.as_bytes(), Path::new("test.xyz")).unwrap().unwrap_err();
+        assert_eq!(error.file_error_kind(), Some(crate::FileErrorKind::TruncatedFile));
+
+        let blank = "2\n\nH 0 0 0\n\nH 0 0 1\n";
+        let error = scan_xyz(&mut blank.as_bytes(), Path::new("test.xyz")).unwrap().unwrap_err();
+        assert_eq!(error.status, Status::FormatError);
+        assert_eq!(error.message, "unexpected empty line in 'test.xyz' in the atoms of step 0");
+
+        let blank = "1\n\nH 0 0 0\n\n1\n\nH 0 0 0\n";
+        let error = scan_xyz(&mut blank.as_bytes(), Path::new("test.xyz")).unwrap().unwrap_err();
+        assert_eq!(error.message, "unexpected empty line in 'test.xyz' before step 1");
+
+        let lammps = "ITEM: TIMESTEP\n0\nITEM: NUMBER OF ATOMS\n0\nITEM: TIMESTEP\n10\n";
+        let offsets = scan_lammps(&mut lammps.as_bytes()).unwrap();
+        assert_eq!(offsets, [0, 41, 59]);
+
+        assert!(TrajectoryIndex::with_format("file.pdb", "PDB").is_err());
+        assert!(TrajectoryIndex::build("file.pdb").is_err());
+    }
+}
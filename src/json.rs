@@ -0,0 +1,366 @@
+// Chemfiles, a modern library for chemistry file reading and writing
+// Copyright (C) 2015-2018 Guillaume Fraux -- BSD licensed
+
+//! Conversion of topologies to and from a stable JSON representation, see
+//! [`Topology::to_json`] for the schema.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{Error, Status};
+use crate::{Atom, BondOrder, Property, Residue, Topology};
+
+/// Name of the JSON schema for topologies
+const SCHEMA: &str = "chemfiles-topology";
+/// Current version of the JSON schema for topologies
+const VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TopologyJson {
+    schema: String,
+    version: u32,
+    atoms: Vec<AtomJson>,
+    #[serde(default)]
+    bonds: Vec<BondJson>,
+    #[serde(default)]
+    residues: Vec<ResidueJson>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AtomJson {
+    name: String,
+    #[serde(rename = "type")]
+    atomic_type: String,
+    mass: f64,
+    charge: f64,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    properties: BTreeMap<String, PropertyJson>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BondJson {
+    atoms: [usize; 2],
+    #[serde(default = "unknown_order")]
+    order: String,
+}
+
+fn unknown_order() -> String {
+    return "unknown".into();
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ResidueJson {
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<i64>,
+    atoms: Vec<usize>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    properties: BTreeMap<String, PropertyJson>,
+}
+
+/// Properties are stored with the corresponding JSON type: booleans, numbers,
+/// strings, and arrays of three numbers for `Vector3D`.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum PropertyJson {
+    Bool(bool),
+    Double(f64),
+    String(String),
+    Vector3D([f64; 3]),
+}
+
+impl From<Property> for PropertyJson {
+    fn from(property: Property) -> PropertyJson {
+        match property {
+            Property::Bool(value) => PropertyJson::Bool(value),
+            Property::Double(value) => PropertyJson::Double(value),
+            Property::String(value) => PropertyJson::String(value),
+            Property::Vector3D(value) => PropertyJson::Vector3D(value),
+        }
+    }
+}
+
+impl From<PropertyJson> for Property {
+    fn from(property: PropertyJson) -> Property {
+        match property {
+            PropertyJson::Bool(value) => Property::Bool(value),
+            PropertyJson::Double(value) => Property::Double(value),
+            PropertyJson::String(value) => Property::String(value),
+            PropertyJson::Vector3D(value) => Property::Vector3D(value),
+        }
+    }
+}
+
+/// Get the name used for `order` in the JSON schema
+fn order_name(order: BondOrder) -> &'static str {
+    match order {
+        BondOrder::Unknown => "unknown",
+        BondOrder::Single => "single",
+        BondOrder::Double => "double",
+        BondOrder::Triple => "triple",
+        BondOrder::Quadruple => "quadruple",
+        BondOrder::Quintuplet => "quintuplet",
+        BondOrder::Amide => "amide",
+        BondOrder::Aromatic => "aromatic",
+    }
+}
+
+fn invalid_json(message: impl std::fmt::Display) -> Error {
+    Error {
+        status: Status::FormatError,
+        message: format!("invalid topology JSON: {message}"),
+    }
+}
+
+impl Topology {
+    /// Convert this topology to JSON, using a stable schema which does not
+    /// depend on the chemfiles version. This is intended to exchange
+    /// topologies with tools which do not use chemfiles.
+    ///
+    /// This function is only available with the `serde` feature.
+    ///
+    /// The JSON document is an object with the following fields:
+    ///
+    /// - `"schema"`: always `"chemfiles-topology"`;
+    /// - `"version"`: version of the schema, currently `1`;
+    /// - `"atoms"`: array of atoms, each one being an object with `"name"`,
+    ///   `"type"` (strings), `"mass"` (in Daltons), `"charge"` (in elementary
+    ///   charges), and an optional `"properties"` object;
+    /// - `"bonds"`: array of bonds, each one being an object with `"atoms"`
+    ///   (array of two atom indexes), and `"order"`, one of `"unknown"`,
+    ///   `"single"`, `"double"`, `"triple"`, `"quadruple"`, `"quintuplet"`,
+    ///   `"amide"` or `"aromatic"`;
+    /// - `"residues"`: array of residues, each one being an object with
+    ///   `"name"` (string), an optional `"id"` (integer), `"atoms"` (array of
+    ///   atom indexes) and an optional `"properties"` object.
+    ///
+    /// Properties are stored as JSON booleans, numbers, strings, or arrays of
+    /// three numbers for `Property::Vector3D`.
+    ///
+    /// ```json
+    /// {
+    ///   "schema": "chemfiles-topology",
+    ///   "version": 1,
+    ///   "atoms": [
+    ///     {"name": "O", "type": "O", "mass": 15.999, "charge": -0.834},
+    ///     {"name": "H1", "type": "H", "mass": 1.008, "charge": 0.417},
+    ///     {"name": "H2", "type": "H", "mass": 1.008, "charge": 0.417}
+    ///   ],
+    ///   "bonds": [
+    ///     {"atoms": [0, 1], "order": "single"},
+    ///     {"atoms": [0, 2], "order": "single"}
+    ///   ],
+    ///   "residues": [
+    ///     {"name": "HOH", "id": 1, "atoms": [0, 1, 2], "properties": {"chainid": "A"}}
+    ///   ]
+    /// }
+    /// ```
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Atom, Topology};
+    /// let mut topology = Topology::new();
+    /// topology.add_atom(&Atom::new("O"));
+    /// topology.add_atom(&Atom::new("H"));
+    /// topology.add_bond(0, 1);
+    ///
+    /// let json = topology.to_json();
+    /// let copy = Topology::from_json(&json).unwrap();
+    /// assert_eq!(copy.size(), 2);
+    /// assert_eq!(copy.bonds(), vec![[0, 1]]);
+    /// ```
+    pub fn to_json(&self) -> String {
+        let atoms = (0..self.size())
+            .map(|i| {
+                let atom = self.atom(i);
+                AtomJson {
+                    name: atom.name(),
+                    atomic_type: atom.atomic_type(),
+                    mass: atom.mass(),
+                    charge: atom.charge(),
+                    properties: atom.properties().map(|(name, value)| (name, value.into())).collect(),
+                }
+            })
+            .collect();
+
+        let bonds = self
+            .bonds()
+            .into_iter()
+            .zip(self.bond_orders())
+            .map(|(atoms, order)| BondJson {
+                atoms,
+                order: order_name(order).into(),
+            })
+            .collect();
+
+        #[allow(clippy::cast_possible_truncation)]
+        let residues = (0..self.residues_count() as usize)
+            .filter_map(|i| self.residue(i))
+            .map(|residue| ResidueJson {
+                name: residue.name(),
+                id: residue.id(),
+                atoms: residue.atoms(),
+                properties: residue.properties().map(|(name, value)| (name, value.into())).collect(),
+            })
+            .collect();
+
+        let json = TopologyJson {
+            schema: SCHEMA.into(),
+            version: VERSION,
+            atoms,
+            bonds,
+            residues,
+        };
+        return serde_json::to_string_pretty(&json).expect("failed to serialize topology to JSON");
+    }
+
+    /// Create a new topology from a JSON document created by
+    /// [`Topology::to_json`], or following the same schema.
+    ///
+    /// This function is only available with the `serde` feature.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the JSON is invalid, does not follow the schema,
+    /// or contains bonds or residues referring to atoms which do not exist.
+    pub fn from_json(json: &str) -> Result<Topology, Error> {
+        let json: TopologyJson = serde_json::from_str(json).map_err(invalid_json)?;
+        if json.schema != SCHEMA {
+            return Err(invalid_json(format!(
+                "expected schema '{SCHEMA}', got '{}'",
+                json.schema
+            )));
+        }
+        if json.version != VERSION {
+            return Err(invalid_json(format!("unsupported schema version {}", json.version)));
+        }
+
+        let mut topology = Topology::new();
+        for atom_json in json.atoms {
+            let mut atom = Atom::new(&*atom_json.name);
+            atom.set_atomic_type(&*atom_json.atomic_type);
+            atom.set_mass(atom_json.mass);
+            atom.set_charge(atom_json.charge);
+            for (name, value) in atom_json.properties {
+                atom.set(&name, Property::from(value));
+            }
+            topology.add_atom(&atom);
+        }
+
+        let size = topology.size();
+        let check_atom = |index: usize| {
+            if index < size {
+                Ok(())
+            } else {
+                Err(invalid_json(format!(
+                    "atom index {index} is out of bounds ({size} atoms)"
+                )))
+            }
+        };
+
+        for bond in json.bonds {
+            let [i, j] = bond.atoms;
+            check_atom(i)?;
+            check_atom(j)?;
+            let order = bond
+                .order
+                .parse::<BondOrder>()
+                .map_err(|error| invalid_json(error.message))?;
+            topology.add_bond_with_order(i, j, order);
+        }
+
+        for residue_json in json.residues {
+            let mut residue = match residue_json.id {
+                Some(id) => Residue::with_id(&*residue_json.name, id),
+                None => Residue::new(&*residue_json.name),
+            };
+            for atom in residue_json.atoms {
+                check_atom(atom)?;
+                residue.add_atom(atom);
+            }
+            for (name, value) in residue_json.properties {
+                residue.set(&name, Property::from(value));
+            }
+            topology
+                .add_residue(&residue)
+                .map_err(|error| invalid_json(error.message))?;
+        }
+
+        return Ok(topology);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let mut topology = Topology::new();
+        let mut atom = Atom::new("O");
+        atom.set_charge(-0.834);
+        atom.set("is_heavy", true);
+        atom.set("dipole", [0.0, 1.0, 2.0]);
+        topology.add_atom(&atom);
+        topology.add_atom(&Atom::new("H1"));
+        topology.add_atom(&Atom::new("H2"));
+        topology.add_bond_with_order(0, 1, BondOrder::Single);
+        topology.add_bond_with_order(0, 2, BondOrder::Aromatic);
+
+        let mut residue = Residue::with_id("HOH", 3);
+        residue.add_atom(0);
+        residue.add_atom(1);
+        residue.add_atom(2);
+        residue.set("chainid", "A");
+        topology.add_residue(&residue).unwrap();
+
+        let copy = Topology::from_json(&topology.to_json()).unwrap();
+        assert_eq!(copy.size(), 3);
+        assert_eq!(copy.atom(0).charge(), -0.834);
+        assert_eq!(copy.atom(0).get("is_heavy"), Some(Property::Bool(true)));
+        assert_eq!(copy.atom(0).get("dipole"), Some(Property::Vector3D([0.0, 1.0, 2.0])));
+        assert_eq!(copy.atom(2).name(), "H2");
+        assert_eq!(copy.bonds(), topology.bonds());
+        assert_eq!(copy.bond_orders(), [BondOrder::Single, BondOrder::Aromatic]);
+
+        let residue = copy.residue(0).unwrap();
+        assert_eq!(residue.name(), "HOH");
+        assert_eq!(residue.id(), Some(3));
+        assert_eq!(residue.atoms(), [0, 1, 2]);
+        assert_eq!(residue.get("chainid"), Some(Property::String("A".into())));
+    }
+
+    #[test]
+    fn errors() {
+        let error = Topology::from_json("{").unwrap_err();
+        assert_eq!(error.status, Status::FormatError);
+
+        let json = r#"{"schema": "foo", "version": 1, "atoms": []}"#;
+        let error = Topology::from_json(json).unwrap_err();
+        assert_eq!(
+            error.message,
+            "invalid topology JSON: expected schema 'chemfiles-topology', got 'foo'"
+        );
+
+        let json = r#"{"schema": "chemfiles-topology", "version": 2, "atoms": []}"#;
+        let error = Topology::from_json(json).unwrap_err();
+        assert_eq!(error.message, "invalid topology JSON: unsupported schema version 2");
+
+        let json = r#"{
+            "schema": "chemfiles-topology",
+            "version": 1,
+            "atoms": [{"name": "A", "type": "A", "mass": 1.0, "charge": 0.0}],
+            "bonds": [{"atoms": [0, 1]}]
+        }"#;
+        let error = Topology::from_json(json).unwrap_err();
+        assert_eq!(
+            error.message,
+            "invalid topology JSON: atom index 1 is out of bounds (1 atoms)"
+        );
+    }
+}
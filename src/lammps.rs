@@ -0,0 +1,215 @@
+// Chemfiles, a modern library for chemistry file reading and writing
+// Copyright (C) 2015-2018 Guillaume Fraux -- BSD licensed
+
+//! Helpers to work with LAMMPS data files.
+//!
+//! When reading LAMMPS data files, chemfiles stores the numeric atom type of
+//! each atom as the atomic type (unless the file contains type names), and the
+//! molecule id of each atom as the id of a residue containing the atom. When
+//! writing data files, the atom types are created from the atomic types, and
+//! the molecule ids from the residues ids. The functions in this module give
+//! typed access to this data, and allow to check that a frame can be written
+//! to a data file without losing information.
+
+use crate::errors::{Error, Status};
+use crate::math::Periodicity;
+use crate::{Atom, CellShape, Frame};
+
+/// Get the numeric LAMMPS atom type of an `atom`, if the atomic type is a
+/// positive integer.
+///
+/// # Example
+/// ```
+/// # use chemfiles::Atom;
+/// # use chemfiles::lammps;
+/// let mut atom = Atom::new("C");
+/// assert_eq!(lammps::atom_type_id(&atom), None);
+///
+/// atom.set_atomic_type("3");
+/// assert_eq!(lammps::atom_type_id(&atom), Some(3));
+/// ```
+pub fn atom_type_id(atom: &Atom) -> Option<u64> {
+    return atom.atomic_type().parse().ok().filter(|&id| id > 0);
+}
+
+/// Get the LAMMPS molecule id of the atom at `index` in the `frame`, *i.e.*
+/// the id of the residue containing this atom.
+///
+/// # Example
+/// ```
+/// # use chemfiles::{Atom, Frame, Residue};
+/// # use chemfiles::lammps;
+/// let mut frame = Frame::new();
+/// frame.add_atom(&Atom::new("O"), [0.0, 0.0, 0.0], None);
+/// frame.add_atom(&Atom::new("Na"), [0.0, 0.0, 0.0], None);
+///
+/// let mut residue = Residue::with_id("water", 12);
+/// residue.add_atom(0);
+/// frame.add_residue(&residue).unwrap();
+///
+/// assert_eq!(lammps::molecule_id(&frame, 0), Some(12));
+/// assert_eq!(lammps::molecule_id(&frame, 1), None);
+/// ```
+pub fn molecule_id(frame: &Frame, index: usize) -> Option<i64> {
+    return frame.residue_for_atom(index).and_then(|residue| residue.id());
+}
+
+/// Get the LAMMPS image flags of all the atoms in the `frame`, *i.e.* the
+/// number of unit cells between each atom and the primary unit cell.
+///
+/// Chemfiles stores unwrapped positions, so the image flags are computed from
+/// the positions and the unit cell. All image flags are zero if the unit cell
+/// is infinite.
+///
+/// # Errors
+///
+/// This function fails if the unit cell is degenerate, *i.e.* if its matrix
+/// can not be inverted.
+///
+/// # Example
+/// ```
+/// # use chemfiles::{Atom, Frame, UnitCell};
+/// # use chemfiles::lammps;
+/// let mut frame = Frame::new();
+/// frame.set_cell(&UnitCell::new([10.0, 10.0, 10.0]));
+/// frame.add_atom(&Atom::new("O"), [5.0, 15.0, -2.0], None);
+///
+/// assert_eq!(lammps::image_flags(&frame).unwrap(), vec![[0, 1, -1]]);
+/// ```
+#[allow(clippy::cast_possible_truncation)]
+pub fn image_flags(frame: &Frame) -> Result<Vec<[i32; 3]>, Error> {
+    let periodicity = Periodicity::new(&frame.cell())?;
+    if periodicity.is_infinite() {
+        return Ok(vec![[0; 3]; frame.size()]);
+    }
+
+    return Ok(frame
+        .positions()
+        .iter()
+        .map(|&position| periodicity.fractional(position).map(|f| f.floor() as i32))
+        .collect());
+}
+
+/// Check that the `frame` can be written to a LAMMPS data file without
+/// losing information.
+///
+/// # Errors
+///
+/// This function returns an error describing the first problem found if the
+/// unit cell is infinite (LAMMPS requires a simulation box), if any atom has a
+/// negative mass or a zero mass without being flagged as a virtual site (see
+/// [`Atom::set_virtual_site`](crate::Atom::set_virtual_site)), or if any atom
+/// is in a residue without id, or with a negative id (molecule ids must be
+/// positive integers).
+///
+/// # Example
+/// ```
+/// # use chemfiles::{Atom, Frame, UnitCell};
+/// # use chemfiles::lammps;
+/// let mut frame = Frame::new();
+/// frame.add_atom(&Atom::new("O"), [0.0, 0.0, 0.0], None);
+/// assert!(lammps::validate_data_file(&frame).is_err());
+///
+/// frame.set_cell(&UnitCell::new([10.0, 10.0, 10.0]));
+/// assert!(lammps::validate_data_file(&frame).is_ok());
+/// ```
+#[allow(clippy::cast_possible_truncation)]
+pub fn validate_data_file(frame: &Frame) -> Result<(), Error> {
+    if frame.cell().shape() == CellShape::Infinite {
+        return Err(Error {
+            status: Status::InvalidArgument,
+            message: "LAMMPS data files require a unit cell, but this frame has an infinite cell".into(),
+        });
+    }
+
+    for (i, atom) in frame.iter_atoms().enumerate() {
+        let mass = atom.mass();
+        if mass < 0.0 || (mass == 0.0 && !atom.has_virtual_site_flag()) {
+            return Err(Error {
+                status: Status::InvalidArgument,
+                message: format!(
+                    "atom {i} ('{}') has an invalid mass ({mass}), flag it as a virtual site if it is massless",
+                    atom.name()
+                ),
+            });
+        }
+    }
+
+    for i in 0..frame.residues_count() {
+        let residue = frame.residue(i as usize).expect("missing residue");
+        match residue.id() {
+            Some(id) if id >= 0 => {}
+            Some(id) => {
+                return Err(Error {
+                    status: Status::InvalidArgument,
+                    message: format!(
+                        "residue '{}' has a negative id ({id}), which is not a valid LAMMPS molecule id",
+                        residue.name()
+                    ),
+                })
+            }
+            None => {
+                return Err(Error {
+                    status: Status::InvalidArgument,
+                    message: format!(
+                        "residue '{}' has no id, which is required for LAMMPS molecule ids",
+                        residue.name()
+                    ),
+                })
+            }
+        }
+    }
+
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Residue, UnitCell};
+
+    #[test]
+    fn validate() {
+        let mut frame = Frame::new();
+        frame.set_cell(&UnitCell::new([10.0, 10.0, 10.0]));
+        frame.add_atom(&Atom::new("O"), [0.0, 0.0, 0.0], None);
+
+        let mut residue = Residue::new("water");
+        residue.add_atom(0);
+        frame.add_residue(&residue).unwrap();
+        let error = validate_data_file(&frame).unwrap_err();
+        assert_eq!(error.message, "residue 'water' has no id, which is required for LAMMPS molecule ids");
+
+        let mut frame = Frame::new();
+        frame.set_cell(&UnitCell::new([10.0, 10.0, 10.0]));
+        frame.add_atom(&Atom::new("MW"), [0.0, 0.0, 0.0], None);
+        let error = validate_data_file(&frame).unwrap_err();
+        assert_eq!(
+            error.message,
+            "atom 0 ('MW') has an invalid mass (0), flag it as a virtual site if it is massless"
+        );
+        frame.atom_mut(0).set_virtual_site(true);
+        assert!(validate_data_file(&frame).is_ok());
+
+        let mut frame = Frame::new();
+        frame.set_cell(&UnitCell::new([10.0, 10.0, 10.0]));
+        frame.add_residue(&Residue::with_id("foo", -3)).unwrap();
+        let error = validate_data_file(&frame).unwrap_err();
+        assert_eq!(
+            error.message,
+            "residue 'foo' has a negative id (-3), which is not a valid LAMMPS molecule id"
+        );
+    }
+
+    #[test]
+    fn images() {
+        let mut frame = Frame::new();
+        frame.set_cell(&UnitCell::triclinic([10.0, 10.0, 10.0], [90.0, 90.0, 120.0]));
+        frame.add_atom(&Atom::new("O"), [1.0, 1.0, 1.0], None);
+        frame.add_atom(&Atom::new("O"), [25.0, 1.0, 31.0], None);
+        assert_eq!(image_flags(&frame).unwrap(), vec![[0, 0, 0], [2, 0, 3]]);
+
+        frame.set_cell(&UnitCell::infinite());
+        assert_eq!(image_flags(&frame).unwrap(), vec![[0, 0, 0], [0, 0, 0]]);
+    }
+}
@@ -16,6 +16,16 @@
 //! - The list of [supported formats][formats];
 //! - The documentation for the [selection language][selections];
 //!
+//! ## Borrowing and the C API
+//!
+//! Many types in this crate are wrappers around pointers in the C API. Types
+//! like [`AtomRef`], [`AtomMut`], [`UnitCellRef`], [`TopologyRef`] or the
+//! slices returned by [`Frame::positions`] point inside another object, and
+//! borrow this object with the usual Rust rules: any number of shared
+//! borrows, or a single mutable borrow. In particular, it is not possible to
+//! modify a frame (and potentially invalidate these pointers) while an atom or
+//! the positions are borrowed.
+//!
 //! [cxx_doc]: https://chemfiles.org/chemfiles
 //! [overview]: https://chemfiles.org/chemfiles/latest/overview.html
 //! [formats]: https://chemfiles.org/chemfiles/latest/formats.html
@@ -34,13 +44,18 @@
 #![doc(test(attr(deny(warnings))))]
 #![doc(test(attr(allow(unused_variables))))]
 
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+
 use chemfiles_sys as ffi;
 
 mod strings;
+mod math;
+mod templates;
 
 mod errors;
-pub use self::errors::set_warning_callback;
-pub use self::errors::{Error, Status};
+pub use self::errors::{set_warning_callback, take_callback_panics, with_warning_capture, CallbackPanic};
+pub use self::errors::{Error, FileErrorKind, Status};
 
 mod atom;
 pub use self::atom::Atom;
@@ -62,22 +77,101 @@ pub use self::topology::BondOrder;
 pub use self::topology::Topology;
 pub use self::topology::TopologyRef;
 
+#[cfg(feature = "serde")]
+mod json;
+
 mod frame;
-pub use self::frame::Frame;
+pub use self::frame::{AtomView, ForcesMut, Frame, FrameIter};
+#[cfg(feature = "rayon")]
+pub use self::frame::ParAtomView;
+
+mod diff;
+pub use self::diff::{DiffOptions, Difference, FrameDiff};
 
 mod trajectory;
+pub use self::trajectory::BondOutput;
+pub use self::trajectory::ErrorPolicy;
+pub use self::trajectory::FrameMetadata;
 pub use self::trajectory::MemoryTrajectoryReader;
 pub use self::trajectory::Trajectory;
 
+mod bookmarks;
+pub use self::bookmarks::Bookmarks;
+
+mod convert;
+pub use self::convert::{ConvertJob, ConvertReport};
+
+mod chunked;
+pub use self::chunked::ChunkedWriter;
+
+mod index;
+pub use self::index::TrajectoryIndex;
+
+mod filter;
+pub use self::filter::FilteredTrajectory;
+
+mod stream;
+pub use self::stream::{pump, FrameSink, FrameSource, Frames, PumpOptions};
+
+pub mod transform;
+
+pub mod analysis;
+#[cfg(feature = "voronoi")]
+mod voronoi;
+
+pub mod units;
+
+pub mod periodic_table;
+
+pub mod naming;
+
+pub mod lammps;
+
 mod selection;
-pub use self::selection::{Match, Selection};
+pub use self::selection::{Match, Selection, SelectionContext, SelectionInfo, SelectionSet, TopologyIndex};
+#[cfg(feature = "instrumentation")]
+pub use self::selection::SelectionStats;
+
+/// Create a [`Selection`] from a string literal, checking the selection
+/// syntax at compile time.
+///
+/// Invalid selections are reported as compile errors instead of runtime
+/// errors from [`Selection::new`]. This macro is only available with the
+/// `macros` feature.
+///
+/// ```
+/// # use chemfiles::{selection, Frame};
+/// let mut selection = selection!("pairs: name(#1) H and distance(#1, #2) < 2.5");
+/// assert_eq!(selection.size(), 2);
+///
+/// let matches = selection.evaluate(&Frame::new());
+/// assert!(matches.is_empty());
+/// ```
+///
+/// ```compile_fail
+/// # use chemfiles::selection;
+/// // `nmae` is not a selector
+/// let selection = selection!("nmae CA");
+/// ```
+#[cfg(feature = "macros")]
+pub use chemfiles_macros::selection;
+
+mod charges;
+pub use self::charges::ChargeTable;
+
+mod symmetry;
+pub use self::symmetry::{SpaceGroup, SymmetryOperation};
 
 mod property;
 pub use self::property::PropertiesIter;
 pub use self::property::Property;
 
+#[cfg(feature = "test-utils")]
+pub mod testing;
+
 mod misc;
-pub use self::misc::{formats_list, guess_format, FormatMetadata};
+pub use self::misc::{atom_type_info, AtomTypeInfo};
+pub use self::misc::{describe, formats_list, guess_format, FileDescription, FormatMetadata, LossWarning};
 
 /// Get the version of the chemfiles library.
 ///
@@ -90,6 +184,10 @@ pub fn version() -> String {
     unsafe { strings::from_c(ffi::chfl_version()) }
 }
 
+/// Paths of the configuration files added with [`add_configuration`], in the
+/// order they were added
+static CONFIGURATION_PATHS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
 /// Read configuration data from the file at `path`.
 ///
 /// By default, chemfiles reads configuration from any file named
@@ -97,6 +195,12 @@ pub fn version() -> String {
 /// function can be used to add data from another configuration file. Data from
 /// the new configuration file will overwrite any existing data.
 ///
+/// The configuration is global to the whole process: it applies to all atoms
+/// and trajectories created after this call, from any thread, and can not be
+/// removed afterwards. Libraries using chemfiles can use
+/// [`configuration_paths`] to check which files were added by the host
+/// application, and [`atom_type_info`] to check the resulting atomic types.
+///
 /// # Errors
 ///
 /// This function will fail if there is no file at `path`, or if the file is
@@ -112,7 +216,40 @@ where
     S: AsRef<str>,
 {
     let buffer = strings::to_c(path.as_ref());
-    unsafe { errors::check(ffi::chfl_add_configuration(buffer.as_ptr())) }
+    unsafe {
+        errors::check(ffi::chfl_add_configuration(buffer.as_ptr()))?;
+    }
+
+    // relative paths are resolved by chemfiles from the current directory
+    let path = Path::new(path.as_ref());
+    let path = std::env::current_dir().map_or_else(|_| path.to_owned(), |cwd| cwd.join(path));
+    CONFIGURATION_PATHS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(path);
+    return Ok(());
+}
+
+/// Get the paths of all the configuration files added with
+/// [`add_configuration`], in the order they were added (later files take
+/// precedence over earlier ones). Relative paths are made absolute using the
+/// current directory at the time of the call to [`add_configuration`].
+///
+/// The `.chemfiles.toml` files read automatically by chemfiles are not
+/// included in this list.
+///
+/// # Example
+/// ```no_run
+/// chemfiles::add_configuration("local-config.toml").unwrap();
+///
+/// let paths = chemfiles::configuration_paths();
+/// assert!(paths.last().unwrap().ends_with("local-config.toml"));
+/// ```
+pub fn configuration_paths() -> Vec<PathBuf> {
+    return CONFIGURATION_PATHS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
 }
 
 #[cfg(test)]
@@ -129,4 +266,18 @@ mod tests {
         assert!(!crate::version().is_empty());
         assert!(crate::version().starts_with("0.10"));
     }
+
+    #[test]
+    fn configuration() {
+        std::fs::write("test-configuration-paths.toml", "[types]\n").unwrap();
+        assert!(crate::add_configuration("not-there.toml").is_err());
+        crate::add_configuration("test-configuration-paths.toml").unwrap();
+        std::fs::remove_file("test-configuration-paths.toml").unwrap();
+
+        let paths = crate::configuration_paths();
+        assert!(paths.iter().all(|path| path.is_absolute()));
+        assert!(!paths.iter().any(|path| path.ends_with("not-there.toml")));
+        let expected = std::env::current_dir().unwrap().join("test-configuration-paths.toml");
+        assert!(paths.contains(&expected));
+    }
 }
@@ -0,0 +1,666 @@
+// Chemfiles, a modern library for chemistry file reading and writing
+// Copyright (C) 2015-2018 Guillaume Fraux -- BSD licensed
+
+//! Small linear algebra and periodic boundary conditions helpers, used by the
+//! analysis and transformation code written in Rust.
+
+use crate::{CellShape, Error, Status, UnitCell};
+
+pub(crate) type Vector3D = [f64; 3];
+pub(crate) type Matrix3 = [[f64; 3]; 3];
+
+pub(crate) fn add(a: Vector3D, b: Vector3D) -> Vector3D {
+    return [a[0] + b[0], a[1] + b[1], a[2] + b[2]];
+}
+
+pub(crate) fn sub(a: Vector3D, b: Vector3D) -> Vector3D {
+    return [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+}
+
+pub(crate) fn scale(a: Vector3D, factor: f64) -> Vector3D {
+    return [a[0] * factor, a[1] * factor, a[2] * factor];
+}
+
+pub(crate) fn dot(a: Vector3D, b: Vector3D) -> f64 {
+    return a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+}
+
+pub(crate) fn cross(a: Vector3D, b: Vector3D) -> Vector3D {
+    return [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ];
+}
+
+pub(crate) fn norm(a: Vector3D) -> f64 {
+    return dot(a, a).sqrt();
+}
+
+/// Compute the matrix-vector product `matrix * vector`
+pub(crate) fn matrix_vector(matrix: &Matrix3, vector: Vector3D) -> Vector3D {
+    return [
+        dot(matrix[0], vector),
+        dot(matrix[1], vector),
+        dot(matrix[2], vector),
+    ];
+}
+
+pub(crate) fn transpose(matrix: &Matrix3) -> Matrix3 {
+    let mut result = [[0.0; 3]; 3];
+    for i in 0..3 {
+        for j in 0..3 {
+            result[i][j] = matrix[j][i];
+        }
+    }
+    return result;
+}
+
+pub(crate) fn determinant(matrix: &Matrix3) -> f64 {
+    return dot(matrix[0], cross(matrix[1], matrix[2]));
+}
+
+/// Get the inverse of `matrix`, or `None` if the matrix is singular
+pub(crate) fn inverse(matrix: &Matrix3) -> Option<Matrix3> {
+    let det = determinant(matrix);
+    if det.abs() < f64::EPSILON {
+        return None;
+    }
+
+    // the rows of the inverse are the columns of the adjugate matrix
+    let columns = [
+        cross(matrix[1], matrix[2]),
+        cross(matrix[2], matrix[0]),
+        cross(matrix[0], matrix[1]),
+    ];
+    let inverse = transpose(&columns);
+    return Some([
+        scale(inverse[0], 1.0 / det),
+        scale(inverse[1], 1.0 / det),
+        scale(inverse[2], 1.0 / det),
+    ]);
+}
+
+/// Compute the matrix-matrix product `a * b`
+pub(crate) fn matrix_matrix(a: &Matrix3, b: &Matrix3) -> Matrix3 {
+    let b = transpose(b);
+    let mut result = [[0.0; 3]; 3];
+    for i in 0..3 {
+        for j in 0..3 {
+            result[i][j] = dot(a[i], b[j]);
+        }
+    }
+    return result;
+}
+
+/// Get the transformation matrix `T` giving the Niggli-reduced cell
+/// `matrix * T` of the cell with vectors in the columns of `matrix`.
+///
+/// This uses the algorithm of Křivý & Gruber (Acta Cryst. A32, 297, 1976),
+/// with the numerical tolerance and handedness fixes of Grosse-Kunstleve,
+/// Sauter & Adams (Acta Cryst. A60, 1, 2004). `T` only contains integers, and
+/// has a determinant of 1.
+#[allow(clippy::many_single_char_names, clippy::similar_names)]
+pub(crate) fn niggli_reduction(matrix: &Matrix3) -> Matrix3 {
+    let columns = transpose(matrix);
+    let mut a = dot(columns[0], columns[0]);
+    let mut b = dot(columns[1], columns[1]);
+    let mut c = dot(columns[2], columns[2]);
+    let mut xi = 2.0 * dot(columns[1], columns[2]);
+    let mut eta = 2.0 * dot(columns[0], columns[2]);
+    let mut zeta = 2.0 * dot(columns[0], columns[1]);
+
+    let epsilon = 1e-5 * determinant(matrix).abs().powf(2.0 / 3.0);
+    let lt = |x: f64, y: f64| x < y - epsilon;
+    let gt = |x: f64, y: f64| y < x - epsilon;
+    let eq = |x: f64, y: f64| !(lt(x, y) || gt(x, y));
+
+    let mut transform = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    let mut update = |change: Matrix3| transform = matrix_matrix(&transform, &change);
+
+    // the algorithm converges in a few iterations, this limit is only here to
+    // prevent infinite loops with degenerate cells
+    for _ in 0..1000 {
+        // step 1: sort A <= B
+        if gt(a, b) || (eq(a, b) && gt(xi.abs(), eta.abs())) {
+            update([[0.0, -1.0, 0.0], [-1.0, 0.0, 0.0], [0.0, 0.0, -1.0]]);
+            std::mem::swap(&mut a, &mut b);
+            std::mem::swap(&mut xi, &mut eta);
+        }
+
+        // step 2: sort B <= C
+        if gt(b, c) || (eq(b, c) && gt(eta.abs(), zeta.abs())) {
+            update([[-1.0, 0.0, 0.0], [0.0, 0.0, -1.0], [0.0, -1.0, 0.0]]);
+            std::mem::swap(&mut b, &mut c);
+            std::mem::swap(&mut eta, &mut zeta);
+            continue;
+        }
+
+        // steps 3 and 4: make xi, eta and zeta all positive or all negative
+        let mut positive = 0;
+        let mut zero = 0;
+        for value in [xi, eta, zeta] {
+            if lt(0.0, value) {
+                positive += 1;
+            } else if !lt(value, 0.0) {
+                zero += 1;
+            }
+        }
+
+        if positive == 3 || (zero == 0 && positive == 1) {
+            let sign = |value: f64| if value < 0.0 { -1.0 } else { 1.0 };
+            update([[sign(xi), 0.0, 0.0], [0.0, sign(eta), 0.0], [0.0, 0.0, sign(zeta)]]);
+            xi = xi.abs();
+            eta = eta.abs();
+            zeta = zeta.abs();
+        } else {
+            let mut signs = [1.0; 3];
+            let mut zero_index = None;
+            for (i, value) in [xi, eta, zeta].into_iter().enumerate() {
+                if gt(value, 0.0) {
+                    signs[i] = -1.0;
+                } else if !lt(value, 0.0) {
+                    zero_index = Some(i);
+                }
+            }
+            if signs[0] * signs[1] * signs[2] < 0.0 {
+                let i = zero_index.expect("one of xi, eta or zeta should be zero");
+                signs[i] = -1.0;
+            }
+            update([[signs[0], 0.0, 0.0], [0.0, signs[1], 0.0], [0.0, 0.0, signs[2]]]);
+            xi = -xi.abs();
+            eta = -eta.abs();
+            zeta = -zeta.abs();
+        }
+
+        // step 5
+        if gt(xi.abs(), b) || (eq(xi, b) && lt(2.0 * eta, zeta)) || (eq(xi, -b) && lt(zeta, 0.0)) {
+            let s = xi.signum();
+            update([[1.0, 0.0, 0.0], [0.0, 1.0, -s], [0.0, 0.0, 1.0]]);
+            c = b + c - xi * s;
+            eta -= zeta * s;
+            xi -= 2.0 * b * s;
+            continue;
+        }
+
+        // step 6
+        if gt(eta.abs(), a) || (eq(eta, a) && lt(2.0 * xi, zeta)) || (eq(eta, -a) && lt(zeta, 0.0)) {
+            let s = eta.signum();
+            update([[1.0, 0.0, -s], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]);
+            c = a + c - eta * s;
+            xi -= zeta * s;
+            eta -= 2.0 * a * s;
+            continue;
+        }
+
+        // step 7
+        if gt(zeta.abs(), a) || (eq(zeta, a) && lt(2.0 * xi, eta)) || (eq(zeta, -a) && lt(eta, 0.0)) {
+            let s = zeta.signum();
+            update([[1.0, -s, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]);
+            b = a + b - zeta * s;
+            xi -= eta * s;
+            zeta -= 2.0 * a * s;
+            continue;
+        }
+
+        // step 8
+        let sum = xi + eta + zeta + a + b;
+        if lt(sum, 0.0) || (eq(sum, 0.0) && gt(2.0 * (a + eta) + zeta, 0.0)) {
+            update([[1.0, 0.0, 1.0], [0.0, 1.0, 1.0], [0.0, 0.0, 1.0]]);
+            c += sum;
+            xi += 2.0 * b + zeta;
+            eta += 2.0 * a + zeta;
+            continue;
+        }
+
+        break;
+    }
+
+    return transform;
+}
+
+/// Compute the eigenvalues and eigenvectors of the real symmetric `matrix`
+/// using the cyclic Jacobi method.
+///
+/// The eigenvalues are returned in decreasing order, and `vectors[i]` is the
+/// normalized eigenvector corresponding to `values[i]`.
+#[allow(clippy::many_single_char_names, clippy::needless_range_loop)]
+pub(crate) fn symmetric_eigen(matrix: &[Vec<f64>]) -> (Vec<f64>, Vec<Vec<f64>>) {
+    let n = matrix.len();
+    let mut a = matrix.to_vec();
+    let mut v = vec![vec![0.0; n]; n];
+    for (i, row) in v.iter_mut().enumerate() {
+        row[i] = 1.0;
+    }
+
+    for _ in 0..100 {
+        let off_diagonal = (0..n)
+            .flat_map(|i| (0..n).filter(move |&j| j != i).map(move |j| (i, j)))
+            .map(|(i, j)| a[i][j] * a[i][j])
+            .sum::<f64>();
+        let scale = (0..n).map(|i| a[i][i] * a[i][i]).sum::<f64>();
+        if off_diagonal <= 1e-30 * scale || off_diagonal == 0.0 {
+            break;
+        }
+
+        for p in 0..n {
+            for q in (p + 1)..n {
+                if a[p][q] == 0.0 {
+                    continue;
+                }
+
+                let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let t = if theta == 0.0 { 1.0 } else { t };
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+
+                for k in 0..n {
+                    let akp = a[k][p];
+                    let akq = a[k][q];
+                    a[k][p] = c * akp - s * akq;
+                    a[k][q] = s * akp + c * akq;
+                }
+                for k in 0..n {
+                    let apk = a[p][k];
+                    let aqk = a[q][k];
+                    a[p][k] = c * apk - s * aqk;
+                    a[q][k] = s * apk + c * aqk;
+                }
+                for row in &mut v {
+                    let vkp = row[p];
+                    let vkq = row[q];
+                    row[p] = c * vkp - s * vkq;
+                    row[q] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+
+    let mut order = (0..n).collect::<Vec<_>>();
+    order.sort_by(|&i, &j| a[j][j].total_cmp(&a[i][i]));
+
+    let values = order.iter().map(|&i| a[i][i]).collect();
+    let vectors = order.iter().map(|&i| v.iter().map(|row| row[i]).collect()).collect();
+    return (values, vectors);
+}
+
+/// Get the rotation matrix minimizing the RMSD between `positions` and
+/// `reference`, both centered on the origin. This uses the quaternion-based
+/// method from Horn (1987).
+pub(crate) fn optimal_rotation(positions: &[Vector3D], reference: &[Vector3D]) -> Matrix3 {
+    debug_assert_eq!(positions.len(), reference.len());
+
+    // correlation matrix between the two sets of positions
+    let mut r = [[0.0; 3]; 3];
+    for (x, y) in positions.iter().zip(reference) {
+        for i in 0..3 {
+            for j in 0..3 {
+                r[i][j] += x[i] * y[j];
+            }
+        }
+    }
+
+    let f = vec![
+        vec![
+            r[0][0] + r[1][1] + r[2][2],
+            r[1][2] - r[2][1],
+            r[2][0] - r[0][2],
+            r[0][1] - r[1][0],
+        ],
+        vec![
+            r[1][2] - r[2][1],
+            r[0][0] - r[1][1] - r[2][2],
+            r[0][1] + r[1][0],
+            r[0][2] + r[2][0],
+        ],
+        vec![
+            r[2][0] - r[0][2],
+            r[0][1] + r[1][0],
+            -r[0][0] + r[1][1] - r[2][2],
+            r[1][2] + r[2][1],
+        ],
+        vec![
+            r[0][1] - r[1][0],
+            r[0][2] + r[2][0],
+            r[1][2] + r[2][1],
+            -r[0][0] - r[1][1] + r[2][2],
+        ],
+    ];
+
+    let (_, vectors) = symmetric_eigen(&f);
+    let q = &vectors[0];
+    let (q0, q1, q2, q3) = (q[0], q[1], q[2], q[3]);
+
+    return [
+        [
+            q0 * q0 + q1 * q1 - q2 * q2 - q3 * q3,
+            2.0 * (q1 * q2 - q0 * q3),
+            2.0 * (q1 * q3 + q0 * q2),
+        ],
+        [
+            2.0 * (q1 * q2 + q0 * q3),
+            q0 * q0 - q1 * q1 + q2 * q2 - q3 * q3,
+            2.0 * (q2 * q3 - q0 * q1),
+        ],
+        [
+            2.0 * (q1 * q3 - q0 * q2),
+            2.0 * (q2 * q3 + q0 * q1),
+            q0 * q0 - q1 * q1 - q2 * q2 + q3 * q3,
+        ],
+    ];
+}
+
+/// Periodic boundary conditions, extracted once from an `UnitCell` to avoid
+/// going through the C API for each vector.
+#[derive(Debug, Clone)]
+pub(crate) struct Periodicity {
+    shape: CellShape,
+    /// Cell matrix, with the cell vectors as columns
+    matrix: Matrix3,
+    /// Inverse of the cell matrix
+    inverse: Matrix3,
+}
+
+impl Periodicity {
+    /// Extract the periodic boundary conditions from `cell`.
+    ///
+    /// This fails if the cell is not infinite and its matrix can not be
+    /// inverted, for example when two of the cell vectors are collinear.
+    pub(crate) fn new(cell: &UnitCell) -> Result<Periodicity, Error> {
+        let shape = cell.shape();
+        let matrix = cell.matrix();
+        let inverse = if shape == CellShape::Infinite {
+            [[0.0; 3]; 3]
+        } else {
+            match inverse(&matrix) {
+                Some(inverse) => inverse,
+                None => {
+                    return Err(Error {
+                        status: Status::InvalidArgument,
+                        message: "the unit cell matrix is degenerate and can not be inverted".into(),
+                    });
+                }
+            }
+        };
+        return Ok(Periodicity { shape, matrix, inverse });
+    }
+
+    /// Create a periodicity for a triclinic cell with the given `matrix`,
+    /// with the cell vectors as columns. This does not go through the C API.
+    #[cfg(test)]
+    pub(crate) fn triclinic(matrix: Matrix3) -> Periodicity {
+        let inverse = inverse(&matrix).expect("the cell matrix should be invertible");
+        return Periodicity {
+            shape: CellShape::Triclinic,
+            matrix,
+            inverse,
+        };
+    }
+
+    /// Is this periodicity for an infinite cell?
+    pub(crate) fn is_infinite(&self) -> bool {
+        return self.shape == CellShape::Infinite;
+    }
+
+    /// Get the volume of the cell, or 0 for infinite cells
+    pub(crate) fn volume(&self) -> f64 {
+        return determinant(&self.matrix).abs();
+    }
+
+    /// Get the `i`-th cell vector, *i.e.* the `i`-th column of the cell
+    /// matrix
+    pub(crate) fn vector(&self, i: usize) -> Vector3D {
+        return [self.matrix[0][i], self.matrix[1][i], self.matrix[2][i]];
+    }
+
+    /// Get the distance between the two faces of the cell which are not
+    /// crossed by the `i`-th cell vector.
+    pub(crate) fn height(&self, i: usize) -> f64 {
+        let face = cross(self.vector((i + 1) % 3), self.vector((i + 2) % 3));
+        return self.volume() / norm(face);
+    }
+
+    /// Get the center of the cell, or the origin for infinite cells
+    pub(crate) fn center(&self) -> Vector3D {
+        if self.is_infinite() {
+            return [0.0; 3];
+        }
+        return matrix_vector(&self.matrix, [0.5, 0.5, 0.5]);
+    }
+
+    /// Get the fractional representation of a cartesian `vector`
+    pub(crate) fn fractional(&self, vector: Vector3D) -> Vector3D {
+        return matrix_vector(&self.inverse, vector);
+    }
+
+    /// Get the cartesian representation of a `fractional` vector
+    pub(crate) fn cartesian(&self, fractional: Vector3D) -> Vector3D {
+        return matrix_vector(&self.matrix, fractional);
+    }
+
+    /// Get the minimal image of the distance `vector`, using the same
+    /// convention as chemfiles `UnitCell::wrap`.
+    pub(crate) fn minimal_image(&self, vector: Vector3D) -> Vector3D {
+        return match self.shape {
+            CellShape::Infinite => vector,
+            CellShape::Orthorhombic => {
+                let a = self.matrix[0][0];
+                let b = self.matrix[1][1];
+                let c = self.matrix[2][2];
+                [
+                    vector[0] - (vector[0] / a).round() * a,
+                    vector[1] - (vector[1] / b).round() * b,
+                    vector[2] - (vector[2] / c).round() * c,
+                ]
+            }
+            CellShape::Triclinic => {
+                let f = self.fractional(vector);
+                self.cartesian([f[0] - f[0].round(), f[1] - f[1].round(), f[2] - f[2].round()])
+            }
+        };
+    }
+
+    /// Get the distance vector `b - a`, using the minimal image convention
+    pub(crate) fn distance_vector(&self, a: Vector3D, b: Vector3D) -> Vector3D {
+        return self.minimal_image(sub(b, a));
+    }
+
+    /// Get the distance between `a` and `b`, using the minimal image
+    /// convention
+    pub(crate) fn distance(&self, a: Vector3D, b: Vector3D) -> f64 {
+        return norm(self.distance_vector(a, b));
+    }
+
+    /// Get the angle formed by `a`, `b` and `c`, using the minimal image
+    /// convention. The result is in radians.
+    pub(crate) fn angle(&self, a: Vector3D, b: Vector3D, c: Vector3D) -> f64 {
+        let ba = self.distance_vector(b, a);
+        let bc = self.distance_vector(b, c);
+        let cos = dot(ba, bc) / (norm(ba) * norm(bc));
+        return cos.clamp(-1.0, 1.0).acos();
+    }
+
+    /// Get the dihedral angle formed by `first`, `second`, `third` and
+    /// `fourth`, using the minimal image convention. The result is in radians,
+    /// between -π and π.
+    pub(crate) fn dihedral(&self, first: Vector3D, second: Vector3D, third: Vector3D, fourth: Vector3D) -> f64 {
+        // same conventions as `chfl_frame_dihedral`
+        let r21 = self.distance_vector(second, first);
+        let r23 = self.distance_vector(second, third);
+        let r34 = self.distance_vector(third, fourth);
+
+        let b12 = cross(r23, r21);
+        let b23 = cross(r23, r34);
+        let y = dot(scale(r21, norm(r23)), b23);
+        let x = dot(b12, b23);
+        return f64::atan2(y, x);
+    }
+
+    /// Get the image of `position` inside the cell, *i.e.* with fractional
+    /// coordinates between 0 and 1.
+    pub(crate) fn inside(&self, position: Vector3D) -> Vector3D {
+        match self.shape {
+            CellShape::Infinite => position,
+            CellShape::Orthorhombic => {
+                let a = self.matrix[0][0];
+                let b = self.matrix[1][1];
+                let c = self.matrix[2][2];
+                [
+                    position[0] - (position[0] / a).floor() * a,
+                    position[1] - (position[1] / b).floor() * b,
+                    position[2] - (position[2] / c).floor() * c,
+                ]
+            }
+            CellShape::Triclinic => {
+                let f = self.fractional(position);
+                self.cartesian([f[0] - f[0].floor(), f[1] - f[1].floor(), f[2] - f[2].floor()])
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_vector3d_eq;
+    use approx::assert_ulps_eq;
+
+    #[test]
+    fn matrices() {
+        let matrix = [[2.0, 1.0, 0.0], [0.0, 3.0, 1.0], [0.0, 0.0, 4.0]];
+        assert_ulps_eq!(determinant(&matrix), 24.0);
+
+        let inverse = inverse(&matrix).unwrap();
+        let vector = [1.0, -2.0, 3.0];
+        let result = matrix_vector(&inverse, matrix_vector(&matrix, vector));
+        assert_vector3d_eq(&result, &vector, 1e-12);
+
+        assert!(super::inverse(&[[1.0, 2.0, 3.0], [2.0, 4.0, 6.0], [0.0, 0.0, 1.0]]).is_none());
+    }
+
+    /// Build a cell matrix (with vectors as columns) from the G6 parameters
+    /// `A, B, C, xi, eta, zeta` of the cell
+    fn matrix_from_g6([a, b, c, xi, eta, zeta]: [f64; 6]) -> Matrix3 {
+        let (a, b, c) = (a.sqrt(), b.sqrt(), c.sqrt());
+        let cos_alpha = xi / (2.0 * b * c);
+        let cos_beta = eta / (2.0 * a * c);
+        let cos_gamma = zeta / (2.0 * a * b);
+        let sin_gamma = (1.0 - cos_gamma * cos_gamma).sqrt();
+
+        let cx = c * cos_beta;
+        let cy = c * (cos_alpha - cos_beta * cos_gamma) / sin_gamma;
+        let cz = (c * c - cx * cx - cy * cy).sqrt();
+        return transpose(&[[a, 0.0, 0.0], [b * cos_gamma, b * sin_gamma, 0.0], [cx, cy, cz]]);
+    }
+
+    fn g6(matrix: &Matrix3) -> [f64; 6] {
+        let [a, b, c] = transpose(matrix);
+        return [dot(a, a), dot(b, b), dot(c, c), 2.0 * dot(b, c), 2.0 * dot(a, c), 2.0 * dot(a, b)];
+    }
+
+    #[test]
+    fn niggli() {
+        // example from Krivy & Gruber (1976)
+        let matrix = matrix_from_g6([9.0, 27.0, 4.0, -5.0, -4.0, -22.0]);
+        let transform = niggli_reduction(&matrix);
+        let reduced = g6(&matrix_matrix(&matrix, &transform));
+        let expected = [4.0, 9.0, 9.0, 9.0, 3.0, 4.0];
+        for (value, expected) in reduced.iter().zip(expected) {
+            assert_ulps_eq!(*value, expected, epsilon = 1e-9);
+        }
+        assert_ulps_eq!(determinant(&transform), 1.0);
+
+        // all negative xi, eta and zeta
+        let matrix = matrix_from_g6([4.0, 9.0, 9.0, -1.0, -2.0, -3.0]);
+        let transform = niggli_reduction(&matrix);
+        let reduced = g6(&matrix_matrix(&matrix, &transform));
+        for (value, expected) in reduced.iter().zip([4.0, 9.0, 9.0, -1.0, -2.0, -3.0]) {
+            assert_ulps_eq!(*value, expected, epsilon = 1e-9);
+        }
+        assert_ulps_eq!(determinant(&transform), 1.0);
+
+        // a reduced cell is left unchanged
+        let matrix = [[3.0, 0.0, 0.0], [0.0, 4.0, 0.0], [0.0, 0.0, 5.0]];
+        assert_eq!(niggli_reduction(&matrix), [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]);
+
+        // a sheared cell is brought back to the orthorhombic one
+        let matrix = [[3.0, 6.0, 0.0], [0.0, 4.0, 0.0], [0.0, 0.0, 5.0]];
+        let transform = niggli_reduction(&matrix);
+        let reduced = g6(&matrix_matrix(&matrix, &transform));
+        for (value, expected) in reduced.iter().zip([9.0, 16.0, 25.0, 0.0, 0.0, 0.0]) {
+            assert_ulps_eq!(*value, expected, epsilon = 1e-9);
+        }
+    }
+
+    #[test]
+    fn eigen() {
+        let matrix = vec![vec![2.0, 1.0, 0.0], vec![1.0, 2.0, 0.0], vec![0.0, 0.0, 5.0]];
+        let (values, vectors) = symmetric_eigen(&matrix);
+
+        assert_ulps_eq!(values[0], 5.0, epsilon = 1e-12);
+        assert_ulps_eq!(values[1], 3.0, epsilon = 1e-12);
+        assert_ulps_eq!(values[2], 1.0, epsilon = 1e-12);
+
+        for (value, vector) in values.iter().zip(&vectors) {
+            for i in 0..3 {
+                let product = (0..3).map(|j| matrix[i][j] * vector[j]).sum::<f64>();
+                assert_ulps_eq!(product, value * vector[i], epsilon = 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn rotation() {
+        let angle = 0.3_f64;
+        let rotation = [
+            [angle.cos(), -angle.sin(), 0.0],
+            [angle.sin(), angle.cos(), 0.0],
+            [0.0, 0.0, 1.0],
+        ];
+
+        let positions = vec![[1.0, 0.0, 0.0], [0.0, 2.0, 0.0], [-1.0, -2.0, 1.0], [0.0, 0.0, -1.0]];
+        let reference = positions.iter().map(|&p| matrix_vector(&rotation, p)).collect::<Vec<_>>();
+
+        let fitted = optimal_rotation(&positions, &reference);
+        for i in 0..3 {
+            assert_vector3d_eq(&fitted[i], &rotation[i], 1e-12);
+        }
+    }
+
+    #[test]
+    fn triclinic_periodicity() {
+        let periodicity = Periodicity::triclinic([[3.0, 1.0, 0.5], [0.0, 4.0, -1.0], [0.0, 0.0, 5.0]]);
+        assert_ulps_eq!(periodicity.volume(), 60.0);
+        assert_ulps_eq!(periodicity.height(2), 5.0);
+
+        let position = [1.0, -2.0, 12.0];
+        assert_vector3d_eq(&periodicity.cartesian(periodicity.fractional(position)), &position, 1e-12);
+
+        let inside = periodicity.fractional(periodicity.inside(position));
+        assert!(inside.iter().all(|&x| (0.0..1.0).contains(&x)));
+    }
+
+    #[test]
+    fn angles() {
+        let periodicity = Periodicity {
+            shape: CellShape::Orthorhombic,
+            matrix: [[10.0, 0.0, 0.0], [0.0, 10.0, 0.0], [0.0, 0.0, 10.0]],
+            inverse: [[0.1, 0.0, 0.0], [0.0, 0.1, 0.0], [0.0, 0.0, 0.1]],
+        };
+
+        let angle = periodicity.angle([1.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 1.0, 0.0]);
+        assert_ulps_eq!(angle, std::f64::consts::FRAC_PI_2);
+        // going through the periodic boundaries
+        let angle = periodicity.angle([9.0, 0.0, 0.0], [0.0, 0.0, 0.0], [1.0, 0.0, 0.0]);
+        assert_ulps_eq!(angle, std::f64::consts::PI);
+
+        let first = [1.0, 0.0, 0.0];
+        let second = [0.0, 0.0, 0.0];
+        let third = [0.0, 1.0, 0.0];
+        assert_ulps_eq!(periodicity.dihedral(first, second, third, [1.0, 1.0, 0.0]), 0.0);
+        assert_ulps_eq!(periodicity.dihedral(first, second, third, [-1.0, 1.0, 0.0]), std::f64::consts::PI);
+        assert_ulps_eq!(periodicity.dihedral(first, second, third, [0.0, 1.0, 1.0]), std::f64::consts::FRAC_PI_2);
+        assert_ulps_eq!(periodicity.dihedral(first, second, third, [0.0, 1.0, 9.0]), -std::f64::consts::FRAC_PI_2);
+    }
+}
@@ -68,6 +68,160 @@ impl FormatMetadata {
     }
 }
 
+/// Formats storing bond orders when writing files
+const BOND_ORDER_FORMATS: &[&str] = &["CML", "MOL2", "SDF"];
+/// Formats storing arbitrary frame properties when writing files
+const FRAME_PROPERTIES_FORMATS: &[&str] = &["CML", "SDF", "XYZ"];
+/// Formats storing arbitrary atomic properties when writing files
+const ATOM_PROPERTIES_FORMATS: &[&str] = &["CML", "XYZ"];
+/// Specific frame properties stored by formats which do not store arbitrary
+/// frame properties
+const FORMAT_FRAME_PROPERTIES: &[(&str, &[&str])] = &[
+    ("PDB", &["name", "pdb_idcode", "classification", "deposition_date"]),
+    ("GRO", &["name"]),
+    ("TRR", &["time", "trr_lambda"]),
+    ("XTC", &["time", "xtc_precision"]),
+];
+
+/// Data from a frame which would be lost when writing it with a given format,
+/// as reported by [`FormatMetadata::lossiness`].
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LossWarning {
+    /// The format can not be written at all
+    NotWritable,
+    /// The format does not store velocities
+    Velocities,
+    /// The format does not store the unit cell
+    UnitCell,
+    /// The format does not store atomic names or types
+    AtomNames,
+    /// The format does not store bonds
+    Bonds,
+    /// The format stores bonds, but not their bond orders
+    BondOrders,
+    /// The format does not store residues
+    Residues,
+    /// The format does not store these frame properties
+    FrameProperties(Vec<String>),
+    /// The format does not store these atomic properties
+    AtomProperties(Vec<String>),
+}
+
+impl std::fmt::Display for LossWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LossWarning::NotWritable => write!(f, "this format can not be written"),
+            LossWarning::Velocities => write!(f, "velocities will be lost"),
+            LossWarning::UnitCell => write!(f, "the unit cell will be lost"),
+            LossWarning::AtomNames => write!(f, "atomic names and types will be lost"),
+            LossWarning::Bonds => write!(f, "bonds will be lost"),
+            LossWarning::BondOrders => write!(f, "bond orders will be lost"),
+            LossWarning::Residues => write!(f, "residues will be lost"),
+            LossWarning::FrameProperties(names) => {
+                write!(f, "frame properties will be lost: {}", names.join(", "))
+            }
+            LossWarning::AtomProperties(names) => {
+                write!(f, "atomic properties will be lost: {}", names.join(", "))
+            }
+        }
+    }
+}
+
+impl FormatMetadata {
+    /// Check which data in `frame` would be lost when writing it with this
+    /// format, to warn users before the data silently disappears.
+    ///
+    /// This uses the capabilities from the format metadata (velocities, unit
+    /// cell, bonds, ...), and a list of formats known to store bond orders and
+    /// properties. Format-specific properties (such as the `"time"` of XTC
+    /// files) are not reported as lost.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Atom, Frame, LossWarning, UnitCell};
+    /// let mut frame = Frame::new();
+    /// frame.set_cell(&UnitCell::new([10.0, 10.0, 10.0]));
+    /// frame.add_velocities();
+    /// frame.add_atom(&Atom::new("O"), [0.0, 0.0, 0.0], [0.0, 0.0, 1.0]);
+    /// frame.set("energy", -42.0);
+    ///
+    /// let formats = chemfiles::formats_list();
+    /// let xyz = formats.iter().find(|format| format.name == "XYZ").unwrap();
+    /// assert_eq!(xyz.lossiness(&frame), [LossWarning::Velocities]);
+    ///
+    /// let pdb = formats.iter().find(|format| format.name == "PDB").unwrap();
+    /// assert_eq!(
+    ///     pdb.lossiness(&frame),
+    ///     [LossWarning::Velocities, LossWarning::FrameProperties(vec!["energy".into()])]
+    /// );
+    /// ```
+    pub fn lossiness(&self, frame: &crate::Frame) -> Vec<LossWarning> {
+        if !self.write {
+            return vec![LossWarning::NotWritable];
+        }
+
+        let mut warnings = Vec::new();
+        if frame.has_velocities() && !self.velocities {
+            warnings.push(LossWarning::Velocities);
+        }
+
+        if frame.cell().shape() != crate::CellShape::Infinite && !self.unit_cell {
+            warnings.push(LossWarning::UnitCell);
+        }
+
+        if frame.size() != 0 && !self.atoms {
+            warnings.push(LossWarning::AtomNames);
+        }
+
+        let topology = frame.topology();
+        if topology.bonds_count() != 0 {
+            if !self.bonds {
+                warnings.push(LossWarning::Bonds);
+            } else if !BOND_ORDER_FORMATS.contains(&self.name)
+                && topology
+                    .bond_orders()
+                    .iter()
+                    .any(|&order| order != crate::BondOrder::Unknown)
+            {
+                warnings.push(LossWarning::BondOrders);
+            }
+        }
+
+        if frame.residues_count() != 0 && !self.residues {
+            warnings.push(LossWarning::Residues);
+        }
+
+        if !FRAME_PROPERTIES_FORMATS.contains(&self.name) {
+            let stored = FORMAT_FRAME_PROPERTIES
+                .iter()
+                .find(|(format, _)| *format == self.name)
+                .map_or(&[][..], |&(_, names)| names);
+
+            let lost = frame
+                .properties_names()
+                .into_iter()
+                .filter(|name| !stored.contains(&name.as_str()))
+                .collect::<Vec<_>>();
+            if !lost.is_empty() {
+                warnings.push(LossWarning::FrameProperties(lost));
+            }
+        }
+
+        if !ATOM_PROPERTIES_FORMATS.contains(&self.name) {
+            let mut lost = std::collections::BTreeSet::new();
+            for i in 0..frame.size() {
+                lost.extend(frame.atom(i).properties_names());
+            }
+            if !lost.is_empty() {
+                warnings.push(LossWarning::AtomProperties(lost.into_iter().collect()));
+            }
+        }
+
+        return warnings;
+    }
+}
+
 /// Get the list of formats known by chemfiles, as well as all associated metadata.
 ///
 /// # Example
@@ -116,10 +270,6 @@ pub fn formats_list() -> Vec<FormatMetadata> {
 ///
 /// This function returns an error if the file format couldn't be guessed.
 ///
-/// # Panics
-///
-/// This function panics if the path can't be converted to a Unicode string.
-///
 /// # Examples
 /// ```
 /// let format = chemfiles::guess_format("trajectory.xyz.xz").unwrap();
@@ -135,8 +285,7 @@ pub fn guess_format<P>(path: P) -> Result<String, Error>
 where
     P: AsRef<Path>,
 {
-    let path = path.as_ref().to_str().expect("couldn't convert path to Unicode");
-    let path = crate::strings::to_c(path);
+    let path = crate::strings::path_to_c(path.as_ref())?;
     let mut buffer = vec![0; 128];
     unsafe {
         check(ffi::chfl_guess_format(
@@ -147,3 +296,230 @@ where
     }
     Ok(crate::strings::from_c(buffer.as_ptr()))
 }
+
+/// Summary of the content of a file, as returned by [`describe`].
+///
+/// With the `serde` feature, this type can be serialized, for example to build
+/// a catalogue of many trajectory files.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FileDescription {
+    /// Path to the file, with any invalid UTF-8 data replaced by `�`
+    pub path: String,
+    /// Format used to read the file, as returned by [`guess_format`]
+    pub format: String,
+    /// Number of steps in the file
+    pub steps: usize,
+    /// Number of atoms in the first frame
+    pub atoms: usize,
+    /// Lengths of the unit cell of the first frame, in Angstroms. This is
+    /// `[0.0, 0.0, 0.0]` for infinite cells.
+    pub cell_lengths: [f64; 3],
+    /// Angles of the unit cell of the first frame, in degrees
+    pub cell_angles: [f64; 3],
+    /// Does the first frame contains velocities?
+    pub has_velocities: bool,
+    /// Names of the properties of the first frame, in alphabetical order
+    pub properties: Vec<String>,
+}
+
+/// Get a short description of the file at `path`: the guessed format, number
+/// of steps, and the number of atoms, unit cell, velocities and properties of
+/// the first frame.
+///
+/// Only the first frame is read, so this is cheap enough to run over a large
+/// number of files. Files without any frame are described with zero atoms and
+/// an infinite unit cell.
+///
+/// # Errors
+///
+/// This function fails if the format of the file can not be guessed, or if the
+/// first frame can not be read.
+///
+/// # Example
+/// ```no_run
+/// let description = chemfiles::describe("water.xyz").unwrap();
+/// println!(
+///     "{} ({}): {} steps of {} atoms",
+///     description.path, description.format, description.steps, description.atoms
+/// );
+/// ```
+pub fn describe<P>(path: P) -> Result<FileDescription, Error>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let format = guess_format(path)?;
+    let mut trajectory = crate::Trajectory::open_with_format(path, 'r', format.as_str())?;
+
+    let mut description = FileDescription {
+        path: path.to_string_lossy().into_owned(),
+        format,
+        steps: trajectory.nsteps(),
+        atoms: 0,
+        cell_lengths: [0.0; 3],
+        cell_angles: [90.0; 3],
+        has_velocities: false,
+        properties: Vec::new(),
+    };
+
+    if let Some(frame) = trajectory.read_next()? {
+        let cell = frame.cell();
+        description.atoms = frame.size();
+        description.cell_lengths = cell.lengths();
+        description.cell_angles = cell.angles();
+        description.has_velocities = frame.has_velocities();
+        description.properties = frame.properties_names();
+    }
+
+    return Ok(description);
+}
+
+/// Properties that chemfiles uses for a given atomic type, as returned by
+/// [`atom_type_info`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AtomTypeInfo {
+    /// Atomic type used by chemfiles. This is different from the requested
+    /// type if the configuration renames it (in the `[types]` section).
+    pub atomic_type: String,
+    /// Full name of the type, if known
+    pub full_name: Option<String>,
+    /// Atomic number of the type, if it is an element
+    pub atomic_number: Option<u64>,
+    /// Default mass of atoms with this type, in atomic mass units
+    pub mass: f64,
+    /// Default charge of atoms with this type, in number of the electron
+    /// charge *e*
+    pub charge: f64,
+    /// Van der Waals radius of the type in Ångströms, if known
+    pub vdw_radius: Option<f64>,
+    /// Covalent radius of the type in Ångströms, if known
+    pub covalent_radius: Option<f64>,
+}
+
+/// Get the mass, charge, full name and radii that chemfiles uses for atoms
+/// with the given `type_name`.
+///
+/// This takes into account the configuration files loaded by chemfiles (see
+/// [`add_configuration`](crate::add_configuration)), and can be used to check
+/// that custom atomic types in `.chemfiles.toml` files are used as expected.
+/// Properties which are not defined for this type, either in the configuration
+/// or in the periodic table, are `None`.
+///
+/// # Example
+/// ```
+/// let info = chemfiles::atom_type_info("Zn");
+/// assert_eq!(info.full_name.as_deref(), Some("Zinc"));
+/// assert_eq!(info.atomic_number, Some(30));
+/// assert_eq!(info.mass, 65.38);
+///
+/// let info = chemfiles::atom_type_info("Xxx");
+/// assert_eq!(info.full_name, None);
+/// assert_eq!(info.mass, 0.0);
+/// ```
+pub fn atom_type_info(type_name: &str) -> AtomTypeInfo {
+    let atom = crate::Atom::new(type_name);
+    let full_name = atom.full_name();
+    let positive = |value: f64| if value > 0.0 { Some(value) } else { None };
+
+    return AtomTypeInfo {
+        atomic_type: atom.atomic_type(),
+        full_name: if full_name.is_empty() { None } else { Some(full_name) },
+        atomic_number: match atom.atomic_number() {
+            0 => None,
+            number => Some(number),
+        },
+        mass: atom.mass(),
+        charge: atom.charge(),
+        vdw_radius: positive(atom.vdw_radius()),
+        covalent_radius: positive(atom.covalent_radius()),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describe() {
+        let root = Path::new(file!()).parent().unwrap().join("..");
+        let path = root.join("data").join("water.xyz");
+        let description = super::describe(&path).unwrap();
+
+        assert_eq!(description.format, "XYZ");
+        assert_eq!(description.steps, 100);
+        assert_eq!(description.atoms, 297);
+        assert!(!description.has_velocities);
+
+        assert!(super::describe("file.unknown-extension").is_err());
+    }
+
+    #[test]
+    fn atom_type_info() {
+        let info = super::atom_type_info("He");
+        assert_eq!(info.atomic_type, "He");
+        assert_eq!(info.full_name.as_deref(), Some("Helium"));
+        assert_eq!(info.atomic_number, Some(2));
+        assert_eq!(info.charge, 0.0);
+        assert_eq!(info.vdw_radius, Some(1.4));
+        assert_eq!(info.covalent_radius, Some(0.32));
+
+        let config = "[types]\nCH3-alias = \"CH3-test\"\n\n[atoms.CH3-test]\nmass = 15.035\ncharge = -0.2\n\
+                      full_name = \"methyl\"\nvdw_radius = 2.0\ncovalent_radius = 1.2\n";
+        std::fs::write("test-atom-type-info.toml", config).unwrap();
+        crate::add_configuration("test-atom-type-info.toml").unwrap();
+        std::fs::remove_file("test-atom-type-info.toml").unwrap();
+
+        let info = super::atom_type_info("CH3-alias");
+        assert_eq!(
+            info,
+            AtomTypeInfo {
+                atomic_type: "CH3-test".into(),
+                full_name: Some("methyl".into()),
+                atomic_number: None,
+                mass: 15.035,
+                charge: -0.2,
+                vdw_radius: Some(2.0),
+                covalent_radius: Some(1.2),
+            }
+        );
+    }
+
+    #[test]
+    fn lossiness() {
+        let formats = formats_list();
+        let format = |name| formats.iter().find(|format| format.name == name).unwrap();
+
+        let mut frame = crate::Frame::new();
+        assert!(format("XYZ").lossiness(&frame).is_empty());
+
+        frame.add_atom(&crate::Atom::new("C"), [0.0, 0.0, 0.0], None);
+        frame.add_atom(&crate::Atom::new("C"), [1.0, 0.0, 0.0], None);
+        frame.add_bond_with_order(0, 1, crate::BondOrder::Double);
+        frame.atom_mut(0).set("is_chiral", false);
+        frame.set("time", 3.0);
+
+        assert_eq!(format("XYZ").lossiness(&frame), [LossWarning::Bonds]);
+        assert_eq!(
+            format("PDB").lossiness(&frame),
+            [
+                LossWarning::BondOrders,
+                LossWarning::FrameProperties(vec!["time".into()]),
+                LossWarning::AtomProperties(vec!["is_chiral".into()]),
+            ]
+        );
+        assert_eq!(
+            format("XTC").lossiness(&frame),
+            [
+                LossWarning::AtomNames,
+                LossWarning::Bonds,
+                LossWarning::AtomProperties(vec!["is_chiral".into()]),
+            ]
+        );
+        assert_eq!(
+            format("SDF").lossiness(&frame),
+            [LossWarning::AtomProperties(vec!["is_chiral".into()])]
+        );
+        assert_eq!(LossWarning::BondOrders.to_string(), "bond orders will be lost");
+    }
+}
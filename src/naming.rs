@@ -0,0 +1,547 @@
+// Chemfiles, a modern library for chemistry file reading and writing
+// Copyright (C) 2015-2018 Guillaume Fraux -- BSD licensed
+
+//! Conversion of atom names between naming schemes.
+//!
+//! Different force fields use different names for the same atoms, especially
+//! for hydrogens. A [`NameMap`] contains renaming rules, either for all atoms
+//! or only for atoms in residues with a given name, and can be applied to a
+//! topology with [`Topology::rename_atoms_with`].
+//!
+//! This module also contains the table of one-letter codes for amino acids,
+//! used by [`Topology::sequence`]; and functions to check and shorten names
+//! which are too long for a given format (see [`NameLimits`]).
+//!
+//! [`Topology::rename_atoms_with`]: crate::Topology::rename_atoms_with
+//! [`Topology::sequence`]: crate::Topology::sequence
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::{Residue, Topology};
+
+/// A set of rules to rename atoms, optionally depending on the name of the
+/// residue containing the atoms.
+///
+/// # Example
+/// ```
+/// # use chemfiles::naming::NameMap;
+/// let mut map = NameMap::new();
+/// map.insert("HN", "H");
+/// map.insert_for_residue("ILE", "CD", "CD1");
+///
+/// assert_eq!(map.get(Some("ALA"), "HN"), Some("H"));
+/// assert_eq!(map.get(Some("ILE"), "CD"), Some("CD1"));
+/// assert_eq!(map.get(Some("LYS"), "CD"), None);
+/// assert_eq!(map.get(None, "CD"), None);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NameMap {
+    global: HashMap<String, String>,
+    residues: HashMap<(String, String), String>,
+}
+
+impl NameMap {
+    /// Create a new empty `NameMap`
+    pub fn new() -> NameMap {
+        return NameMap::default();
+    }
+
+    /// Rename all atoms named `from` to `to`, unless a rule specific to their
+    /// residue exists.
+    pub fn insert(&mut self, from: &str, to: &str) {
+        let _ = self.global.insert(from.into(), to.into());
+    }
+
+    /// Rename atoms named `from` to `to` when they are in a residue named
+    /// `residue`.
+    pub fn insert_for_residue(&mut self, residue: &str, from: &str, to: &str) {
+        let _ = self.residues.insert((residue.into(), from.into()), to.into());
+    }
+
+    /// Get the new name of an atom named `name` in the residue named
+    /// `residue`, or `None` if this atom should not be renamed.
+    pub fn get(&self, residue: Option<&str>, name: &str) -> Option<&str> {
+        if let Some(residue) = residue {
+            if let Some(new_name) = self.residues.get(&(residue.into(), name.into())) {
+                return Some(new_name);
+            }
+        }
+        return self.global.get(name).map(String::as_str);
+    }
+
+    /// Get a `NameMap` doing the opposite renaming of this one.
+    ///
+    /// If multiple names are mapped to the same name, only one of them is
+    /// used in the inverse map.
+    #[must_use]
+    pub fn inverse(&self) -> NameMap {
+        let mut inverse = NameMap::new();
+        for (from, to) in &self.global {
+            inverse.insert(to, from);
+        }
+        for ((residue, from), to) in &self.residues {
+            inverse.insert_for_residue(residue, to, from);
+        }
+        return inverse;
+    }
+
+    /// Get a `NameMap` converting protein atom names from the CHARMM naming
+    /// scheme to the AMBER naming scheme.
+    ///
+    /// This renames backbone and terminal hydrogens and oxygens, the
+    /// methylene hydrogens (`HB1/HB2` to `HB2/HB3`, ...), isoleucine `CD` and
+    /// its hydrogens, and monoatomic ions.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::naming::NameMap;
+    /// let map = NameMap::charmm_to_amber();
+    /// assert_eq!(map.get(Some("SER"), "HN"), Some("H"));
+    /// assert_eq!(map.get(Some("SER"), "HB1"), Some("HB2"));
+    /// assert_eq!(map.get(Some("SER"), "HB2"), Some("HB3"));
+    /// assert_eq!(map.get(Some("ALA"), "HB1"), None);
+    /// ```
+    pub fn charmm_to_amber() -> NameMap {
+        let mut map = NameMap::new();
+        for (from, to) in CHARMM_TO_AMBER {
+            map.insert(from, to);
+        }
+
+        for (residues, hydrogens) in METHYLENE_HYDROGENS {
+            for residue in *residues {
+                for hydrogen in *hydrogens {
+                    map.insert_for_residue(residue, &format!("{hydrogen}1"), &format!("{hydrogen}2"));
+                    map.insert_for_residue(residue, &format!("{hydrogen}2"), &format!("{hydrogen}3"));
+                }
+            }
+        }
+
+        for (from, to) in CHARMM_TO_AMBER_ILE {
+            map.insert_for_residue("ILE", from, to);
+        }
+        return map;
+    }
+
+    /// Get a `NameMap` converting protein atom names from the AMBER naming
+    /// scheme to the CHARMM naming scheme. This is the inverse of
+    /// [`NameMap::charmm_to_amber`].
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::naming::NameMap;
+    /// let map = NameMap::amber_to_charmm();
+    /// assert_eq!(map.get(Some("SER"), "H"), Some("HN"));
+    /// assert_eq!(map.get(Some("SER"), "HB3"), Some("HB2"));
+    /// assert_eq!(map.get(Some("ILE"), "CD1"), Some("CD"));
+    /// ```
+    pub fn amber_to_charmm() -> NameMap {
+        return NameMap::charmm_to_amber().inverse();
+    }
+}
+
+/// Get the one-letter code of the amino acid with the given residue `name`,
+/// if any.
+///
+/// This knows about the 20 standard amino acids, selenocysteine and
+/// pyrrolysine, as well as the protonation and disulfide variants used by the
+/// AMBER and CHARMM force fields (`HID`, `HSD`, `CYX`, `ASH`, ...) and
+/// selenomethionine (`MSE`).
+///
+/// # Example
+/// ```
+/// # use chemfiles::naming::one_letter_code;
+/// assert_eq!(one_letter_code("ALA"), Some('A'));
+/// assert_eq!(one_letter_code("HSD"), Some('H'));
+/// assert_eq!(one_letter_code("HOH"), None);
+/// ```
+pub fn one_letter_code(name: &str) -> Option<char> {
+    return ONE_LETTER_CODES
+        .iter()
+        .find(|(residue, _)| *residue == name)
+        .map(|&(_, code)| code);
+}
+
+/// One-letter codes for amino acids, including common variants
+const ONE_LETTER_CODES: &[(&str, char)] = &[
+    ("ALA", 'A'),
+    ("ARG", 'R'),
+    ("ASN", 'N'),
+    ("ASP", 'D'),
+    ("ASH", 'D'),
+    ("CYS", 'C'),
+    ("CYX", 'C'),
+    ("CYM", 'C'),
+    ("GLN", 'Q'),
+    ("GLU", 'E'),
+    ("GLH", 'E'),
+    ("GLY", 'G'),
+    ("HIS", 'H'),
+    ("HID", 'H'),
+    ("HIE", 'H'),
+    ("HIP", 'H'),
+    ("HSD", 'H'),
+    ("HSE", 'H'),
+    ("HSP", 'H'),
+    ("ILE", 'I'),
+    ("LEU", 'L'),
+    ("LYS", 'K'),
+    ("LYN", 'K'),
+    ("MET", 'M'),
+    ("MSE", 'M'),
+    ("PHE", 'F'),
+    ("PRO", 'P'),
+    ("SER", 'S'),
+    ("THR", 'T'),
+    ("TRP", 'W'),
+    ("TYR", 'Y'),
+    ("VAL", 'V'),
+    ("SEC", 'U'),
+    ("PYL", 'O'),
+];
+
+/// Maximal length of names (in characters) that can be written by a format.
+/// `None` means that there is no limit.
+///
+/// Formats with fixed-width columns truncate longer names when writing, which
+/// can make different atoms share the same name. [`check_names`] reports the
+/// names which would be truncated, and [`shorten_names`] renames them to
+/// unique shorter names before writing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NameLimits {
+    /// Maximal length of atom names
+    pub atom_name: Option<usize>,
+    /// Maximal length of atom types
+    pub atom_type: Option<usize>,
+    /// Maximal length of residue names
+    pub residue_name: Option<usize>,
+}
+
+impl NameLimits {
+    /// Get the limits on names for the format with the given `name`, as used
+    /// in [`Trajectory::open_with_format`]. Formats without known limits get
+    /// no limits.
+    ///
+    /// [`Trajectory::open_with_format`]: crate::Trajectory::open_with_format
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::naming::NameLimits;
+    /// let limits = NameLimits::for_format("PDB");
+    /// assert_eq!(limits.atom_name, Some(4));
+    /// assert_eq!(limits.residue_name, Some(3));
+    ///
+    /// assert_eq!(NameLimits::for_format("XYZ"), NameLimits::default());
+    /// ```
+    pub fn for_format(name: &str) -> NameLimits {
+        match name {
+            "PDB" => NameLimits {
+                atom_name: Some(4),
+                atom_type: Some(2),
+                residue_name: Some(3),
+            },
+            "GRO" => NameLimits {
+                atom_name: Some(5),
+                atom_type: None,
+                residue_name: Some(5),
+            },
+            _ => NameLimits::default(),
+        }
+    }
+}
+
+/// Which kind of name is changed in a [`NameChange`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum NameKind {
+    /// Name of an atom
+    AtomName,
+    /// Type of an atom
+    AtomType,
+    /// Name of a residue
+    ResidueName,
+}
+
+/// A name which is too long for a format, and the name used instead
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameChange {
+    /// Which kind of name is changed
+    pub kind: NameKind,
+    /// The original name
+    pub name: String,
+    /// The name used instead of the original one
+    pub renamed: String,
+}
+
+/// Get all the names in `topology` which are too long according to `limits`,
+/// and the truncated names which would be written instead.
+///
+/// Each name is reported once, even if it is used by multiple atoms or
+/// residues. Truncated names can be the same as other names in the topology;
+/// use [`shorten_names`] to get unique names instead.
+///
+/// # Example
+/// ```
+/// # use chemfiles::{Topology, Atom};
+/// # use chemfiles::naming::{check_names, NameChange, NameKind, NameLimits};
+/// let mut topology = Topology::new();
+/// topology.add_atom(&Atom::new("HB21"));
+/// topology.add_atom(&Atom::new("HB211"));
+///
+/// let changes = check_names(&topology, NameLimits::for_format("PDB"));
+/// assert_eq!(changes, [NameChange {
+///     kind: NameKind::AtomName,
+///     name: "HB211".into(),
+///     renamed: "HB21".into(),
+/// }]);
+/// ```
+pub fn check_names(topology: &Topology, limits: NameLimits) -> Vec<NameChange> {
+    let mut changes = Vec::new();
+    for (kind, names, limit) in names_with_limits(topology, limits) {
+        for name in names.into_iter().filter(|name| name.chars().count() > limit) {
+            let renamed = name.chars().take(limit).collect();
+            changes.push(NameChange { kind, name, renamed });
+        }
+    }
+    return changes;
+}
+
+/// Rename all the names in `topology` which are too long according to
+/// `limits` to unique shorter names, and return the list of changes.
+///
+/// Names are first truncated to the maximal length. If the truncated name is
+/// already used by another name of the same kind, the end of the name is
+/// replaced by a number (`"HB211"` and `"HB212"` become `"HB22"` and `"HB23"`
+/// with a limit of 4 characters if `"HB21"` is already used).
+///
+/// # Example
+/// ```
+/// # use chemfiles::{Topology, Atom};
+/// # use chemfiles::naming::{shorten_names, NameLimits};
+/// let mut topology = Topology::new();
+/// topology.add_atom(&Atom::new("HB21"));
+/// topology.add_atom(&Atom::new("HB211"));
+/// topology.add_atom(&Atom::new("HB212"));
+///
+/// let changes = shorten_names(&mut topology, NameLimits::for_format("PDB"));
+/// assert_eq!(changes.len(), 2);
+/// assert_eq!(topology.atom(0).name(), "HB21");
+/// assert_eq!(topology.atom(1).name(), "HB22");
+/// assert_eq!(topology.atom(2).name(), "HB23");
+/// ```
+pub fn shorten_names(topology: &mut Topology, limits: NameLimits) -> Vec<NameChange> {
+    let mut changes = Vec::new();
+    for (kind, names, limit) in names_with_limits(topology, limits) {
+        let mut used = names
+            .iter()
+            .filter(|name| name.chars().count() <= limit)
+            .cloned()
+            .collect::<BTreeSet<_>>();
+
+        for name in names.into_iter().filter(|name| name.chars().count() > limit) {
+            let renamed = unique_short_name(&name, limit, &used);
+            let _ = used.insert(renamed.clone());
+            changes.push(NameChange { kind, name, renamed });
+        }
+    }
+
+    let renamed = |kind| {
+        changes
+            .iter()
+            .filter(|change| change.kind == kind)
+            .map(|change| (change.name.as_str(), change.renamed.as_str()))
+            .collect::<BTreeMap<_, _>>()
+    };
+    let atom_names = renamed(NameKind::AtomName);
+    let atom_types = renamed(NameKind::AtomType);
+    let residue_names = renamed(NameKind::ResidueName);
+
+    for i in 0..topology.size() {
+        let mut atom = topology.atom_mut(i);
+        if let Some(name) = atom_names.get(atom.name().as_str()) {
+            atom.set_name(*name);
+        }
+        if let Some(atomic_type) = atom_types.get(atom.atomic_type().as_str()) {
+            atom.set_atomic_type(*atomic_type);
+        }
+    }
+
+    if !residue_names.is_empty() {
+        rename_residues(topology, &residue_names);
+    }
+
+    return changes;
+}
+
+/// Get the sorted set of names of each kind in `topology`, together with the
+/// corresponding limit, for all kinds of names with a limit
+fn names_with_limits(topology: &Topology, limits: NameLimits) -> Vec<(NameKind, BTreeSet<String>, usize)> {
+    let mut names = Vec::new();
+    if let Some(limit) = limits.atom_name {
+        let atoms = (0..topology.size()).map(|i| topology.atom(i).name()).collect();
+        names.push((NameKind::AtomName, atoms, limit));
+    }
+    if let Some(limit) = limits.atom_type {
+        let types = (0..topology.size()).map(|i| topology.atom(i).atomic_type()).collect();
+        names.push((NameKind::AtomType, types, limit));
+    }
+    if let Some(limit) = limits.residue_name {
+        #[allow(clippy::cast_possible_truncation)]
+        let residues = (0..topology.residues_count() as usize)
+            .filter_map(|i| topology.residue(i).map(|residue| residue.name()))
+            .collect();
+        names.push((NameKind::ResidueName, residues, limit));
+    }
+    return names;
+}
+
+/// Get a name with at most `limit` characters for `name`, which is not
+/// already in `used`
+fn unique_short_name(name: &str, limit: usize, used: &BTreeSet<String>) -> String {
+    let truncated = name.chars().take(limit).collect::<String>();
+    if !used.contains(&truncated) {
+        return truncated;
+    }
+
+    for counter in 1.. {
+        let suffix = counter.to_string();
+        if suffix.len() > limit {
+            break;
+        }
+        let candidate = name.chars().take(limit - suffix.len()).collect::<String>() + &suffix;
+        if !used.contains(&candidate) {
+            return candidate;
+        }
+    }
+
+    // all possible names are used, fall back to the truncated name
+    return truncated;
+}
+
+/// Rename the residues in `topology` using `names`. The C API does not allow
+/// to modify residues inside a topology, so the topology is rebuilt.
+fn rename_residues(topology: &mut Topology, names: &BTreeMap<&str, &str>) {
+    let mut rebuilt = Topology::new();
+    for i in 0..topology.size() {
+        rebuilt.add_atom(&topology.atom(i));
+    }
+    for (bond, order) in topology.bonds().into_iter().zip(topology.bond_orders()) {
+        rebuilt.add_bond_with_order(bond[0], bond[1], order);
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    for residue in (0..topology.residues_count() as usize).filter_map(|i| topology.residue(i)) {
+        let name = residue.name();
+        let name = names.get(name.as_str()).copied().unwrap_or(&name);
+        let mut copy = match residue.id() {
+            Some(id) => Residue::with_id(name, id),
+            None => Residue::new(name),
+        };
+        for atom in residue.atoms() {
+            copy.add_atom(atom);
+        }
+        for (property, value) in residue.properties() {
+            copy.set(&property, value);
+        }
+        rebuilt
+            .add_residue(&copy)
+            .expect("residues from an existing topology should not overlap");
+    }
+
+    *topology = rebuilt;
+}
+
+/// Renaming from CHARMM to AMBER names for all residues
+const CHARMM_TO_AMBER: &[(&str, &str)] = &[
+    ("HN", "H"),
+    ("HT1", "H1"),
+    ("HT2", "H2"),
+    ("HT3", "H3"),
+    ("OT1", "O"),
+    ("OT2", "OXT"),
+    ("SOD", "Na+"),
+    ("POT", "K+"),
+    ("CLA", "Cl-"),
+];
+
+/// Residues and prefixes of the methylene hydrogens numbered 1/2 in CHARMM and
+/// 2/3 in AMBER
+const METHYLENE_HYDROGENS: &[(&[&str], &[&str])] = &[
+    (&["GLY"], &["HA"]),
+    (
+        &[
+            "ARG", "ASN", "ASP", "CYS", "GLN", "GLU", "HIS", "HSD", "HSE", "HSP", "LEU", "LYS", "MET", "PHE", "PRO",
+            "SER", "TRP", "TYR",
+        ],
+        &["HB"],
+    ),
+    (&["ARG", "GLN", "GLU", "LYS", "MET", "PRO"], &["HG"]),
+    (&["ARG", "LYS", "PRO"], &["HD"]),
+    (&["LYS"], &["HE"]),
+    (&["ILE"], &["HG1"]),
+];
+
+/// Renaming from CHARMM to AMBER names for isoleucine
+const CHARMM_TO_AMBER_ILE: &[(&str, &str)] = &[("CD", "CD1"), ("HD1", "HD11"), ("HD2", "HD12"), ("HD3", "HD13")];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_names() {
+        let used = ["CA", "CB", "C1"].iter().map(|&name| String::from(name)).collect();
+        assert_eq!(unique_short_name("CD", 2, &used), "CD");
+        assert_eq!(unique_short_name("CAX", 2, &used), "C2");
+        assert_eq!(unique_short_name("CBX", 3, &used), "CBX");
+        assert_eq!(unique_short_name("ÅÅÅ", 2, &used), "ÅÅ");
+
+        let used = (1..10).map(|i| format!("A{i}")).chain(["AB".into()]).collect();
+        assert_eq!(unique_short_name("ABC", 2, &used), "10");
+    }
+
+    #[test]
+    fn shorten_residue_names() {
+        let mut topology = Topology::new();
+        topology.resize(3);
+        topology.add_bond_with_order(0, 1, crate::BondOrder::Double);
+        for (i, name) in ["LIGAND", "LIGANT", "LIG"].iter().enumerate() {
+            let mut residue = Residue::new(*name);
+            residue.add_atom(i);
+            residue.set("chainid", "A");
+            topology.add_residue(&residue).unwrap();
+        }
+
+        let limits = NameLimits::for_format("PDB");
+        assert_eq!(check_names(&topology, limits).len(), 2);
+
+        let changes = shorten_names(&mut topology, limits);
+        assert_eq!(changes.len(), 2);
+        assert!(changes.iter().all(|change| change.kind == NameKind::ResidueName));
+
+        assert_eq!(topology.size(), 3);
+        assert_eq!(topology.bond_orders(), [crate::BondOrder::Double]);
+        assert_eq!(topology.residue(0).unwrap().name(), "LI1");
+        assert_eq!(topology.residue(1).unwrap().name(), "LI2");
+        assert_eq!(topology.residue(2).unwrap().name(), "LIG");
+        assert_eq!(topology.residue(1).unwrap().atoms(), [1]);
+        assert!(topology.residue(0).unwrap().get("chainid").is_some());
+        assert!(check_names(&topology, limits).is_empty());
+    }
+
+    #[test]
+    fn charmm_amber() {
+        let map = NameMap::charmm_to_amber();
+        assert_eq!(map.get(Some("GLY"), "HA1"), Some("HA2"));
+        assert_eq!(map.get(Some("ILE"), "HG11"), Some("HG12"));
+        assert_eq!(map.get(Some("ILE"), "HG12"), Some("HG13"));
+        assert_eq!(map.get(Some("ILE"), "HD1"), Some("HD11"));
+        assert_eq!(map.get(Some("LYS"), "HD1"), Some("HD2"));
+        assert_eq!(map.get(None, "OT2"), Some("OXT"));
+        assert_eq!(map.get(Some("ALA"), "CA"), None);
+
+        let inverse = NameMap::amber_to_charmm();
+        for (residue, name) in [("GLY", "HA2"), ("LYS", "HE3"), ("ILE", "HD13"), ("ALA", "H")] {
+            let charmm = inverse.get(Some(residue), name).unwrap();
+            assert_eq!(map.get(Some(residue), charmm), Some(name));
+        }
+    }
+}
@@ -0,0 +1,259 @@
+// Chemfiles, a modern library for chemistry file reading and writing
+// Copyright (C) 2015-2018 Guillaume Fraux -- BSD licensed
+
+//! Periodic table data used by chemfiles.
+//!
+//! This module contains the same element data as the one used by chemfiles to
+//! set the default mass, full name and radii of atoms (see for example
+//! [`Atom::mass`](crate::Atom::mass) or
+//! [`Atom::covalent_radius`](crate::Atom::covalent_radius)), as a static
+//! table. It can be used to look up elemental properties without creating
+//! atoms, and keeps downstream code consistent with the values used by
+//! chemfiles.
+//!
+//! # Example
+//! ```
+//! use chemfiles::periodic_table;
+//!
+//! let carbon = periodic_table::by_symbol("C").unwrap();
+//! assert_eq!(carbon.number, 6);
+//! assert_eq!(carbon.name, "Carbon");
+//! assert_eq!(carbon.mass, 12.011);
+//!
+//! assert_eq!(periodic_table::by_number(8).unwrap().symbol, "O");
+//! assert_eq!(periodic_table::ELEMENTS.len(), 118);
+//! ```
+
+/// Data associated with a single chemical element
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Element {
+    /// Atomic number of the element
+    pub number: u64,
+    /// Symbol of the element, e.g. `"Zn"`
+    pub symbol: &'static str,
+    /// Full name of the element, e.g. `"Zinc"`
+    pub name: &'static str,
+    /// Standard atomic weight of the element, in atomic mass units. For
+    /// elements without stable isotopes, this is the mass number of the most
+    /// stable isotope.
+    pub mass: f64,
+    /// Covalent radius of the element in Ångströms, if known
+    pub covalent_radius: Option<f64>,
+    /// Van der Waals radius of the element in Ångströms, if known
+    pub vdw_radius: Option<f64>,
+}
+
+/// Helper to keep the [`ELEMENTS`] table readable
+const fn element(
+    number: u64,
+    symbol: &'static str,
+    name: &'static str,
+    mass: f64,
+    covalent_radius: Option<f64>,
+    vdw_radius: Option<f64>,
+) -> Element {
+    Element {
+        number,
+        symbol,
+        name,
+        mass,
+        covalent_radius,
+        vdw_radius,
+    }
+}
+
+/// All the elements known to chemfiles, sorted by atomic number. The element
+/// with atomic number `z` is at index `z - 1`.
+pub static ELEMENTS: [Element; 118] = [
+    element(1, "H", "Hydrogen", 1.008, Some(0.37), Some(1.2)),
+    element(2, "He", "Helium", 4.002_602, Some(0.32), Some(1.4)),
+    element(3, "Li", "Lithium", 6.94, Some(1.34), Some(1.82)),
+    element(4, "Be", "Beryllium", 9.012_183_1, Some(0.9), None),
+    element(5, "B", "Boron", 10.81, Some(0.82), None),
+    element(6, "C", "Carbon", 12.011, Some(0.77), Some(1.7)),
+    element(7, "N", "Nitrogen", 14.007, Some(0.75), Some(1.55)),
+    element(8, "O", "Oxygen", 15.999, Some(0.73), Some(1.52)),
+    element(9, "F", "Fluorine", 18.998_403_163, Some(0.71), Some(1.47)),
+    element(10, "Ne", "Neon", 20.179_7, Some(0.69), Some(1.54)),
+    element(11, "Na", "Sodium", 22.989_769_28, Some(1.54), Some(2.27)),
+    element(12, "Mg", "Magnesium", 24.305, Some(1.3), Some(1.73)),
+    element(13, "Al", "Aluminium", 26.981_538_5, Some(1.18), None),
+    element(14, "Si", "Silicon", 28.085, Some(1.11), Some(2.1)),
+    element(15, "P", "Phosphorus", 30.973_761_998, Some(1.06), Some(1.8)),
+    element(16, "S", "Sulfur", 32.06, Some(1.02), Some(1.8)),
+    element(17, "Cl", "Chlorine", 35.45, Some(0.99), Some(1.75)),
+    element(18, "Ar", "Argon", 39.948, Some(0.97), Some(1.88)),
+    element(19, "K", "Potassium", 39.098_3, Some(1.96), Some(2.75)),
+    element(20, "Ca", "Calcium", 40.078, Some(1.74), None),
+    element(21, "Sc", "Scandium", 44.955_908, Some(1.44), None),
+    element(22, "Ti", "Titanium", 47.867, Some(1.36), None),
+    element(23, "V", "Vanadium", 50.941_5, Some(1.25), None),
+    element(24, "Cr", "Chromium", 51.996_1, Some(1.27), None),
+    element(25, "Mn", "Manganese", 54.938_044, Some(1.39), None),
+    element(26, "Fe", "Iron", 55.845, Some(1.25), None),
+    element(27, "Co", "Cobalt", 58.933_194, Some(1.26), None),
+    element(28, "Ni", "Nickel", 58.693_4, Some(1.21), Some(1.63)),
+    element(29, "Cu", "Copper", 63.546, Some(1.38), Some(1.4)),
+    element(30, "Zn", "Zinc", 65.38, Some(1.31), Some(1.39)),
+    element(31, "Ga", "Gallium", 69.723, Some(1.26), Some(1.87)),
+    element(32, "Ge", "Germanium", 72.63, Some(1.22), None),
+    element(33, "As", "Arsenic", 74.921_595, Some(1.19), Some(1.85)),
+    element(34, "Se", "Selenium", 78.971, Some(1.16), Some(1.9)),
+    element(35, "Br", "Bromine", 79.904, Some(1.14), Some(1.85)),
+    element(36, "Kr", "Krypton", 83.798, Some(1.1), Some(2.02)),
+    element(37, "Rb", "Rubidium", 85.467_8, Some(2.11), None),
+    element(38, "Sr", "Strontium", 87.62, Some(1.92), None),
+    element(39, "Y", "Yttrium", 88.905_84, Some(1.62), None),
+    element(40, "Zr", "Zirconium", 91.224, Some(1.48), None),
+    element(41, "Nb", "Niobium", 92.906_37, Some(1.37), None),
+    element(42, "Mo", "Molybdenum", 95.95, Some(1.45), None),
+    element(43, "Tc", "Technetium", 98.0, Some(1.56), None),
+    element(44, "Ru", "Ruthenium", 101.07, Some(1.26), None),
+    element(45, "Rh", "Rhodium", 102.905_5, Some(1.35), None),
+    element(46, "Pd", "Palladium", 106.42, Some(1.31), Some(1.63)),
+    element(47, "Ag", "Silver", 107.868_2, Some(1.53), Some(1.72)),
+    element(48, "Cd", "Cadmium", 112.414, Some(1.48), Some(1.58)),
+    element(49, "In", "Indium", 114.818, Some(1.44), Some(1.93)),
+    element(50, "Sn", "Tin", 118.71, Some(1.41), Some(2.17)),
+    element(51, "Sb", "Antimony", 121.76, Some(1.38), None),
+    element(52, "Te", "Tellurium", 127.6, Some(1.35), Some(2.06)),
+    element(53, "I", "Iodine", 126.904_47, Some(1.33), Some(1.98)),
+    element(54, "Xe", "Xenon", 131.293, Some(1.3), Some(2.16)),
+    element(55, "Cs", "Caesium", 132.905_451_96, Some(2.25), None),
+    element(56, "Ba", "Barium", 137.327, Some(1.98), None),
+    element(57, "La", "Lanthanum", 138.905_47, Some(1.69), None),
+    element(58, "Ce", "Cerium", 140.116, None, None),
+    element(59, "Pr", "Praseodymium", 140.907_66, None, None),
+    element(60, "Nd", "Neodymium", 144.242, None, None),
+    element(61, "Pm", "Promethium", 145.0, None, None),
+    element(62, "Sm", "Samarium", 150.36, None, None),
+    element(63, "Eu", "Europium", 151.964, None, None),
+    element(64, "Gd", "Gadolinium", 157.25, None, None),
+    element(65, "Tb", "Terbium", 158.925_35, None, None),
+    element(66, "Dy", "Dysprosium", 162.5, None, None),
+    element(67, "Ho", "Holmium", 164.930_33, None, None),
+    element(68, "Er", "Erbium", 167.259, None, None),
+    element(69, "Tm", "Thulium", 168.934_22, None, None),
+    element(70, "Yb", "Ytterbium", 173.045, None, None),
+    element(71, "Lu", "Lutetium", 174.966_8, Some(1.6), None),
+    element(72, "Hf", "Hafnium", 178.49, Some(1.5), None),
+    element(73, "Ta", "Tantalum", 180.947_88, Some(1.38), None),
+    element(74, "W", "Tungsten", 183.84, Some(1.46), None),
+    element(75, "Re", "Rhenium", 186.207, Some(1.59), None),
+    element(76, "Os", "Osmium", 190.23, Some(1.28), None),
+    element(77, "Ir", "Iridium", 192.217, Some(1.37), None),
+    element(78, "Pt", "Platinum", 195.084, Some(1.28), Some(1.75)),
+    element(79, "Au", "Gold", 196.966_569, Some(1.44), Some(1.66)),
+    element(80, "Hg", "Mercury", 200.592, Some(1.49), Some(1.55)),
+    element(81, "Tl", "Thallium", 204.38, Some(1.48), Some(1.96)),
+    element(82, "Pb", "Lead", 207.2, Some(1.47), Some(2.02)),
+    element(83, "Bi", "Bismuth", 208.980_4, Some(1.46), None),
+    element(84, "Po", "Polonium", 209.0, None, None),
+    element(85, "At", "Astatine", 210.0, None, None),
+    element(86, "Rn", "Radon", 222.0, Some(1.45), None),
+    element(87, "Fr", "Francium", 223.0, None, None),
+    element(88, "Ra", "Radium", 226.0, None, None),
+    element(89, "Ac", "Actinium", 227.0, None, None),
+    element(90, "Th", "Thorium", 232.037_7, None, None),
+    element(91, "Pa", "Protactinium", 231.035_88, None, None),
+    element(92, "U", "Uranium", 238.028_91, None, Some(1.86)),
+    element(93, "Np", "Neptunium", 237.0, None, None),
+    element(94, "Pu", "Plutonium", 244.0, None, None),
+    element(95, "Am", "Americium", 243.0, None, None),
+    element(96, "Cm", "Curium", 247.0, None, None),
+    element(97, "Bk", "Berkelium", 247.0, None, None),
+    element(98, "Cf", "Californium", 251.0, None, None),
+    element(99, "Es", "Einsteinium", 252.0, None, None),
+    element(100, "Fm", "Fermium", 257.0, None, None),
+    element(101, "Md", "Mendelevium", 258.0, None, None),
+    element(102, "No", "Nobelium", 259.0, None, None),
+    element(103, "Lr", "Lawrencium", 266.0, None, None),
+    element(104, "Rf", "Rutherfordium", 267.0, None, None),
+    element(105, "Db", "Dubnium", 268.0, None, None),
+    element(106, "Sg", "Seaborgium", 269.0, None, None),
+    element(107, "Bh", "Bohrium", 270.0, None, None),
+    element(108, "Hs", "Hassium", 270.0, None, None),
+    element(109, "Mt", "Meitnerium", 278.0, None, None),
+    element(110, "Ds", "Darmstadtium", 281.0, None, None),
+    element(111, "Rg", "Roentgenium", 282.0, None, None),
+    element(112, "Cn", "Copernicium", 285.0, None, None),
+    element(113, "Nh", "Nihonium", 286.0, None, None),
+    element(114, "Fl", "Flerovium", 289.0, None, None),
+    element(115, "Mc", "Moscovium", 290.0, None, None),
+    element(116, "Lv", "Livermorium", 293.0, None, None),
+    element(117, "Ts", "Tennessine", 294.0, None, None),
+    element(118, "Og", "Oganesson", 294.0, None, None),
+];
+
+/// Get the element with the given `symbol`, ignoring the case of the symbol.
+/// This returns `None` if there is no element with this symbol.
+///
+/// # Example
+/// ```
+/// # use chemfiles::periodic_table;
+/// assert_eq!(periodic_table::by_symbol("Zn").unwrap().name, "Zinc");
+/// assert_eq!(periodic_table::by_symbol("ZN").unwrap().name, "Zinc");
+/// assert!(periodic_table::by_symbol("Xxx").is_none());
+/// ```
+pub fn by_symbol(symbol: &str) -> Option<&'static Element> {
+    return ELEMENTS
+        .iter()
+        .find(|element| element.symbol.eq_ignore_ascii_case(symbol));
+}
+
+/// Get the element with the given atomic `number`. This returns `None` if
+/// there is no element with this atomic number.
+///
+/// # Example
+/// ```
+/// # use chemfiles::periodic_table;
+/// assert_eq!(periodic_table::by_number(30).unwrap().symbol, "Zn");
+/// assert!(periodic_table::by_number(0).is_none());
+/// assert!(periodic_table::by_number(119).is_none());
+/// ```
+pub fn by_number(number: u64) -> Option<&'static Element> {
+    let index = usize::try_from(number).ok()?.checked_sub(1)?;
+    return ELEMENTS.get(index);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Atom;
+
+    #[test]
+    fn lookup() {
+        for (i, element) in ELEMENTS.iter().enumerate() {
+            assert_eq!(element.number, i as u64 + 1);
+            assert_eq!(by_number(element.number), Some(element));
+            assert_eq!(by_symbol(element.symbol), Some(element));
+        }
+
+        assert_eq!(by_symbol("he").unwrap().number, 2);
+        assert_eq!(by_symbol(""), None);
+        assert_eq!(by_number(0), None);
+        assert_eq!(by_number(119), None);
+    }
+
+    #[test]
+    fn consistent_with_chemfiles() {
+        for element in &ELEMENTS {
+            let atom = Atom::new(element.symbol);
+            assert_eq!(atom.atomic_number(), element.number, "{}", element.symbol);
+            assert_eq!(atom.full_name(), element.name, "{}", element.symbol);
+            assert_eq!(atom.mass(), element.mass, "{}", element.symbol);
+            assert_eq!(
+                atom.covalent_radius(),
+                element.covalent_radius.unwrap_or(0.0),
+                "{}",
+                element.symbol
+            );
+            assert_eq!(
+                atom.vdw_radius(),
+                element.vdw_radius.unwrap_or(0.0),
+                "{}",
+                element.symbol
+            );
+        }
+    }
+}
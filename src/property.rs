@@ -2,7 +2,9 @@
 // Copyright (C) 2015-2018 Guillaume Fraux -- BSD licensed
 use chemfiles_sys as ffi;
 
-use crate::errors::{check, check_not_null, check_success, Error};
+use std::convert::TryFrom;
+
+use crate::errors::{check, check_not_null, check_success, Error, Status};
 use crate::strings;
 
 /// A thin wrapper around `ffi::CHFL_PROPERTY`
@@ -103,6 +105,24 @@ impl Drop for RawProperty {
 
 /// A `Property` is a piece of data that can be associated with an `Atom` or a
 /// `Frame`.
+///
+/// Properties can be created from the corresponding Rust types with `From`,
+/// and converted back with `TryFrom`. When the `nalgebra` feature is enabled,
+/// `Vector3D` properties can also be converted from and to
+/// `nalgebra::Vector3<f64>`.
+///
+/// # Example
+/// ```
+/// # use chemfiles::Atom;
+/// # use std::convert::TryFrom;
+/// let mut atom = Atom::new("O");
+/// atom.set("dipole", (0.0, 0.5, 1.0));
+///
+/// let dipole = <[f64; 3]>::try_from(atom.get("dipole").unwrap()).unwrap();
+/// assert_eq!(dipole, [0.0, 0.5, 1.0]);
+///
+/// assert!(f64::try_from(atom.get("dipole").unwrap()).is_err());
+/// ```
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub enum Property {
     /// Boolean property
@@ -145,7 +165,96 @@ impl From<[f64; 3]> for Property {
     }
 }
 
+impl From<(f64, f64, f64)> for Property {
+    fn from((x, y, z): (f64, f64, f64)) -> Self {
+        Property::Vector3D([x, y, z])
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl From<nalgebra::Vector3<f64>> for Property {
+    fn from(value: nalgebra::Vector3<f64>) -> Self {
+        Property::Vector3D([value.x, value.y, value.z])
+    }
+}
+
+/// Create the error returned when converting a `property` to a type
+/// corresponding to a different kind of property
+fn conversion_error(property: &Property, expected: &str) -> Error {
+    Error {
+        status: Status::PropertyError,
+        message: format!("can not convert {} property to {expected}", property.kind_name()),
+    }
+}
+
+impl TryFrom<Property> for bool {
+    type Error = Error;
+    fn try_from(property: Property) -> Result<bool, Error> {
+        match property {
+            Property::Bool(value) => Ok(value),
+            other => Err(conversion_error(&other, "bool")),
+        }
+    }
+}
+
+impl TryFrom<Property> for f64 {
+    type Error = Error;
+    fn try_from(property: Property) -> Result<f64, Error> {
+        match property {
+            Property::Double(value) => Ok(value),
+            other => Err(conversion_error(&other, "f64")),
+        }
+    }
+}
+
+impl TryFrom<Property> for String {
+    type Error = Error;
+    fn try_from(property: Property) -> Result<String, Error> {
+        match property {
+            Property::String(value) => Ok(value),
+            other => Err(conversion_error(&other, "String")),
+        }
+    }
+}
+
+impl TryFrom<Property> for [f64; 3] {
+    type Error = Error;
+    fn try_from(property: Property) -> Result<[f64; 3], Error> {
+        match property {
+            Property::Vector3D(value) => Ok(value),
+            other => Err(conversion_error(&other, "[f64; 3]")),
+        }
+    }
+}
+
+impl TryFrom<Property> for (f64, f64, f64) {
+    type Error = Error;
+    fn try_from(property: Property) -> Result<(f64, f64, f64), Error> {
+        let [x, y, z] = <[f64; 3]>::try_from(property)?;
+        return Ok((x, y, z));
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl TryFrom<Property> for nalgebra::Vector3<f64> {
+    type Error = Error;
+    fn try_from(property: Property) -> Result<nalgebra::Vector3<f64>, Error> {
+        let [x, y, z] = <[f64; 3]>::try_from(property)?;
+        return Ok(nalgebra::Vector3::new(x, y, z));
+    }
+}
+
 impl Property {
+    /// Get the name of the kind of this property, for error messages
+    fn kind_name(&self) -> &'static str {
+        match self {
+            Property::Bool(_) => "bool",
+            Property::Double(_) => "double",
+            Property::String(_) => "string",
+            Property::Vector3D(_) => "Vector3D",
+        }
+    }
+
     pub(crate) fn as_raw(&self) -> RawProperty {
         match *self {
             Property::Bool(value) => RawProperty::bool(value),
@@ -172,7 +281,8 @@ impl Property {
     }
 }
 
-/// An iterator over the properties in an atom/frame/residue
+/// An iterator over the properties in an atom/frame/residue, yielding the
+/// properties in alphabetical order of their names
 pub struct PropertiesIter<'a> {
     pub(crate) names: std::vec::IntoIter<String>,
     pub(crate) getter: Box<dyn Fn(&str) -> Property + 'a>,
@@ -281,4 +391,36 @@ mod tests {
             assert_eq!(Property::from_raw(raw), property);
         }
     }
+
+    mod conversions {
+        use super::super::*;
+
+        #[test]
+        fn try_from() {
+            assert_eq!(bool::try_from(Property::Bool(true)), Ok(true));
+            assert_eq!(f64::try_from(Property::Double(2.5)), Ok(2.5));
+            assert_eq!(String::try_from(Property::from("foo")), Ok("foo".into()));
+            assert_eq!(<[f64; 3]>::try_from(Property::from((1.0, 2.0, 3.0))), Ok([1.0, 2.0, 3.0]));
+            assert_eq!(
+                <(f64, f64, f64)>::try_from(Property::Vector3D([1.0, 2.0, 3.0])),
+                Ok((1.0, 2.0, 3.0))
+            );
+
+            let error = f64::try_from(Property::Bool(false)).unwrap_err();
+            assert_eq!(error.status, Status::PropertyError);
+            assert_eq!(error.message, "can not convert bool property to f64");
+
+            let error = <(f64, f64, f64)>::try_from(Property::Double(3.0)).unwrap_err();
+            assert_eq!(error.message, "can not convert double property to [f64; 3]");
+        }
+
+        #[test]
+        #[cfg(feature = "nalgebra")]
+        fn nalgebra() {
+            let vector = nalgebra::Vector3::new(1.0, 2.0, 3.0);
+            let property = Property::from(vector);
+            assert_eq!(property, Property::Vector3D([1.0, 2.0, 3.0]));
+            assert_eq!(nalgebra::Vector3::try_from(property), Ok(vector));
+        }
+    }
 }
@@ -218,7 +218,7 @@ impl Residue {
     /// ```
     /// # use chemfiles::Residue;
     /// let mut residue = Residue::new("water");
-    /// assert_eq!(residue.atoms(), vec![]);
+    /// assert!(residue.atoms().is_empty());
     ///
     /// residue.add_atom(56);
     /// assert_eq!(residue.atoms(), vec![56]);
@@ -285,24 +285,80 @@ impl Residue {
         }
     }
 
-    /// Get an iterator over all (name, property) pairs for this frame
+    /// Remove the property with the given `name` from this residue, returning
+    /// it if it existed.
     ///
-    /// # Examples
+    /// The C API of chemfiles does not support removing properties, so this
+    /// function rebuilds the residue with all the other data.
+    ///
+    /// # Example
     /// ```
     /// # use chemfiles::{Residue, Property};
     /// let mut residue = Residue::new("ALA");
-    /// residue.set("foo", Property::Double(22.2));
-    /// residue.set("bar", Property::Bool(false));
+    /// residue.set("foo", 2.0);
     ///
-    /// for (name, property) in residue.properties() {
-    ///     if name == "foo" {
-    ///         assert_eq!(property, Property::Double(22.2));
-    ///     } else if name == "bar" {
-    ///         assert_eq!(property, Property::Bool(false));
-    ///     }
-    /// }
+    /// assert_eq!(residue.remove_property("foo"), Some(Property::Double(2.0)));
+    /// assert_eq!(residue.get("foo"), None);
     /// ```
-    pub fn properties(&self) -> PropertiesIter {
+    pub fn remove_property(&mut self, name: &str) -> Option<Property> {
+        let property = self.get(name)?;
+        *self = self.filtered_copy(&|other| other != name);
+        return Some(property);
+    }
+
+    /// Remove all the properties of this residue.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Residue;
+    /// let mut residue = Residue::with_id("ALA", 3);
+    /// residue.add_atom(8);
+    /// residue.set("foo", 2.0);
+    /// residue.set("bar", "baz");
+    ///
+    /// residue.clear_properties();
+    /// assert_eq!(residue.properties().count(), 0);
+    /// assert_eq!(residue.atoms(), [8]);
+    /// assert_eq!(residue.id(), Some(3));
+    /// ```
+    pub fn clear_properties(&mut self) {
+        if self.properties().count() != 0 {
+            *self = self.filtered_copy(&|_| false);
+        }
+    }
+
+    /// Get a copy of this residue, only keeping the properties for which
+    /// `keep` returns `true`.
+    fn filtered_copy(&self, keep: &dyn Fn(&str) -> bool) -> Residue {
+        let name = self.name();
+        let mut residue = match self.id() {
+            Some(id) => Residue::with_id(&*name, id),
+            None => Residue::new(&*name),
+        };
+        for atom in self.atoms() {
+            residue.add_atom(atom);
+        }
+        for (name, property) in self.properties() {
+            if keep(&name) {
+                residue.set(&name, property);
+            }
+        }
+        return residue;
+    }
+
+    /// Get the names of all properties of this residue, sorted in
+    /// alphabetical order.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::Residue;
+    /// let mut residue = Residue::new("ALA");
+    /// residue.set("foo", 1.0);
+    /// residue.set("bar", false);
+    ///
+    /// assert_eq!(residue.properties_names(), ["bar", "foo"]);
+    /// ```
+    pub fn properties_names(&self) -> Vec<String> {
         let mut count = 0;
         unsafe {
             check_success(ffi::chfl_residue_properties_count(self.as_ptr(), &mut count));
@@ -319,13 +375,30 @@ impl Residue {
             ));
         }
 
-        let mut names = Vec::new();
-        for ptr in c_names {
-            names.push(strings::from_c(ptr));
-        }
+        let mut names = c_names.into_iter().map(|ptr| strings::from_c(ptr)).collect::<Vec<_>>();
+        names.sort_unstable();
+        return names;
+    }
 
+    /// Get an iterator over all (name, property) pairs for this residue, in
+    /// alphabetical order of the names.
+    ///
+    /// # Examples
+    /// ```
+    /// # use chemfiles::{Residue, Property};
+    /// let mut residue = Residue::new("ALA");
+    /// residue.set("foo", Property::Double(22.2));
+    /// residue.set("bar", Property::Bool(false));
+    ///
+    /// let properties = residue.properties().collect::<Vec<_>>();
+    /// assert_eq!(properties, [
+    ///     ("bar".to_string(), Property::Bool(false)),
+    ///     ("foo".to_string(), Property::Double(22.2)),
+    /// ]);
+    /// ```
+    pub fn properties(&self) -> PropertiesIter {
         PropertiesIter {
-            names: names.into_iter(),
+            names: self.properties_names().into_iter(),
             getter: Box::new(move |name| self.get(name).expect("failed to get property")),
         }
     }
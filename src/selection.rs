@@ -1,10 +1,17 @@
 // Chemfiles, a modern library for chemistry file reading and writing
 // Copyright (C) 2015-2018 Guillaume Fraux -- BSD licensed
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::str::FromStr;
+#[cfg(feature = "instrumentation")]
+use std::time::{Duration, Instant};
+
 use chemfiles_sys as ffi;
 
-use crate::errors::{check, check_not_null, check_success, Error, Status};
+use crate::errors::{check, check_not_null, check_success, with_warning_capture, Error, Status};
 use crate::frame::Frame;
 use crate::strings;
+use crate::topology::Topology;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 /// A `Match` is a set of atomic indexes matching a given selection. It can
@@ -92,6 +99,195 @@ impl<'a> IntoIterator for &'a Match {
     }
 }
 
+/// The context of a selection, *i.e.* the kind of atomic groups it matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectionContext {
+    /// Single atoms, this is the default context
+    Atoms,
+    /// Any pair of atoms
+    Pairs,
+    /// Any three atoms
+    Three,
+    /// Any four atoms
+    Four,
+    /// Pairs of bonded atoms
+    Bonds,
+    /// Three atoms forming an angle
+    Angles,
+    /// Four atoms forming a dihedral angle
+    Dihedrals,
+}
+
+impl SelectionContext {
+    /// Get the context corresponding to the given `selection` string. This
+    /// only looks at the selection prefix, and does not validate the rest of
+    /// the selection.
+    fn from_selection(selection: &str) -> SelectionContext {
+        let prefix = match selection.split_once(':') {
+            Some((prefix, _)) => prefix.trim(),
+            None => return SelectionContext::Atoms,
+        };
+
+        match prefix {
+            "pairs" => SelectionContext::Pairs,
+            "three" => SelectionContext::Three,
+            "four" => SelectionContext::Four,
+            "bonds" => SelectionContext::Bonds,
+            "angles" => SelectionContext::Angles,
+            "dihedrals" => SelectionContext::Dihedrals,
+            _ => SelectionContext::Atoms,
+        }
+    }
+}
+
+/// Information about a valid selection string, as returned by
+/// [`Selection::check`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectionInfo {
+    /// Number of atoms matched together by the selection
+    pub size: usize,
+    /// Context of the selection
+    pub context: SelectionContext,
+    /// Warnings emitted by chemfiles while parsing the selection
+    pub warnings: Vec<String>,
+}
+
+/// Statistics about the evaluations of a selection, as returned by
+/// [`Selection::stats`].
+#[cfg(feature = "instrumentation")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SelectionStats {
+    /// Number of times the selection was evaluated
+    pub evaluations: usize,
+    /// Number of atoms visited by the evaluations. A full evaluation visits
+    /// all the atoms in the frame, while evaluations using a
+    /// [`TopologyIndex`] only visit the matching atoms.
+    pub atoms_visited: usize,
+    /// Total number of matches found by the evaluations
+    pub matches: usize,
+    /// Total time spent evaluating the selection
+    pub time: Duration,
+}
+
+/// Pre-computed lists of atoms with a given name or type in a topology.
+///
+/// Selections which only check atomic names or types can use this index
+/// instead of comparing the name of every atom, which is useful when
+/// evaluating the same selections on many frames sharing a large topology.
+/// See [`Selection::list_with_index`].
+///
+/// # Example
+/// ```
+/// # use chemfiles::{Atom, Topology, TopologyIndex};
+/// let mut topology = Topology::new();
+/// topology.add_atom(&Atom::new("H"));
+/// topology.add_atom(&Atom::new("O"));
+/// topology.add_atom(&Atom::new("H"));
+///
+/// let index = TopologyIndex::new(&topology);
+/// assert_eq!(index.by_name("H"), [0, 2]);
+/// assert_eq!(index.by_type("O"), [1]);
+/// assert!(index.by_name("C").is_empty());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TopologyIndex {
+    size: usize,
+    names: HashMap<String, Vec<usize>>,
+    types: HashMap<String, Vec<usize>>,
+}
+
+impl TopologyIndex {
+    /// Create a new index of the atom names and types in `topology`
+    pub fn new(topology: &Topology) -> TopologyIndex {
+        let mut index = TopologyIndex {
+            size: topology.size(),
+            ..Default::default()
+        };
+        for i in 0..topology.size() {
+            let atom = topology.atom(i);
+            index.names.entry(atom.name()).or_default().push(i);
+            index.types.entry(atom.atomic_type()).or_default().push(i);
+        }
+        return index;
+    }
+
+    /// Get the number of atoms in the indexed topology
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Get the indexes of all the atoms with the given `name`, in increasing
+    /// order
+    pub fn by_name(&self, name: &str) -> &[usize] {
+        return self.names.get(name).map_or(&[], Vec::as_slice);
+    }
+
+    /// Get the indexes of all the atoms with the given atomic type `name`,
+    /// in increasing order
+    pub fn by_type(&self, name: &str) -> &[usize] {
+        return self.types.get(name).map_or(&[], Vec::as_slice);
+    }
+}
+
+/// A selection which only compares the atomic name or type to a set of
+/// values, such as `name H`, `type == O` or `name(#1) HW1 HW2`
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SimpleSelection {
+    Names(Vec<String>),
+    Types(Vec<String>),
+}
+
+impl SimpleSelection {
+    /// Try to parse the given `selection` string as a simple selection,
+    /// returning `None` for any other kind of selection.
+    fn parse(selection: &str) -> Option<SimpleSelection> {
+        let selection = selection.trim();
+        let selection = selection.strip_prefix("atoms:").unwrap_or(selection).trim_start();
+
+        let (is_name, rest) = if let Some(rest) = selection.strip_prefix("name") {
+            (true, rest)
+        } else {
+            (false, selection.strip_prefix("type")?)
+        };
+        let rest = rest.strip_prefix("(#1)").unwrap_or(rest);
+        if !rest.starts_with(char::is_whitespace) {
+            return None;
+        }
+        let mut rest = rest.trim_start();
+        rest = rest.strip_prefix("==").unwrap_or(rest).trim_start();
+
+        let mut values = Vec::new();
+        while !rest.is_empty() {
+            let (value, remaining) = if let Some(quoted) = rest.strip_prefix('"') {
+                let end = quoted.find('"')?;
+                (&quoted[..end], &quoted[end + 1..])
+            } else {
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                let value = &rest[..end];
+                let is_identifier = value.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+                if !is_identifier || matches!(value, "and" | "or" | "not") {
+                    return None;
+                }
+                (value, &rest[end..])
+            };
+            if !remaining.is_empty() && !remaining.starts_with(char::is_whitespace) {
+                return None;
+            }
+            values.push(value.to_owned());
+            rest = remaining.trim_start();
+        }
+
+        if values.is_empty() {
+            return None;
+        }
+        return Some(if is_name {
+            SimpleSelection::Names(values)
+        } else {
+            SimpleSelection::Types(values)
+        });
+    }
+}
+
 /// A `Selection` allow to select atoms in a `Frame`, from a selection
 /// language. The selection language is built by combining basic operations.
 /// Each basic operation follows the `<selector>[(<variable>)] <operator>
@@ -100,14 +296,22 @@ impl<'a> IntoIterator for &'a Match {
 #[derive(Debug)]
 pub struct Selection {
     handle: *mut ffi::CHFL_SELECTION,
+    /// Parsed version of the selection, if it only checks names or types
+    simple: Option<SimpleSelection>,
+    #[cfg(feature = "instrumentation")]
+    stats: SelectionStats,
 }
 
 impl Clone for Selection {
     fn clone(&self) -> Selection {
-        unsafe {
+        let mut selection = unsafe {
             let new_handle = ffi::chfl_selection_copy(self.as_ptr());
             Selection::from_ptr(new_handle)
-        }
+        };
+        selection.simple.clone_from(&self.simple);
+        #[cfg(feature = "instrumentation")]
+        selection.stats.clone_from(&self.stats);
+        return selection;
     }
 }
 
@@ -126,7 +330,12 @@ impl Selection {
     #[inline]
     pub(crate) unsafe fn from_ptr(ptr: *mut ffi::CHFL_SELECTION) -> Selection {
         check_not_null(ptr);
-        Selection { handle: ptr }
+        Selection {
+            handle: ptr,
+            simple: None,
+            #[cfg(feature = "instrumentation")]
+            stats: SelectionStats::default(),
+        }
     }
 
     /// Get the underlying C pointer as a const pointer.
@@ -145,7 +354,9 @@ impl Selection {
     ///
     /// # Errors
     ///
-    /// This function fails if the selection string is invalid.
+    /// This function fails if the selection string is invalid. With the
+    /// `macros` feature, the [`selection!`](crate::selection!) macro checks
+    /// literal selection strings at compile time instead.
     ///
     /// # Example
     /// ```
@@ -153,18 +364,53 @@ impl Selection {
     /// let selection = Selection::new("pairs: name(#1) H and name(#2) O").unwrap();
     /// ```
     pub fn new<'a, S: Into<&'a str>>(selection: S) -> Result<Selection, Error> {
-        let buffer = strings::to_c(selection.into());
-        unsafe {
+        let selection = selection.into();
+        let buffer = strings::to_c(selection);
+        let mut selection = unsafe {
             let handle = ffi::chfl_selection(buffer.as_ptr());
             if handle.is_null() {
-                Err(Error {
+                return Err(Error {
                     status: Status::SelectionError,
                     message: Error::last_error(),
-                })
-            } else {
-                Ok(Selection::from_ptr(handle))
+                });
             }
-        }
+            Selection::from_ptr(handle)
+        };
+        selection.simple = SimpleSelection::parse(&selection.string());
+        return Ok(selection);
+    }
+
+    /// Check that the given `selection` string is valid, and get information
+    /// about it (size, context and parsing warnings) without keeping a
+    /// `Selection` around.
+    ///
+    /// This is intended for code validating user input, for example when
+    /// the user is typing a selection in a graphical interface. The selection
+    /// is fully compiled by the C++ library, exactly like in
+    /// [`Selection::new`], so this is not cheaper than creating a selection;
+    /// the compiled selection is only freed before returning.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the selection string is invalid.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Selection, SelectionContext};
+    /// let info = Selection::check("angles: name(#1) H").unwrap();
+    /// assert_eq!(info.size, 3);
+    /// assert_eq!(info.context, SelectionContext::Angles);
+    /// assert!(info.warnings.is_empty());
+    ///
+    /// assert!(Selection::check("name H and").is_err());
+    /// ```
+    pub fn check(selection: &str) -> Result<SelectionInfo, Error> {
+        let (parsed, warnings) = with_warning_capture(|| Selection::new(selection));
+        return Ok(SelectionInfo {
+            size: parsed?.size(),
+            context: SelectionContext::from_selection(selection),
+            warnings,
+        });
     }
 
     /// Get the size of the selection, i.e. the number of atoms we are selecting
@@ -229,6 +475,9 @@ impl Selection {
     /// ```
     pub fn evaluate(&mut self, frame: &Frame) -> Vec<Match> {
         #![allow(clippy::cast_possible_truncation)]
+        #[cfg(feature = "instrumentation")]
+        let start = Instant::now();
+
         let mut count = 0;
         unsafe {
             check(ffi::chfl_selection_evaluate(
@@ -250,7 +499,7 @@ impl Selection {
             .expect("failed to extract matches");
         }
 
-        return chfl_matches
+        let matches = chfl_matches
             .into_iter()
             .map(|chfl_match| Match {
                 size: chfl_match.size as usize,
@@ -261,6 +510,79 @@ impl Selection {
                     chfl_match.atoms[3] as usize,
                 ],
             })
+            .collect::<Vec<_>>();
+
+        #[cfg(feature = "instrumentation")]
+        self.record(frame.size(), matches.len(), start);
+        return matches;
+    }
+
+    /// Evaluate a selection for a given frame, and return the corresponding
+    /// matches as maps from variable names (`"#1"`, `"#2"`, `"#3"` and `"#4"`)
+    /// to atomic indexes.
+    ///
+    /// This is especially useful for selections with multiple atoms, such as
+    /// `angles` or `dihedrals`, to avoid relying on the position of atoms in
+    /// the `Match`.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Selection, Frame, Atom};
+    /// let mut frame = Frame::new();
+    /// frame.add_atom(&Atom::new("H"), [1.0, 0.0, 0.0], None);
+    /// frame.add_atom(&Atom::new("O"), [0.0, 0.0, 0.0], None);
+    /// frame.add_atom(&Atom::new("H"), [-1.0, 0.0, 0.0], None);
+    ///
+    /// let mut selection = Selection::new("pairs: name(#1) H and name(#2) O").unwrap();
+    /// let matches = selection.evaluate_named(&frame);
+    ///
+    /// assert_eq!(matches.len(), 2);
+    /// assert_eq!(matches[0]["#1"], 0);
+    /// assert_eq!(matches[0]["#2"], 1);
+    /// assert_eq!(matches[1]["#1"], 2);
+    /// assert_eq!(matches[1]["#2"], 1);
+    /// ```
+    pub fn evaluate_named(&mut self, frame: &Frame) -> Vec<HashMap<&'static str, usize>> {
+        const VARIABLES: [&str; 4] = ["#1", "#2", "#3", "#4"];
+        let size = self.size();
+        return self.evaluate_labeled(frame, &VARIABLES[..size]);
+    }
+
+    /// Evaluate a selection for a given frame, and return the corresponding
+    /// matches as maps from the given `labels` to atomic indexes. The first
+    /// label is used for the atom matching `#1`, the second label for the atom
+    /// matching `#2`, *etc.*
+    ///
+    /// # Panics
+    ///
+    /// If the number of labels is not the same as the selection size
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Selection, Frame, Atom};
+    /// let mut frame = Frame::new();
+    /// frame.add_atom(&Atom::new("H"), [1.0, 0.0, 0.0], None);
+    /// frame.add_atom(&Atom::new("O"), [0.0, 0.0, 0.0], None);
+    /// frame.add_atom(&Atom::new("H"), [-1.0, 0.0, 0.0], None);
+    ///
+    /// let mut selection = Selection::new("pairs: name(#1) H and name(#2) O").unwrap();
+    /// let matches = selection.evaluate_labeled(&frame, &["hydrogen", "oxygen"]);
+    ///
+    /// assert_eq!(matches.len(), 2);
+    /// assert_eq!(matches[0]["hydrogen"], 0);
+    /// assert_eq!(matches[0]["oxygen"], 1);
+    /// ```
+    pub fn evaluate_labeled<'l>(&mut self, frame: &Frame, labels: &[&'l str]) -> Vec<HashMap<&'l str, usize>> {
+        assert!(
+            self.size() == labels.len(),
+            "the number of labels ({}) does not match the selection size ({})",
+            labels.len(),
+            self.size()
+        );
+        return self
+            .evaluate(frame)
+            .iter()
+            .map(|m| labels.iter().copied().zip(m.iter().copied()).collect())
             .collect();
     }
 
@@ -293,6 +615,314 @@ impl Selection {
         );
         return self.evaluate(frame).into_iter().map(|m| m[0]).collect();
     }
+
+    /// Evaluates a selection of size 1 on a given `frame`, like
+    /// [`Selection::list`], using the pre-computed `index` when possible.
+    ///
+    /// Selections which only compare atomic names or types to a list of
+    /// values (such as `name H`, `type == O` or `name HW1 HW2`) are directly
+    /// answered from the index, without looking at all the atoms in the frame.
+    /// Other selections, or frames with a different number of atoms than the
+    /// indexed topology, are evaluated as usual. The index must be created
+    /// from the topology of `frame`, and re-created if this topology changes.
+    ///
+    /// # Panics
+    ///
+    /// If the selection size is not 1
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Selection, Frame, Atom, TopologyIndex};
+    /// let mut frame = Frame::new();
+    /// frame.add_atom(&Atom::new("H"), [1.0, 0.0, 0.0], None);
+    /// frame.add_atom(&Atom::new("O"), [0.0, 0.0, 0.0], None);
+    /// frame.add_atom(&Atom::new("H"), [-1.0, 0.0, 0.0], None);
+    ///
+    /// let index = TopologyIndex::new(&frame.topology());
+    /// let mut selection = Selection::new("name H").unwrap();
+    /// assert_eq!(selection.list_with_index(&frame, &index), vec![0, 2]);
+    /// ```
+    pub fn list_with_index(&mut self, frame: &Frame, index: &TopologyIndex) -> Vec<usize> {
+        assert!(
+            self.size() == 1,
+            "can not call `Selection::list_with_index` on a multiple selection"
+        );
+
+        let simple = match self.simple {
+            Some(ref simple) if index.size() == frame.size() => simple,
+            _ => return self.list(frame),
+        };
+
+        #[cfg(feature = "instrumentation")]
+        let start = Instant::now();
+
+        let (values, lookup): (_, for<'i> fn(&'i TopologyIndex, &str) -> &'i [usize]) = match simple {
+            SimpleSelection::Names(names) => (names, TopologyIndex::by_name),
+            SimpleSelection::Types(types) => (types, TopologyIndex::by_type),
+        };
+        let mut matches = values
+            .iter()
+            .flat_map(|value| lookup(index, value))
+            .copied()
+            .collect::<Vec<_>>();
+        if values.len() > 1 {
+            // merge the lists for each value, keeping the matches sorted
+            matches.sort_unstable();
+            matches.dedup();
+        }
+
+        #[cfg(feature = "instrumentation")]
+        self.record(matches.len(), matches.len(), start);
+        return matches;
+    }
+
+    /// Get statistics about all the evaluations of this selection so far.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Selection, Frame, Atom};
+    /// let mut frame = Frame::new();
+    /// frame.add_atom(&Atom::new("H"), [1.0, 0.0, 0.0], None);
+    /// frame.add_atom(&Atom::new("O"), [0.0, 0.0, 0.0], None);
+    ///
+    /// let mut selection = Selection::new("name H").unwrap();
+    /// let _ = selection.list(&frame);
+    /// let _ = selection.list(&frame);
+    ///
+    /// let stats = selection.stats();
+    /// assert_eq!(stats.evaluations, 2);
+    /// assert_eq!(stats.atoms_visited, 4);
+    /// assert_eq!(stats.matches, 2);
+    /// ```
+    #[cfg(feature = "instrumentation")]
+    pub fn stats(&self) -> &SelectionStats {
+        &self.stats
+    }
+
+    /// Reset the statistics of this selection, see [`Selection::stats`].
+    #[cfg(feature = "instrumentation")]
+    pub fn reset_stats(&mut self) {
+        self.stats = SelectionStats::default();
+    }
+
+    /// Record a new evaluation in the statistics, visiting `atoms` atoms and
+    /// finding `matches` matches since `start`.
+    #[cfg(feature = "instrumentation")]
+    fn record(&mut self, atoms: usize, matches: usize, start: Instant) {
+        self.stats.evaluations += 1;
+        self.stats.atoms_visited += atoms;
+        self.stats.matches += matches;
+        self.stats.time += start.elapsed();
+    }
+
+    /// Save the selection string of this selection to the file at `path`,
+    /// replacing any existing file.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the file can not be written.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use chemfiles::Selection;
+    /// let selection = Selection::new("name O and resname HOH").unwrap();
+    /// selection.save("water-oxygens.txt").unwrap();
+    ///
+    /// let selection = Selection::load("water-oxygens.txt").unwrap();
+    /// assert_eq!(selection.string(), "name O and resname HOH");
+    /// ```
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        return std::fs::write(path, self.string() + "\n").map_err(|error| Error {
+            status: Status::FileError,
+            message: format!("failed to write selection at '{}': {error}", path.display()),
+        });
+    }
+
+    /// Read a selection from the file at `path`, as written by
+    /// [`Selection::save`]. The file should contain a single selection string,
+    /// leading and trailing whitespace is ignored.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the file can not be read, or if it does not
+    /// contain a valid selection.
+    pub fn load(path: impl AsRef<Path>) -> Result<Selection, Error> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|error| Error {
+            status: Status::FileError,
+            message: format!("failed to read selection at '{}': {error}", path.display()),
+        })?;
+        return Selection::new(content.trim());
+    }
+}
+
+/// A `SelectionSet` is a collection of named selections, which can be shared
+/// between multiple analyses or scripts.
+///
+/// Selection sets can be read from and written to a simple text format, where
+/// each line contains a name and a selection string, separated by `=`. Empty
+/// lines and lines starting with `#` are ignored. Names can not contain
+/// whitespace or `=`.
+///
+/// ```text
+/// # selections for the protein/water system
+/// backbone = name CA C N O
+/// water = resname HOH
+/// hbonds = pairs: name(#1) O and name(#2) H and distance(#1, #2) < 2.5
+/// ```
+///
+/// # Example
+/// ```
+/// # use chemfiles::{SelectionSet, Frame, Atom};
+/// let selections: SelectionSet = "
+///     oxygens = name O
+///     hydrogens = name H
+/// ".parse().unwrap();
+///
+/// let mut frame = Frame::new();
+/// frame.add_atom(&Atom::new("O"), [0.0, 0.0, 0.0], None);
+/// frame.add_atom(&Atom::new("H"), [1.0, 0.0, 0.0], None);
+///
+/// let mut oxygens = selections.selection("oxygens").unwrap();
+/// assert_eq!(oxygens.list(&frame), vec![0]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SelectionSet {
+    selections: BTreeMap<String, String>,
+}
+
+impl SelectionSet {
+    /// Create a new empty selection set
+    pub fn new() -> SelectionSet {
+        return SelectionSet::default();
+    }
+
+    /// Read a selection set from the file at the given `path`.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the file can not be read, or if it is not a
+    /// valid selection set.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<SelectionSet, Error> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|error| Error {
+            status: Status::FileError,
+            message: format!("failed to read selection set at '{}': {error}", path.display()),
+        })?;
+        return content.parse();
+    }
+
+    /// Write this selection set to the file at `path`, replacing any existing
+    /// file. The selections are written in alphabetical order of their names.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the file can not be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        return std::fs::write(path, self.to_string()).map_err(|error| Error {
+            status: Status::FileError,
+            message: format!("failed to write selection set at '{}': {error}", path.display()),
+        });
+    }
+
+    /// Add the given `selection` string to this set as `name`, replacing any
+    /// previous selection with the same name.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the `selection` string is invalid, or if `name`
+    /// is empty or contains whitespace or `=`.
+    pub fn insert(&mut self, name: &str, selection: &str) -> Result<(), Error> {
+        if name.is_empty() || name.contains(|c: char| c.is_whitespace() || c == '=') {
+            return Err(Error {
+                status: Status::InvalidArgument,
+                message: format!("invalid selection name '{name}'"),
+            });
+        }
+        let _ = Selection::check(selection)?;
+        let _ = self.selections.insert(name.into(), selection.trim().into());
+        return Ok(());
+    }
+
+    /// Remove the selection named `name` from this set, returning the
+    /// corresponding selection string if it was present.
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        return self.selections.remove(name);
+    }
+
+    /// Get the selection string for the selection named `name`, if any.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        return self.selections.get(name).map(String::as_str);
+    }
+
+    /// Create a new [`Selection`] for the selection named `name`, if any.
+    pub fn selection(&self, name: &str) -> Option<Selection> {
+        let selection = self.selections.get(name)?;
+        return Some(Selection::new(selection.as_str()).expect("selection strings are checked on insertion"));
+    }
+
+    /// Get the names of all the selections in this set, in alphabetical order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        return self.selections.keys().map(String::as_str);
+    }
+
+    /// Get the number of selections in this set
+    pub fn len(&self) -> usize {
+        return self.selections.len();
+    }
+
+    /// Check if this set is empty
+    pub fn is_empty(&self) -> bool {
+        return self.selections.is_empty();
+    }
+}
+
+impl FromStr for SelectionSet {
+    type Err = Error;
+
+    fn from_str(content: &str) -> Result<SelectionSet, Error> {
+        let mut set = SelectionSet::new();
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (name, selection) = match line.split_once('=') {
+                Some(parts) => parts,
+                None => {
+                    return Err(Error {
+                        status: Status::FormatError,
+                        message: format!(
+                            "expected 'name = selection' on line {} of selection set, got '{line}'",
+                            i + 1
+                        ),
+                    })
+                }
+            };
+
+            set.insert(name.trim(), selection).map_err(|error| Error {
+                status: Status::FormatError,
+                message: format!(
+                    "invalid selection on line {} of selection set: {}",
+                    i + 1,
+                    error.message
+                ),
+            })?;
+        }
+        return Ok(set);
+    }
+}
+
+impl std::fmt::Display for SelectionSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (name, selection) in &self.selections {
+            writeln!(f, "{name} = {selection}")?;
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -420,6 +1050,66 @@ mod tests {
         assert_eq!(res, vec![0, 3]);
     }
 
+    #[test]
+    fn simple_selection() {
+        let names = |values: &[&str]| Some(SimpleSelection::Names(values.iter().map(|&v| v.into()).collect()));
+        let types = |values: &[&str]| Some(SimpleSelection::Types(values.iter().map(|&v| v.into()).collect()));
+
+        assert_eq!(SimpleSelection::parse("name H"), names(&["H"]));
+        assert_eq!(SimpleSelection::parse("  name == H  "), names(&["H"]));
+        assert_eq!(SimpleSelection::parse("name(#1) HW1 HW2"), names(&["HW1", "HW2"]));
+        assert_eq!(SimpleSelection::parse("atoms: name \"H 1\" O"), names(&["H 1", "O"]));
+        assert_eq!(SimpleSelection::parse("type C_ar"), types(&["C_ar"]));
+
+        assert_eq!(SimpleSelection::parse("name != H"), None);
+        assert_eq!(SimpleSelection::parse("name H and index 0"), None);
+        assert_eq!(SimpleSelection::parse("name H or type O"), None);
+        assert_eq!(SimpleSelection::parse("name"), None);
+        assert_eq!(SimpleSelection::parse("names H"), None);
+        assert_eq!(SimpleSelection::parse("name(#2) H"), None);
+        assert_eq!(SimpleSelection::parse("name \"H"), None);
+        assert_eq!(SimpleSelection::parse("name \"H\"O"), None);
+        assert_eq!(SimpleSelection::parse("pairs: name(#1) H"), None);
+    }
+
+    #[test]
+    fn list_with_index() {
+        let frame = testing_frame();
+        let index = TopologyIndex::new(&frame.topology());
+        assert_eq!(index.size(), 4);
+        assert_eq!(index.by_name("O"), [1, 2]);
+
+        for selection in ["name H", "name O H", "type == O", "name H and index 0", "index 1"] {
+            let mut selection = Selection::new(selection).unwrap();
+            let expected = selection.list(&frame);
+            assert_eq!(selection.clone().list_with_index(&frame, &index), expected);
+        }
+
+        // the index is not used if it does not match the frame
+        let index = TopologyIndex::new(&Topology::new());
+        let mut selection = Selection::new("name H").unwrap();
+        assert_eq!(selection.list_with_index(&frame, &index), vec![0, 3]);
+    }
+
+    #[test]
+    #[cfg(feature = "instrumentation")]
+    fn stats() {
+        let frame = testing_frame();
+        let index = TopologyIndex::new(&frame.topology());
+
+        let mut selection = Selection::new("name H").unwrap();
+        let _ = selection.evaluate(&frame);
+        let _ = selection.list_with_index(&frame, &index);
+        let stats = selection.stats();
+        assert_eq!(stats.evaluations, 2);
+        assert_eq!(stats.atoms_visited, 4 + 2);
+        assert_eq!(stats.matches, 4);
+
+        assert_eq!(selection.clone().stats(), selection.stats());
+        selection.reset_stats();
+        assert_eq!(selection.stats(), &SelectionStats::default());
+    }
+
     #[test]
     #[should_panic = "can not call `Selection::list` on a multiple selection"]
     fn list_on_size_1_selection() {
@@ -427,4 +1117,113 @@ mod tests {
         let mut selection = Selection::new("pairs: name(#1) H").unwrap();
         let _list = selection.list(&frame);
     }
+
+    #[test]
+    fn evaluate_named() {
+        let frame = testing_frame();
+
+        let mut selection = Selection::new("bonds: name(#1) H").unwrap();
+        let res = selection.evaluate_named(&frame);
+        assert_eq!(res.len(), 2);
+        assert_eq!(res[0].len(), 2);
+        assert_eq!(res[0]["#1"], 0);
+        assert_eq!(res[0]["#2"], 1);
+        assert_eq!(res[1]["#1"], 3);
+        assert_eq!(res[1]["#2"], 2);
+
+        let mut selection = Selection::new("dihedrals: all").unwrap();
+        let res = selection.evaluate_labeled(&frame, &["i", "j", "k", "m"]);
+        assert_eq!(res.len(), 1);
+        let dihedral = &res[0];
+        assert_eq!(dihedral["j"] + dihedral["k"], 3);
+        assert_eq!(dihedral["i"] + dihedral["m"], 3);
+    }
+
+    #[test]
+    #[should_panic = "the number of labels (1) does not match the selection size (2)"]
+    fn evaluate_labeled_wrong_size() {
+        let frame = testing_frame();
+        let mut selection = Selection::new("pairs: all").unwrap();
+        let _ = selection.evaluate_labeled(&frame, &["a"]);
+    }
+
+    #[test]
+    fn check() {
+        let info = Selection::check("name H").unwrap();
+        assert_eq!(info.size, 1);
+        assert!(info.warnings.is_empty());
+        assert_eq!(info.context, SelectionContext::Atoms);
+
+        let info = Selection::check("pairs: name(#1) H").unwrap();
+        assert_eq!(info.size, 2);
+        assert_eq!(info.context, SelectionContext::Pairs);
+
+        let info = Selection::check("dihedrals: all").unwrap();
+        assert_eq!(info.size, 4);
+        assert_eq!(info.context, SelectionContext::Dihedrals);
+
+        let error = Selection::check("foo").unwrap_err();
+        assert_eq!(error.status, Status::SelectionError);
+    }
+
+    #[test]
+    fn save_load() {
+        let selection = Selection::new("pairs: name(#1) H and name(#2) O").unwrap();
+        selection.save("test-selection-tmp.txt").unwrap();
+        let loaded = Selection::load("test-selection-tmp.txt").unwrap();
+        assert_eq!(loaded.string(), selection.string());
+        std::fs::remove_file("test-selection-tmp.txt").unwrap();
+
+        let error = Selection::load("not-there.txt").unwrap_err();
+        assert_eq!(error.status, Status::FileError);
+    }
+
+    #[test]
+    fn selection_set() {
+        let mut set: SelectionSet = "
+            # comment
+            oxygens = name O
+            pairs = pairs: name(#1) H and name(#2) O
+        "
+        .parse()
+        .unwrap();
+
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.get("oxygens"), Some("name O"));
+        assert_eq!(set.names().collect::<Vec<_>>(), ["oxygens", "pairs"]);
+
+        let frame = testing_frame();
+        assert_eq!(set.selection("oxygens").unwrap().list(&frame), vec![1, 2]);
+        assert_eq!(set.selection("pairs").unwrap().size(), 2);
+        assert!(set.selection("hydrogens").is_none());
+
+        set.insert("hydrogens", "name H").unwrap();
+        assert_eq!(
+            set.insert("bad name", "name H").unwrap_err().status,
+            Status::InvalidArgument
+        );
+        assert_eq!(
+            set.insert("bad", "name H and").unwrap_err().status,
+            Status::SelectionError
+        );
+        assert_eq!(set.remove("pairs").as_deref(), Some("pairs: name(#1) H and name(#2) O"));
+
+        set.save("test-selection-set-tmp.txt").unwrap();
+        let loaded = SelectionSet::from_file("test-selection-set-tmp.txt").unwrap();
+        assert_eq!(loaded, set);
+        std::fs::remove_file("test-selection-set-tmp.txt").unwrap();
+
+        let error = "oxygens name O".parse::<SelectionSet>().unwrap_err();
+        assert_eq!(error.status, Status::FormatError);
+        assert_eq!(
+            error.message,
+            "expected 'name = selection' on line 1 of selection set, got 'oxygens name O'"
+        );
+
+        let error = "\nfoo = name O and".parse::<SelectionSet>().unwrap_err();
+        assert_eq!(error.status, Status::FormatError);
+        assert!(error
+            .message
+            .starts_with("invalid selection on line 2 of selection set"));
+    }
 }
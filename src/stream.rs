@@ -0,0 +1,393 @@
+// Chemfiles, a modern library for chemistry file reading and writing
+// Copyright (C) 2015-2018 Guillaume Fraux -- BSD licensed
+use crate::analysis::Accumulator;
+use crate::errors::{Error, Status};
+use crate::transform::Pipeline;
+use crate::{ChunkedWriter, FilteredTrajectory, Frame, MemoryTrajectoryReader, Trajectory};
+
+/// A `FrameSource` produces a stream of frames, one at a time.
+///
+/// This trait is implemented by [`Trajectory`], [`MemoryTrajectoryReader`],
+/// [`FilteredTrajectory`] and [`Pipeline`], allowing to write functions
+/// working with any stream of frames. It is also implemented for iterators
+/// over owned frames, such as `Vec<Frame>::into_iter()`.
+///
+/// # Example
+/// ```no_run
+/// # use chemfiles::{Error, FrameSource, Selection, Trajectory, FilteredTrajectory};
+/// fn count_atoms(source: &mut impl FrameSource) -> Result<usize, Error> {
+///     let mut count = 0;
+///     while let Some(frame) = source.next_frame()? {
+///         count += frame.size();
+///     }
+///     Ok(count)
+/// }
+///
+/// let mut trajectory = Trajectory::open("water.xyz", 'r').unwrap();
+/// let all = count_atoms(&mut trajectory).unwrap();
+///
+/// let trajectory = Trajectory::open("water.xyz", 'r').unwrap();
+/// let selection = Selection::new("name O").unwrap();
+/// let mut oxygens = FilteredTrajectory::new(trajectory, selection).unwrap();
+/// let oxygens = count_atoms(&mut oxygens).unwrap();
+/// ```
+pub trait FrameSource {
+    /// Get the next frame from this source, or `None` if there are no more
+    /// frames.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the next frame can not be produced, for example
+    /// in case of I/O errors or incorrectly formatted files.
+    fn next_frame(&mut self) -> Result<Option<Frame>, Error>;
+
+    /// Give all the remaining frames in this source to `accumulator`, and
+    /// return the result of the accumulator.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if any frame can not be produced.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use chemfiles::{FrameSource, Trajectory};
+    /// # use chemfiles::analysis::TrajectoryStats;
+    /// let mut trajectory = Trajectory::open("simulation.xtc", 'r').unwrap();
+    /// let stats = trajectory.accumulate(TrajectoryStats::default()).unwrap();
+    /// println!("read {} frames", stats.frames.len());
+    /// ```
+    fn accumulate<A: Accumulator>(&mut self, mut accumulator: A) -> Result<A::Output, Error>
+    where
+        Self: Sized,
+    {
+        while let Some(frame) = self.next_frame()? {
+            accumulator.accumulate(&frame);
+        }
+        return Ok(accumulator.finish());
+    }
+
+    /// Get an iterator over the remaining frames in this source. The iterator
+    /// stops after the first error.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use chemfiles::{FrameSource, Trajectory};
+    /// let trajectory = Trajectory::open("water.xyz", 'r').unwrap();
+    /// for frame in trajectory.frames() {
+    ///     let frame = frame.unwrap();
+    ///     println!("{} atoms", frame.size());
+    /// }
+    /// ```
+    fn frames(self) -> Frames<Self>
+    where
+        Self: Sized,
+    {
+        Frames {
+            source: self,
+            done: false,
+        }
+    }
+}
+
+/// Iterator over the frames of a [`FrameSource`], as returned by
+/// [`FrameSource::frames`].
+#[derive(Debug)]
+pub struct Frames<S> {
+    source: S,
+    done: bool,
+}
+
+impl<S> Frames<S> {
+    /// Get back the underlying frame source
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+}
+
+impl<S: FrameSource> Iterator for Frames<S> {
+    type Item = Result<Frame, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let next = self.source.next_frame();
+        if !matches!(next, Ok(Some(_))) {
+            self.done = true;
+        }
+        return next.transpose();
+    }
+}
+
+impl<S: FrameSource + ?Sized> FrameSource for &mut S {
+    fn next_frame(&mut self) -> Result<Option<Frame>, Error> {
+        (**self).next_frame()
+    }
+}
+
+impl<S: FrameSource + ?Sized> FrameSource for Box<S> {
+    fn next_frame(&mut self) -> Result<Option<Frame>, Error> {
+        (**self).next_frame()
+    }
+}
+
+impl FrameSource for std::vec::IntoIter<Frame> {
+    fn next_frame(&mut self) -> Result<Option<Frame>, Error> {
+        Ok(self.next())
+    }
+}
+
+impl FrameSource for Trajectory {
+    fn next_frame(&mut self) -> Result<Option<Frame>, Error> {
+        self.read_next()
+    }
+}
+
+impl FrameSource for MemoryTrajectoryReader<'_> {
+    fn next_frame(&mut self) -> Result<Option<Frame>, Error> {
+        self.read_next()
+    }
+}
+
+impl FrameSource for FilteredTrajectory {
+    fn next_frame(&mut self) -> Result<Option<Frame>, Error> {
+        let mut frame = Frame::new();
+        match self.read(&mut frame) {
+            Ok(()) => return Ok(Some(frame)),
+            Err(error) if error.is_eof() => return Ok(None),
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+impl FrameSource for Pipeline {
+    fn next_frame(&mut self) -> Result<Option<Frame>, Error> {
+        let mut frame = Frame::new();
+        match self.read(&mut frame) {
+            Ok(()) => return Ok(Some(frame)),
+            Err(error) if error.is_eof() => return Ok(None),
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+/// A `FrameSink` consumes a stream of frames, one at a time.
+///
+/// This trait is implemented by [`Trajectory`] (including in-memory writers
+/// created with [`Trajectory::memory_writer`]) and [`ChunkedWriter`], allowing
+/// to write functions working with any destination for frames. It is also
+/// implemented for `Vec<Frame>`, which stores a copy of all the frames.
+///
+/// See [`pump`] to copy all the frames from a [`FrameSource`] to a
+/// `FrameSink`.
+pub trait FrameSink {
+    /// Write a single `frame` to this sink.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the frame can not be written, for example in
+    /// case of I/O errors or if the frame can not be represented in the
+    /// output format.
+    fn write_frame(&mut self, frame: &Frame) -> Result<(), Error>;
+}
+
+impl<S: FrameSink + ?Sized> FrameSink for &mut S {
+    fn write_frame(&mut self, frame: &Frame) -> Result<(), Error> {
+        (**self).write_frame(frame)
+    }
+}
+
+impl<S: FrameSink + ?Sized> FrameSink for Box<S> {
+    fn write_frame(&mut self, frame: &Frame) -> Result<(), Error> {
+        (**self).write_frame(frame)
+    }
+}
+
+impl FrameSink for Vec<Frame> {
+    fn write_frame(&mut self, frame: &Frame) -> Result<(), Error> {
+        self.push(frame.clone());
+        return Ok(());
+    }
+}
+
+impl FrameSink for Trajectory {
+    fn write_frame(&mut self, frame: &Frame) -> Result<(), Error> {
+        self.write(frame)
+    }
+}
+
+impl FrameSink for ChunkedWriter {
+    fn write_frame(&mut self, frame: &Frame) -> Result<(), Error> {
+        self.write(frame)
+    }
+}
+
+/// Options controlling which frames are copied by [`pump`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PumpOptions {
+    /// Only copy one frame every `stride` frames, starting with the first
+    /// one. This must be at least 1.
+    pub stride: usize,
+    /// Maximal number of frames to write to the sink, or `None` to copy all
+    /// the frames
+    pub max_frames: Option<usize>,
+}
+
+impl Default for PumpOptions {
+    fn default() -> PumpOptions {
+        PumpOptions {
+            stride: 1,
+            max_frames: None,
+        }
+    }
+}
+
+/// Copy the frames produced by `source` to `sink`, following the given
+/// `options`, and return the number of frames written to the sink.
+///
+/// # Errors
+///
+/// This function fails if `options.stride` is zero, or if reading a frame
+/// from the source or writing it to the sink fails. Frames written before the
+/// error stay in the sink.
+///
+/// # Example
+/// ```no_run
+/// # use chemfiles::{pump, PumpOptions, Trajectory};
+/// let input = Trajectory::open("trajectory.dcd", 'r').unwrap();
+/// let mut output = Trajectory::open("trajectory.xyz", 'w').unwrap();
+///
+/// let options = PumpOptions {
+///     stride: 10,
+///     ..Default::default()
+/// };
+/// let written = pump(input, &mut output, &options).unwrap();
+/// println!("wrote {written} frames");
+/// ```
+pub fn pump(mut source: impl FrameSource, mut sink: impl FrameSink, options: &PumpOptions) -> Result<usize, Error> {
+    if options.stride == 0 {
+        return Err(Error {
+            status: Status::InvalidArgument,
+            message: "the stride must be at least 1 in pump".into(),
+        });
+    }
+
+    let mut read = 0;
+    let mut written = 0;
+    if options.max_frames == Some(0) {
+        return Ok(written);
+    }
+
+    while let Some(frame) = source.next_frame()? {
+        if read % options.stride == 0 {
+            sink.write_frame(&frame)?;
+            written += 1;
+            if options.max_frames == Some(written) {
+                break;
+            }
+        }
+        read += 1;
+    }
+
+    return Ok(written);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::TrajectoryStats;
+    use crate::transform::Translate;
+    use crate::Atom;
+
+    fn frames() -> Vec<Frame> {
+        let mut frames = Vec::new();
+        for i in 0..3 {
+            let mut frame = Frame::new();
+            frame.set_step(i);
+            frame.add_atom(&Atom::new("O"), [0.0, 0.0, 0.0], None);
+            frame.add_atom(&Atom::new("H"), [1.0, 0.0, 0.0], None);
+            frames.push(frame);
+        }
+        return frames;
+    }
+
+    #[test]
+    fn sources() {
+        let mut trajectory = Trajectory::open("test-stream-tmp.xyz", 'w').unwrap();
+        for frame in frames() {
+            trajectory.write(&frame).unwrap();
+        }
+        drop(trajectory);
+
+        let trajectory = Trajectory::open("test-stream-tmp.xyz", 'r').unwrap();
+        let sizes = trajectory
+            .frames()
+            .map(|frame| frame.unwrap().size())
+            .collect::<Vec<_>>();
+        assert_eq!(sizes, [2, 2, 2]);
+
+        let trajectory = Trajectory::open("test-stream-tmp.xyz", 'r').unwrap();
+        let mut filtered = FilteredTrajectory::with_indexes(trajectory, vec![1]);
+        let stats = filtered.accumulate(TrajectoryStats::default()).unwrap();
+        assert_eq!(stats.frames.len(), 3);
+        assert!(filtered.next_frame().unwrap().is_none());
+
+        let trajectory = Trajectory::open("test-stream-tmp.xyz", 'r').unwrap();
+        let mut pipeline = Pipeline::new(trajectory).with(Translate::new([1.0, 0.0, 0.0]));
+        let frame = pipeline.next_frame().unwrap().unwrap();
+        assert_eq!(frame.positions()[0], [1.0, 0.0, 0.0]);
+
+        // trait objects
+        let mut source: Box<dyn FrameSource> = Box::new(pipeline);
+        assert_eq!((&mut source).frames().count(), 2);
+
+        std::fs::remove_file("test-stream-tmp.xyz").unwrap();
+
+        let mut source = frames().into_iter();
+        assert_eq!(source.next_frame().unwrap().unwrap().step(), 0);
+        let steps = source.frames().map(|frame| frame.unwrap().step()).collect::<Vec<_>>();
+        assert_eq!(steps, [1, 2]);
+    }
+
+    #[test]
+    fn sinks() {
+        let mut output = Vec::new();
+        let written = pump(frames().into_iter(), &mut output, &PumpOptions::default()).unwrap();
+        assert_eq!(written, 3);
+        assert_eq!(output.len(), 3);
+
+        let options = PumpOptions {
+            stride: 2,
+            max_frames: None,
+        };
+        let mut output = Vec::new();
+        assert_eq!(pump(frames().into_iter(), &mut output, &options).unwrap(), 2);
+        let steps = output.iter().map(Frame::step).collect::<Vec<_>>();
+        assert_eq!(steps, [0, 2]);
+
+        let options = PumpOptions {
+            stride: 1,
+            max_frames: Some(1),
+        };
+        let mut source = frames().into_iter();
+        let mut output = Vec::new();
+        assert_eq!(pump(&mut source, &mut output, &options).unwrap(), 1);
+        assert_eq!(source.len(), 2);
+
+        let options = PumpOptions {
+            stride: 0,
+            max_frames: None,
+        };
+        let error = pump(frames().into_iter(), Vec::new(), &options).unwrap_err();
+        assert_eq!(error.status, Status::InvalidArgument);
+
+        let mut trajectory = Trajectory::memory_writer("XYZ").unwrap();
+        assert_eq!(
+            pump(frames().into_iter(), &mut trajectory, &PumpOptions::default()).unwrap(),
+            3
+        );
+        let buffer = trajectory.memory_buffer().unwrap();
+        assert_eq!(buffer.lines().filter(|line| *line == "2").count(), 3);
+    }
+}
@@ -4,8 +4,9 @@
 //! String conversions between C and Rust
 use std::ffi::{CStr, CString};
 use std::os::raw::c_char;
+use std::path::Path;
 
-use crate::errors::{check, Error};
+use crate::errors::{check, Error, Status};
 
 /// Create a Rust string from a C string. Clones all characters in `buffer`.
 pub fn from_c(buffer: *const c_char) -> String {
@@ -23,6 +24,26 @@ where
     CString::new(string).expect("Invalid C string from Rust")
 }
 
+/// Create a C string from a path.
+///
+/// On Unix, the C library uses the bytes of the path as-is, so paths which are
+/// not valid UTF-8 (for example using a legacy encoding) are supported. On
+/// other platforms, the path must be valid UTF-8.
+pub fn path_to_c(path: &Path) -> Result<CString, Error> {
+    #[cfg(unix)]
+    let bytes = {
+        use std::os::unix::ffi::OsStrExt;
+        path.as_os_str().as_bytes()
+    };
+    #[cfg(not(unix))]
+    let bytes = path.to_str().ok_or_else(|| Error::utf8_path_error(path))?.as_bytes();
+
+    return CString::new(bytes).map_err(|_| Error {
+        status: Status::InvalidArgument,
+        message: format!("path '{}' contains a NUL byte", path.display()),
+    });
+}
+
 /// Check if a string buffer was big enough when passed to a C function
 fn buffer_was_big_enough(buffer: &[c_char]) -> bool {
     let len = buffer.len();
@@ -0,0 +1,176 @@
+// Chemfiles, a modern library for chemistry file reading and writing
+// Copyright (C) 2015-2018 Guillaume Fraux -- BSD licensed
+use std::str::FromStr;
+
+use crate::errors::{Error, Status};
+
+/// A crystallographic symmetry operation, acting on fractional coordinates.
+///
+/// Symmetry operations are parsed from the `x,y,z` notation used in CIF
+/// files (for example in `_symmetry_equiv_pos_as_xyz` or
+/// `_space_group_symop_operation_xyz`), such as `-x+1/2, y, -z`.
+///
+/// # Example
+/// ```
+/// # use chemfiles::SymmetryOperation;
+/// let operation: SymmetryOperation = "-x+1/2, y, -z".parse().unwrap();
+/// assert_eq!(operation.apply([0.25, 0.5, 0.5]), [0.25, 0.5, -0.5]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SymmetryOperation {
+    /// Rotation part of the operation
+    pub rotation: [[f64; 3]; 3],
+    /// Translation part of the operation, in fractional coordinates
+    pub translation: [f64; 3],
+}
+
+impl SymmetryOperation {
+    /// Get the identity operation
+    pub fn identity() -> SymmetryOperation {
+        SymmetryOperation {
+            rotation: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            translation: [0.0; 3],
+        }
+    }
+
+    /// Apply this operation to the given `fractional` coordinates
+    pub fn apply(&self, fractional: [f64; 3]) -> [f64; 3] {
+        let mut result = self.translation;
+        for (i, row) in self.rotation.iter().enumerate() {
+            for (j, value) in row.iter().enumerate() {
+                result[i] += value * fractional[j];
+            }
+        }
+        return result;
+    }
+}
+
+impl FromStr for SymmetryOperation {
+    type Err = Error;
+
+    fn from_str(operation: &str) -> Result<SymmetryOperation, Error> {
+        let invalid = |reason: &str| Error {
+            status: Status::FormatError,
+            message: format!("invalid symmetry operation '{operation}': {reason}"),
+        };
+
+        let components = operation.trim().trim_matches('\'').split(',').collect::<Vec<_>>();
+        if components.len() != 3 {
+            return Err(invalid("expected 3 components separated by commas"));
+        }
+
+        let mut result = SymmetryOperation {
+            rotation: [[0.0; 3]; 3],
+            translation: [0.0; 3],
+        };
+
+        for (i, component) in components.iter().enumerate() {
+            let component = component.chars().filter(|c| !c.is_whitespace()).collect::<String>();
+            if component.is_empty() {
+                return Err(invalid("empty component"));
+            }
+
+            // split the component in signed terms
+            let mut terms = Vec::new();
+            let mut start = 0;
+            for (position, c) in component.char_indices() {
+                if (c == '+' || c == '-') && position != start {
+                    terms.push(&component[start..position]);
+                    start = position;
+                }
+            }
+            terms.push(&component[start..]);
+
+            for term in terms {
+                let (sign, value) = match term.strip_prefix('-') {
+                    Some(value) => (-1.0, value),
+                    None => (1.0, term.strip_prefix('+').unwrap_or(term)),
+                };
+
+                match value.to_ascii_lowercase().as_str() {
+                    "x" => result.rotation[i][0] += sign,
+                    "y" => result.rotation[i][1] += sign,
+                    "z" => result.rotation[i][2] += sign,
+                    number => {
+                        let number = parse_fraction(number).ok_or_else(|| invalid(&format!("unknown term '{term}'")))?;
+                        result.translation[i] += sign * number;
+                    }
+                }
+            }
+        }
+
+        return Ok(result);
+    }
+}
+
+/// A crystallographic space group, identified by its Hermann-Mauguin name
+/// and optionally by its number in the International Tables for
+/// Crystallography.
+///
+/// # Example
+/// ```
+/// # use chemfiles::{Frame, SpaceGroup};
+/// let mut frame = Frame::new();
+/// frame.set_space_group(&SpaceGroup::new("P 21/c", 14));
+///
+/// let space_group = frame.space_group().unwrap();
+/// assert_eq!(space_group.name, "P 21/c");
+/// assert_eq!(space_group.number, Some(14));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpaceGroup {
+    /// Hermann-Mauguin name of the space group, e.g. `P 21/c`
+    pub name: String,
+    /// Number of the space group in the International Tables, between 1 and
+    /// 230
+    pub number: Option<u32>,
+}
+
+impl SpaceGroup {
+    /// Create a new `SpaceGroup` with the given `name` and optional `number`
+    pub fn new(name: &str, number: impl Into<Option<u32>>) -> SpaceGroup {
+        SpaceGroup {
+            name: name.into(),
+            number: number.into(),
+        }
+    }
+}
+
+/// Parse a number written as a decimal (`0.5`) or a fraction (`1/2`)
+fn parse_fraction(value: &str) -> Option<f64> {
+    match value.split_once('/') {
+        Some((numerator, denominator)) => {
+            let numerator = numerator.parse::<f64>().ok()?;
+            let denominator = denominator.parse::<f64>().ok()?;
+            if denominator == 0.0 {
+                None
+            } else {
+                Some(numerator / denominator)
+            }
+        }
+        None => value.parse().ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let operation: SymmetryOperation = "x,y,z".parse().unwrap();
+        assert_eq!(operation, SymmetryOperation::identity());
+
+        let operation: SymmetryOperation = "'-y+1/2, x-y, z+0.25'".parse().unwrap();
+        assert_eq!(operation.rotation, [[0.0, -1.0, 0.0], [1.0, -1.0, 0.0], [0.0, 0.0, 1.0]]);
+        assert_eq!(operation.translation, [0.5, 0.0, 0.25]);
+
+        let operation: SymmetryOperation = "1/2+X,-Y,Z".parse().unwrap();
+        assert_eq!(operation.apply([0.1, 0.2, 0.3]), [0.6, -0.2, 0.3]);
+
+        for invalid in ["x,y", "x,,z", "x,y,w", "x,y,z+1/0"] {
+            let error = invalid.parse::<SymmetryOperation>().unwrap_err();
+            assert_eq!(error.status, Status::FormatError);
+        }
+    }
+}
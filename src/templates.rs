@@ -0,0 +1,247 @@
+// Chemfiles, a modern library for chemistry file reading and writing
+// Copyright (C) 2015-2018 Guillaume Fraux -- BSD licensed
+
+//! Built-in library of bonds inside standard residues (amino acids,
+//! nucleotides and water), keyed by residue and atom names. This is used by
+//! `Topology::apply_residue_templates`.
+//!
+//! The templates use the PDB names for the heavy atoms, with a few common
+//! alternative names (`O1P` for `OP1`, `C5M` for `C7`, `HN` for `H`, ...).
+//! Hydrogens are only included for water and the protein backbone, since
+//! their names vary a lot between force fields.
+
+/// A list of bonds, as pairs of atom names
+type Bonds = &'static [(&'static str, &'static str)];
+
+/// Bonds in the backbone of all amino acids
+const AMINO_ACID_BACKBONE: Bonds = &[
+    ("N", "CA"),
+    ("CA", "C"),
+    ("C", "O"),
+    ("C", "OXT"),
+    ("C", "OT1"),
+    ("C", "OT2"),
+    ("N", "H"),
+    ("N", "HN"),
+    ("N", "H1"),
+    ("N", "H2"),
+    ("N", "H3"),
+    ("CA", "HA"),
+    ("CA", "HA2"),
+    ("CA", "HA3"),
+    ("CA", "CB"),
+];
+
+/// Bonds in the side chains of the standard amino acids
+const AMINO_ACID_SIDE_CHAINS: &[(&str, Bonds)] = &[
+    ("ALA", &[]),
+    ("GLY", &[]),
+    (
+        "ARG",
+        &[
+            ("CB", "CG"),
+            ("CG", "CD"),
+            ("CD", "NE"),
+            ("NE", "CZ"),
+            ("CZ", "NH1"),
+            ("CZ", "NH2"),
+        ],
+    ),
+    ("ASN", &[("CB", "CG"), ("CG", "OD1"), ("CG", "ND2")]),
+    ("ASP", &[("CB", "CG"), ("CG", "OD1"), ("CG", "OD2")]),
+    ("CYS", &[("CB", "SG")]),
+    ("GLN", &[("CB", "CG"), ("CG", "CD"), ("CD", "OE1"), ("CD", "NE2")]),
+    ("GLU", &[("CB", "CG"), ("CG", "CD"), ("CD", "OE1"), ("CD", "OE2")]),
+    (
+        "HIS",
+        &[
+            ("CB", "CG"),
+            ("CG", "ND1"),
+            ("ND1", "CE1"),
+            ("CE1", "NE2"),
+            ("NE2", "CD2"),
+            ("CD2", "CG"),
+        ],
+    ),
+    // CHARMM names the last carbon CD instead of CD1
+    ("ILE", &[("CB", "CG1"), ("CB", "CG2"), ("CG1", "CD1"), ("CG1", "CD")]),
+    ("LEU", &[("CB", "CG"), ("CG", "CD1"), ("CG", "CD2")]),
+    ("LYS", &[("CB", "CG"), ("CG", "CD"), ("CD", "CE"), ("CE", "NZ")]),
+    ("MET", &[("CB", "CG"), ("CG", "SD"), ("SD", "CE")]),
+    ("MSE", &[("CB", "CG"), ("CG", "SE"), ("SE", "CE")]),
+    (
+        "PHE",
+        &[
+            ("CB", "CG"),
+            ("CG", "CD1"),
+            ("CG", "CD2"),
+            ("CD1", "CE1"),
+            ("CD2", "CE2"),
+            ("CE1", "CZ"),
+            ("CE2", "CZ"),
+        ],
+    ),
+    ("PRO", &[("CB", "CG"), ("CG", "CD"), ("CD", "N")]),
+    ("SER", &[("CB", "OG")]),
+    ("THR", &[("CB", "OG1"), ("CB", "CG2")]),
+    (
+        "TRP",
+        &[
+            ("CB", "CG"),
+            ("CG", "CD1"),
+            ("CG", "CD2"),
+            ("CD1", "NE1"),
+            ("NE1", "CE2"),
+            ("CD2", "CE2"),
+            ("CD2", "CE3"),
+            ("CE2", "CZ2"),
+            ("CE3", "CZ3"),
+            ("CZ2", "CH2"),
+            ("CZ3", "CH2"),
+        ],
+    ),
+    (
+        "TYR",
+        &[
+            ("CB", "CG"),
+            ("CG", "CD1"),
+            ("CG", "CD2"),
+            ("CD1", "CE1"),
+            ("CD2", "CE2"),
+            ("CE1", "CZ"),
+            ("CE2", "CZ"),
+            ("CZ", "OH"),
+        ],
+    ),
+    ("VAL", &[("CB", "CG1"), ("CB", "CG2")]),
+];
+
+/// Alternative names for protonation states and variants of amino acids,
+/// sharing the heavy atoms of the standard residue
+const AMINO_ACID_VARIANTS: &[(&str, &str)] = &[
+    ("ASH", "ASP"),
+    ("CYX", "CYS"),
+    ("CYM", "CYS"),
+    ("GLH", "GLU"),
+    ("HID", "HIS"),
+    ("HIE", "HIS"),
+    ("HIP", "HIS"),
+    ("HSD", "HIS"),
+    ("HSE", "HIS"),
+    ("HSP", "HIS"),
+    ("LYN", "LYS"),
+];
+
+/// Bonds in the sugar-phosphate backbone of all nucleotides. The `O2'` atom
+/// is only present in RNA.
+const NUCLEOTIDE_BACKBONE: Bonds = &[
+    ("P", "OP1"),
+    ("P", "OP2"),
+    ("P", "O1P"),
+    ("P", "O2P"),
+    ("P", "OP3"),
+    ("P", "O5'"),
+    ("O5'", "C5'"),
+    ("C5'", "C4'"),
+    ("C4'", "O4'"),
+    ("C4'", "C3'"),
+    ("C3'", "O3'"),
+    ("C3'", "C2'"),
+    ("C2'", "O2'"),
+    ("C2'", "C1'"),
+    ("C1'", "O4'"),
+];
+
+/// Bonds in purine bases, and between the base and the sugar
+const PURINE: Bonds = &[
+    ("C1'", "N9"),
+    ("N9", "C8"),
+    ("C8", "N7"),
+    ("N7", "C5"),
+    ("C5", "C6"),
+    ("C6", "N1"),
+    ("N1", "C2"),
+    ("C2", "N3"),
+    ("N3", "C4"),
+    ("C4", "C5"),
+    ("C4", "N9"),
+];
+
+/// Bonds in pyrimidine bases, and between the base and the sugar
+const PYRIMIDINE: Bonds = &[
+    ("C1'", "N1"),
+    ("N1", "C2"),
+    ("C2", "O2"),
+    ("C2", "N3"),
+    ("N3", "C4"),
+    ("C4", "C5"),
+    ("C5", "C6"),
+    ("C6", "N1"),
+];
+
+/// Bonds specific to each nucleic base, in addition to the purine or
+/// pyrimidine ring
+const NUCLEOTIDE_BASES: &[(&str, Bonds, Bonds)] = &[
+    ("A", PURINE, &[("C6", "N6")]),
+    ("G", PURINE, &[("C6", "O6"), ("C2", "N2")]),
+    ("C", PYRIMIDINE, &[("C4", "N4")]),
+    ("U", PYRIMIDINE, &[("C4", "O4")]),
+    // the methyl carbon is named C5M in older PDB files
+    ("T", PYRIMIDINE, &[("C4", "O4"), ("C5", "C7"), ("C5", "C5M")]),
+];
+
+/// Bonds in water molecules, for the common names of water residues
+const WATER: Bonds = &[("O", "H1"), ("O", "H2"), ("OW", "HW1"), ("OW", "HW2")];
+
+/// Names of water residues
+const WATER_NAMES: &[&str] = &["HOH", "WAT", "SOL", "H2O", "TIP3", "TIP3P", "SPC"];
+
+/// Get the list of bonds (as pairs of atom names) inside a residue named
+/// `residue`, if this is one of the standard residues in the templates
+/// library.
+pub(crate) fn residue_bonds(residue: &str) -> Option<Vec<(&'static str, &'static str)>> {
+    if WATER_NAMES.contains(&residue) {
+        return Some(WATER.to_vec());
+    }
+
+    let amino_acid = AMINO_ACID_VARIANTS
+        .iter()
+        .find(|(variant, _)| *variant == residue)
+        .map_or(residue, |&(_, standard)| standard);
+    if let Some((_, side_chain)) = AMINO_ACID_SIDE_CHAINS.iter().find(|(name, _)| *name == amino_acid) {
+        let mut bonds = AMINO_ACID_BACKBONE.to_vec();
+        bonds.extend_from_slice(side_chain);
+        return Some(bonds);
+    }
+
+    // RNA nucleotides are named A, G, ... and DNA nucleotides DA, DG, ...
+    let base = residue.strip_prefix('D').unwrap_or(residue);
+    if let Some((_, ring, specific)) = NUCLEOTIDE_BASES.iter().find(|(name, _, _)| *name == base) {
+        let mut bonds = NUCLEOTIDE_BACKBONE.to_vec();
+        bonds.extend_from_slice(ring);
+        bonds.extend_from_slice(specific);
+        return Some(bonds);
+    }
+
+    return None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn templates() {
+        let bonds = residue_bonds("SER").unwrap();
+        assert!(bonds.contains(&("N", "CA")));
+        assert!(bonds.contains(&("CB", "OG")));
+
+        assert_eq!(residue_bonds("HID"), residue_bonds("HIS"));
+        assert_eq!(residue_bonds("DA").unwrap().len(), residue_bonds("A").unwrap().len());
+        assert!(residue_bonds("DT").unwrap().contains(&("C5", "C7")));
+        assert!(residue_bonds("HOH").unwrap().contains(&("O", "H1")));
+
+        assert_eq!(residue_bonds("LIG"), None);
+        assert_eq!(residue_bonds("D"), None);
+    }
+}
@@ -0,0 +1,251 @@
+// Chemfiles, a modern library for chemistry file reading and writing
+// Copyright (C) 2015-2018 Guillaume Fraux -- BSD licensed
+
+//! Utilities to write tests for code using chemfiles.
+//!
+//! This module is only available with the `test-utils` cargo feature. It
+//! contains functions creating small reference frames, comparing frames with
+//! tolerances (including against golden files stored on disk), and creating
+//! temporary trajectories.
+//!
+//! ```toml
+//! [dev-dependencies]
+//! chemfiles = {version = "0.10", features = ["test-utils"]}
+//! ```
+use std::path::{Path, PathBuf};
+
+use crate::{Atom, DiffOptions, Error, Frame, Trajectory, UnitCell};
+
+/// Name of the environment variable used to regenerate golden files in
+/// [`assert_matches_golden`].
+pub const UPDATE_GOLDEN_ENV: &str = "CHEMFILES_UPDATE_GOLDEN";
+
+/// Create a frame containing a single water molecule, with bonds between the
+/// oxygen and the hydrogen atoms, in a cubic unit cell of 10 Å.
+///
+/// # Example
+/// ```
+/// let frame = chemfiles::testing::water();
+/// assert_eq!(frame.size(), 3);
+/// assert_eq!(frame.topology().bonds(), vec![[0, 1], [0, 2]]);
+/// ```
+pub fn water() -> Frame {
+    let mut frame = Frame::new();
+    frame.set_cell(&UnitCell::new([10.0, 10.0, 10.0]));
+    frame.add_atom(&Atom::new("O"), [5.0, 5.0, 5.0], None);
+    frame.add_atom(&Atom::new("H"), [5.757, 5.586, 5.0], None);
+    frame.add_atom(&Atom::new("H"), [4.243, 5.586, 5.0], None);
+    frame.add_bond(0, 1);
+    frame.add_bond(0, 2);
+    return frame;
+}
+
+/// Create a frame containing `n * n * n` argon atoms on a simple cubic lattice
+/// with the given `spacing` (in Å), in a matching cubic unit cell.
+///
+/// # Example
+/// ```
+/// let frame = chemfiles::testing::argon_lattice(3, 4.0);
+/// assert_eq!(frame.size(), 27);
+/// assert_eq!(frame.cell().lengths(), [12.0, 12.0, 12.0]);
+/// assert_eq!(frame.positions()[1], [0.0, 0.0, 4.0]);
+/// ```
+#[allow(clippy::cast_precision_loss)]
+pub fn argon_lattice(n: usize, spacing: f64) -> Frame {
+    let mut frame = Frame::new();
+    let length = n as f64 * spacing;
+    frame.set_cell(&UnitCell::new([length, length, length]));
+
+    let argon = Atom::new("Ar");
+    for i in 0..n {
+        for j in 0..n {
+            for k in 0..n {
+                let position = [i as f64 * spacing, j as f64 * spacing, k as f64 * spacing];
+                frame.add_atom(&argon, position, None);
+            }
+        }
+    }
+    return frame;
+}
+
+/// Check that two frames are equal within the tolerances in `options`.
+///
+/// # Panics
+///
+/// If the frames are different, with a message listing all the differences.
+///
+/// # Example
+/// ```
+/// # use chemfiles::DiffOptions;
+/// use chemfiles::testing::{assert_frames_eq, water};
+///
+/// let mut frame = water();
+/// frame.positions_mut()[0][0] += 1e-9;
+/// assert_frames_eq(&frame, &water(), DiffOptions::default());
+/// ```
+#[track_caller]
+pub fn assert_frames_eq(left: &Frame, right: &Frame, options: DiffOptions) {
+    let diff = left.diff(right, options);
+    assert!(diff.is_empty(), "frames are different:\n{diff}");
+}
+
+/// Check that `frame` matches the first frame in the golden file at `path`,
+/// within the tolerances in `options`. The format of the golden file is guessed
+/// from its extension.
+///
+/// If the `CHEMFILES_UPDATE_GOLDEN` environment variable is set, the golden
+/// file is (re-)created from `frame` instead.
+///
+/// # Panics
+///
+/// If the golden file can not be read or written, or if the frames are
+/// different.
+///
+/// # Example
+/// ```no_run
+/// use chemfiles::DiffOptions;
+/// use chemfiles::testing::{assert_matches_golden, water};
+///
+/// let frame = water();
+/// assert_matches_golden(&frame, "tests/golden/water.xyz", DiffOptions::default());
+/// ```
+#[track_caller]
+pub fn assert_matches_golden(frame: &Frame, path: impl AsRef<Path>, options: DiffOptions) {
+    let path = path.as_ref();
+    if std::env::var_os(UPDATE_GOLDEN_ENV).is_some() {
+        let mut trajectory = Trajectory::open(path, 'w')
+            .unwrap_or_else(|error| panic!("failed to create golden file at {}: {error}", path.display()));
+        trajectory
+            .write(frame)
+            .unwrap_or_else(|error| panic!("failed to write golden file at {}: {error}", path.display()));
+        return;
+    }
+
+    let golden = read_first_frame(path).unwrap_or_else(|error| {
+        panic!(
+            "failed to read golden file at {}: {error}. Set {UPDATE_GOLDEN_ENV} to create it",
+            path.display()
+        )
+    });
+
+    let diff = golden.diff(frame, options);
+    assert!(
+        diff.is_empty(),
+        "frame does not match golden file at {}:\n{diff}",
+        path.display()
+    );
+}
+
+fn read_first_frame(path: &Path) -> Result<Frame, Error> {
+    let mut trajectory = Trajectory::open(path, 'r')?;
+    let mut frame = Frame::new();
+    trajectory.read(&mut frame)?;
+    return Ok(frame);
+}
+
+/// A trajectory file stored in a temporary directory, which is removed when
+/// this value is dropped.
+///
+/// # Example
+/// ```
+/// use chemfiles::testing::{water, TempTrajectory};
+///
+/// let file = TempTrajectory::with_frames("xyz", &[water(), water()]).unwrap();
+///
+/// let mut trajectory = file.open('r').unwrap();
+/// assert_eq!(trajectory.nsteps(), 2);
+/// ```
+#[derive(Debug)]
+pub struct TempTrajectory {
+    // keep the directory alive as long as the trajectory
+    _directory: tempfile::TempDir,
+    path: PathBuf,
+}
+
+impl TempTrajectory {
+    /// Create a new (non-existing) temporary trajectory path, using the given
+    /// file `extension` to select the format.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the temporary directory can not be created.
+    pub fn new(extension: &str) -> Result<TempTrajectory, std::io::Error> {
+        let directory = tempfile::tempdir()?;
+        let path = directory.path().join(format!("trajectory.{extension}"));
+        return Ok(TempTrajectory {
+            _directory: directory,
+            path,
+        });
+    }
+
+    /// Create a new temporary trajectory with the given file `extension`,
+    /// containing all the `frames`.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the temporary directory can not be created, or
+    /// if writing the frames fails.
+    pub fn with_frames(extension: &str, frames: &[Frame]) -> Result<TempTrajectory, Error> {
+        let file = TempTrajectory::new(extension).map_err(|error| Error {
+            status: crate::Status::FileError,
+            message: format!("failed to create temporary directory: {error}"),
+        })?;
+
+        let mut trajectory = file.open('w')?;
+        for frame in frames {
+            trajectory.write(frame)?;
+        }
+        return Ok(file);
+    }
+
+    /// Get the path to this temporary trajectory
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Open this temporary trajectory with the given `mode`
+    ///
+    /// # Errors
+    ///
+    /// See [`Trajectory::open`].
+    pub fn open(&self, mode: char) -> Result<Trajectory, Error> {
+        return Trajectory::open(&self.path, mode);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames() {
+        let frame = water();
+        assert_eq!(frame.size(), 3);
+        assert_eq!(frame.atom(0).name(), "O");
+
+        let frame = argon_lattice(2, 3.0);
+        assert_eq!(frame.size(), 8);
+        assert_eq!(frame.positions()[7], [3.0, 3.0, 3.0]);
+    }
+
+    #[test]
+    fn golden() {
+        // XYZ files do not store bonds, use a frame without any
+        let file = TempTrajectory::with_frames("xyz", &[argon_lattice(2, 3.0)]).unwrap();
+        assert!(file.path().exists());
+        assert_matches_golden(&argon_lattice(2, 3.0), file.path(), DiffOptions::default());
+
+        let mut frame = argon_lattice(2, 3.0);
+        frame.positions_mut()[1][0] = 6.0;
+        let result = std::panic::catch_unwind(|| {
+            assert_matches_golden(&frame, file.path(), DiffOptions::default());
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "different number of atoms: 3 != 8")]
+    fn frames_eq() {
+        assert_frames_eq(&water(), &argon_lattice(2, 1.0), DiffOptions::default());
+    }
+}
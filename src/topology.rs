@@ -1,14 +1,31 @@
 // Chemfiles, a modern library for chemistry file reading and writing
 // Copyright (C) 2015-2018 Guillaume Fraux -- BSD licensed
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::marker::PhantomData;
 use std::ops::{Deref, Drop};
+use std::str::FromStr;
 
 use chemfiles_sys as ffi;
 
-use crate::errors::{check, check_not_null, check_success, Error};
+use crate::errors::{check, check_not_null, check_success, Error, Status};
+use crate::naming::NameMap;
+use crate::Property;
 use crate::{Atom, AtomMut, AtomRef};
 use crate::{Residue, ResidueRef};
 
+/// Approximate size in bytes of a C++ `Topology` without any atom
+const TOPOLOGY_SIZE: usize = 256;
+/// Approximate size in bytes of a C++ `Atom` (name, type, mass, charge and
+/// properties map)
+const ATOM_SIZE: usize = 136;
+/// Approximate size in bytes of a bond and its bond order, stored in sorted
+/// vectors in the C++ `Connectivity`
+const BOND_SIZE: usize = 20;
+/// Approximate size in bytes of a C++ `Residue` without any atom
+const RESIDUE_SIZE: usize = 128;
+/// Approximate size in bytes of a single property and its name
+const PROPERTY_SIZE: usize = 96;
+
 /// Possible bond order associated with bonds
 #[repr(C)]
 #[non_exhaustive]
@@ -47,6 +64,80 @@ impl BondOrder {
     }
 }
 
+impl BondOrder {
+    /// Get the numeric value of this bond order, as used by many chemistry
+    /// toolkits. Aromatic bonds have a bond order of 1.5, amide bonds are
+    /// considered as single bonds, and unknown bonds have a bond order of 0.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::BondOrder;
+    /// assert_eq!(BondOrder::Double.as_f64(), 2.0);
+    /// assert_eq!(BondOrder::Aromatic.as_f64(), 1.5);
+    /// assert_eq!(BondOrder::Unknown.as_f64(), 0.0);
+    /// ```
+    pub fn as_f64(self) -> f64 {
+        match self {
+            BondOrder::Unknown => 0.0,
+            BondOrder::Single | BondOrder::Amide => 1.0,
+            BondOrder::Aromatic => 1.5,
+            BondOrder::Double => 2.0,
+            BondOrder::Triple => 3.0,
+            BondOrder::Quadruple => 4.0,
+            BondOrder::Quintuplet => 5.0,
+        }
+    }
+}
+
+impl TryFrom<u8> for BondOrder {
+    type Error = Error;
+
+    /// Get the bond order corresponding to an integer value, with 0 being an
+    /// unknown bond order, 1 a single bond, *etc.* up to 5 for quintuplet
+    /// bonds.
+    fn try_from(value: u8) -> Result<BondOrder, Error> {
+        match value {
+            0 => Ok(BondOrder::Unknown),
+            1 => Ok(BondOrder::Single),
+            2 => Ok(BondOrder::Double),
+            3 => Ok(BondOrder::Triple),
+            4 => Ok(BondOrder::Quadruple),
+            5 => Ok(BondOrder::Quintuplet),
+            _ => Err(Error {
+                status: Status::InvalidArgument,
+                message: format!("invalid bond order {value}, expected a value between 0 and 5"),
+            }),
+        }
+    }
+}
+
+impl FromStr for BondOrder {
+    type Err = Error;
+
+    /// Parse a bond order from its name (`"single"`, `"aromatic"`, ...), its
+    /// integer value (`"1"`, `"2"`, ...) or the corresponding MOL2 bond type
+    /// (`"ar"`, `"am"`, `"un"`). The parsing is case insensitive.
+    fn from_str(value: &str) -> Result<BondOrder, Error> {
+        let order = match value.trim().to_ascii_lowercase().as_str() {
+            "unknown" | "un" | "0" => BondOrder::Unknown,
+            "single" | "1" => BondOrder::Single,
+            "double" | "2" => BondOrder::Double,
+            "triple" | "3" => BondOrder::Triple,
+            "quadruple" | "4" => BondOrder::Quadruple,
+            "quintuplet" | "5" => BondOrder::Quintuplet,
+            "amide" | "am" => BondOrder::Amide,
+            "aromatic" | "ar" => BondOrder::Aromatic,
+            _ => {
+                return Err(Error {
+                    status: Status::InvalidArgument,
+                    message: format!("unknown bond order '{value}'"),
+                })
+            }
+        };
+        return Ok(order);
+    }
+}
+
 impl From<ffi::chfl_bond_order> for BondOrder {
     fn from(order: ffi::chfl_bond_order) -> BondOrder {
         match order {
@@ -237,6 +328,35 @@ impl Topology {
         }
     }
 
+    /// Reserve memory for at least `natoms` atoms in this topology, without
+    /// changing its size. This avoids repeated reallocations when adding
+    /// atoms one by one with [`Topology::add_atom`] and the final size is
+    /// known in advance.
+    ///
+    /// The C API does not expose capacity management, so this function
+    /// resizes the topology to `natoms` and then back to its initial size,
+    /// which keeps the allocated memory around. There is no equivalent for
+    /// bonds. This function does nothing if the topology already contains
+    /// `natoms` atoms or more.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Topology, Atom};
+    /// let mut topology = Topology::new();
+    /// topology.reserve(1000);
+    /// assert_eq!(topology.size(), 0);
+    ///
+    /// topology.add_atoms(&Atom::new("Ar"), 1000);
+    /// assert_eq!(topology.size(), 1000);
+    /// ```
+    pub fn reserve(&mut self, natoms: usize) {
+        let size = self.size();
+        if natoms > size {
+            self.resize(natoms);
+            self.resize(size);
+        }
+    }
+
     /// Add an `Atom` at the end of this topology
     ///
     /// # Example
@@ -254,6 +374,25 @@ impl Topology {
         }
     }
 
+    /// Add `count` copies of the `atom` at the end of this topology.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Topology, Atom};
+    /// let mut topology = Topology::new();
+    /// topology.add_atoms(&Atom::new("Ar"), 1000);
+    ///
+    /// assert_eq!(topology.size(), 1000);
+    /// assert_eq!(topology.atom(999).name(), "Ar");
+    /// ```
+    pub fn add_atoms(&mut self, atom: &Atom, count: usize) {
+        for _ in 0..count {
+            unsafe {
+                check_success(ffi::chfl_topology_add_atom(self.as_mut_ptr(), atom.as_ptr()));
+            }
+        }
+    }
+
     /// Remove an `Atom` from this topology by `index`. This modify all the
     /// other atoms indexes.
     ///
@@ -598,6 +737,36 @@ impl Topology {
         return order.into();
     }
 
+    /// Set the bond order of the existing bond between the atoms at indexes
+    /// `i` and `j` to `order`.
+    ///
+    /// # Panics
+    ///
+    /// If there is no bond between the atoms at indexes `i` and `j`.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Topology, BondOrder};
+    /// let mut topology = Topology::new();
+    /// topology.resize(2);
+    ///
+    /// topology.add_bond(0, 1);
+    /// assert_eq!(topology.bond_order(0, 1), BondOrder::Unknown);
+    ///
+    /// topology.set_bond_order(1, 0, BondOrder::Triple);
+    /// assert_eq!(topology.bond_order(0, 1), BondOrder::Triple);
+    /// ```
+    pub fn set_bond_order(&mut self, i: usize, j: usize, order: BondOrder) {
+        let bond = if i < j { [i, j] } else { [j, i] };
+        assert!(
+            self.bonds().binary_search(&bond).is_ok(),
+            "there is no bond between atoms {i} and {j} in `Topology::set_bond_order`"
+        );
+        // adding an already existing bond does not change its order
+        self.remove_bond(i, j);
+        self.add_bond_with_order(i, j, order);
+    }
+
     /// Get the bond order for all the bonds in the topology
     ///
     /// # Example
@@ -682,6 +851,120 @@ impl Topology {
         }
     }
 
+    /// Get a reference to the first residue with the given `id` in this
+    /// topology, if any.
+    ///
+    /// Residue ids are not always unique, for example PDB files can contain
+    /// residues with the same id in different chains. In this case, the
+    /// `"chainid"` property of the residues can be used to distinguish them.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Topology, Residue};
+    /// let mut topology = Topology::new();
+    /// topology.add_residue(&Residue::with_id("ALA", 153)).unwrap();
+    /// topology.add_residue(&Residue::with_id("GLY", 154)).unwrap();
+    ///
+    /// let residue = topology.residue_by_id(154).unwrap();
+    /// assert_eq!(residue.name(), "GLY");
+    ///
+    /// assert!(topology.residue_by_id(3).is_none());
+    /// ```
+    pub fn residue_by_id(&self, id: i64) -> Option<ResidueRef<'_>> {
+        #[allow(clippy::cast_possible_truncation)]
+        let count = self.residues_count() as usize;
+        return (0..count)
+            .filter_map(|i| self.residue(i))
+            .find(|residue| residue.id() == Some(id));
+    }
+
+    /// Get the indexes of all the residues named `name` in this topology.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Topology, Residue};
+    /// let mut topology = Topology::new();
+    /// topology.add_residue(&Residue::with_id("WAT", 1)).unwrap();
+    /// topology.add_residue(&Residue::with_id("ALA", 2)).unwrap();
+    /// topology.add_residue(&Residue::with_id("WAT", 3)).unwrap();
+    ///
+    /// assert_eq!(topology.residues_named("WAT"), vec![0, 2]);
+    /// assert!(topology.residues_named("GLY").is_empty());
+    /// ```
+    pub fn residues_named(&self, name: &str) -> Vec<usize> {
+        #[allow(clippy::cast_possible_truncation)]
+        let count = self.residues_count() as usize;
+        return (0..count)
+            .filter(|&i| self.residue(i).map_or(false, |residue| residue.name() == name))
+            .collect();
+    }
+
+    /// Get the one-letter amino acid sequence of the residues in this
+    /// topology, in the order of the residues.
+    ///
+    /// If `chain` is `Some`, only residues with a `"chainid"` property equal to
+    /// `chain` are used. Residues which are not amino acids according to
+    /// [`naming::one_letter_code`] (water, ions, ligands, ...) are skipped.
+    /// Use [`Topology::sequence_with`] to handle non-standard residues.
+    ///
+    /// [`naming::one_letter_code`]: crate::naming::one_letter_code
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Topology, Residue};
+    /// let mut topology = Topology::new();
+    /// for (i, name) in ["MET", "LYS", "HOH", "GLY"].iter().enumerate() {
+    ///     topology.add_residue(&Residue::with_id(*name, i as i64)).unwrap();
+    /// }
+    ///
+    /// assert_eq!(topology.sequence(None), "MKG");
+    /// ```
+    pub fn sequence(&self, chain: Option<&str>) -> String {
+        return self.sequence_with(chain, crate::naming::one_letter_code);
+    }
+
+    /// Get the one-letter sequence of the residues in this topology, using
+    /// `code` to get the letter corresponding to a residue name.
+    ///
+    /// Residues for which `code` returns `None` are skipped. If `chain` is
+    /// `Some`, only residues with a `"chainid"` property equal to `chain` are
+    /// used.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Topology, Residue};
+    /// # use chemfiles::naming::one_letter_code;
+    /// let mut topology = Topology::new();
+    /// for (i, name) in ["ALA", "NLE", "HOH"].iter().enumerate() {
+    ///     topology.add_residue(&Residue::with_id(*name, i as i64)).unwrap();
+    /// }
+    ///
+    /// // represent norleucine as L, and keep other unknown residues as X
+    /// let sequence = topology.sequence_with(None, |name| match name {
+    ///     "NLE" => Some('L'),
+    ///     "HOH" => None,
+    ///     _ => Some(one_letter_code(name).unwrap_or('X')),
+    /// });
+    /// assert_eq!(sequence, "AL");
+    /// ```
+    pub fn sequence_with(&self, chain: Option<&str>, code: impl Fn(&str) -> Option<char>) -> String {
+        #[allow(clippy::cast_possible_truncation)]
+        let count = self.residues_count() as usize;
+        let mut sequence = String::new();
+        for residue in (0..count).filter_map(|i| self.residue(i)) {
+            if let Some(chain) = chain {
+                match residue.get("chainid") {
+                    Some(Property::String(ref id)) if id == chain => {}
+                    _ => continue,
+                }
+            }
+            if let Some(letter) = code(&residue.name()) {
+                sequence.push(letter);
+            }
+        }
+        return sequence;
+    }
+
     /// Get a copy of the residue containing the atom at index `index` in this
     /// topology, if any.
     ///
@@ -711,6 +994,33 @@ impl Topology {
         }
     }
 
+    /// Get the index of the residue containing the atom at index `index` in
+    /// this topology, if any.
+    ///
+    /// This is the index to use with [`Topology::residue`], which is not
+    /// always the same as the residue `id`.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Topology, Residue};
+    /// let mut topology = Topology::new();
+    /// topology.resize(8);
+    ///
+    /// topology.add_residue(&Residue::new("water")).unwrap();
+    /// let mut residue = Residue::new("methane");
+    /// residue.add_atom(3);
+    /// residue.add_atom(4);
+    /// topology.add_residue(&residue).unwrap();
+    ///
+    /// assert_eq!(topology.residue_index_for_atom(4), Some(1));
+    /// assert_eq!(topology.residue_index_for_atom(6), None);
+    /// ```
+    pub fn residue_index_for_atom(&self, index: usize) -> Option<usize> {
+        #[allow(clippy::cast_possible_truncation)]
+        let count = self.residues_count() as usize;
+        return (0..count).find(|&i| self.residue(i).map_or(false, |residue| residue.contains(index)));
+    }
+
     /// Get the number of residues in this topology.
     ///
     /// # Example
@@ -755,6 +1065,9 @@ impl Topology {
     /// linked together, *i.e.* if there is a bond between one atom in the
     /// first residue and one atom in the second one.
     ///
+    /// The residues returned by [`Topology::residue`] can be used directly
+    /// here, without cloning them. See also [`Topology::are_linked_by_index`].
+    ///
     /// # Example
     /// ```
     /// # use chemfiles::{Topology, Residue};
@@ -779,6 +1092,723 @@ impl Topology {
         }
         return linked != 0;
     }
+
+    /// Check if the residues at indexes `first` and `second` in this topology
+    /// are linked together, *i.e.* if there is a bond between one atom in the
+    /// first residue and one atom in the second one.
+    ///
+    /// # Panics
+    ///
+    /// If `first` or `second` is out of bounds.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Topology, Residue};
+    /// let mut topology = Topology::new();
+    /// topology.resize(4);
+    ///
+    /// let mut residue = Residue::with_id("A", 1);
+    /// residue.add_atom(0);
+    /// residue.add_atom(1);
+    /// topology.add_residue(&residue).unwrap();
+    ///
+    /// let mut residue = Residue::with_id("B", 2);
+    /// residue.add_atom(2);
+    /// residue.add_atom(3);
+    /// topology.add_residue(&residue).unwrap();
+    ///
+    /// assert!(!topology.are_linked_by_index(0, 1));
+    /// topology.add_bond(1, 2);
+    /// assert!(topology.are_linked_by_index(0, 1));
+    /// ```
+    pub fn are_linked_by_index(&self, first: usize, second: usize) -> bool {
+        let count = self.residues_count();
+        let first = self
+            .residue(first)
+            .unwrap_or_else(|| panic!("residue index {first} is out of bounds (count is {count})"));
+        let second = self
+            .residue(second)
+            .unwrap_or_else(|| panic!("residue index {second} is out of bounds (count is {count})"));
+        return self.are_linked(&first, &second);
+    }
+
+    /// Get the list of bonds between atoms in the residue at index
+    /// `residue` in this topology.
+    ///
+    /// # Panics
+    ///
+    /// If `residue` is out of bounds.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Topology, Residue};
+    /// let mut topology = Topology::new();
+    /// topology.resize(4);
+    ///
+    /// let mut residue = Residue::new("first");
+    /// residue.add_atom(0);
+    /// residue.add_atom(1);
+    /// topology.add_residue(&residue).unwrap();
+    ///
+    /// topology.add_bond(0, 1);
+    /// topology.add_bond(1, 2);
+    /// assert_eq!(topology.bonds_in_residue(0), vec![[0, 1]]);
+    /// ```
+    pub fn bonds_in_residue(&self, residue: usize) -> Vec<[usize; 2]> {
+        let atoms = self.residue_atoms(residue, "bonds_in_residue");
+        return self
+            .bonds()
+            .into_iter()
+            .filter(|bond| atoms.contains(&bond[0]) && atoms.contains(&bond[1]))
+            .collect();
+    }
+
+    /// Get the list of bonds between an atom in the residue at index `first`
+    /// and an atom in the residue at index `second` in this topology.
+    ///
+    /// This gives the atoms linking the two residues when
+    /// [`Topology::are_linked`] is `true`.
+    ///
+    /// # Panics
+    ///
+    /// If `first` or `second` are out of bounds.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Topology, Residue};
+    /// let mut topology = Topology::new();
+    /// topology.resize(4);
+    ///
+    /// let mut residue = Residue::with_id("first", 1);
+    /// residue.add_atom(0);
+    /// residue.add_atom(1);
+    /// topology.add_residue(&residue).unwrap();
+    ///
+    /// let mut residue = Residue::with_id("second", 2);
+    /// residue.add_atom(2);
+    /// residue.add_atom(3);
+    /// topology.add_residue(&residue).unwrap();
+    ///
+    /// topology.add_bond(0, 1);
+    /// topology.add_bond(1, 2);
+    /// topology.add_bond(2, 3);
+    /// assert_eq!(topology.bonds_between(0, 1), vec![[1, 2]]);
+    /// ```
+    pub fn bonds_between(&self, first: usize, second: usize) -> Vec<[usize; 2]> {
+        let first = self.residue_atoms(first, "bonds_between");
+        let second = self.residue_atoms(second, "bonds_between");
+        return self
+            .bonds()
+            .into_iter()
+            .filter(|bond| {
+                (first.contains(&bond[0]) && second.contains(&bond[1]))
+                    || (first.contains(&bond[1]) && second.contains(&bond[0]))
+            })
+            .collect();
+    }
+
+    /// Set the charges of the atoms in this topology from a `map` associating
+    /// (residue name, atom name) pairs to charges, and return the number of
+    /// atoms whose charge was set.
+    ///
+    /// Atoms which are not part of a residue, or without an entry in the map,
+    /// keep their current charge. See [`ChargeTable`] to read such map from
+    /// a file.
+    ///
+    /// [`ChargeTable`]: crate::ChargeTable
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use chemfiles::{Topology, Atom, Residue};
+    /// let mut topology = Topology::new();
+    /// topology.add_atom(&Atom::new("O"));
+    /// topology.add_atom(&Atom::new("H"));
+    /// topology.add_atom(&Atom::new("Na"));
+    ///
+    /// let mut residue = Residue::new("HOH");
+    /// residue.add_atom(0);
+    /// residue.add_atom(1);
+    /// topology.add_residue(&residue).unwrap();
+    ///
+    /// let mut charges = HashMap::new();
+    /// charges.insert(("HOH", "O"), -0.834);
+    /// charges.insert(("HOH", "H"), 0.417);
+    ///
+    /// assert_eq!(topology.apply_charges(&charges), 2);
+    /// assert_eq!(topology.atom(0).charge(), -0.834);
+    /// assert_eq!(topology.atom(2).charge(), 0.0);
+    /// ```
+    pub fn apply_charges(&mut self, map: &HashMap<(&str, &str), f64>) -> usize {
+        let mut count = 0;
+        for i in 0..self.size() {
+            let residue = match self.residue_for_atom(i) {
+                Some(residue) => residue.name(),
+                None => continue,
+            };
+            let name = self.atom(i).name();
+            if let Some(&charge) = map.get(&(residue.as_str(), name.as_str())) {
+                self.atom_mut(i).set_charge(charge);
+                count += 1;
+            }
+        }
+        return count;
+    }
+
+    /// Rename all atoms in this topology using the `map` function, and return
+    /// the number of renamed atoms.
+    ///
+    /// `map` is called with the current name of each atom, and should return
+    /// the new name, or `None` to keep the current name.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Topology, Atom};
+    /// let mut topology = Topology::new();
+    /// topology.add_atom(&Atom::new("OW"));
+    /// topology.add_atom(&Atom::new("HW1"));
+    ///
+    /// let count = topology.rename_atoms(&|name| name.strip_suffix('W').map(str::to_owned));
+    /// assert_eq!(count, 1);
+    /// assert_eq!(topology.atom(0).name(), "O");
+    /// assert_eq!(topology.atom(1).name(), "HW1");
+    /// ```
+    pub fn rename_atoms(&mut self, map: &dyn Fn(&str) -> Option<String>) -> usize {
+        let mut count = 0;
+        for i in 0..self.size() {
+            if let Some(name) = map(&self.atom(i).name()) {
+                self.atom_mut(i).set_name(&*name);
+                count += 1;
+            }
+        }
+        return count;
+    }
+
+    /// Rename all atoms in this topology using the rules in `map`, taking the
+    /// name of the residue containing each atom into account. This returns the
+    /// number of renamed atoms.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Topology, Atom, Residue};
+    /// # use chemfiles::naming::NameMap;
+    /// let mut topology = Topology::new();
+    /// topology.add_atom(&Atom::new("HN"));
+    /// topology.add_atom(&Atom::new("HB1"));
+    /// topology.add_atom(&Atom::new("HB2"));
+    ///
+    /// let mut residue = Residue::new("SER");
+    /// residue.add_atom(0);
+    /// residue.add_atom(1);
+    /// residue.add_atom(2);
+    /// topology.add_residue(&residue).unwrap();
+    ///
+    /// assert_eq!(topology.rename_atoms_with(&NameMap::charmm_to_amber()), 3);
+    /// assert_eq!(topology.atom(0).name(), "H");
+    /// assert_eq!(topology.atom(1).name(), "HB2");
+    /// assert_eq!(topology.atom(2).name(), "HB3");
+    /// ```
+    pub fn rename_atoms_with(&mut self, map: &NameMap) -> usize {
+        let mut count = 0;
+        for i in 0..self.size() {
+            let residue = self.residue_for_atom(i).map(|residue| residue.name());
+            let name = self.atom(i).name();
+            if let Some(new_name) = map.get(residue.as_deref(), &name) {
+                self.atom_mut(i).set_name(new_name);
+                count += 1;
+            }
+        }
+        return count;
+    }
+
+    /// Set the type of all atoms in this topology to the chemical element
+    /// guessed from the atom name, and return the number of atoms whose type
+    /// was set.
+    ///
+    /// Digits around the name are ignored (`"1HB"` and `"Cl2"` are hydrogen
+    /// and chlorine). For atoms in standard protein, nucleic acid and water
+    /// residues, upper case names are interpreted as PDB names, and the
+    /// element is given by the first letter (`"CA"` is a carbon). Otherwise,
+    /// two letters element are used when the second letter is lower case,
+    /// when the atom is alone in a residue with the same name (`"CA"` ions),
+    /// or when the atom is not in a residue (`"CA"` from an XYZ file is
+    /// calcium). Atoms for which no element can be guessed are left unchanged.
+    ///
+    /// The masses are not modified, use [`Topology::guess_masses`] to update
+    /// them. Radii are always computed from the atom type.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Topology, Atom, Residue};
+    /// let mut topology = Topology::new();
+    /// topology.add_atom(&Atom::new("CA"));
+    /// topology.add_atom(&Atom::new("1HB"));
+    /// topology.add_atom(&Atom::new("Cl2"));
+    ///
+    /// let mut residue = Residue::new("ALA");
+    /// residue.add_atom(0);
+    /// residue.add_atom(1);
+    /// topology.add_residue(&residue).unwrap();
+    ///
+    /// assert_eq!(topology.guess_types_from_names(), 3);
+    /// assert_eq!(topology.atom(0).atomic_type(), "C");
+    /// assert_eq!(topology.atom(1).atomic_type(), "H");
+    /// assert_eq!(topology.atom(2).atomic_type(), "Cl");
+    /// ```
+    pub fn guess_types_from_names(&mut self) -> usize {
+        let mut count = 0;
+        for i in 0..self.size() {
+            let residue = self.residue_for_atom(i).map(|residue| (residue.name(), residue.size()));
+            let name = self.atom(i).name();
+            let residue = residue.as_ref().map(|(name, size)| (name.as_str(), *size));
+            if let Some(element) = guess_element(&name, residue) {
+                self.atom_mut(i).set_atomic_type(&*element);
+                count += 1;
+            }
+        }
+        return count;
+    }
+
+    /// Set the mass of all atoms in this topology from their atomic type, and
+    /// return the number of atoms whose mass was set.
+    ///
+    /// Atoms with a type which is not a known element, and atoms flagged as
+    /// virtual sites (see [`Atom::set_virtual_site`]) keep their current
+    /// mass. Combined with [`Topology::guess_types_from_names`], this allows
+    /// to get physical masses for topologies read from formats without
+    /// masses, as needed to compute centers of mass or temperatures.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Topology, Atom};
+    /// let mut topology = Topology::new();
+    /// let mut atom = Atom::new("O");
+    /// atom.set_mass(0.0);
+    /// topology.add_atom(&atom);
+    ///
+    /// assert_eq!(topology.guess_masses(), 1);
+    /// assert_eq!(topology.atom(0).mass(), 15.999);
+    /// ```
+    pub fn guess_masses(&mut self) -> usize {
+        let mut count = 0;
+        for i in 0..self.size() {
+            let atom = self.atom(i);
+            if atom.has_virtual_site_flag() {
+                continue;
+            }
+
+            let element = Atom::new(&*atom.atomic_type());
+            if element.atomic_number() != 0 {
+                self.atom_mut(i).set_mass(element.mass());
+                count += 1;
+            }
+        }
+        return count;
+    }
+
+    /// Get the indexes of all the virtual sites in this topology, *i.e.* the
+    /// atoms flagged as virtual sites or with a zero mass. See
+    /// [`Atom::is_virtual_site`] for more information.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Topology, Atom};
+    /// let mut topology = Topology::new();
+    /// topology.add_atom(&Atom::new("OW"));
+    /// topology.add_atom(&Atom::new("HW1"));
+    /// topology.add_atom(&Atom::new("HW2"));
+    /// topology.add_atom(&Atom::new("MW"));
+    /// for i in 0..3 {
+    ///     topology.atom_mut(i).set_mass(1.0);
+    /// }
+    ///
+    /// assert_eq!(topology.virtual_sites(), vec![3]);
+    /// ```
+    pub fn virtual_sites(&self) -> Vec<usize> {
+        return (0..self.size()).filter(|&i| self.atom(i).is_virtual_site()).collect();
+    }
+
+    /// Create residues for all the atoms not already in a residue, using the
+    /// bonds in this topology, and return the number of residues created.
+    ///
+    /// Each group of bonded atoms becomes a separate residue, and atoms without
+    /// bonds are put in their own residue. Water molecules are named `"HOH"`,
+    /// single atoms (typically ions) are named after their upper case atomic
+    /// type, and all the other residues are named `"MOL"`. The residues ids
+    /// start after the largest id already in the topology.
+    ///
+    /// This is useful before writing topologies from formats without residues
+    /// (XYZ, ...) to formats where residues are expected (PDB, ...). Bonds can
+    /// be guessed from positions first with [`Frame::guess_bonds`].
+    ///
+    /// [`Frame::guess_bonds`]: crate::Frame::guess_bonds
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Topology, Atom};
+    /// let mut topology = Topology::new();
+    /// topology.add_atom(&Atom::new("O"));
+    /// topology.add_atom(&Atom::new("H"));
+    /// topology.add_atom(&Atom::new("H"));
+    /// topology.add_atom(&Atom::new("Na"));
+    /// topology.add_bond(0, 1);
+    /// topology.add_bond(0, 2);
+    ///
+    /// assert_eq!(topology.guess_residues(), 2);
+    /// assert_eq!(topology.residue(0).unwrap().name(), "HOH");
+    /// assert_eq!(topology.residue(0).unwrap().atoms(), vec![0, 1, 2]);
+    /// assert_eq!(topology.residue(1).unwrap().name(), "NA");
+    /// ```
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn guess_residues(&mut self) -> usize {
+        let natoms = self.size();
+        let mut in_residue = vec![false; natoms];
+        let mut next_id = 1;
+        for i in 0..self.residues_count() {
+            let residue = self.residue(i as usize).expect("missing residue");
+            for atom in residue.atoms() {
+                in_residue[atom] = true;
+            }
+            if let Some(id) = residue.id() {
+                next_id = next_id.max(id + 1);
+            }
+        }
+
+        // find connected groups of atoms with a disjoint-set forest
+        let mut parents = (0..natoms).collect::<Vec<_>>();
+        for [i, j] in self.bonds() {
+            let (root_i, root_j) = (root(&mut parents, i), root(&mut parents, j));
+            parents[root_i.max(root_j)] = root_i.min(root_j);
+        }
+
+        let mut groups = BTreeMap::<usize, Vec<usize>>::new();
+        for atom in (0..natoms).filter(|&atom| !in_residue[atom]) {
+            groups.entry(root(&mut parents, atom)).or_default().push(atom);
+        }
+
+        let count = groups.len();
+        for (atoms, id) in groups.into_values().zip(next_id..) {
+            let name = self.guess_residue_name(&atoms);
+            let mut residue = Residue::with_id(&*name, id);
+            for atom in atoms {
+                residue.add_atom(atom);
+            }
+            self.add_residue(&residue)
+                .expect("failed to add residue in `Topology::guess_residues`");
+        }
+        return count;
+    }
+
+    /// Add the bonds inside standard residues (amino acids, nucleotides and
+    /// water) using a built-in library of templates, keyed by residue and atom
+    /// names. This returns the number of bonds added to the topology.
+    ///
+    /// This is cheaper and more reliable than guessing bonds from distances
+    /// with [`Frame::guess_bonds`] for biomolecules, but only adds bonds
+    /// inside residues: bonds between residues (peptide bonds, disulfide
+    /// bridges, ...) and bonds in non-standard residues are not added. The
+    /// templates use PDB atom names, and only include hydrogens for water and
+    /// the protein backbone. Atoms missing from a residue are ignored.
+    ///
+    /// [`Frame::guess_bonds`]: crate::Frame::guess_bonds
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Topology, Atom, Residue};
+    /// let mut topology = Topology::new();
+    /// let mut residue = Residue::with_id("SER", 1);
+    /// for (i, name) in ["N", "CA", "C", "O", "CB", "OG"].iter().enumerate() {
+    ///     topology.add_atom(&Atom::new(*name));
+    ///     residue.add_atom(i);
+    /// }
+    /// topology.add_residue(&residue).unwrap();
+    ///
+    /// assert_eq!(topology.apply_residue_templates(), 5);
+    /// assert_eq!(topology.bonds(), vec![[0, 1], [1, 2], [1, 4], [2, 3], [4, 5]]);
+    /// ```
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn apply_residue_templates(&mut self) -> usize {
+        let mut bonds = self.bonds().into_iter().collect::<BTreeSet<_>>();
+        let mut added = 0;
+        for i in 0..self.residues_count() as usize {
+            let residue = self.residue(i).expect("missing residue");
+            let template = match crate::templates::residue_bonds(residue.name().trim()) {
+                Some(template) => template,
+                None => continue,
+            };
+
+            // use the first atom with a given name, in case of alternative
+            // locations
+            let mut atoms = HashMap::new();
+            for atom in residue.atoms() {
+                let _ = atoms.entry(self.atom(atom).name()).or_insert(atom);
+            }
+
+            for (first, second) in template {
+                let (i, j) = match (atoms.get(first), atoms.get(second)) {
+                    (Some(&i), Some(&j)) => (i, j),
+                    _ => continue,
+                };
+                if bonds.insert([i.min(j), i.max(j)]) {
+                    self.add_bond(i, j);
+                    added += 1;
+                }
+            }
+        }
+        return added;
+    }
+
+    /// Set the ids of all the residues in this topology to consecutive values
+    /// starting at `start`, following the order of the residues in the
+    /// topology.
+    ///
+    /// This is useful after merging or stripping systems, which can lead to
+    /// duplicated ids or gaps in the ids. The C API does not allow to modify
+    /// residues inside a topology, so the topology is rebuilt.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Topology, Residue};
+    /// let mut topology = Topology::new();
+    /// topology.add_residue(&Residue::with_id("ALA", 12)).unwrap();
+    /// topology.add_residue(&Residue::with_id("GLY", 12)).unwrap();
+    /// topology.add_residue(&Residue::new("HOH")).unwrap();
+    ///
+    /// topology.renumber_residues(1);
+    /// assert_eq!(topology.residue(0).unwrap().id(), Some(1));
+    /// assert_eq!(topology.residue(1).unwrap().id(), Some(2));
+    /// assert_eq!(topology.residue(2).unwrap().id(), Some(3));
+    /// ```
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    pub fn renumber_residues(&mut self, start: i64) {
+        let count = self.residues_count() as usize;
+        let ids = (0..count).map(|i| Some(start + i as i64)).collect::<Vec<_>>();
+        self.set_residues_ids(&ids);
+    }
+
+    /// Set the ids of the residues in each chain to consecutive values
+    /// starting at `start`, following the order of the residues in the
+    /// topology. Chains are identified by the `"chainid"` property of the
+    /// residues, and residues without this property are numbered together.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Topology, Residue};
+    /// let mut topology = Topology::new();
+    /// for (id, chain) in [(5, "A"), (7, "A"), (5, "B")] {
+    ///     let mut residue = Residue::with_id("ALA", id);
+    ///     residue.set("chainid", chain);
+    ///     topology.add_residue(&residue).unwrap();
+    /// }
+    ///
+    /// topology.renumber_residues_by_chain(1);
+    /// assert_eq!(topology.residue(0).unwrap().id(), Some(1));
+    /// assert_eq!(topology.residue(1).unwrap().id(), Some(2));
+    /// assert_eq!(topology.residue(2).unwrap().id(), Some(1));
+    /// ```
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn renumber_residues_by_chain(&mut self, start: i64) {
+        let mut next_ids = HashMap::<Option<String>, i64>::new();
+        let ids = (0..self.residues_count() as usize)
+            .map(|i| {
+                let residue = self.residue(i).expect("missing residue");
+                let chain = match residue.get("chainid") {
+                    Some(Property::String(chain)) => Some(chain),
+                    _ => None,
+                };
+                let next = next_ids.entry(chain).or_insert(start);
+                let id = *next;
+                *next += 1;
+                Some(id)
+            })
+            .collect::<Vec<_>>();
+        self.set_residues_ids(&ids);
+    }
+
+    /// Remove the gaps in the residues ids, keeping the relative order of the
+    /// ids. The distinct ids are replaced by consecutive values starting at
+    /// the smallest existing id, and residues sharing the same id (for
+    /// example in different chains) still share the same id afterwards.
+    /// Residues without id are left unchanged.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Topology, Residue};
+    /// let mut topology = Topology::new();
+    /// topology.add_residue(&Residue::with_id("ALA", 3)).unwrap();
+    /// topology.add_residue(&Residue::with_id("GLY", 10)).unwrap();
+    /// topology.add_residue(&Residue::with_id("SER", 7)).unwrap();
+    /// topology.add_residue(&Residue::with_id("ALA", 3)).unwrap();
+    ///
+    /// topology.compact_residue_ids();
+    /// assert_eq!(topology.residue(0).unwrap().id(), Some(3));
+    /// assert_eq!(topology.residue(1).unwrap().id(), Some(5));
+    /// assert_eq!(topology.residue(2).unwrap().id(), Some(4));
+    /// assert_eq!(topology.residue(3).unwrap().id(), Some(3));
+    /// ```
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    pub fn compact_residue_ids(&mut self) {
+        let ids = (0..self.residues_count() as usize)
+            .map(|i| self.residue(i).expect("missing residue").id())
+            .collect::<Vec<_>>();
+
+        let distinct = ids.iter().flatten().copied().collect::<BTreeSet<_>>();
+        let first = match distinct.iter().next() {
+            Some(&first) => first,
+            None => return,
+        };
+        let compacted = distinct
+            .into_iter()
+            .enumerate()
+            .map(|(i, id)| (id, first + i as i64))
+            .collect::<BTreeMap<_, _>>();
+
+        let ids = ids.iter().map(|id| id.map(|id| compacted[&id])).collect::<Vec<_>>();
+        self.set_residues_ids(&ids);
+    }
+
+    /// Set the id of the residue at index `i` to `ids[i]`. The C API does not
+    /// allow to modify residues inside a topology, so the topology is rebuilt.
+    #[allow(clippy::cast_possible_truncation)]
+    fn set_residues_ids(&mut self, ids: &[Option<i64>]) {
+        debug_assert_eq!(ids.len(), self.residues_count() as usize);
+
+        let mut rebuilt = Topology::new();
+        for i in 0..self.size() {
+            rebuilt.add_atom(&self.atom(i));
+        }
+        for (bond, order) in self.bonds().into_iter().zip(self.bond_orders()) {
+            rebuilt.add_bond_with_order(bond[0], bond[1], order);
+        }
+
+        for (i, &id) in ids.iter().enumerate() {
+            let residue = self.residue(i).expect("missing residue");
+            let name = residue.name();
+            let mut copy = match id {
+                Some(id) => Residue::with_id(&*name, id),
+                None => Residue::new(&*name),
+            };
+            for atom in residue.atoms() {
+                copy.add_atom(atom);
+            }
+            for (property, value) in residue.properties() {
+                copy.set(&property, value);
+            }
+            rebuilt
+                .add_residue(&copy)
+                .expect("residues from an existing topology should not overlap");
+        }
+
+        *self = rebuilt;
+    }
+
+    /// Get an estimate of the memory used by this topology in the C++
+    /// library, in bytes.
+    ///
+    /// This accounts for the atoms, bonds, residues, and their properties,
+    /// using the typical size of the corresponding C++ types on 64-bit
+    /// platforms. Heap allocations for long strings and the caches of
+    /// angles and dihedrals are not included, so the real memory usage can be
+    /// a bit larger.
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::{Atom, Topology};
+    /// let mut topology = Topology::new();
+    /// let empty = topology.memory_footprint();
+    ///
+    /// topology.add_atoms(&Atom::new("Ar"), 1000);
+    /// assert!(topology.memory_footprint() > empty + 1000 * 100);
+    /// ```
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn memory_footprint(&self) -> usize {
+        let mut size = TOPOLOGY_SIZE + self.bonds_count() * BOND_SIZE;
+        for i in 0..self.size() {
+            size += ATOM_SIZE + self.atom(i).properties().count() * PROPERTY_SIZE;
+        }
+
+        for i in 0..self.residues_count() {
+            let residue = self.residue(i as usize).expect("missing residue");
+            size += RESIDUE_SIZE + residue.size() * std::mem::size_of::<usize>();
+            size += residue.properties().count() * PROPERTY_SIZE;
+        }
+        // the topology also stores an optional residue index for each atom
+        size += self.size() * 2 * std::mem::size_of::<usize>();
+
+        return size;
+    }
+
+    /// Get the name of a residue containing the given `atoms`
+    fn guess_residue_name(&self, atoms: &[usize]) -> String {
+        let mut types = atoms.iter().map(|&i| self.atom(i).atomic_type()).collect::<Vec<_>>();
+        types.sort_unstable();
+        match &types[..] {
+            [single] => single.to_ascii_uppercase(),
+            [h1, h2, o] if h1 == "H" && h2 == "H" && o == "O" => "HOH".into(),
+            _ => "MOL".into(),
+        }
+    }
+
+    /// Get the set of atoms in the residue at index `residue`, panicking with
+    /// a message mentioning `function` if the index is out of bounds.
+    fn residue_atoms(&self, residue: usize, function: &str) -> BTreeSet<usize> {
+        match self.residue(residue) {
+            Some(residue) => residue.atoms().into_iter().collect(),
+            None => panic!(
+                "out of bounds residue index {residue} in `Topology::{function}`: we have {} residues",
+                self.residues_count()
+            ),
+        }
+    }
+}
+
+/// Names of residues where atoms use upper case PDB names
+const BIOMOLECULAR_RESIDUES: &[&str] = &[
+    "ALA", "ARG", "ASN", "ASP", "CYS", "CYX", "GLN", "GLU", "GLY", "HIS", "HID", "HIE", "HIP", "ILE", "LEU", "LYS",
+    "MET", "PHE", "PRO", "SER", "THR", "TRP", "TYR", "VAL", "ACE", "NME", "A", "C", "G", "U", "DA", "DC", "DG", "DT",
+    "HOH", "WAT", "SOL", "TIP3",
+];
+
+/// Find the root of the set containing `i` in a disjoint-set forest
+fn root(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    return i;
+}
+
+/// Guess the element of an atom from its `name` and the name and size of the
+/// residue containing it, if any.
+fn guess_element(name: &str, residue: Option<(&str, usize)>) -> Option<String> {
+    let letters = name
+        .trim_start_matches(|c: char| c.is_ascii_digit())
+        .chars()
+        .take_while(char::is_ascii_alphabetic)
+        .collect::<Vec<_>>();
+
+    let first = *letters.first()?;
+    let one_letter = first.to_ascii_uppercase().to_string();
+    let two_letters = letters
+        .get(1)
+        .map(|second| format!("{}{}", first.to_ascii_uppercase(), second.to_ascii_lowercase()));
+
+    let prefer_two_letters = match residue {
+        _ if letters.get(1).map_or(false, char::is_ascii_lowercase) => true,
+        Some((residue, size)) => size == 1 && residue.eq_ignore_ascii_case(name),
+        None => true,
+    };
+    let is_biomolecular = residue.map_or(false, |(residue, _)| BIOMOLECULAR_RESIDUES.contains(&residue));
+
+    let candidates = if prefer_two_letters && !is_biomolecular {
+        [two_letters, Some(one_letter)]
+    } else {
+        [Some(one_letter), two_letters]
+    };
+    return candidates
+        .into_iter()
+        .flatten()
+        .find(|element| Atom::new(element.as_str()).atomic_number() != 0);
 }
 
 impl Drop for Topology {
@@ -793,6 +1823,114 @@ impl Drop for Topology {
 mod test {
     use super::*;
 
+    #[test]
+    fn memory_footprint() {
+        // optional residue index stored for each atom
+        let residue_index = 2 * std::mem::size_of::<usize>();
+
+        let mut topology = Topology::new();
+        let empty = topology.memory_footprint();
+        assert_eq!(empty, TOPOLOGY_SIZE);
+
+        topology.add_atoms(&Atom::new("O"), 10);
+        topology.add_bond(0, 1);
+        topology.add_residue(&Residue::new("foo")).unwrap();
+        assert_eq!(
+            topology.memory_footprint(),
+            TOPOLOGY_SIZE + 10 * ATOM_SIZE + BOND_SIZE + RESIDUE_SIZE + 10 * residue_index
+        );
+
+        let mut atom = Atom::new("O");
+        atom.set("foo", 3.0);
+        topology.add_atom(&atom);
+        assert_eq!(
+            topology.memory_footprint(),
+            TOPOLOGY_SIZE + 11 * ATOM_SIZE + PROPERTY_SIZE + BOND_SIZE + RESIDUE_SIZE + 11 * residue_index
+        );
+    }
+
+    #[test]
+    fn guess_residues() {
+        let mut topology = Topology::new();
+        for name in ["C", "O", "Cl", "H", "O", "H", "C"] {
+            topology.add_atom(&Atom::new(name));
+        }
+        topology.add_bond(0, 1);
+        topology.add_bond(1, 6);
+        topology.add_bond(4, 3);
+        topology.add_bond(4, 5);
+
+        let mut residue = Residue::with_id("LIG", 7);
+        residue.add_atom(0);
+        topology.add_residue(&residue).unwrap();
+
+        assert_eq!(topology.guess_residues(), 3);
+        assert_eq!(topology.residues_count(), 4);
+
+        let residue = topology.residue(1).unwrap();
+        assert_eq!(residue.name(), "MOL");
+        assert_eq!(residue.id(), Some(8));
+        assert_eq!(residue.atoms(), [1, 6]);
+
+        let residue = topology.residue(2).unwrap();
+        assert_eq!(residue.name(), "CL");
+        assert_eq!(residue.atoms(), [2]);
+
+        let residue = topology.residue(3).unwrap();
+        assert_eq!(residue.name(), "HOH");
+        assert_eq!(residue.atoms(), [3, 4, 5]);
+
+        // all atoms are already in residues
+        assert_eq!(topology.guess_residues(), 0);
+    }
+
+    #[test]
+    fn guess_elements() {
+        assert_eq!(guess_element("CA", Some(("ALA", 10))).unwrap(), "C");
+        assert_eq!(guess_element("CA", Some(("CA", 1))).unwrap(), "Ca");
+        assert_eq!(guess_element("CA", None).unwrap(), "Ca");
+        assert_eq!(guess_element("CB", None).unwrap(), "C");
+        assert_eq!(guess_element("CD1", Some(("LIG", 12))).unwrap(), "C");
+        assert_eq!(guess_element("Cl3", Some(("LIG", 12))).unwrap(), "Cl");
+        assert_eq!(guess_element("2HG1", Some(("VAL", 16))).unwrap(), "H");
+        assert_eq!(guess_element("OW", Some(("SOL", 3))).unwrap(), "O");
+        assert_eq!(guess_element("123", None), None);
+        assert_eq!(guess_element("Xx", None), None);
+
+        let mut topology = Topology::new();
+        topology.add_atom(&Atom::new("NA"));
+        let mut atom = Atom::new("Xx");
+        atom.set_mass(3.0);
+        topology.add_atom(&atom);
+        let mut residue = Residue::new("NA");
+        residue.add_atom(0);
+        topology.add_residue(&residue).unwrap();
+
+        assert_eq!(topology.guess_types_from_names(), 1);
+        assert_eq!(topology.atom(0).atomic_type(), "Na");
+        assert_eq!(topology.guess_masses(), 1);
+        assert_eq!(topology.atom(0).mass(), 22.98976928);
+        assert_eq!(topology.atom(1).mass(), 3.0);
+    }
+
+    #[test]
+    fn virtual_sites() {
+        let mut topology = Topology::new();
+        topology.add_atom(&Atom::new("O"));
+        topology.add_atom(&Atom::new("H"));
+        topology.add_atom(&Atom::new("H"));
+        topology.add_atom(&Atom::new("MW"));
+        let mut site = Atom::new("O");
+        site.set_mass(0.0);
+        site.set_virtual_site(true);
+        topology.add_atom(&site);
+
+        assert_eq!(topology.virtual_sites(), vec![3, 4]);
+        // flagged virtual sites keep their zero mass
+        assert_eq!(topology.guess_masses(), 3);
+        assert_eq!(topology.atom(4).mass(), 0.0);
+    }
+
     #[test]
     fn clone() {
         let mut topology = Topology::new();
@@ -803,6 +1941,9 @@ mod test {
 
         topology.resize(10);
         assert_eq!(topology.size(), 10);
+
+        topology.reserve(100);
+        assert_eq!(topology.size(), 10);
         assert_eq!(copy.size(), 0);
     }
 
@@ -832,6 +1973,13 @@ mod test {
 
         assert_eq!(topology.atom(0).name(), "Hg");
         assert_eq!(topology.atom(3).name(), "Fe");
+
+        let mut atom = Atom::new("O");
+        atom.set_charge(-0.8);
+        topology.add_atoms(&atom, 3);
+        assert_eq!(topology.size(), 7);
+        assert_eq!(*topology.atom(4), atom);
+        assert_eq!(*topology.atom(6), atom);
     }
 
     #[test]
@@ -876,6 +2024,10 @@ mod test {
         assert_eq!(topology.bond_order(0, 1), BondOrder::Unknown);
         assert_eq!(topology.bond_order(3, 7), BondOrder::Aromatic);
 
+        topology.set_bond_order(7, 3, BondOrder::Double);
+        assert_eq!(topology.bond_order(3, 7), BondOrder::Double);
+        assert_eq!(topology.bonds_count(), 3);
+
         topology.remove_bond(3, 7);
         // Removing unexisting bond is OK if both indexes are in bounds
         topology.remove_bond(8, 7);
@@ -901,6 +2053,36 @@ mod test {
         topology.remove_bond(300, 7);
     }
 
+    #[test]
+    #[should_panic(expected = "there is no bond between atoms 2 and 3 in `Topology::set_bond_order`")]
+    fn set_missing_bond_order() {
+        let mut topology = Topology::new();
+        topology.resize(12);
+        topology.add_bond(2, 4);
+        topology.set_bond_order(2, 3, BondOrder::Single);
+    }
+
+    #[test]
+    fn bond_order_conversions() {
+        assert_eq!(BondOrder::Single.as_f64(), 1.0);
+        assert_eq!(BondOrder::Amide.as_f64(), 1.0);
+        assert_eq!(BondOrder::Quintuplet.as_f64(), 5.0);
+
+        assert_eq!(BondOrder::try_from(0).unwrap(), BondOrder::Unknown);
+        assert_eq!(BondOrder::try_from(3).unwrap(), BondOrder::Triple);
+        assert_eq!(BondOrder::try_from(6).unwrap_err().status, Status::InvalidArgument);
+
+        assert_eq!("Aromatic".parse::<BondOrder>().unwrap(), BondOrder::Aromatic);
+        assert_eq!("ar".parse::<BondOrder>().unwrap(), BondOrder::Aromatic);
+        assert_eq!(" 2 ".parse::<BondOrder>().unwrap(), BondOrder::Double);
+        assert_eq!("am".parse::<BondOrder>().unwrap(), BondOrder::Amide);
+        assert_eq!("QUADRUPLE".parse::<BondOrder>().unwrap(), BondOrder::Quadruple);
+        assert_eq!(
+            "sextuple".parse::<BondOrder>().unwrap_err().status,
+            Status::InvalidArgument
+        );
+    }
+
     #[test]
     #[should_panic(expected = "out of bounds atomic index in `Topology::add_bond`")]
     fn out_of_bounds_bonds_with_order() {
@@ -995,9 +2177,146 @@ mod test {
         let second = topology.residue(0).unwrap();
         assert!(topology.are_linked(&first, &second));
 
+        assert!(topology.residue_by_id(0).is_none());
+        assert_eq!(topology.residues_named("Bar"), vec![1]);
+        assert!(topology.residues_named("Baz").is_empty());
+
+        assert_eq!(topology.residue_index_for_atom(2), Some(0));
+        assert_eq!(topology.residue_index_for_atom(3), Some(1));
+        assert_eq!(topology.residue_index_for_atom(1), None);
+        assert!(!topology.are_linked_by_index(0, 1));
+        topology.add_bond(2, 3);
+        assert!(topology.are_linked_by_index(0, 1));
+
         // missing residue
         assert!(topology.residue_for_atom(1).is_none());
         // out of bounds
         assert!(topology.residue_for_atom(67).is_none());
     }
+
+    #[test]
+    fn sequence() {
+        let mut topology = Topology::new();
+        for (id, name, chain) in [
+            (1, "MET", "A"),
+            (2, "ALA", "A"),
+            (3, "HOH", "A"),
+            (1, "SER", "B"),
+            (2, "XYZ", "B"),
+        ] {
+            let mut residue = Residue::with_id(name, id);
+            residue.set("chainid", chain);
+            topology.add_residue(&residue).unwrap();
+        }
+        topology.add_residue(&Residue::new("GLY")).unwrap();
+
+        assert_eq!(topology.sequence(None), "MASG");
+        assert_eq!(topology.sequence(Some("A")), "MA");
+        assert_eq!(topology.sequence(Some("B")), "S");
+        assert_eq!(topology.sequence(Some("C")), "");
+
+        let sequence = topology.sequence_with(Some("B"), |name| {
+            Some(crate::naming::one_letter_code(name).unwrap_or('X'))
+        });
+        assert_eq!(sequence, "SX");
+    }
+
+    #[test]
+    fn residue_bonds() {
+        let mut topology = Topology::new();
+        topology.resize(6);
+
+        let mut residue = Residue::with_id("Foo", 1);
+        residue.add_atom(0);
+        residue.add_atom(1);
+        residue.add_atom(2);
+        topology.add_residue(&residue).unwrap();
+
+        let mut residue = Residue::with_id("Bar", 2);
+        residue.add_atom(3);
+        residue.add_atom(4);
+        topology.add_residue(&residue).unwrap();
+
+        topology.add_bond(0, 1);
+        topology.add_bond(1, 2);
+        topology.add_bond(2, 3);
+        topology.add_bond(0, 4);
+        topology.add_bond(3, 4);
+        topology.add_bond(4, 5);
+
+        assert_eq!(topology.bonds_in_residue(0), vec![[0, 1], [1, 2]]);
+        assert_eq!(topology.bonds_in_residue(1), vec![[3, 4]]);
+        assert_eq!(topology.bonds_between(0, 1), vec![[0, 4], [2, 3]]);
+        assert_eq!(topology.bonds_between(1, 0), vec![[0, 4], [2, 3]]);
+        assert_eq!(topology.bonds_between(0, 0), vec![[0, 1], [1, 2]]);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds residue index 3 in `Topology::bonds_between`: we have 0 residues")]
+    fn out_of_bounds_residue_bonds() {
+        let topology = Topology::new();
+        let _ = topology.bonds_between(3, 0);
+    }
+
+    #[test]
+    #[allow(clippy::cast_possible_truncation)]
+    fn renumber_residues() {
+        let mut topology = Topology::new();
+        topology.add_atoms(&Atom::new("C"), 4);
+        topology.add_bond_with_order(0, 1, BondOrder::Double);
+        for (atom, id, chain) in [(0, 8, "A"), (1, 3, "A"), (2, 8, "B"), (3, 20, "B")] {
+            let mut residue = Residue::with_id("ALA", id);
+            residue.add_atom(atom);
+            residue.set("chainid", chain);
+            topology.add_residue(&residue).unwrap();
+        }
+        topology.add_residue(&Residue::new("HOH")).unwrap();
+
+        let ids = |topology: &Topology| {
+            (0..topology.residues_count() as usize)
+                .map(|i| topology.residue(i).unwrap().id())
+                .collect::<Vec<_>>()
+        };
+
+        topology.compact_residue_ids();
+        assert_eq!(ids(&topology), [Some(4), Some(3), Some(4), Some(5), None]);
+
+        topology.renumber_residues_by_chain(10);
+        assert_eq!(ids(&topology), [Some(10), Some(11), Some(10), Some(11), Some(10)]);
+
+        topology.renumber_residues(1);
+        assert_eq!(ids(&topology), [Some(1), Some(2), Some(3), Some(4), Some(5)]);
+
+        // everything else is preserved
+        assert_eq!(topology.size(), 4);
+        assert_eq!(topology.bond_order(0, 1), BondOrder::Double);
+        let residue = topology.residue(3).unwrap();
+        assert_eq!(residue.atoms(), [3]);
+        assert_eq!(residue.get("chainid"), Some(Property::String("B".into())));
+    }
+
+    #[test]
+    fn residue_templates() {
+        let mut topology = Topology::new();
+        for name in ["OW", "HW1", "HW2", "C1", "C2"] {
+            topology.add_atom(&Atom::new(name));
+        }
+        topology.add_bond(0, 1);
+
+        let mut water = Residue::with_id("SOL", 1);
+        water.add_atom(0);
+        water.add_atom(1);
+        water.add_atom(2);
+        topology.add_residue(&water).unwrap();
+
+        let mut ligand = Residue::with_id("LIG", 2);
+        ligand.add_atom(3);
+        ligand.add_atom(4);
+        topology.add_residue(&ligand).unwrap();
+
+        // the existing bond is not counted, and unknown residues are ignored
+        assert_eq!(topology.apply_residue_templates(), 1);
+        assert_eq!(topology.bonds(), vec![[0, 1], [0, 2]]);
+        assert_eq!(topology.apply_residue_templates(), 0);
+    }
 }
@@ -1,23 +1,116 @@
 // Chemfiles, a modern library for chemistry file reading and writing
 // Copyright (C) 2015-2018 Guillaume Fraux -- BSD licensed
+use std::collections::HashMap;
 use std::convert::TryInto;
 use std::os::raw::c_char;
 use std::path::Path;
+use std::sync::mpsc::{self, Receiver};
+use std::thread::{self, JoinHandle};
 
 use chemfiles_sys as ffi;
 
-use crate::errors::{check, check_success, Error, Status};
+use crate::analysis::Accumulator;
+use crate::errors::{check, check_success, send_warning, Error, Status};
 use crate::strings;
-use crate::{Frame, Topology, UnitCell};
+use crate::{Bookmarks, CellShape, Frame, Property, Topology, UnitCell};
+
+/// Which bonds should be written to files, for formats storing bonds (such as
+/// CONECT records in PDB files).
+///
+/// By default, all bonds are written. Writing all the bonds can make files
+/// for large systems much bigger, for example with one CONECT record for each
+/// water molecule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BondOutput {
+    /// Write all bonds
+    #[default]
+    All,
+    /// Only write bonds involving at least one hetero atom, *i.e.* an atom
+    /// which is not part of a water molecule or of a standard PDB residue
+    /// (amino acids and nucleic acids).
+    Hetero,
+    /// Do not write any bond
+    None,
+}
+
+/// How a [`Trajectory`] should react to errors when reading frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorPolicy {
+    /// Return all errors to the caller
+    #[default]
+    Fail,
+    /// Skip frames which can not be decoded, sending a warning through the
+    /// warning callback (see [`crate::set_warning_callback`]) and reading the
+    /// next frame instead. This allows to read partially corrupted files, for
+    /// example from a crashed simulation, for formats where frames can be
+    /// located independently of each other.
+    SkipCorrupted,
+}
+
+/// Metadata about a single frame in a trajectory, as read by
+/// [`Trajectory::read_and_keep_metadata`].
+#[derive(Debug, Clone)]
+pub struct FrameMetadata {
+    /// The step of the frame
+    pub step: usize,
+    /// The number of atoms in the frame
+    pub natoms: usize,
+    /// The unit cell of the frame
+    pub cell: UnitCell,
+    /// The properties of the frame
+    pub properties: HashMap<String, Property>,
+}
+
+impl FrameMetadata {
+    /// Create new empty metadata, for a frame without atoms and with an
+    /// infinite unit cell.
+    pub fn new() -> FrameMetadata {
+        FrameMetadata {
+            step: 0,
+            natoms: 0,
+            cell: UnitCell::infinite(),
+            properties: HashMap::new(),
+        }
+    }
+}
+
+impl Default for FrameMetadata {
+    fn default() -> FrameMetadata {
+        FrameMetadata::new()
+    }
+}
 
 /// The `Trajectory` type is the main entry point when using chemfiles. A
 /// `Trajectory` behave a bit like a file, allowing to read and/or write
 /// `Frame`.
+///
+/// # DCD files
+///
+/// The chemfiles C API does not offer format-specific reader options, so the
+/// way DCD files are interpreted (fixed atoms, fourth dimension, CHARMM or
+/// NAMD unit cell conventions) is decided by the C++ DCD reader from the file
+/// header, and can not be configured from Rust. If the unit cell read from a
+/// DCD file is incorrect, it can be replaced for all the frames with
+/// [`Trajectory::set_cell`].
 #[derive(Debug)]
 pub struct Trajectory {
     handle: *mut ffi::CHFL_TRAJECTORY,
+    /// Data from the last frame of the file, used to check frames before
+    /// writing them with `Trajectory::open_append_checked`
+    append_check: Option<AppendCheck>,
+    /// Which bonds to write with this trajectory
+    bond_output: BondOutput,
+    /// How to react to errors when reading frames
+    error_policy: ErrorPolicy,
+    /// Index of the next step to read, used to skip corrupted frames
+    next_step: usize,
 }
 
+// SAFETY: a `Trajectory` owns its C++ object, which is only accessed through
+// this handle, and can be used from any thread. It is not `Sync`, since
+// reading frames requires mutable access to the underlying file.
+unsafe impl Send for Trajectory {}
+
 impl Drop for Trajectory {
     fn drop(&mut self) {
         unsafe {
@@ -38,7 +131,13 @@ impl Trajectory {
                 message: Error::last_error(),
             })
         } else {
-            Ok(Trajectory { handle: ptr })
+            Ok(Trajectory {
+                handle: ptr,
+                append_check: None,
+                bond_output: BondOutput::All,
+                error_policy: ErrorPolicy::Fail,
+                next_step: 0,
+            })
         }
     }
 
@@ -58,6 +157,11 @@ impl Trajectory {
     ///
     /// Valid modes are `'r'` for read, `'w'` for write and `'a'` for append.
     ///
+    /// On Unix, `path` is passed as-is to the C library and does not need to
+    /// be valid UTF-8, which allows opening files with names using legacy
+    /// encodings. On other platforms, non UTF-8 paths are rejected with
+    /// [`Status::UTF8PathError`](crate::Status::UTF8PathError).
+    ///
     /// # Errors
     ///
     /// This function fails if the file is not accessible for the given mode, if
@@ -73,16 +177,12 @@ impl Trajectory {
     where
         P: AsRef<Path>,
     {
-        let path = path
-            .as_ref()
-            .to_str()
-            .ok_or_else(|| Error::utf8_path_error(path.as_ref()))?;
-
-        let path = strings::to_c(path);
+        let path = path.as_ref();
+        let c_path = strings::path_to_c(path)?;
         unsafe {
             #[allow(clippy::cast_possible_wrap)]
-            let handle = ffi::chfl_trajectory_open(path.as_ptr(), mode as c_char);
-            Trajectory::from_ptr(handle)
+            let handle = ffi::chfl_trajectory_open(c_path.as_ptr(), mode as c_char);
+            Trajectory::from_ptr(handle).map_err(|error| error.refine_open_error(path, mode))
         }
     }
 
@@ -112,18 +212,64 @@ impl Trajectory {
         P: AsRef<Path>,
         S: Into<&'a str>,
     {
-        let filename = filename
-            .as_ref()
-            .to_str()
-            .ok_or_else(|| Error::utf8_path_error(filename.as_ref()))?;
-
-        let filename = strings::to_c(filename);
+        let filename = filename.as_ref();
+        let c_filename = strings::path_to_c(filename)?;
         let format = strings::to_c(format.into());
         unsafe {
             #[allow(clippy::cast_possible_wrap)]
-            let handle = ffi::chfl_trajectory_with_format(filename.as_ptr(), mode as c_char, format.as_ptr());
-            Trajectory::from_ptr(handle)
+            let handle = ffi::chfl_trajectory_with_format(c_filename.as_ptr(), mode as c_char, format.as_ptr());
+            Trajectory::from_ptr(handle).map_err(|error| error.refine_open_error(filename, mode))
+        }
+    }
+
+    /// Open the file at the given `path` in append mode, checking that all the
+    /// frames written to it contain the same atoms, in the same order, as the
+    /// last frame already in the file.
+    ///
+    /// This prevents silently corrupting a trajectory by appending frames from
+    /// a different system. The check always compares the number of atoms. The
+    /// atom names are only compared for formats storing them, and the shape of
+    /// the unit cell for formats storing a unit cell (see [`FormatMetadata`]).
+    /// The cell lengths and angles are not compared, since they change during
+    /// constant pressure simulations. If the file does not exist or is empty,
+    /// it is created and no check is done.
+    ///
+    /// [`FormatMetadata`]: crate::FormatMetadata
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the existing file can not be read, or if the
+    /// file can not be opened in append mode.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use chemfiles::{Trajectory, Frame};
+    /// let mut trajectory = Trajectory::open_append_checked("water.xyz").unwrap();
+    ///
+    /// let frame = Frame::new();
+    /// // the file contains water molecules, not an empty frame
+    /// assert!(trajectory.write(&frame).is_err());
+    /// ```
+    pub fn open_append_checked<P>(path: P) -> Result<Trajectory, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+
+        let mut append_check = None;
+        if path.exists() {
+            let mut trajectory = Trajectory::open(path, 'r')?;
+            let nsteps = trajectory.nsteps();
+            if nsteps > 0 {
+                let mut frame = Frame::new();
+                trajectory.read_step(nsteps - 1, &mut frame)?;
+                append_check = Some(AppendCheck::new(&frame, &crate::guess_format(path)?));
+            }
         }
+
+        let mut trajectory = Trajectory::open(path, 'a')?;
+        trajectory.append_check = append_check;
+        return Ok(trajectory);
     }
 
     /// Write to a memory buffer as though it was a formatted file.
@@ -166,7 +312,9 @@ impl Trajectory {
     /// # Errors
     ///
     /// This function fails if the data is incorrectly formatted for the
-    /// corresponding format, or in case of I/O errors from the OS.
+    /// corresponding format, or in case of I/O errors from the OS. With
+    /// [`ErrorPolicy::SkipCorrupted`], this function only fails if all the
+    /// remaining steps can not be read.
     ///
     /// # Example
     /// ```no_run
@@ -177,7 +325,207 @@ impl Trajectory {
     /// trajectory.read(&mut frame).unwrap();
     /// ```
     pub fn read(&mut self, frame: &mut Frame) -> Result<(), Error> {
-        unsafe { check(ffi::chfl_trajectory_read(self.as_mut_ptr(), frame.as_mut_ptr())) }
+        let result = unsafe { check(ffi::chfl_trajectory_read(self.as_mut_ptr(), frame.as_mut_ptr())) };
+        match result {
+            Ok(()) => {
+                self.next_step += 1;
+                return Ok(());
+            }
+            Err(error) if self.error_policy == ErrorPolicy::SkipCorrupted && !error.is_eof() => {
+                return self.skip_corrupted(error, frame);
+            }
+            Err(error) => return Err(error),
+        }
+    }
+
+    /// Skip the step which failed to be read with `error`, and read the
+    /// following steps into `frame` until one of them succeeds.
+    fn skip_corrupted(&mut self, mut error: Error, frame: &mut Frame) -> Result<(), Error> {
+        let nsteps = self.nsteps();
+        loop {
+            send_warning(format!(
+                "skipping corrupted frame at step {} in '{}': {}",
+                self.next_step,
+                self.path(),
+                error.message
+            ));
+
+            self.next_step += 1;
+            if self.next_step >= nsteps {
+                return Err(error);
+            }
+
+            match self.read_step(self.next_step, frame) {
+                Ok(()) => return Ok(()),
+                Err(next_error) => error = next_error,
+            }
+        }
+    }
+
+    /// Set how this trajectory should react to errors when reading frames.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use chemfiles::{Trajectory, ErrorPolicy};
+    /// let mut trajectory = Trajectory::open("crashed.xyz", 'r').unwrap();
+    /// trajectory.set_error_policy(ErrorPolicy::SkipCorrupted);
+    ///
+    /// // corrupted frames are reported as warnings and skipped
+    /// while let Some(frame) = trajectory.read_next().unwrap() {
+    ///     println!("read step {}", frame.step());
+    /// }
+    /// ```
+    pub fn set_error_policy(&mut self, policy: ErrorPolicy) {
+        self.error_policy = policy;
+    }
+
+    /// Get how this trajectory reacts to errors when reading frames
+    pub fn error_policy(&self) -> ErrorPolicy {
+        self.error_policy
+    }
+
+    /// Read the next step of this trajectory into a new frame, returning
+    /// `None` once all the steps have been read.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the data is incorrectly formatted for the
+    /// corresponding format, or in case of I/O errors from the OS. Reaching
+    /// the end of the trajectory is not an error.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use chemfiles::{Trajectory, Error};
+    /// # fn main() -> Result<(), Error> {
+    /// let mut trajectory = Trajectory::open("water.xyz", 'r')?;
+    /// while let Some(frame) = trajectory.read_next()? {
+    ///     println!("read a frame with {} atoms", frame.size());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_next(&mut self) -> Result<Option<Frame>, Error> {
+        let mut frame = Frame::new();
+        match self.read(&mut frame) {
+            Ok(()) => return Ok(Some(frame)),
+            Err(error) if error.is_eof() => return Ok(None),
+            Err(error) => return Err(error),
+        }
+    }
+
+    /// Read all the remaining frames of this trajectory in a background
+    /// thread, and send them over a channel. This allows to overlap reading
+    /// and decoding the file with the analysis of the frames.
+    ///
+    /// At most `buffer` frames are kept in the channel, and the background
+    /// thread waits for the receiver to consume frames before reading more of
+    /// them. The thread stops after sending the first error, when reaching
+    /// the end of the trajectory, or when the receiver is dropped. The
+    /// trajectory is returned by the thread and can be recovered by joining
+    /// it.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use chemfiles::Trajectory;
+    /// let trajectory = Trajectory::open("water.xtc", 'r').unwrap();
+    /// let (handle, frames) = trajectory.spawn_reader(16);
+    ///
+    /// for frame in frames {
+    ///     let frame = frame.unwrap();
+    ///     println!("read step {}", frame.step());
+    /// }
+    ///
+    /// let trajectory = handle.join().unwrap();
+    /// ```
+    pub fn spawn_reader(mut self, buffer: usize) -> (JoinHandle<Trajectory>, Receiver<Result<Frame, Error>>) {
+        let (sender, receiver) = mpsc::sync_channel(buffer);
+        let handle = thread::spawn(move || {
+            loop {
+                let result = match self.read_next() {
+                    Ok(Some(frame)) => Ok(frame),
+                    Ok(None) => break,
+                    Err(error) => Err(error),
+                };
+
+                let is_error = result.is_err();
+                if sender.send(result).is_err() || is_error {
+                    // the receiver was dropped, or we sent an error
+                    break;
+                }
+            }
+            return self;
+        });
+
+        return (handle, receiver);
+    }
+
+    /// Read the next step of this trajectory, and only keep the metadata of
+    /// the frame (step, number of atoms, unit cell and properties) in
+    /// `metadata`.
+    ///
+    /// This reads and decodes the full frame, including positions,
+    /// velocities and topology, and then drops everything except the metadata.
+    /// It costs as much time as [`Trajectory::read`], since the chemfiles C
+    /// API can not read only the metadata of a frame. It only avoids keeping
+    /// the positions and topology in memory, for example when collecting the
+    /// metadata of all the frames in a long trajectory.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the data is incorrectly formatted for the
+    /// corresponding format, or in case of I/O errors from the OS.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use chemfiles::{Trajectory, FrameMetadata};
+    /// let mut trajectory = Trajectory::open("water.xyz", 'r').unwrap();
+    /// let mut metadata = FrameMetadata::new();
+    ///
+    /// for _ in 0..trajectory.nsteps() {
+    ///     trajectory.read_and_keep_metadata(&mut metadata).unwrap();
+    ///     println!("step {} contains {} atoms", metadata.step, metadata.natoms);
+    /// }
+    /// ```
+    pub fn read_and_keep_metadata(&mut self, metadata: &mut FrameMetadata) -> Result<(), Error> {
+        let mut frame = Frame::new();
+        self.read(&mut frame)?;
+
+        metadata.step = frame.step();
+        metadata.natoms = frame.size();
+        metadata.cell = (*frame.cell()).clone();
+        metadata.properties.clear();
+        metadata.properties.extend(frame.properties());
+        return Ok(());
+    }
+
+    /// Read all the frames in this trajectory, starting from the first one
+    /// regardless of previous reads, and give them to `accumulator`. This
+    /// returns the result of the accumulator once all frames have been read.
+    ///
+    /// Multiple analyses can share a single pass over the file by using a
+    /// tuple or a `Vec` of accumulators. See also
+    /// [`FrameSource::accumulate`](crate::FrameSource::accumulate), which
+    /// reads the remaining frames of any frame source.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if any frame can not be read.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use chemfiles::Trajectory;
+    /// # use chemfiles::analysis::TrajectoryStats;
+    /// let mut trajectory = Trajectory::open("simulation.xtc", 'r').unwrap();
+    /// let stats = trajectory.fold_frames(TrajectoryStats::default()).unwrap();
+    /// println!("read {} frames", stats.frames.len());
+    /// ```
+    pub fn fold_frames<A: Accumulator>(&mut self, mut accumulator: A) -> Result<A::Output, Error> {
+        let mut frame = Frame::new();
+        for step in 0..self.nsteps() {
+            self.read_step(step, &mut frame)?;
+            accumulator.accumulate(&frame);
+        }
+        return Ok(accumulator.finish());
     }
 
     /// Read a specific `step` of this trajectory into a `frame`.
@@ -204,8 +552,129 @@ impl Trajectory {
                 self.as_mut_ptr(),
                 step as u64,
                 frame.as_mut_ptr(),
-            ))
+            ))?;
+        }
+        self.next_step = step + 1;
+        return Ok(());
+    }
+
+    /// Read the frame with the time stamp closest to `time` (in picoseconds)
+    /// from this trajectory, using the `"time"` property of the frames (see
+    /// [`Frame::time`]).
+    ///
+    /// The time stamps are assumed to be increasing along the trajectory,
+    /// which allows to find the frame with a binary search, reading only a
+    /// few frames from the file.
+    ///
+    /// # Errors
+    ///
+    /// This function fails with [`Status::OutOfBounds`] if the trajectory is
+    /// empty or if the closest frame is more than `tolerance` picoseconds away
+    /// from `time`, and with [`Status::PropertyError`] if one of the frames
+    /// read during the search does not have a time stamp.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use chemfiles::Trajectory;
+    /// let mut trajectory = Trajectory::open("trajectory.xtc", 'r').unwrap();
+    ///
+    /// // get the frame at 100 ns
+    /// let frame = trajectory.read_at_time(100_000.0, 1.0).unwrap();
+    /// ```
+    pub fn read_at_time(&mut self, time: f64, tolerance: f64) -> Result<Frame, Error> {
+        let nsteps = self.nsteps();
+        if nsteps == 0 {
+            return Err(Error {
+                status: Status::OutOfBounds,
+                message: format!("can not read frame at time {time} ps in an empty trajectory"),
+            });
+        }
+
+        let mut frame = Frame::new();
+        let read_time = |trajectory: &mut Trajectory, step: usize, frame: &mut Frame| {
+            trajectory.read_step(step, frame)?;
+            return frame.time().ok_or_else(|| Error {
+                status: Status::PropertyError,
+                message: format!("the frame at step {step} does not have a 'time' property"),
+            });
+        };
+
+        // find the first step with a time larger than or equal to `time`
+        let mut low = 0;
+        let mut high = nsteps;
+        while low < high {
+            let middle = low + (high - low) / 2;
+            if read_time(self, middle, &mut frame)? < time {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+
+        // the closest frame is either this step or the one before
+        let mut best = None;
+        for step in [low.checked_sub(1), Some(low)].into_iter().flatten() {
+            if step >= nsteps {
+                continue;
+            }
+            let distance = (read_time(self, step, &mut frame)? - time).abs();
+            if !matches!(best, Some((_, best_distance)) if best_distance <= distance) {
+                best = Some((step, distance));
+            }
+        }
+
+        let (step, distance) = best.expect("no step in non-empty trajectory");
+        if distance > tolerance {
+            return Err(Error {
+                status: Status::OutOfBounds,
+                message: format!(
+                    "no frame within {tolerance} ps of {time} ps, the closest one is {distance} ps away at step {step}"
+                ),
+            });
+        }
+
+        let _ = read_time(self, step, &mut frame)?;
+        return Ok(frame);
+    }
+
+    /// Read the frame associated with the bookmark `label`.
+    ///
+    /// The bookmarks are first searched in the sidecar file next to this
+    /// trajectory (see [`Bookmarks::load`]), and then in the `"bookmark"`
+    /// property of the frames (see [`Bookmarks::scan`]), which requires
+    /// reading the whole trajectory.
+    ///
+    /// # Errors
+    ///
+    /// This function fails with [`Status::InvalidArgument`] if there is no
+    /// bookmark with this label. It also fails if the sidecar file is invalid,
+    /// or if the frames can not be read.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use chemfiles::Trajectory;
+    /// let mut trajectory = Trajectory::open("trajectory.xtc", 'r').unwrap();
+    /// let frame = trajectory.read_bookmark("equilibrated").unwrap();
+    /// ```
+    pub fn read_bookmark(&mut self, label: &str) -> Result<Frame, Error> {
+        let mut step = Bookmarks::load(self.path())?.get(label);
+        if step.is_none() {
+            step = Bookmarks::scan(self)?.get(label);
         }
+
+        let step = match step {
+            Some(step) => step,
+            None => {
+                return Err(Error {
+                    status: Status::InvalidArgument,
+                    message: format!("no bookmark named '{label}' for the file at '{}'", self.path()),
+                })
+            }
+        };
+
+        let mut frame = Frame::new();
+        self.read_step(step, &mut frame)?;
+        return Ok(frame);
     }
 
     /// Write a `frame` to this trajectory.
@@ -213,7 +682,9 @@ impl Trajectory {
     /// # Errors
     ///
     /// This function fails if the data is incorrectly formatted for the
-    /// corresponding format.
+    /// corresponding format, or if this trajectory was opened with
+    /// [`Trajectory::open_append_checked`] and the atoms in the frame do not
+    /// match the atoms in the file.
     ///
     /// # Example
     /// ```no_run
@@ -224,9 +695,147 @@ impl Trajectory {
     /// trajectory.write(&mut frame).unwrap();
     /// ```
     pub fn write(&mut self, frame: &Frame) -> Result<(), Error> {
+        if let Some(ref append_check) = self.append_check {
+            self.check_appended_frame(append_check, frame)?;
+        }
+
+        let filtered;
+        let frame = if self.bond_output == BondOutput::All {
+            frame
+        } else {
+            filtered = filter_bonds(frame, self.bond_output);
+            &filtered
+        };
+
         unsafe { check(ffi::chfl_trajectory_write(self.as_mut_ptr(), frame.as_ptr())) }
     }
 
+    /// Set which bonds are written by this trajectory, for formats which can
+    /// store bonds. This only applies to the bonds in the written frames, and
+    /// not to the topology set with [`Trajectory::set_topology`].
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use chemfiles::{Trajectory, BondOutput, Frame};
+    /// let mut trajectory = Trajectory::open("output.pdb", 'w').unwrap();
+    /// trajectory.set_bond_output(BondOutput::Hetero);
+    ///
+    /// // only the bonds of ligands and other hetero groups are written as
+    /// // CONECT records
+    /// let frame = Frame::new();
+    /// trajectory.write(&frame).unwrap();
+    /// ```
+    pub fn set_bond_output(&mut self, output: BondOutput) {
+        self.bond_output = output;
+    }
+
+    /// Get which bonds are written by this trajectory
+    pub fn bond_output(&self) -> BondOutput {
+        self.bond_output
+    }
+
+    /// Write all the `frames` to this trajectory, in order.
+    ///
+    /// # Errors
+    ///
+    /// This function stops at the first frame which can not be written, and
+    /// returns the corresponding error, with the index of the frame added to
+    /// the error message. The frames before this one are already written.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use chemfiles::{Trajectory, Frame};
+    /// let frames = vec![Frame::new(), Frame::new()];
+    ///
+    /// let mut trajectory = Trajectory::open("output.xyz", 'w').unwrap();
+    /// trajectory.write_all(&frames).unwrap();
+    /// ```
+    pub fn write_all<'a>(&mut self, frames: impl IntoIterator<Item = &'a Frame>) -> Result<(), Error> {
+        for (i, frame) in frames.into_iter().enumerate() {
+            self.write(frame).map_err(|error| write_all_error(error, i))?;
+        }
+        return Ok(());
+    }
+
+    /// Write all the `frames` to this trajectory, in order, setting the step
+    /// of the written frames to consecutive values starting at `first_step`.
+    ///
+    /// The `frames` themselves are not modified, a copy of each frame is
+    /// written with the updated step.
+    ///
+    /// # Errors
+    ///
+    /// This function stops at the first frame which can not be written, and
+    /// returns the corresponding error, with the index of the frame added to
+    /// the error message. The frames before this one are already written.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use chemfiles::{Trajectory, Frame};
+    /// let frames = vec![Frame::new(), Frame::new()];
+    ///
+    /// let mut trajectory = Trajectory::open("output.xyz", 'a').unwrap();
+    /// trajectory.write_all_renumbered(&frames, 100).unwrap();
+    /// ```
+    pub fn write_all_renumbered<'a>(
+        &mut self,
+        frames: impl IntoIterator<Item = &'a Frame>,
+        first_step: usize,
+    ) -> Result<(), Error> {
+        for (i, frame) in frames.into_iter().enumerate() {
+            let mut frame = frame.clone();
+            frame.set_step(first_step + i);
+            self.write(&frame).map_err(|error| write_all_error(error, i))?;
+        }
+        return Ok(());
+    }
+
+    /// Check that the atoms in `frame` match the atom `names` from the file
+    fn check_appended_frame(&self, append_check: &AppendCheck, frame: &Frame) -> Result<(), Error> {
+        let size = frame.size();
+        if size != append_check.size {
+            return Err(Error {
+                status: Status::InvalidArgument,
+                message: format!(
+                    "can not append a frame with {size} atoms to '{}', which contains frames with {} atoms",
+                    self.path(),
+                    append_check.size
+                ),
+            });
+        }
+
+        if let Some(ref names) = append_check.names {
+            for (i, name) in names.iter().enumerate() {
+                let atom = frame.atom(i);
+                if atom.name() != *name {
+                    return Err(Error {
+                        status: Status::InvalidArgument,
+                        message: format!(
+                            "can not append frame to '{}': atom {i} is named '{}' in the frame but '{name}' in the file",
+                            self.path(),
+                            atom.name(),
+                        ),
+                    });
+                }
+            }
+        }
+
+        if let Some(ref shape) = append_check.cell_shape {
+            let frame_shape = frame.cell().shape();
+            if frame_shape != *shape {
+                return Err(Error {
+                    status: Status::InvalidArgument,
+                    message: format!(
+                        "can not append a frame with {frame_shape:?} unit cell to '{}', which contains frames with {shape:?} unit cell",
+                        self.path(),
+                    ),
+                });
+            }
+        }
+
+        return Ok(());
+    }
+
     /// Set the `topology` associated with this trajectory. This topology will
     /// be used when reading and writing the files, replacing any topology in
     /// the frames or files.
@@ -269,12 +878,7 @@ impl Trajectory {
     where
         P: AsRef<Path>,
     {
-        let path = path
-            .as_ref()
-            .to_str()
-            .ok_or_else(|| Error::utf8_path_error(path.as_ref()))?;
-
-        let path = strings::to_c(path);
+        let path = strings::path_to_c(path.as_ref())?;
         unsafe {
             check(ffi::chfl_trajectory_topology_file(
                 self.as_mut_ptr(),
@@ -307,13 +911,8 @@ impl Trajectory {
         P: AsRef<Path>,
         S: Into<&'a str>,
     {
-        let path = path
-            .as_ref()
-            .to_str()
-            .ok_or_else(|| Error::utf8_path_error(path.as_ref()))?;
-
+        let path = strings::path_to_c(path.as_ref())?;
         let format = strings::to_c(format.into());
-        let path = strings::to_c(path);
         unsafe {
             check(ffi::chfl_trajectory_topology_file(
                 self.as_mut_ptr(),
@@ -411,6 +1010,78 @@ impl Trajectory {
     }
 }
 
+/// Get a copy of `frame` containing only the bonds selected by `output`
+fn filter_bonds(frame: &Frame, output: BondOutput) -> Frame {
+    let mut filtered = frame.clone();
+    filtered.clear_bonds();
+    if output == BondOutput::None {
+        return filtered;
+    }
+
+    let mut is_hetero = vec![true; frame.size()];
+    frame.with_topology(|topology| {
+        #[allow(clippy::cast_possible_truncation)]
+        for residue in (0..topology.residues_count()).filter_map(|i| topology.residue(i as usize)) {
+            let standard = matches!(residue.get("is_standard_pdb"), Some(Property::Bool(true)));
+            let water = WATER_RESIDUES.contains(&residue.name().as_str());
+            if standard || water {
+                for atom in residue.atoms() {
+                    is_hetero[atom] = false;
+                }
+            }
+        }
+
+        for (bond, order) in topology.bonds().into_iter().zip(topology.bond_orders()) {
+            if is_hetero[bond[0]] || is_hetero[bond[1]] {
+                filtered.add_bond_with_order(bond[0], bond[1], order);
+            }
+        }
+    });
+    return filtered;
+}
+
+/// Names of water residues, for which bonds are not written with
+/// `BondOutput::Hetero`
+const WATER_RESIDUES: &[&str] = &["HOH", "WAT", "SOL", "H2O", "TIP3"];
+
+/// Add the index of the frame which failed to be written to an `error`
+fn write_all_error(error: Error, index: usize) -> Error {
+    return Error {
+        status: error.status,
+        message: format!("failed to write frame {index}: {}", error.message),
+    };
+}
+
+/// Data from the last frame of a file opened with
+/// `Trajectory::open_append_checked`, compared with the frames appended to it
+#[derive(Debug)]
+struct AppendCheck {
+    /// Number of atoms in the frame
+    size: usize,
+    /// Names of the atoms, if the format stores them
+    names: Option<Vec<String>>,
+    /// Shape of the unit cell, if the format stores it
+    cell_shape: Option<CellShape>,
+}
+
+impl AppendCheck {
+    /// Get the data to check from the last `frame` of a file in the given
+    /// `format`, as returned by `guess_format`
+    fn new(frame: &Frame, format: &str) -> AppendCheck {
+        // remove the compression method, for example in "XYZ / GZ"
+        let name = format.split('/').next().unwrap_or(format).trim();
+        let metadata = crate::formats_list().into_iter().find(|metadata| metadata.name == name);
+        // compare everything if the format is unknown
+        let (atoms, unit_cell) = metadata.map_or((true, true), |metadata| (metadata.atoms, metadata.unit_cell));
+
+        return AppendCheck {
+            size: frame.size(),
+            names: atoms.then(|| (0..frame.size()).map(|i| frame.atom(i).name()).collect()),
+            cell_shape: unit_cell.then(|| frame.cell().shape()),
+        };
+    }
+}
+
 /// `MemoryTrajectoryReader` is a handle for a `Trajectory` in memory.
 pub struct MemoryTrajectoryReader<'data> {
     inner: Trajectory,
@@ -560,6 +1231,296 @@ mod test {
         assert_eq!(frame.size(), 125);
     }
 
+    #[test]
+    fn read_and_keep_metadata() {
+        let root = Path::new(file!()).parent().unwrap().join("..");
+        let mut file = Trajectory::open(root.join("data").join("water.xyz"), 'r').unwrap();
+
+        let mut metadata = FrameMetadata::new();
+        file.read_and_keep_metadata(&mut metadata).unwrap();
+        file.read_and_keep_metadata(&mut metadata).unwrap();
+        assert_eq!(metadata.step, 1);
+        assert_eq!(metadata.natoms, 297);
+        assert_eq!(metadata.cell.shape(), CellShape::Infinite);
+    }
+
+    #[test]
+    fn read_next() {
+        let root = Path::new(file!()).parent().unwrap().join("..");
+        let mut file = Trajectory::open(root.join("data").join("water.xyz"), 'r').unwrap();
+
+        let mut count = 0;
+        while let Some(frame) = file.read_next().unwrap() {
+            assert_eq!(frame.size(), 297);
+            count += 1;
+        }
+        assert_eq!(count, 100);
+        assert!(file.read_next().unwrap().is_none());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn non_utf8_path() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        // "test-é-tmp.xyz" in Latin-1
+        let path = Path::new(OsStr::from_bytes(b"test-\xe9-tmp.xyz"));
+        assert!(path.to_str().is_none());
+
+        let mut trajectory = Trajectory::open(path, 'w').unwrap();
+        let mut frame = Frame::new();
+        frame.add_atom(&Atom::new("Ar"), [1.0, 2.0, 3.0], None);
+        trajectory.write(&frame).unwrap();
+        drop(trajectory);
+
+        let mut trajectory = Trajectory::open_with_format(path, 'r', "XYZ").unwrap();
+        let frame = trajectory.read_next().unwrap().unwrap();
+        assert_eq!(frame.positions(), [[1.0, 2.0, 3.0]]);
+        drop(trajectory);
+
+        assert_eq!(crate::guess_format(path).unwrap(), "XYZ");
+        std::fs::remove_file(path).unwrap();
+
+        let error = Trajectory::open(Path::new(OsStr::from_bytes(b"nul\0.xyz")), 'r').unwrap_err();
+        assert_eq!(error.status, Status::InvalidArgument);
+    }
+
+    #[test]
+    fn read_at_time() {
+        let mut trajectory = Trajectory::open("test-time-tmp.xyz", 'w').unwrap();
+        let mut frame = Frame::new();
+        frame.add_atom(&Atom::new("Ar"), [0.0, 0.0, 0.0], None);
+        for step in 0..10 {
+            frame.set_time(2.0 * f64::from(step));
+            frame.positions_mut()[0][0] = f64::from(step);
+            trajectory.write(&frame).unwrap();
+        }
+        drop(trajectory);
+
+        let mut trajectory = Trajectory::open("test-time-tmp.xyz", 'r').unwrap();
+        let frame = trajectory.read_at_time(6.0, 0.1).unwrap();
+        assert_eq!(frame.positions()[0][0], 3.0);
+
+        let frame = trajectory.read_at_time(8.9, 1.0).unwrap();
+        assert_eq!(frame.positions()[0][0], 4.0);
+
+        let frame = trajectory.read_at_time(-1.0, 1.0).unwrap();
+        assert_eq!(frame.positions()[0][0], 0.0);
+
+        let error = trajectory.read_at_time(25.0, 1.0).unwrap_err();
+        assert_eq!(error.status, Status::OutOfBounds);
+        drop(trajectory);
+
+        std::fs::remove_file("test-time-tmp.xyz").unwrap();
+    }
+
+    #[test]
+    fn read_bookmark() {
+        let mut trajectory = Trajectory::open("test-bookmark-tmp.xyz", 'w').unwrap();
+        let mut frame = Frame::new();
+        frame.add_atom(&Atom::new("Ar"), [0.0, 0.0, 0.0], None);
+        for step in 0..4 {
+            if step == 1 {
+                frame.set_bookmark("equilibrated");
+            } else {
+                frame.set_bookmark("");
+            }
+            frame.positions_mut()[0][0] = f64::from(step);
+            trajectory.write(&frame).unwrap();
+        }
+        drop(trajectory);
+
+        let mut trajectory = Trajectory::open("test-bookmark-tmp.xyz", 'r').unwrap();
+        let frame = trajectory.read_bookmark("equilibrated").unwrap();
+        assert_eq!(frame.positions()[0][0], 1.0);
+
+        let mut bookmarks = Bookmarks::new();
+        bookmarks.insert("last", 3);
+        bookmarks.save("test-bookmark-tmp.xyz").unwrap();
+        let frame = trajectory.read_bookmark("last").unwrap();
+        assert_eq!(frame.positions()[0][0], 3.0);
+
+        let error = trajectory.read_bookmark("missing").unwrap_err();
+        assert_eq!(error.status, Status::InvalidArgument);
+        drop(trajectory);
+
+        std::fs::remove_file("test-bookmark-tmp.xyz").unwrap();
+        std::fs::remove_file(Bookmarks::sidecar("test-bookmark-tmp.xyz")).unwrap();
+    }
+
+    #[test]
+    fn skip_corrupted() {
+        let content = "1\n\nAr 0 0 0\n1\n\nAr 1 foo 0\n1\n\nAr 2 0 0\n";
+        std::fs::write("test-corrupted-tmp.xyz", content).unwrap();
+
+        let mut trajectory = Trajectory::open("test-corrupted-tmp.xyz", 'r').unwrap();
+        assert_eq!(trajectory.error_policy(), ErrorPolicy::Fail);
+        let mut frame = Frame::new();
+        trajectory.read(&mut frame).unwrap();
+        assert!(trajectory.read(&mut frame).is_err());
+
+        let mut trajectory = Trajectory::open("test-corrupted-tmp.xyz", 'r').unwrap();
+        trajectory.set_error_policy(ErrorPolicy::SkipCorrupted);
+        let (positions, warnings) = crate::with_warning_capture(|| {
+            let mut positions = Vec::new();
+            while let Some(frame) = trajectory.read_next().unwrap() {
+                positions.push(frame.positions()[0][0]);
+            }
+            positions
+        });
+        drop(trajectory);
+        std::fs::remove_file("test-corrupted-tmp.xyz").unwrap();
+
+        assert_eq!(positions, [0.0, 2.0]);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("skipping corrupted frame at step 1"));
+    }
+
+    #[test]
+    fn spawn_reader() {
+        let root = Path::new(file!()).parent().unwrap().join("..");
+        let trajectory = Trajectory::open(root.join("data").join("water.xyz"), 'r').unwrap();
+
+        let (handle, frames) = trajectory.spawn_reader(4);
+        let mut count = 0;
+        for frame in frames {
+            assert_eq!(frame.unwrap().size(), 297);
+            count += 1;
+        }
+        assert_eq!(count, 100);
+
+        let mut trajectory = handle.join().unwrap();
+        assert_eq!(trajectory.nsteps(), 100);
+        assert!(trajectory.read_next().unwrap().is_none());
+    }
+
+    #[test]
+    fn bond_output() {
+        let mut frame = Frame::new();
+        for name in ["N", "CA", "O", "H", "H", "C", "O"] {
+            frame.add_atom(&Atom::new(name), [0.0, 0.0, 0.0], None);
+        }
+        frame.add_bond(0, 1);
+        frame.add_bond(2, 3);
+        frame.add_bond(2, 4);
+        frame.add_bond_with_order(5, 6, crate::BondOrder::Double);
+        frame.add_bond(1, 5);
+
+        let mut residue = crate::Residue::new("ALA");
+        residue.add_atom(0);
+        residue.add_atom(1);
+        residue.set("is_standard_pdb", true);
+        frame.add_residue(&residue).unwrap();
+        let mut residue = crate::Residue::new("HOH");
+        residue.add_atom(2);
+        residue.add_atom(3);
+        residue.add_atom(4);
+        frame.add_residue(&residue).unwrap();
+
+        let filtered = filter_bonds(&frame, BondOutput::Hetero);
+        assert_eq!(filtered.bonds(), vec![[1, 5], [5, 6]]);
+        assert_eq!(filtered.topology().bond_order(5, 6), crate::BondOrder::Double);
+        assert_eq!(filtered.size(), 7);
+
+        let filtered = filter_bonds(&frame, BondOutput::None);
+        assert!(filtered.bonds().is_empty());
+        assert_eq!(frame.bonds().len(), 5);
+    }
+
+    #[test]
+    fn write_all() {
+        let path = "test-write-all-tmp.xyz";
+        let mut frame = Frame::new();
+        frame.add_atom(&Atom::new("Ar"), [0.0, 0.0, 0.0], None);
+        let frames = vec![frame.clone(), frame.clone(), frame];
+
+        {
+            let mut file = Trajectory::open(path, 'w').unwrap();
+            file.write_all(&frames).unwrap();
+            file.write_all_renumbered(frames.iter().take(2), 10).unwrap();
+            assert_eq!(frames[1].step(), 0);
+        }
+
+        let mut file = Trajectory::open(path, 'r').unwrap();
+        assert_eq!(file.nsteps(), 5);
+        std::fs::remove_file(path).unwrap();
+
+        let mut file = Trajectory::open_append_checked("test-write-all-checked-tmp.xyz").unwrap();
+        file.append_check = Some(AppendCheck {
+            size: 1,
+            names: Some(vec!["Ar".into()]),
+            cell_shape: None,
+        });
+        let mut wrong = Frame::new();
+        wrong.add_atom(&Atom::new("Kr"), [0.0, 0.0, 0.0], None);
+        let error = file.write_all([&frames[0], &wrong]).unwrap_err();
+        assert!(error.message.starts_with("failed to write frame 1: "));
+        std::mem::drop(file);
+        std::fs::remove_file("test-write-all-checked-tmp.xyz").unwrap();
+    }
+
+    #[test]
+    fn append_checked() {
+        let path = "test-append-tmp.xyz";
+
+        let mut frame = Frame::new();
+        frame.add_atom(&Atom::new("O"), [0.0, 0.0, 0.0], None);
+        frame.add_atom(&Atom::new("H"), [1.0, 0.0, 0.0], None);
+
+        {
+            // no check when creating the file
+            let mut file = Trajectory::open_append_checked(path).unwrap();
+            file.write(&frame).unwrap();
+        }
+
+        let mut file = Trajectory::open_append_checked(path).unwrap();
+        file.write(&frame).unwrap();
+
+        let mut swapped = Frame::new();
+        swapped.add_atom(&Atom::new("H"), [1.0, 0.0, 0.0], None);
+        swapped.add_atom(&Atom::new("O"), [0.0, 0.0, 0.0], None);
+        let error = file.write(&swapped).unwrap_err();
+        assert_eq!(error.status, Status::InvalidArgument);
+        assert!(error.message.contains("atom 0 is named 'H' in the frame but 'O' in the file"));
+
+        swapped.add_atom(&Atom::new("H"), [1.0, 0.0, 0.0], None);
+        let error = file.write(&swapped).unwrap_err();
+        assert!(error.message.contains("can not append a frame with 3 atoms"));
+
+        let mut periodic = frame.clone();
+        periodic.set_cell(&UnitCell::new([10.0, 10.0, 10.0]));
+        let error = file.write(&periodic).unwrap_err();
+        assert!(error.message.contains("which contains frames with Infinite unit cell"));
+        std::mem::drop(file);
+
+        let mut file = Trajectory::open(path, 'r').unwrap();
+        assert_eq!(file.nsteps(), 2);
+        std::mem::drop(file);
+        std::fs::remove_file(path).unwrap();
+
+        // XTC files do not store atom names, so only the number of atoms and
+        // the cell shape are checked
+        let path = "test-append-tmp.xtc";
+        frame.set_cell(&UnitCell::new([10.0, 10.0, 10.0]));
+        Trajectory::open(path, 'w').unwrap().write(&frame).unwrap();
+
+        let mut file = Trajectory::open_append_checked(path).unwrap();
+        swapped.resize(2);
+        swapped.set_cell(&UnitCell::new([11.0, 11.0, 11.0]));
+        file.write(&swapped).unwrap();
+
+        swapped.add_atom(&Atom::new("H"), [1.0, 0.0, 0.0], None);
+        let error = file.write(&swapped).unwrap_err();
+        assert!(error.message.contains("can not append a frame with 3 atoms"));
+        std::mem::drop(file);
+
+        let mut file = Trajectory::open(path, 'r').unwrap();
+        assert_eq!(file.nsteps(), 2);
+        std::mem::drop(file);
+        std::fs::remove_file(path).unwrap();
+    }
+
     fn write_file<P>(path: P)
     where
         P: AsRef<Path>,
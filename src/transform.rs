@@ -0,0 +1,778 @@
+// Chemfiles, a modern library for chemistry file reading and writing
+// Copyright (C) 2015-2018 Guillaume Fraux -- BSD licensed
+
+//! Transformations modifying frames, applied as they are read from a
+//! trajectory by a [`Pipeline`].
+
+use std::collections::VecDeque;
+
+use crate::errors::{Error, Status};
+use crate::math::{self, Matrix3, Periodicity, Vector3D};
+use crate::{Frame, Selection, Trajectory};
+
+/// A `Transform` modifies a `Frame` in place. Transforms can be applied
+/// directly to a frame, or on-the-fly when reading a trajectory through a
+/// [`Pipeline`].
+///
+/// This trait is implemented for all closures taking `&mut Frame`, making it
+/// easy to define custom transformations.
+pub trait Transform {
+    /// Apply this transformation to the given `frame`.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the transformation can not be applied to this
+    /// frame.
+    fn apply(&mut self, frame: &mut Frame) -> Result<(), Error>;
+}
+
+impl<F> Transform for F
+where
+    F: FnMut(&mut Frame) -> Result<(), Error>,
+{
+    fn apply(&mut self, frame: &mut Frame) -> Result<(), Error> {
+        self(frame)
+    }
+}
+
+/// Wrap all atoms inside the unit cell. This does nothing for frames with an
+/// infinite unit cell.
+///
+/// # Example
+/// ```
+/// # use chemfiles::{Frame, Atom, UnitCell};
+/// # use chemfiles::transform::{Transform, Wrap};
+/// let mut frame = Frame::new();
+/// frame.set_cell(&UnitCell::new([10.0, 10.0, 10.0]));
+/// frame.add_atom(&Atom::new("Ar"), [12.0, -1.0, 5.0], None);
+///
+/// Wrap.apply(&mut frame).unwrap();
+/// assert_eq!(frame.positions()[0], [2.0, 9.0, 5.0]);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Wrap;
+
+impl Transform for Wrap {
+    fn apply(&mut self, frame: &mut Frame) -> Result<(), Error> {
+        let periodicity = Periodicity::new(&frame.cell())?;
+        for position in frame.positions_mut() {
+            *position = periodicity.inside(*position);
+        }
+        return Ok(());
+    }
+}
+
+/// Make molecules whole again, by moving atoms to the periodic image closest
+/// to the atoms they are bonded to. Molecules are defined from the bonds in the
+/// frame topology.
+///
+/// # Example
+/// ```
+/// # use chemfiles::{Frame, Atom, UnitCell};
+/// # use chemfiles::transform::{Transform, Unwrap};
+/// let mut frame = Frame::new();
+/// frame.set_cell(&UnitCell::new([10.0, 10.0, 10.0]));
+/// frame.add_atom(&Atom::new("O"), [0.5, 5.0, 5.0], None);
+/// frame.add_atom(&Atom::new("H"), [9.5, 5.0, 5.0], None);
+/// frame.add_bond(0, 1);
+///
+/// Unwrap.apply(&mut frame).unwrap();
+/// assert_eq!(frame.positions()[1], [-0.5, 5.0, 5.0]);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Unwrap;
+
+impl Transform for Unwrap {
+    fn apply(&mut self, frame: &mut Frame) -> Result<(), Error> {
+        let periodicity = Periodicity::new(&frame.cell())?;
+        if periodicity.is_infinite() {
+            return Ok(());
+        }
+
+        let neighbors = neighbors_list(frame);
+        let positions = frame.positions_mut();
+        let mut visited = vec![false; positions.len()];
+        let mut queue = VecDeque::new();
+        for start in 0..positions.len() {
+            if visited[start] {
+                continue;
+            }
+            visited[start] = true;
+            queue.push_back(start);
+
+            while let Some(i) = queue.pop_front() {
+                for &j in &neighbors[i] {
+                    if !visited[j] {
+                        visited[j] = true;
+                        let delta = periodicity.distance_vector(positions[i], positions[j]);
+                        positions[j] = math::add(positions[i], delta);
+                        queue.push_back(j);
+                    }
+                }
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+/// Get the list of bonded neighbors of all the atoms in the `frame`
+pub(crate) fn neighbors_list(frame: &Frame) -> Vec<Vec<usize>> {
+    let mut neighbors = vec![Vec::new(); frame.size()];
+    for [i, j] in frame.topology().bonds() {
+        neighbors[i].push(j);
+        neighbors[j].push(i);
+    }
+    return neighbors;
+}
+
+/// Translate all atoms by a fixed vector.
+///
+/// # Example
+/// ```
+/// # use chemfiles::{Frame, Atom};
+/// # use chemfiles::transform::{Transform, Translate};
+/// let mut frame = Frame::new();
+/// frame.add_atom(&Atom::new("Ar"), [1.0, 2.0, 3.0], None);
+///
+/// Translate::new([1.0, -2.0, 0.5]).apply(&mut frame).unwrap();
+/// assert_eq!(frame.positions()[0], [2.0, 0.0, 3.5]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Translate {
+    vector: Vector3D,
+}
+
+impl Translate {
+    /// Create a new translation by the given `vector`
+    pub fn new(vector: [f64; 3]) -> Translate {
+        Translate { vector }
+    }
+}
+
+impl Transform for Translate {
+    fn apply(&mut self, frame: &mut Frame) -> Result<(), Error> {
+        frame.translate(self.vector, None);
+        return Ok(());
+    }
+}
+
+/// Rotate all atoms (and velocities, if any) with a fixed rotation matrix,
+/// around a fixed center. The unit cell of the frame is not modified.
+///
+/// # Example
+/// ```
+/// # use chemfiles::{Frame, Atom};
+/// # use chemfiles::transform::{Transform, Rotate};
+/// let mut frame = Frame::new();
+/// frame.add_atom(&Atom::new("Ar"), [1.0, 0.0, 0.0], None);
+///
+/// // rotation of 90° around the z axis
+/// let rotation = [[0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]];
+/// Rotate::new(rotation).apply(&mut frame).unwrap();
+/// assert_eq!(frame.positions()[0], [0.0, 1.0, 0.0]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Rotate {
+    rotation: Matrix3,
+    center: Vector3D,
+}
+
+impl Rotate {
+    /// Create a new rotation around the origin, using the given `rotation`
+    /// matrix.
+    pub fn new(rotation: [[f64; 3]; 3]) -> Rotate {
+        return Rotate::around(rotation, [0.0; 3]);
+    }
+
+    /// Create a new rotation around the given `center`, using the given
+    /// `rotation` matrix.
+    pub fn around(rotation: [[f64; 3]; 3], center: [f64; 3]) -> Rotate {
+        Rotate { rotation, center }
+    }
+}
+
+impl Transform for Rotate {
+    fn apply(&mut self, frame: &mut Frame) -> Result<(), Error> {
+        frame.rotate(self.rotation, self.center, None);
+        return Ok(());
+    }
+}
+
+/// Translate all atoms to put the geometric center of the atoms matching a
+/// selection at the center of the unit cell, or at the origin for infinite
+/// cells.
+///
+/// # Example
+/// ```
+/// # use chemfiles::{Frame, Atom, Selection, UnitCell};
+/// # use chemfiles::transform::{Transform, CenterOnSelection};
+/// let mut frame = Frame::new();
+/// frame.set_cell(&UnitCell::new([10.0, 10.0, 10.0]));
+/// frame.add_atom(&Atom::new("Zn"), [1.0, 1.0, 1.0], None);
+/// frame.add_atom(&Atom::new("O"), [2.0, 2.0, 2.0], None);
+///
+/// let selection = Selection::new("name Zn").unwrap();
+/// CenterOnSelection::new(selection).unwrap().apply(&mut frame).unwrap();
+/// assert_eq!(frame.positions()[0], [5.0, 5.0, 5.0]);
+/// assert_eq!(frame.positions()[1], [6.0, 6.0, 6.0]);
+/// ```
+#[derive(Debug)]
+pub struct CenterOnSelection {
+    selection: Selection,
+}
+
+impl CenterOnSelection {
+    /// Create a new `CenterOnSelection` transform using the given `selection`.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the selection size is not 1.
+    pub fn new(selection: Selection) -> Result<CenterOnSelection, Error> {
+        if selection.size() != 1 {
+            return Err(Error {
+                status: Status::SelectionError,
+                message: format!("expected a selection of size 1, got a selection of size {}", selection.size()),
+            });
+        }
+        return Ok(CenterOnSelection { selection });
+    }
+}
+
+impl Transform for CenterOnSelection {
+    fn apply(&mut self, frame: &mut Frame) -> Result<(), Error> {
+        let selected = self.selection.list(frame);
+        if selected.is_empty() {
+            return Err(Error {
+                status: Status::SelectionError,
+                message: format!("the selection '{}' did not match any atom", self.selection.string()),
+            });
+        }
+
+        let positions = frame.positions();
+        let mut center = [0.0; 3];
+        for &i in &selected {
+            center = math::add(center, positions[i]);
+        }
+        #[allow(clippy::cast_precision_loss)]
+        let center = math::scale(center, 1.0 / selected.len() as f64);
+
+        let target = Periodicity::new(&frame.cell())?.center();
+        return Translate::new(math::sub(target, center)).apply(frame);
+    }
+}
+
+/// Center the system on a selection and put everything back in the unit
+/// cell, keeping molecules whole. This is the usual "center the protein in
+/// the box" operation done before visualizing or analyzing a simulation.
+///
+/// For each frame, molecules (see [`Frame::molecules`]) are first made whole
+/// as with [`Unwrap`], and all atoms are translated to put the geometric
+/// center of the atoms matching the selection at the center of the unit
+/// cell. Then, each molecule is moved as a single block to the periodic image
+/// where its geometric center is inside the unit cell. Atoms without bonds
+/// are wrapped individually, as with [`Wrap`].
+///
+/// For frames with an infinite unit cell, the selection is centered on the
+/// origin and nothing is wrapped.
+///
+/// # Example
+/// ```
+/// # use chemfiles::{Frame, Atom, Selection, UnitCell};
+/// # use chemfiles::transform::{Transform, CenterAndWrap};
+/// let mut frame = Frame::new();
+/// frame.set_cell(&UnitCell::new([10.0, 10.0, 10.0]));
+/// frame.add_atom(&Atom::new("C"), [0.5, 1.0, 1.0], None);
+/// frame.add_atom(&Atom::new("C"), [9.5, 1.0, 1.0], None);
+/// frame.add_atom(&Atom::new("Na"), [3.0, 1.0, 1.0], None);
+/// frame.add_bond(0, 1);
+///
+/// let selection = Selection::new("name C").unwrap();
+/// CenterAndWrap::new(selection).unwrap().apply(&mut frame).unwrap();
+/// assert_eq!(frame.positions()[0], [5.5, 5.0, 5.0]);
+/// assert_eq!(frame.positions()[1], [4.5, 5.0, 5.0]);
+/// assert_eq!(frame.positions()[2], [8.0, 5.0, 5.0]);
+/// ```
+#[derive(Debug)]
+pub struct CenterAndWrap {
+    selection: Selection,
+}
+
+impl CenterAndWrap {
+    /// Create a new `CenterAndWrap` transform, centering the system on the
+    /// atoms matching `selection`.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the selection size is not 1.
+    pub fn new(selection: Selection) -> Result<CenterAndWrap, Error> {
+        if selection.size() != 1 {
+            return Err(Error {
+                status: Status::SelectionError,
+                message: format!("expected a selection of size 1, got a selection of size {}", selection.size()),
+            });
+        }
+        return Ok(CenterAndWrap { selection });
+    }
+}
+
+impl Transform for CenterAndWrap {
+    fn apply(&mut self, frame: &mut Frame) -> Result<(), Error> {
+        let selected = self.selection.list(frame);
+        if selected.is_empty() {
+            return Err(Error {
+                status: Status::SelectionError,
+                message: format!("the selection '{}' did not match any atom", self.selection.string()),
+            });
+        }
+
+        Unwrap.apply(frame)?;
+
+        let periodicity = Periodicity::new(&frame.cell())?;
+        let positions = frame.positions();
+        let selected = selected.iter().map(|&i| positions[i]).collect::<Vec<_>>();
+        let translation = math::sub(periodicity.center(), centroid(&selected));
+        Translate::new(translation).apply(frame)?;
+
+        if periodicity.is_infinite() {
+            return Ok(());
+        }
+
+        let molecules = frame.molecules();
+        let positions = frame.positions_mut();
+        for molecule in molecules {
+            let members = molecule.iter().map(|&i| positions[i]).collect::<Vec<_>>();
+            let center = centroid(&members);
+            let shift = math::sub(periodicity.inside(center), center);
+            for i in molecule {
+                positions[i] = math::add(positions[i], shift);
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+/// Align frames to a reference structure, by finding the rigid body rotation
+/// and translation minimizing the RMSD between the frame and the reference.
+///
+/// # Example
+/// ```
+/// # use chemfiles::{Frame, Atom};
+/// # use chemfiles::transform::{Transform, AlignToReference};
+/// let mut reference = Frame::new();
+/// reference.add_atom(&Atom::new("C"), [0.0, 0.0, 0.0], None);
+/// reference.add_atom(&Atom::new("C"), [1.0, 0.0, 0.0], None);
+/// reference.add_atom(&Atom::new("C"), [0.0, 2.0, 0.0], None);
+///
+/// let mut frame = Frame::new();
+/// frame.add_atom(&Atom::new("C"), [5.0, 5.0, 5.0], None);
+/// frame.add_atom(&Atom::new("C"), [5.0, 6.0, 5.0], None);
+/// frame.add_atom(&Atom::new("C"), [3.0, 5.0, 5.0], None);
+///
+/// AlignToReference::new(&reference).apply(&mut frame).unwrap();
+/// for (position, expected) in frame.positions().iter().zip(reference.positions()) {
+///     for k in 0..3 {
+///         assert!((position[k] - expected[k]).abs() < 1e-9);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AlignToReference {
+    reference: Vec<Vector3D>,
+    indexes: Vec<usize>,
+}
+
+impl AlignToReference {
+    /// Create a new `AlignToReference` transform using all the atoms in the
+    /// `reference` frame for the alignment.
+    pub fn new(reference: &Frame) -> AlignToReference {
+        AlignToReference {
+            reference: reference.positions().to_vec(),
+            indexes: (0..reference.size()).collect(),
+        }
+    }
+
+    /// Create a new `AlignToReference` transform using only the atoms at the
+    /// given `indexes` for the alignment. The whole frame is still moved by
+    /// this transform.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if any index is out of bounds for the `reference`
+    /// frame, or if `indexes` is empty.
+    pub fn with_indexes(reference: &Frame, indexes: Vec<usize>) -> Result<AlignToReference, Error> {
+        if indexes.is_empty() {
+            return Err(Error {
+                status: Status::InvalidArgument,
+                message: "can not align frames using an empty set of atoms".into(),
+            });
+        }
+
+        let size = reference.size();
+        if let Some(&index) = indexes.iter().find(|&&index| index >= size) {
+            return Err(Error {
+                status: Status::OutOfBounds,
+                message: format!("out of bounds atomic index {index} in a reference frame with {size} atoms"),
+            });
+        }
+
+        return Ok(AlignToReference {
+            reference: reference.positions().to_vec(),
+            indexes,
+        });
+    }
+}
+
+impl Transform for AlignToReference {
+    fn apply(&mut self, frame: &mut Frame) -> Result<(), Error> {
+        if frame.size() != self.reference.len() {
+            return Err(Error {
+                status: Status::InvalidArgument,
+                message: format!(
+                    "can not align a frame with {} atoms to a reference with {} atoms",
+                    frame.size(),
+                    self.reference.len()
+                ),
+            });
+        }
+
+        let reference = self.indexes.iter().map(|&i| self.reference[i]).collect::<Vec<_>>();
+        let positions = self.indexes.iter().map(|&i| frame.positions()[i]).collect::<Vec<_>>();
+
+        let reference_center = centroid(&reference);
+        let center = centroid(&positions);
+
+        let reference = reference.iter().map(|&r| math::sub(r, reference_center)).collect::<Vec<_>>();
+        let positions = positions.iter().map(|&r| math::sub(r, center)).collect::<Vec<_>>();
+        let rotation = math::optimal_rotation(&positions, &reference);
+
+        Rotate::around(rotation, center).apply(frame)?;
+        return Translate::new(math::sub(reference_center, center)).apply(frame);
+    }
+}
+
+/// Get the geometric center of a set of positions
+fn centroid(positions: &[Vector3D]) -> Vector3D {
+    let mut center = [0.0; 3];
+    for &position in positions {
+        center = math::add(center, position);
+    }
+    #[allow(clippy::cast_precision_loss)]
+    return math::scale(center, 1.0 / positions.len() as f64);
+}
+
+/// Sort the atoms in a frame in a canonical order: by residue id, then by
+/// atom name. Atoms which are not part of a residue are put last, and atoms
+/// with the same residue and name keep their relative order.
+///
+/// Writing frames after this transform produces files with the same atom
+/// order regardless of the order of the atoms in memory, which makes it
+/// possible to compare outputs from different tools. Bonds and residues are
+/// updated to the new atomic indexes.
+///
+/// # Example
+/// ```
+/// # use chemfiles::{Frame, Atom, Residue};
+/// # use chemfiles::transform::{Transform, SortAtoms};
+/// let mut frame = Frame::new();
+/// frame.add_atom(&Atom::new("Na"), [0.0, 0.0, 0.0], None);
+/// frame.add_atom(&Atom::new("O"), [1.0, 0.0, 0.0], None);
+/// frame.add_atom(&Atom::new("C"), [2.0, 0.0, 0.0], None);
+///
+/// let mut residue = Residue::with_id("CO", 1);
+/// residue.add_atom(1);
+/// residue.add_atom(2);
+/// frame.add_residue(&residue).unwrap();
+///
+/// SortAtoms.apply(&mut frame).unwrap();
+/// assert_eq!(frame.atom(0).name(), "C");
+/// assert_eq!(frame.atom(1).name(), "O");
+/// assert_eq!(frame.atom(2).name(), "Na");
+/// assert_eq!(frame.positions()[0], [2.0, 0.0, 0.0]);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SortAtoms;
+
+impl Transform for SortAtoms {
+    #[allow(clippy::cast_possible_truncation)]
+    fn apply(&mut self, frame: &mut Frame) -> Result<(), Error> {
+        let size = frame.size();
+        // (residue id, residue index) for each atom
+        let mut residues = vec![(i64::MAX, usize::MAX); size];
+        for residue_index in 0..frame.residues_count() {
+            let residue = frame.residue(residue_index as usize).expect("missing residue");
+            let id = residue.id().unwrap_or(i64::MAX);
+            for atom in residue.atoms() {
+                residues[atom] = (id, residue_index as usize);
+            }
+        }
+
+        let keys = (0..size)
+            .map(|i| (residues[i], frame.atom(i).name()))
+            .collect::<Vec<_>>();
+
+        let mut order = (0..size).collect::<Vec<_>>();
+        order.sort_by(|&i, &j| keys[i].cmp(&keys[j]));
+        if order.iter().enumerate().any(|(i, &j)| i != j) {
+            *frame = frame.subset(&order);
+        }
+        return Ok(());
+    }
+}
+
+/// A `Pipeline` reads frames from a `Trajectory`, and applies a list of
+/// transformations to each frame as it is read.
+///
+/// # Example
+/// ```no_run
+/// # use chemfiles::{Frame, Trajectory};
+/// # use chemfiles::transform::{Pipeline, Unwrap, Wrap};
+/// let trajectory = Trajectory::open("water.xyz", 'r').unwrap();
+/// let mut pipeline = Pipeline::new(trajectory).with(Wrap).with(Unwrap);
+///
+/// let mut frame = Frame::new();
+/// pipeline.read(&mut frame).unwrap();
+/// ```
+pub struct Pipeline {
+    trajectory: Trajectory,
+    transforms: Vec<Box<dyn Transform>>,
+}
+
+impl std::fmt::Debug for Pipeline {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt.debug_struct("Pipeline")
+            .field("trajectory", &self.trajectory)
+            .field("transforms", &self.transforms.len())
+            .finish()
+    }
+}
+
+impl Pipeline {
+    /// Create a new `Pipeline` reading frames from the given `trajectory`,
+    /// without any transformation.
+    pub fn new(trajectory: Trajectory) -> Pipeline {
+        Pipeline {
+            trajectory,
+            transforms: Vec::new(),
+        }
+    }
+
+    /// Add a new `transform` at the end of this pipeline. Transforms are
+    /// applied in the same order they are added.
+    #[must_use]
+    pub fn with(mut self, transform: impl Transform + 'static) -> Pipeline {
+        self.transforms.push(Box::new(transform));
+        return self;
+    }
+
+    /// Apply all the transformations in this pipeline to the given `frame`.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if any of the transformations fails.
+    pub fn apply(&mut self, frame: &mut Frame) -> Result<(), Error> {
+        for transform in &mut self.transforms {
+            transform.apply(frame)?;
+        }
+        return Ok(());
+    }
+
+    /// Read the next step of the underlying trajectory into `frame`, and apply
+    /// all the transformations to it.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if reading the trajectory fails, or if any of the
+    /// transformations fails.
+    pub fn read(&mut self, frame: &mut Frame) -> Result<(), Error> {
+        self.trajectory.read(frame)?;
+        return self.apply(frame);
+    }
+
+    /// Read the given `step` of the underlying trajectory into `frame`, and
+    /// apply all the transformations to it.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if reading the trajectory fails, or if any of the
+    /// transformations fails.
+    pub fn read_step(&mut self, step: usize, frame: &mut Frame) -> Result<(), Error> {
+        self.trajectory.read_step(step, frame)?;
+        return self.apply(frame);
+    }
+
+    /// Get the number of steps (the number of frames) in the underlying
+    /// trajectory.
+    pub fn nsteps(&mut self) -> usize {
+        return self.trajectory.nsteps();
+    }
+
+    /// Get back the underlying trajectory.
+    pub fn into_inner(self) -> Trajectory {
+        self.trajectory
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_vector3d_eq, Atom, UnitCell};
+    use std::path::Path;
+
+    fn water() -> Frame {
+        let mut frame = Frame::new();
+        frame.set_cell(&UnitCell::new([10.0, 10.0, 10.0]));
+        frame.add_atom(&Atom::new("O"), [0.2, 5.0, 5.0], None);
+        frame.add_atom(&Atom::new("H"), [9.4, 5.5, 5.0], None);
+        frame.add_atom(&Atom::new("H"), [0.6, 4.3, 5.0], None);
+        frame.add_bond(0, 1);
+        frame.add_bond(0, 2);
+        return frame;
+    }
+
+    #[test]
+    fn wrap_unwrap() {
+        let mut frame = water();
+        Unwrap.apply(&mut frame).unwrap();
+        assert_vector3d_eq(&frame.positions()[1], &[-0.6, 5.5, 5.0], 1e-12);
+        assert_vector3d_eq(&frame.positions()[2], &[0.6, 4.3, 5.0], 1e-12);
+
+        Wrap.apply(&mut frame).unwrap();
+        assert_vector3d_eq(&frame.positions()[1], &[9.4, 5.5, 5.0], 1e-12);
+    }
+
+    #[test]
+    fn rotate_translate() {
+        let mut frame = water();
+        frame.add_velocities();
+        frame.velocities_mut().unwrap()[0] = [1.0, 0.0, 0.0];
+
+        let rotation = [[0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]];
+        Rotate::around(rotation, [0.2, 5.0, 5.0]).apply(&mut frame).unwrap();
+        assert_vector3d_eq(&frame.positions()[0], &[0.2, 5.0, 5.0], 1e-12);
+        assert_vector3d_eq(&frame.positions()[2], &[0.9, 5.4, 5.0], 1e-12);
+        assert_vector3d_eq(&frame.velocities().unwrap()[0], &[0.0, 1.0, 0.0], 1e-12);
+
+        Translate::new([1.0, 1.0, 1.0]).apply(&mut frame).unwrap();
+        assert_vector3d_eq(&frame.positions()[0], &[1.2, 6.0, 6.0], 1e-12);
+    }
+
+    #[test]
+    fn center() {
+        let mut frame = water();
+        let selection = Selection::new("name O").unwrap();
+        CenterOnSelection::new(selection).unwrap().apply(&mut frame).unwrap();
+        assert_vector3d_eq(&frame.positions()[0], &[5.0, 5.0, 5.0], 1e-12);
+
+        let selection = Selection::new("pairs: all").unwrap();
+        assert!(CenterOnSelection::new(selection).is_err());
+
+        let selection = Selection::new("name Zn").unwrap();
+        let error = CenterOnSelection::new(selection).unwrap().apply(&mut frame).unwrap_err();
+        assert_eq!(error.status, Status::SelectionError);
+    }
+
+    #[test]
+    fn center_and_wrap() {
+        let mut frame = water();
+        frame.add_atom(&Atom::new("Na"), [9.0, 5.0, 5.0], None);
+
+        let selection = Selection::new("name Na").unwrap();
+        CenterAndWrap::new(selection).unwrap().apply(&mut frame).unwrap();
+        assert_vector3d_eq(&frame.positions()[3], &[5.0, 5.0, 5.0], 1e-12);
+        // the water molecule is kept whole, with its center inside the cell
+        assert_vector3d_eq(&frame.positions()[0], &[6.2, 5.0, 5.0], 1e-12);
+        assert_vector3d_eq(&frame.positions()[1], &[5.4, 5.5, 5.0], 1e-12);
+        assert_vector3d_eq(&frame.positions()[2], &[6.6, 4.3, 5.0], 1e-12);
+
+        let selection = Selection::new("name O").unwrap();
+        CenterAndWrap::new(selection).unwrap().apply(&mut frame).unwrap();
+        assert_vector3d_eq(&frame.positions()[0], &[5.0, 5.0, 5.0], 1e-12);
+        assert_vector3d_eq(&frame.positions()[3], &[3.8, 5.0, 5.0], 1e-12);
+
+        let selection = Selection::new("pairs: all").unwrap();
+        assert!(CenterAndWrap::new(selection).is_err());
+
+        let selection = Selection::new("name Zn").unwrap();
+        let error = CenterAndWrap::new(selection).unwrap().apply(&mut frame).unwrap_err();
+        assert_eq!(error.status, Status::SelectionError);
+    }
+
+    #[test]
+    fn align() {
+        let reference = water();
+        let mut frame = reference.clone();
+
+        let rotation = [[0.0, 0.0, 1.0], [0.0, 1.0, 0.0], [-1.0, 0.0, 0.0]];
+        Rotate::new(rotation).apply(&mut frame).unwrap();
+        Translate::new([3.0, -2.0, 1.0]).apply(&mut frame).unwrap();
+
+        AlignToReference::new(&reference).apply(&mut frame).unwrap();
+        for (position, expected) in frame.positions().iter().zip(reference.positions()) {
+            assert_vector3d_eq(position, expected, 1e-9);
+        }
+
+        let align = AlignToReference::with_indexes(&reference, vec![0, 4]);
+        assert_eq!(align.unwrap_err().status, Status::OutOfBounds);
+
+        let mut frame = Frame::new();
+        frame.resize(4);
+        let error = AlignToReference::new(&reference).apply(&mut frame).unwrap_err();
+        assert_eq!(error.status, Status::InvalidArgument);
+    }
+
+    #[test]
+    #[allow(clippy::cast_precision_loss)]
+    fn sort_atoms() {
+        let mut frame = Frame::new();
+        for name in ["Zn", "HB", "CA", "N", "CA", "HB"] {
+            frame.add_atom(&Atom::new(name), [0.0, 0.0, 0.0], None);
+        }
+        for (i, position) in frame.positions_mut().iter_mut().enumerate() {
+            position[0] = i as f64;
+        }
+        frame.add_bond(1, 2);
+
+        let mut first = crate::Residue::with_id("ALA", 2);
+        first.add_atom(1);
+        first.add_atom(2);
+        frame.add_residue(&first).unwrap();
+        let mut second = crate::Residue::with_id("GLY", 1);
+        second.add_atom(3);
+        second.add_atom(4);
+        second.add_atom(5);
+        frame.add_residue(&second).unwrap();
+
+        SortAtoms.apply(&mut frame).unwrap();
+        let positions = frame.positions().iter().map(|position| position[0]).collect::<Vec<_>>();
+        assert_eq!(positions, [4.0, 5.0, 3.0, 2.0, 1.0, 0.0]);
+        assert_eq!(frame.bonds(), vec![[3, 4]]);
+        assert_eq!(frame.residue_for_atom(0).unwrap().name(), "GLY");
+        assert!(frame.residue_for_atom(5).is_none());
+
+        // sorting is idempotent
+        let copy = frame.clone();
+        SortAtoms.apply(&mut frame).unwrap();
+        assert!(frame.diff(&copy, crate::DiffOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn pipeline() {
+        let root = Path::new(file!()).parent().unwrap().join("..");
+        let trajectory = Trajectory::open(root.join("data").join("water.xyz"), 'r').unwrap();
+
+        let mut pipeline = Pipeline::new(trajectory)
+            .with(Translate::new([1.0, 0.0, 0.0]))
+            .with(|frame: &mut Frame| {
+                frame.set_step(33);
+                Ok(())
+            });
+        assert_eq!(pipeline.nsteps(), 100);
+
+        let mut frame = Frame::new();
+        pipeline.read(&mut frame).unwrap();
+        assert_eq!(frame.step(), 33);
+        approx::assert_ulps_eq!(frame.positions()[0][0], 1.417219);
+    }
+}
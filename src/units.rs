@@ -0,0 +1,142 @@
+// Chemfiles, a modern library for chemistry file reading and writing
+// Copyright (C) 2015-2018 Guillaume Fraux -- BSD licensed
+
+//! Conversion between the units used by chemfiles and other common units.
+//!
+//! Chemfiles always uses Ångströms for lengths and degrees for angles in the
+//! cell, and the format readers and writers convert to and from the units
+//! used by each format (for example nanometers in GROMACS files). The
+//! functions in this module make the conversions explicit when exchanging data
+//! with other tools.
+
+/// Number of Ångströms in a nanometer
+pub const ANGSTROM_PER_NANOMETER: f64 = 10.0;
+
+/// Number of kJ in a kcal (thermochemical calorie)
+pub const KJ_PER_KCAL: f64 = 4.184;
+
+/// Convert a length from Ångströms to nanometers
+///
+/// # Example
+/// ```
+/// # use chemfiles::units;
+/// assert_eq!(units::angstrom_to_nanometer(15.0), 1.5);
+/// ```
+pub fn angstrom_to_nanometer(value: f64) -> f64 {
+    return value / ANGSTROM_PER_NANOMETER;
+}
+
+/// Convert a length from nanometers to Ångströms
+///
+/// # Example
+/// ```
+/// # use chemfiles::units;
+/// assert_eq!(units::nanometer_to_angstrom(1.5), 15.0);
+/// ```
+pub fn nanometer_to_angstrom(value: f64) -> f64 {
+    return value * ANGSTROM_PER_NANOMETER;
+}
+
+/// Convert an energy from kcal/mol to kJ/mol
+///
+/// # Example
+/// ```
+/// # use chemfiles::units;
+/// assert_eq!(units::kcal_to_kj(1.0), 4.184);
+/// ```
+pub fn kcal_to_kj(value: f64) -> f64 {
+    return value * KJ_PER_KCAL;
+}
+
+/// Convert an energy from kJ/mol to kcal/mol
+///
+/// # Example
+/// ```
+/// # use chemfiles::units;
+/// assert_eq!(units::kj_to_kcal(4.184), 1.0);
+/// ```
+pub fn kj_to_kcal(value: f64) -> f64 {
+    return value / KJ_PER_KCAL;
+}
+
+/// Convert an angle from degrees to radians
+///
+/// # Example
+/// ```
+/// # use chemfiles::units;
+/// let angle = units::degree_to_radian(180.0);
+/// assert!((angle - std::f64::consts::PI).abs() < 1e-12);
+/// ```
+pub fn degree_to_radian(value: f64) -> f64 {
+    return value.to_radians();
+}
+
+/// Convert an angle from radians to degrees
+///
+/// # Example
+/// ```
+/// # use chemfiles::units;
+/// let angle = units::radian_to_degree(std::f64::consts::PI);
+/// assert!((angle - 180.0).abs() < 1e-12);
+/// ```
+pub fn radian_to_degree(value: f64) -> f64 {
+    return value.to_degrees();
+}
+
+/// Units of length that can be used when exporting positions from a `Frame`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LengthUnit {
+    /// Ångströms, the unit used by chemfiles
+    Angstrom,
+    /// Nanometers, the unit used by GROMACS
+    Nanometer,
+}
+
+impl LengthUnit {
+    /// Convert a `value` in Ångströms to this unit
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::units::LengthUnit;
+    /// assert_eq!(LengthUnit::Angstrom.convert_from_angstrom(3.0), 3.0);
+    /// assert_eq!(LengthUnit::Nanometer.convert_from_angstrom(3.0), 0.3);
+    /// ```
+    pub fn convert_from_angstrom(self, value: f64) -> f64 {
+        match self {
+            LengthUnit::Angstrom => value,
+            LengthUnit::Nanometer => angstrom_to_nanometer(value),
+        }
+    }
+
+    /// Convert a `value` in this unit to Ångströms
+    ///
+    /// # Example
+    /// ```
+    /// # use chemfiles::units::LengthUnit;
+    /// assert_eq!(LengthUnit::Angstrom.convert_to_angstrom(3.0), 3.0);
+    /// assert_eq!(LengthUnit::Nanometer.convert_to_angstrom(0.3), 3.0);
+    /// ```
+    pub fn convert_to_angstrom(self, value: f64) -> f64 {
+        match self {
+            LengthUnit::Angstrom => value,
+            LengthUnit::Nanometer => nanometer_to_angstrom(value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_ulps_eq;
+
+    #[test]
+    fn round_trip() {
+        assert_ulps_eq!(nanometer_to_angstrom(angstrom_to_nanometer(1.234)), 1.234);
+        assert_ulps_eq!(kj_to_kcal(kcal_to_kj(-12.5)), -12.5);
+        assert_ulps_eq!(radian_to_degree(degree_to_radian(109.5)), 109.5);
+
+        for unit in [LengthUnit::Angstrom, LengthUnit::Nanometer] {
+            assert_ulps_eq!(unit.convert_to_angstrom(unit.convert_from_angstrom(42.0)), 42.0);
+        }
+    }
+}
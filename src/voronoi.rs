@@ -0,0 +1,481 @@
+// Chemfiles, a modern library for chemistry file reading and writing
+// Copyright (C) 2015-2018 Guillaume Fraux -- BSD licensed
+
+//! Pure Rust Voronoi tessellation of periodic systems.
+//!
+//! The Voronoi cell of each atom is built by starting from a large cube around
+//! the atom, and clipping it with the bisector planes between the atom and its
+//! neighbors (including periodic images), from the closest to the furthest.
+//! Neighbors are found with a cell list, and the search stops as soon as the
+//! remaining atoms are too far to modify the cell.
+
+use crate::errors::{Error, Status};
+use crate::math::{self, Periodicity, Vector3D};
+use crate::Frame;
+
+/// Voronoi cell of a single atom, as computed by [`voronoi`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct VoronoiCell {
+    /// Volume of the cell, in cubic Angstroms
+    pub volume: f64,
+    /// Indexes of the atoms sharing a face with this cell. An atom can appear
+    /// multiple times if multiple of its periodic images share a face with
+    /// this cell, which only happens with very small unit cells.
+    pub neighbors: Vec<usize>,
+    /// Area of the face shared with each neighbor, in square Angstroms.
+    /// `areas[i]` corresponds to `neighbors[i]`.
+    pub areas: Vec<f64>,
+}
+
+/// Compute the Voronoi tessellation of a periodic `frame`, and get the
+/// Voronoi cell of each atom. This is only available with the `voronoi`
+/// feature.
+///
+/// The Voronoi cell of an atom contains all the points closer to this atom
+/// than to any other atom (or periodic image of an atom). The sum of the
+/// volumes of all the cells is the volume of the unit cell. Voronoi volumes
+/// can be used to compute local densities and free volume, and atoms sharing a
+/// face are a parameter-free definition of neighbors.
+///
+/// # Errors
+///
+/// This function fails if the frame has an infinite unit cell.
+///
+/// # Example
+/// ```
+/// # use chemfiles::{Frame, Atom, UnitCell};
+/// # use chemfiles::analysis::voronoi;
+/// let mut frame = Frame::new();
+/// frame.set_cell(&UnitCell::new([4.0, 4.0, 4.0]));
+/// frame.add_atom(&Atom::new("Ar"), [0.0, 0.0, 0.0], None);
+/// frame.add_atom(&Atom::new("Ar"), [2.0, 2.0, 2.0], None);
+///
+/// let cells = voronoi(&frame).unwrap();
+/// assert!((cells[0].volume - 32.0).abs() < 1e-9);
+/// assert!((cells[1].volume - 32.0).abs() < 1e-9);
+/// ```
+pub fn voronoi(frame: &Frame) -> Result<Vec<VoronoiCell>, Error> {
+    let periodicity = Periodicity::new(&frame.cell())?;
+    if periodicity.is_infinite() {
+        return Err(Error {
+            status: Status::InvalidArgument,
+            message: "can not compute a Voronoi tessellation with an infinite unit cell".into(),
+        });
+    }
+
+    return Ok(voronoi_cells(&periodicity, frame.positions()));
+}
+
+/// Compute the Voronoi cells for all `positions` in the periodic system
+/// defined by `periodicity`
+fn voronoi_cells(periodicity: &Periodicity, positions: &[Vector3D]) -> Vec<VoronoiCell> {
+    if positions.is_empty() {
+        return Vec::new();
+    }
+
+    let grid = Grid::new(periodicity, positions);
+    // the initial cube must contain the Wigner-Seitz cell of the lattice
+    let half_size = (0..3).map(|i| math::norm(periodicity.vector(i))).sum::<f64>();
+
+    let mut cells = Vec::with_capacity(positions.len());
+    for i in 0..positions.len() {
+        let mut polyhedron = Polyhedron::cube(half_size);
+        let center = grid.position(i);
+
+        let mut shell = 0;
+        loop {
+            let mut candidates = grid.shell(i, shell);
+            for candidate in &mut candidates {
+                candidate.1 = math::sub(candidate.1, center);
+            }
+            candidates.sort_by(|a, b| math::dot(a.1, a.1).total_cmp(&math::dot(b.1, b.1)));
+
+            for (j, vector) in candidates {
+                polyhedron.clip(j, vector);
+            }
+
+            // all the atoms not yet seen are further than this distance, and
+            // can not modify the cell if they are further than twice the
+            // furthest vertex
+            #[allow(clippy::cast_precision_loss)]
+            let distance = shell as f64 * grid.min_height;
+            if distance >= 2.0 * polyhedron.radius() {
+                break;
+            }
+            shell += 1;
+        }
+
+        cells.push(polyhedron.cell());
+    }
+
+    return cells;
+}
+
+/// Cell list used to find neighbors in periodic systems
+struct Grid {
+    /// Periodicity of the system
+    periodicity: Periodicity,
+    /// Fractional positions of all atoms, wrapped inside the unit cell
+    fractional: Vec<Vector3D>,
+    /// Number of bins along each cell vector
+    size: [usize; 3],
+    /// Atoms in each bin
+    bins: Vec<Vec<usize>>,
+    /// Bin containing each atom
+    atom_bins: Vec<[usize; 3]>,
+    /// Smallest distance between two opposite faces of a bin
+    min_height: f64,
+}
+
+impl Grid {
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    fn new(periodicity: &Periodicity, positions: &[Vector3D]) -> Grid {
+        // use bins containing a few atoms each
+        let bin_size = 1.5 * (periodicity.volume() / positions.len() as f64).cbrt();
+        let mut size = [1; 3];
+        for (k, size) in size.iter_mut().enumerate() {
+            *size = ((periodicity.height(k) / bin_size).floor() as usize).clamp(1, 64);
+        }
+
+        let fractional = positions
+            .iter()
+            .map(|&position| {
+                let fractional = periodicity.fractional(position);
+                [
+                    fractional[0] - fractional[0].floor(),
+                    fractional[1] - fractional[1].floor(),
+                    fractional[2] - fractional[2].floor(),
+                ]
+            })
+            .collect::<Vec<_>>();
+
+        let mut bins = vec![Vec::new(); size[0] * size[1] * size[2]];
+        let mut atom_bins = Vec::with_capacity(positions.len());
+        for (i, fractional) in fractional.iter().enumerate() {
+            let mut bin = [0; 3];
+            for k in 0..3 {
+                bin[k] = ((fractional[k] * size[k] as f64) as usize).min(size[k] - 1);
+            }
+            bins[(bin[0] * size[1] + bin[1]) * size[2] + bin[2]].push(i);
+            atom_bins.push(bin);
+        }
+
+        let min_height = (0..3)
+            .map(|k| periodicity.height(k) / size[k] as f64)
+            .fold(f64::INFINITY, f64::min);
+
+        return Grid {
+            periodicity: periodicity.clone(),
+            fractional,
+            size,
+            bins,
+            atom_bins,
+            min_height,
+        };
+    }
+
+    /// Get the cartesian position of atom `i`, wrapped inside the unit cell
+    fn position(&self, i: usize) -> Vector3D {
+        return self.periodicity.cartesian(self.fractional[i]);
+    }
+
+    /// Get all the atoms (and their cartesian position, including the
+    /// periodic image) in bins at a distance of exactly `shell` bins from the
+    /// bin containing atom `i`.
+    #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss, clippy::cast_precision_loss)]
+    fn shell(&self, i: usize, shell: usize) -> Vec<(usize, Vector3D)> {
+        let shell = shell as isize;
+        let bin = self.atom_bins[i];
+        let mut atoms = Vec::new();
+        for da in -shell..=shell {
+            for db in -shell..=shell {
+                for dc in -shell..=shell {
+                    if da.abs().max(db.abs()).max(dc.abs()) != shell {
+                        continue;
+                    }
+
+                    let mut index = [0; 3];
+                    let mut image = [0.0; 3];
+                    for (k, delta) in [da, db, dc].into_iter().enumerate() {
+                        let size = self.size[k] as isize;
+                        let raw = bin[k] as isize + delta;
+                        index[k] = raw.rem_euclid(size) as usize;
+                        image[k] = raw.div_euclid(size) as f64;
+                    }
+
+                    let bin = (index[0] * self.size[1] + index[1]) * self.size[2] + index[2];
+                    for &j in &self.bins[bin] {
+                        if j == i && shell == 0 {
+                            continue;
+                        }
+                        let fractional = math::add(self.fractional[j], image);
+                        atoms.push((j, self.periodicity.cartesian(fractional)));
+                    }
+                }
+            }
+        }
+        return atoms;
+    }
+}
+
+/// A face of a polyhedron
+struct Face {
+    /// Atom on the other side of this face, if any
+    neighbor: Option<usize>,
+    /// Vertices of this face, in order around the face
+    vertices: Vec<Vector3D>,
+}
+
+impl Face {
+    /// Get the area of this face
+    fn area(&self) -> f64 {
+        let first = self.vertices[0];
+        let mut area = 0.0;
+        for pair in self.vertices[1..].windows(2) {
+            area += math::norm(math::cross(math::sub(pair[0], first), math::sub(pair[1], first)));
+        }
+        return area / 2.0;
+    }
+}
+
+/// Convex polyhedron centered on the origin, used to build Voronoi cells
+struct Polyhedron {
+    faces: Vec<Face>,
+}
+
+impl Polyhedron {
+    /// Create a cube of the given `half_size` centered on the origin
+    fn cube(half_size: f64) -> Polyhedron {
+        let h = half_size;
+        let faces = [
+            [[h, -h, -h], [h, h, -h], [h, h, h], [h, -h, h]],
+            [[-h, -h, -h], [-h, -h, h], [-h, h, h], [-h, h, -h]],
+            [[-h, h, -h], [-h, h, h], [h, h, h], [h, h, -h]],
+            [[-h, -h, -h], [h, -h, -h], [h, -h, h], [-h, -h, h]],
+            [[-h, -h, h], [h, -h, h], [h, h, h], [-h, h, h]],
+            [[-h, -h, -h], [-h, h, -h], [h, h, -h], [h, -h, -h]],
+        ];
+
+        Polyhedron {
+            faces: faces
+                .iter()
+                .map(|vertices| Face {
+                    neighbor: None,
+                    vertices: vertices.to_vec(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Get the largest distance between the origin and a vertex
+    fn radius(&self) -> f64 {
+        self.faces
+            .iter()
+            .flat_map(|face| &face.vertices)
+            .map(|&vertex| math::norm(vertex))
+            .fold(0.0, f64::max)
+    }
+
+    /// Remove the part of this polyhedron closer to `vector` than to the
+    /// origin, adding a new face shared with the atom `neighbor` if needed.
+    fn clip(&mut self, neighbor: usize, vector: Vector3D) {
+        let offset = math::dot(vector, vector) / 2.0;
+        let distance = |point: Vector3D| math::dot(point, vector) - offset;
+
+        if offset >= math::norm(vector) * self.radius() {
+            // the plane does not intersect the polyhedron
+            return;
+        }
+
+        let mut points = Vec::new();
+        let mut faces = Vec::with_capacity(self.faces.len() + 1);
+        for face in self.faces.drain(..) {
+            let mut vertices = Vec::with_capacity(face.vertices.len() + 1);
+            for (k, &first) in face.vertices.iter().enumerate() {
+                let second = face.vertices[(k + 1) % face.vertices.len()];
+                let first_distance = distance(first);
+                let second_distance = distance(second);
+
+                if first_distance <= 0.0 {
+                    vertices.push(first);
+                }
+
+                if (first_distance <= 0.0) != (second_distance <= 0.0) {
+                    let t = first_distance / (first_distance - second_distance);
+                    let point = math::add(first, math::scale(math::sub(second, first), t));
+                    vertices.push(point);
+                    points.push(point);
+                }
+            }
+
+            if vertices.len() >= 3 {
+                faces.push(Face {
+                    neighbor: face.neighbor,
+                    vertices,
+                });
+            }
+        }
+
+        if let Some(vertices) = sort_polygon(points, vector) {
+            faces.push(Face {
+                neighbor: Some(neighbor),
+                vertices,
+            });
+        }
+
+        self.faces = faces;
+    }
+
+    /// Get the Voronoi cell corresponding to this polyhedron
+    fn cell(&self) -> VoronoiCell {
+        let mut volume = 0.0;
+        let mut neighbors = Vec::new();
+        let mut areas = Vec::new();
+        for face in &self.faces {
+            let first = face.vertices[0];
+            for pair in face.vertices[1..].windows(2) {
+                volume += math::dot(first, math::cross(pair[0], pair[1])).abs() / 6.0;
+            }
+
+            let area = face.area();
+            if let Some(neighbor) = face.neighbor {
+                if area > 1e-12 {
+                    neighbors.push(neighbor);
+                    areas.push(area);
+                }
+            }
+        }
+
+        return VoronoiCell {
+            volume,
+            neighbors,
+            areas,
+        };
+    }
+}
+
+/// Remove duplicated `points` and sort the remaining ones around their
+/// center, in the plane with the given `normal`. This returns `None` if there
+/// are less than three distinct points.
+fn sort_polygon(points: Vec<Vector3D>, normal: Vector3D) -> Option<Vec<Vector3D>> {
+    let mut unique: Vec<Vector3D> = Vec::with_capacity(points.len());
+    for point in points {
+        let tolerance = 1e-10 * (1.0 + math::norm(point));
+        if unique
+            .iter()
+            .all(|&other| math::norm(math::sub(point, other)) > tolerance)
+        {
+            unique.push(point);
+        }
+    }
+
+    if unique.len() < 3 {
+        return None;
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    let center = math::scale(
+        unique.iter().fold([0.0; 3], |a, &b| math::add(a, b)),
+        1.0 / unique.len() as f64,
+    );
+    let u = math::sub(unique[0], center);
+    let v = math::cross(normal, u);
+
+    let angle = |point: Vector3D| {
+        let point = math::sub(point, center);
+        f64::atan2(math::dot(point, v), math::dot(point, u))
+    };
+    unique.sort_by(|&a, &b| angle(a).total_cmp(&angle(b)));
+    return Some(unique);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_ulps_eq;
+
+    fn cubic(size: f64) -> Periodicity {
+        Periodicity::triclinic([[size, 0.0, 0.0], [0.0, size, 0.0], [0.0, 0.0, size]])
+    }
+
+    #[test]
+    fn simple_cubic() {
+        let periodicity = cubic(6.0);
+        let mut positions = Vec::new();
+        for i in 0..3 {
+            for j in 0..3 {
+                for k in 0..3 {
+                    positions.push([2.0 * f64::from(i), 2.0 * f64::from(j), 2.0 * f64::from(k)]);
+                }
+            }
+        }
+
+        let cells = voronoi_cells(&periodicity, &positions);
+        assert_eq!(cells.len(), 27);
+        for cell in &cells {
+            assert_ulps_eq!(cell.volume, 8.0, epsilon = 1e-9);
+            assert_eq!(cell.neighbors.len(), 6);
+            for &area in &cell.areas {
+                assert_ulps_eq!(area, 4.0, epsilon = 1e-9);
+            }
+        }
+
+        let mut neighbors = cells[0].neighbors.clone();
+        neighbors.sort_unstable();
+        assert_eq!(neighbors, [1, 2, 3, 6, 9, 18]);
+    }
+
+    #[test]
+    fn face_centered_cubic() {
+        let periodicity = cubic(4.0);
+        let positions = [[0.0, 0.0, 0.0], [2.0, 2.0, 0.0], [2.0, 0.0, 2.0], [0.0, 2.0, 2.0]];
+
+        let cells = voronoi_cells(&periodicity, &positions);
+        for cell in &cells {
+            // rhombic dodecahedron
+            assert_ulps_eq!(cell.volume, 16.0, epsilon = 1e-9);
+            assert_eq!(cell.neighbors.len(), 12);
+        }
+    }
+
+    #[test]
+    fn single_atom() {
+        let periodicity = Periodicity::triclinic([[3.0, 1.0, 0.5], [0.0, 4.0, -1.0], [0.0, 0.0, 5.0]]);
+        let cells = voronoi_cells(&periodicity, &[[1.0, -2.0, 12.0]]);
+        assert_ulps_eq!(cells[0].volume, 60.0, epsilon = 1e-9);
+        assert!(cells[0].neighbors.iter().all(|&i| i == 0));
+        assert!(cells[0].neighbors.len() >= 6);
+    }
+
+    #[test]
+    fn total_volume() {
+        let periodicity = Periodicity::triclinic([[8.0, -2.0, 1.5], [0.0, 9.0, 1.0], [0.0, 0.0, 10.0]]);
+
+        // pseudo-random positions from a linear congruential generator
+        let mut state = 42_u64;
+        let mut random = || {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            #[allow(clippy::cast_precision_loss)]
+            let value = (state >> 11) as f64 / (1_u64 << 53) as f64;
+            return 20.0 * value - 5.0;
+        };
+        let positions = (0..50).map(|_| [random(), random(), random()]).collect::<Vec<_>>();
+
+        let cells = voronoi_cells(&periodicity, &positions);
+        let total = cells.iter().map(|cell| cell.volume).sum::<f64>();
+        assert_ulps_eq!(total, periodicity.volume(), epsilon = 1e-6);
+
+        // neighbors are symmetric
+        for (i, cell) in cells.iter().enumerate() {
+            for &j in &cell.neighbors {
+                assert!(cells[j].neighbors.contains(&i));
+            }
+        }
+    }
+}